@@ -102,6 +102,10 @@ impl Translator {
         translate!(self, "Create")
     }
 
+    pub fn team_demote_hint(&self) -> String {
+        translate!(self, "Demote")
+    }
+
     pub fn team_deny_hint(&self) -> String {
         translate!(self, "Deny")
     }
@@ -118,10 +122,26 @@ impl Translator {
         translate!(self, "New team")
     }
 
+    pub fn team_officer_hint(&self) -> String {
+        translate!(self, "Officer")
+    }
+
+    pub fn team_promote_hint(&self) -> String {
+        translate!(self, "Promote")
+    }
+
+    pub fn team_rename_hint(&self) -> String {
+        translate!(self, "Rename")
+    }
+
     pub fn team_request_hint(&self) -> String {
         translate!(self, "Request Join")
     }
 
+    pub fn team_transfer_hint(&self) -> String {
+        translate!(self, "Make leader")
+    }
+
     pub fn online(&self, players: u32) -> String {
         translate!(self, "{players} online")
     }
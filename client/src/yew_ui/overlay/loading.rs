@@ -0,0 +1,39 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use crate::{translate, use_translator, Curtain, Meter, Position, Positioner};
+use stylist::yew::styled_component;
+use yew::{html, AttrValue, Html, Properties};
+
+#[derive(PartialEq, Properties)]
+pub struct LoadingOverlayProps {
+    /// 0 to 1, e.g. from
+    /// [`LockstepClient::transfer_progress`][`crate::LockstepClient::transfer_progress`].
+    pub progress: f32,
+}
+
+/// Shown while a late joiner receives the game state.
+#[styled_component(LoadingOverlay)]
+pub fn loading_overlay(props: &LoadingOverlayProps) -> Html {
+    let meter_css_class = css!(
+        r#"
+        min-width: 12rem;
+        "#
+    );
+
+    let t = use_translator();
+    let loading_progress =
+        |percent: u8| -> String { translate!(t, "loading_progress", "Loading... {percent}%") };
+    // Don't report 100% until done.
+    let percent = (props.progress.clamp(0.0, 1.0) * 100.0).floor() as u8;
+
+    html! {
+        <Curtain>
+            <Positioner position={Position::Center}>
+                <Meter value={props.progress} class={meter_css_class}>
+                    {AttrValue::from(loading_progress(percent))}
+                </Meter>
+            </Positioner>
+        </Curtain>
+    }
+}
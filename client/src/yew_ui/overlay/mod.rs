@@ -6,6 +6,7 @@ mod escape_menu;
 mod fatal_error;
 mod instructions;
 mod leaderboard;
+mod loading;
 mod reconnecting;
 pub mod spawn;
 mod splash;
@@ -16,6 +17,7 @@ pub(crate) use escape_menu::EscapeMenu;
 pub use fatal_error::{FatalErrorDialog, FatalErrorProps};
 pub use instructions::{Instruction, Instructions, InstructionsProps};
 pub use leaderboard::{LeaderboardOverlay, LeaderboardProps};
+pub use loading::{LoadingOverlay, LoadingOverlayProps};
 pub(crate) use reconnecting::Reconnecting;
 pub use spawn::{nickname_placeholder, use_splash_screen, SpawnOverlay, SpawnOverlayProps};
 pub use splash::*;
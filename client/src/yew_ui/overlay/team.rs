@@ -4,7 +4,8 @@
 use crate::{
     event_target, high_contrast_class, use_change_common_settings_callback, use_core_state,
     use_ctw, use_translator, InvitationLink, JoinedStatus, Manifestation, Member, PlayerDto,
-    PlayerId, Position, Positioner, Team, TeamId, TeamName, TeamRequest, TranslateFn,
+    PlayerId, Position, Positioner, Team, TeamId, TeamName, TeamPermissions, TeamRequest, TeamRole,
+    TranslateFn,
 };
 use kodiak_common::arrayvec::ArrayVec;
 use std::rc::Rc;
@@ -185,8 +186,11 @@ pub fn team_overlay<
     let high_contrast_class = high_contrast_class!(ctw, css);
     let team_request_callback = &props.team_request_callback;
     let input_ref = use_node_ref();
+    let rename_input_ref = use_node_ref();
     let change_common_settings_callback = use_change_common_settings_callback();
 
+    let renaming = use_state_eq(|| false);
+
     let team_name_empty = use_state_eq(|| true);
     // Can't auto-spawn..
     //let invitation_id = use_state_eq(|| Option::<InvitationId>::None);
@@ -241,6 +245,24 @@ pub fn team_overlay<
     };
     let on_create_team_2 = on_create_team.clone();
 
+    let on_rename_team = {
+        let cb = team_request_callback.clone();
+        let renaming = renaming.clone();
+        let rename_input_ref = rename_input_ref.clone();
+        move || {
+            if *renaming && let Some(input) = rename_input_ref.cast::<HtmlInputElement>() {
+                let new_team_name = input.value();
+                if !new_team_name.is_empty() {
+                    cb.emit(TeamRequest::Name(TeamName::new_input_sanitized(
+                        &new_team_name,
+                    )));
+                }
+            }
+            renaming.set(!*renaming);
+        }
+    };
+    let on_rename_team_2 = on_rename_team.clone();
+
     let on_kick_from_team = {
         let cb = team_request_callback.clone();
         move |player_id: PlayerId| {
@@ -253,6 +275,27 @@ pub fn team_overlay<
         move || cb.emit(TeamRequest::Leave)
     };
 
+    let on_promote = {
+        let cb = team_request_callback.clone();
+        move |player_id: PlayerId| {
+            cb.emit(TeamRequest::Promote(player_id));
+        }
+    };
+
+    let on_demote = {
+        let cb = team_request_callback.clone();
+        move |player_id: PlayerId| {
+            cb.emit(TeamRequest::Demote(player_id));
+        }
+    };
+
+    let on_transfer_leadership = {
+        let cb = team_request_callback.clone();
+        move |player_id: PlayerId| {
+            cb.emit(TeamRequest::TransferLeadership(player_id));
+        }
+    };
+
     let on_reject_join_team = {
         let cb = team_request_callback.clone();
         move |player_id: PlayerId| {
@@ -269,9 +312,17 @@ pub fn team_overlay<
 
     const CHECK_MARK: &str = "✔";
     const X_MARK: &str = "✘";
+    const UP_MARK: &str = "▲";
+    const DOWN_MARK: &str = "▼";
+    const CROWN_MARK: &str = "♛";
+    const STAR_MARK: &str = "★";
+    const PENCIL_MARK: &str = "✎";
 
     let my_player_id = use_core_state().player_id;
-    let i_am_team_captain = my_player_id == props.team.leader().map(|l| l.player_id); // TODO
+    let i_am_team_captain = my_player_id == props.team.leader().map(|l| l.player_id);
+    let my_permissions = my_player_id
+        .map(|player_id| props.team.permissions(player_id))
+        .unwrap_or(TeamPermissions::NONE);
     let team_full = props.team.members.len() > M::MAX_MEMBERS;
     #[cfg(feature = "pointer_lock")]
     let hide_buttons = ctw.pointer_locked;
@@ -288,31 +339,91 @@ pub fn team_overlay<
                 <table class={table_css_class}>
                     <tr>
                         <th colspan="3">
-                            <InvitationLink show_code={true} style={invitation_link_style}>{AttrValue::from(Rc::from(team_name.as_str()))}</InvitationLink>
+                            if *renaming {
+                                <form onsubmit={move |e: SubmitEvent| {
+                                    e.prevent_default();
+                                    e.stop_propagation();
+                                    on_rename_team();
+                                }}>
+                                    <input
+                                        ref={rename_input_ref}
+                                        type="text"
+                                        minlength="1"
+                                        maxlength="6"
+                                        value={team_name.as_str().to_owned()}
+                                        class={input_css_class.clone()}
+                                    />
+                                </form>
+                            } else {
+                                <InvitationLink show_code={true} style={invitation_link_style}>{AttrValue::from(Rc::from(team_name.as_str()))}</InvitationLink>
+                            }
+                        </th>
+                        <th>
+                            if my_permissions.rename && !hide_buttons {
+                                <button
+                                    class={button_css_class.clone()}
+                                    onclick={move |event: MouseEvent| {
+                                        event.stop_propagation();
+                                        on_rename_team_2();
+                                    }}
+                                    title={t.team_rename_hint()}
+                                >{if *renaming { CHECK_MARK } else { PENCIL_MARK }}</button>
+                            }
                         </th>
                     </tr>
                     {props.team.iter().enumerate().filter_map(|(i, Member{player_id, ..})| core_state.player_or_bot(*player_id).map(|p| (i, p))).map(|(i, PlayerDto{alias, player_id, ..})| {
                         let me = my_player_id == Some(player_id);
                         let team_captain = i == 0;
+                        let officer = props.team.role(player_id) == Some(TeamRole::Officer);
+                        let can_kick = my_player_id.is_some_and(|my_player_id| props.team.can_kick(my_player_id, player_id));
+                        let manage_role = i_am_team_captain && !me;
                         let on_leave_team = on_leave_team.clone();
                         let on_kick_from_team = on_kick_from_team.clone();
+                        let on_promote = on_promote.clone();
+                        let on_demote = on_demote.clone();
+                        let on_transfer_leadership = on_transfer_leadership.clone();
 
                         html_nested!{
                             <tr class={tr_css_class.clone()}>
                                 <td class={classes!(
                                     name_css_class.clone(),
                                     team_captain.then(|| owner_css_class.clone()))
-                                }>{alias.as_str()}</td>
+                                }>
+                                    {alias.as_str()}
+                                    if officer {
+                                        <span title={t.team_officer_hint()}>{" "}{STAR_MARK}</span>
+                                    }
+                                </td>
                                 if !hide_buttons {
-                                    // for spacing only.
                                     <td><button
-                                        class={classes!(button_css_class.clone(), hidden_css_class.clone())}
-                                    >{CHECK_MARK}</button></td>
+                                        class={classes!(button_css_class.clone(), (!manage_role).then(|| hidden_css_class.clone()))}
+                                        onclick={move |event: MouseEvent| {
+                                            event.stop_propagation();
+                                            if officer {
+                                                on_demote(player_id);
+                                            } else {
+                                                on_promote(player_id);
+                                            }
+                                        }}
+                                        title={if officer {
+                                            t.team_demote_hint()
+                                        } else {
+                                            t.team_promote_hint()
+                                        }}
+                                    >{if officer { DOWN_MARK } else { UP_MARK }}</button></td>
+                                    <td><button
+                                        class={classes!(button_css_class.clone(), (!manage_role).then(|| hidden_css_class.clone()))}
+                                        onclick={move |event: MouseEvent| {
+                                            event.stop_propagation();
+                                            on_transfer_leadership(player_id);
+                                        }}
+                                        title={t.team_transfer_hint()}
+                                    >{CROWN_MARK}</button></td>
                                     <td><button
                                         class={classes!(
                                             button_css_class.clone(),
                                             (
-                                                (!me && !i_am_team_captain)
+                                                (!me && !can_kick)
                                                 || (me && !M::MEMBERS_CAN_LEAVE)
                                                 || (team_captain && !M::LEADER_CAN_LEAVE)
                                                 || (props.team.members.len() == 1 && !M::CAN_LEAVE_SOLO_TEAM)
@@ -336,7 +447,7 @@ pub fn team_overlay<
                             </tr>
                         }
                     }).collect::<Html>()}
-                    if i_am_team_captain {
+                    if my_permissions.accept {
                         {props.team.joiners.iter().filter_map(|player_id| core_state.player_or_bot(*player_id)).map(|PlayerDto{alias, player_id, ..}| {
                             let on_accept_join_team = on_accept_join_team.clone();
                             let on_reject_join_team = on_reject_join_team.clone();
@@ -344,6 +455,10 @@ pub fn team_overlay<
                                 <tr class={tr_css_class.clone()}>
                                     <td class={classes!(name_css_class.clone(), name_pending_css_class.clone())}>{alias.as_str()}</td>
                                     if !hide_buttons {
+                                        // for spacing only.
+                                        <td><button
+                                            class={classes!(button_css_class.clone(), hidden_css_class.clone())}
+                                        >{CHECK_MARK}</button></td>
                                         <td><button
                                             class={classes!(button_css_class.clone(), team_full.then(|| disabled_css_class.clone()))}
                                             onclick={move |event: MouseEvent| {
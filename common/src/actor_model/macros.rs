@@ -255,7 +255,10 @@ macro_rules! define_world {
                         }
 
                         let mut completes_len = 0;
-                        for actor_id in (visibility.[<$actor:snake>])(&knowledge) {
+                        let in_view = knowledge.viewpoint.into_iter().flat_map(|viewpoint| {
+                            <<$actor as Actor>::Id as ActorId>::in_view(&self.[<$actor:snake>], viewpoint)
+                        });
+                        for actor_id in (visibility.[<$actor:snake>])(&knowledge).into_iter().chain(in_view) {
                             debug_assert!(Map::contains(&self.[<$actor:snake>], actor_id), "visible actor {actor_id:?} does not exist");
                             // TODO Map::get_or_insert_with.
                             if let Some(knowledge) = Map::get_mut(&mut knowledge.[<$actor:snake>], actor_id) {
@@ -403,7 +406,10 @@ macro_rules! define_world {
             /// What part of the world a client knows about.
             #[derive(Debug, Default)]
             pub struct Knowledge {
-                $(pub [<$actor:snake>]: <<$actor as Actor>::Id as ActorId>::SparseMap<ActorKnowledge>),*
+                $(pub [<$actor:snake>]: <<$actor as Actor>::Id as ActorId>::SparseMap<ActorKnowledge>,)*
+                /// Actors keyed by [`SectorId2d`][`$crate::SectorId2d`] are visible if their sector
+                /// is in view, regardless of [`Visibility`].
+                pub viewpoint: Option<$crate::actor_model::SectorViewpoint2d>,
             }
 
             $(
@@ -421,7 +427,8 @@ macro_rules! define_world {
                 }
             }
 
-            /// Which actors a client can see this frame. Can contain duplicates.
+            /// Which actors a client can see this frame, besides those in [`Knowledge::viewpoint`].
+            /// Can contain duplicates.
             pub struct Visibility<$($actor),+> {
                 $(pub [<$actor:snake>]: $actor),+
             }
@@ -15,6 +15,7 @@ mod tests;
 pub use self::context::{Dst, Src};
 pub use self::sector_2d::{
     Entities2d, Entity2d, EntityIndex2d, OutOfBounds, SectorArray2d, SectorId2d, SectorMap2d,
+    SectorViewpoint2d,
 };
 pub use self::singletons::{
     Accumulate, Actor, ActorId, ActorKnowledge, Checksum, IsActive, Message, SequentialInbox,
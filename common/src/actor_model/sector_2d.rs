@@ -3,11 +3,13 @@
 
 use crate::actor_model::{ActorId, Efficient, Map, OrdIter, SortedVecMap};
 use crate::bitcode::{self, *};
+use crate::{Aabb2d, Collider2d};
+use fxhash::FxHashSet;
 use glam::{IVec2, UVec2, Vec2};
 use serde::{Deserialize, Serialize};
 use std::array;
 use std::cmp::Ordering;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BinaryHeap};
 
 // NOTE: This is a reimplementation of crate::entities::Entities and kiomet::ChunkMap
 // - no longer necessarily a *square*
@@ -221,6 +223,38 @@ impl<const WIDTH: usize, const HEIGHT: usize, const SCALE: u16> TryFrom<Vec2>
     }
 }
 
+/// Where a client is looking, for automatically subscribing it to [`Actor`]s keyed by
+/// [`SectorId2d`].
+///
+/// [`Actor`]: crate::actor_model::Actor
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SectorViewpoint2d {
+    pub center: Vec2,
+    /// Sectors that intersect a circle of this radius are visible.
+    pub radius: f32,
+}
+
+impl SectorViewpoint2d {
+    pub fn new(center: Vec2, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// Returns true if the sector with `id` is visible.
+    pub fn contains<const WIDTH: usize, const HEIGHT: usize, const SCALE: u16>(
+        &self,
+        id: SectorId2d<WIDTH, HEIGHT, SCALE>,
+    ) -> bool {
+        id.in_radius(self.center, self.radius)
+    }
+
+    /// Returns an iterator over all the visible [`SectorId2d`]s.
+    pub fn iter<const WIDTH: usize, const HEIGHT: usize, const SCALE: u16>(
+        &self,
+    ) -> impl Iterator<Item = SectorId2d<WIDTH, HEIGHT, SCALE>> + Clone {
+        SectorId2d::iter_radius(self.center, self.radius)
+    }
+}
+
 /// A 2D map of sectors.
 #[derive(Debug, Clone, Hash, Encode, Decode)]
 pub struct SectorMap2d<T, const WIDTH: usize, const HEIGHT: usize, const SCALE: u16> {
@@ -559,6 +593,226 @@ impl<E: Entity2d, const WIDTH: usize, const HEIGHT: usize, const SCALE: u16>
                 })
         })
     }
+
+    /// Returns an iterator over entities positioned within `aabb`.
+    pub fn iter_aabb<'a>(
+        aabb: Aabb2d,
+        get_entities: impl Fn(SectorId2d<WIDTH, HEIGHT, SCALE>) -> Option<&'a Self>,
+    ) -> impl Iterator<Item = (EntityIndex2d<WIDTH, HEIGHT, SCALE>, &'a E)> {
+        let start = SectorId2d::<WIDTH, HEIGHT, SCALE>::saturating_from(aabb.min);
+        let end = SectorId2d::<WIDTH, HEIGHT, SCALE>::saturating_from(aabb.max);
+        SectorId2d::iter(start, end).flat_map(move |sector_id| {
+            get_entities(sector_id)
+                .into_iter()
+                .flat_map(move |entities| {
+                    entities
+                        .iter()
+                        .filter(move |(_, e)| aabb.contains_point(e.position()))
+                        .map(move |(index, entity)| (EntityIndex2d::new(sector_id, index), entity))
+                })
+        })
+    }
+
+    /// Returns up to `k` entities positioned within `max_distance` of `center` for which
+    /// `filter` returns true, nearest first (ties go to the lesser [`EntityIndex2d`]).
+    ///
+    /// Sectors are searched in rings around `center`, stopping once no unsearched sector could
+    /// contain a nearer entity.
+    pub fn nearest<'a>(
+        center: Vec2,
+        k: usize,
+        max_distance: f32,
+        get_entities: impl Fn(SectorId2d<WIDTH, HEIGHT, SCALE>) -> Option<&'a Self>,
+        mut filter: impl FnMut(EntityIndex2d<WIDTH, HEIGHT, SCALE>, &E) -> bool,
+    ) -> Vec<(EntityIndex2d<WIDTH, HEIGHT, SCALE>, &'a E)> {
+        // Max heap of the k nearest so far. Non-negative floats are ordered like their bits.
+        let mut nearest = BinaryHeap::<(u32, EntityIndex2d<WIDTH, HEIGHT, SCALE>)>::new();
+        if k == 0 {
+            return Vec::new();
+        }
+        let max_distance_squared = max_distance.powi(2);
+        let dims = IVec2::new(WIDTH as i32, HEIGHT as i32);
+        let origin = SectorId2d::<WIDTH, HEIGHT, SCALE>::saturating_from(center);
+        let origin = IVec2::new(origin.x as i32, origin.y as i32);
+
+        for ring in 0..dims.max_element() {
+            // Sectors at a Chebyshev distance of exactly `ring` from `origin`.
+            let min = (origin - ring).max(IVec2::ZERO);
+            let max = (origin + ring).min(dims - 1);
+            for y in min.y..=max.y {
+                let step = if y == origin.y - ring || y == origin.y + ring {
+                    1
+                } else {
+                    (2 * ring).max(1) as usize
+                };
+                for x in (origin.x - ring..=origin.x + ring).step_by(step) {
+                    if x < min.x || x > max.x {
+                        continue;
+                    }
+                    let sector_id = SectorId2d::new(x as u8, y as u8);
+                    let Some(entities) = get_entities(sector_id) else {
+                        continue;
+                    };
+                    for (index, entity) in entities.iter() {
+                        let distance_squared = entity.position().distance_squared(center);
+                        if distance_squared > max_distance_squared {
+                            continue;
+                        }
+                        let key = (
+                            distance_squared.to_bits(),
+                            EntityIndex2d::new(sector_id, index),
+                        );
+                        if nearest.len() == k && &key >= nearest.peek().unwrap() {
+                            continue;
+                        }
+                        if !filter(key.1, entity) {
+                            continue;
+                        }
+                        if nearest.len() == k {
+                            nearest.pop();
+                        }
+                        nearest.push(key);
+                    }
+                }
+            }
+
+            // Unsearched sectors are at least this far away, in directions that have any.
+            let block_min = SectorId2d::<WIDTH, HEIGHT, SCALE>::new(min.x as u8, min.y as u8);
+            let block_max = SectorId2d::<WIDTH, HEIGHT, SCALE>::new(max.x as u8, max.y as u8);
+            let below = center - block_min.bottom_left();
+            let above = block_max.top_right() - center;
+            let bound = [
+                (min.x > 0).then_some(below.x),
+                (min.y > 0).then_some(below.y),
+                (max.x < dims.x - 1).then_some(above.x),
+                (max.y < dims.y - 1).then_some(above.y),
+            ]
+            .into_iter()
+            .flatten()
+            .fold(f32::INFINITY, f32::min)
+            .max(0.0);
+            if bound > max_distance
+                || (nearest.len() == k && f32::from_bits(nearest.peek().unwrap().0) < bound.powi(2))
+            {
+                break;
+            }
+        }
+
+        nearest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(_, index)| {
+                let entity = get_entities(index.sector_id)
+                    .and_then(|entities| entities.get(index.index))
+                    .unwrap();
+                (index, entity)
+            })
+            .collect()
+    }
+
+    /// Casts a ray from `origin` in the normalized `direction`, returning the first entity whose
+    /// collider it enters within `max_distance`, and the distance. `collider` returns the
+    /// collider of an entity, or `None` to ignore it, and colliders mustn't extend further than
+    /// `max_radius` from their entity's position. Ties go to the lesser [`EntityIndex2d`].
+    ///
+    /// Sectors are searched in the order the ray passes through them, stopping once no
+    /// unsearched sector could contain a nearer hit.
+    pub fn ray_cast<'a>(
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+        max_radius: f32,
+        get_entities: impl Fn(SectorId2d<WIDTH, HEIGHT, SCALE>) -> Option<&'a Self>,
+        mut collider: impl FnMut(EntityIndex2d<WIDTH, HEIGHT, SCALE>, &E) -> Option<Collider2d>,
+    ) -> Option<(EntityIndex2d<WIDTH, HEIGHT, SCALE>, &'a E, f32)> {
+        debug_assert!(direction.is_normalized());
+        let scale = SCALE as f32;
+        let dims = IVec2::new(WIDTH as i32, HEIGHT as i32);
+        let half_dims = dims.as_vec2() * 0.5;
+        // Colliders of entities in neighboring sectors within this many sectors may be hit.
+        let margin = (max_radius / scale).ceil() as i32;
+
+        // Skip to where the ray enters the map, which is expanded to include all colliders.
+        let bounds = Aabb2d::from_center_half_size(Vec2::ZERO, half_dims * scale + max_radius);
+        let mut enter = bounds.ray_distance(origin, direction, max_distance)?;
+
+        // Traverse sectors along the ray. See: http://www.cse.yorku.ca/~amana/research/grid.pdf
+        let grid_origin = origin / scale + half_dims;
+        let mut sector = (grid_origin + direction * (enter / scale))
+            .floor()
+            .as_ivec2();
+        let step = IVec2::new(
+            if direction.x < 0.0 { -1 } else { 1 },
+            if direction.y < 0.0 { -1 } else { 1 },
+        );
+        let mut exit = Vec2::ZERO;
+        let mut delta = Vec2::ZERO;
+        for i in 0..2 {
+            if direction[i] == 0.0 {
+                exit[i] = f32::INFINITY;
+                delta[i] = f32::INFINITY;
+            } else {
+                let boundary = (sector[i] + (step[i] > 0) as i32) as f32;
+                exit[i] = (boundary - grid_origin[i]) * scale / direction[i];
+                delta[i] = scale / direction[i].abs();
+            }
+        }
+
+        let mut searched = FxHashSet::<SectorId2d<WIDTH, HEIGHT, SCALE>>::default();
+        let mut first: Option<(f32, EntityIndex2d<WIDTH, HEIGHT, SCALE>, &'a E)> = None;
+        while enter <= max_distance
+            && !matches!(first, Some((distance, ..)) if distance <= enter)
+            && sector.cmpge(IVec2::splat(-margin - 1)).all()
+            && sector.cmple(dims + margin).all()
+        {
+            let min = (sector - margin).max(IVec2::ZERO);
+            let max = (sector + margin).min(dims - 1);
+            for y in min.y..=max.y {
+                for x in min.x..=max.x {
+                    let sector_id = SectorId2d::new(x as u8, y as u8);
+                    if !searched.insert(sector_id) {
+                        continue;
+                    }
+                    let Some(entities) = get_entities(sector_id) else {
+                        continue;
+                    };
+                    for (index, entity) in entities.iter() {
+                        let index = EntityIndex2d::new(sector_id, index);
+                        if let Some(c) = collider(index, entity)
+                            && let Some(distance) = c.ray_distance(origin, direction, max_distance)
+                            && !matches!(first, Some((d, i, _)) if (d, i) <= (distance, index))
+                        {
+                            first = Some((distance, index, entity));
+                        }
+                    }
+                }
+            }
+
+            if exit.x < exit.y {
+                enter = exit.x;
+                exit.x += delta.x;
+                sector.x += step.x;
+            } else {
+                enter = exit.y;
+                exit.y += delta.y;
+                sector.y += step.y;
+            }
+        }
+        first.map(|(distance, index, entity)| (index, entity, distance))
+    }
+
+    /// Like [`Self::ray_cast`], but from `start` to `end`.
+    pub fn segment_cast<'a>(
+        start: Vec2,
+        end: Vec2,
+        max_radius: f32,
+        get_entities: impl Fn(SectorId2d<WIDTH, HEIGHT, SCALE>) -> Option<&'a Self>,
+        collider: impl FnMut(EntityIndex2d<WIDTH, HEIGHT, SCALE>, &E) -> Option<Collider2d>,
+    ) -> Option<(EntityIndex2d<WIDTH, HEIGHT, SCALE>, &'a E, f32)> {
+        let length = start.distance(end);
+        let direction = (end - start).try_normalize().unwrap_or(Vec2::X);
+        Self::ray_cast(start, direction, length, max_radius, get_entities, collider)
+    }
 }
 
 impl<const WIDTH: usize, const HEIGHT: usize, const SCALE: u16> ActorId
@@ -568,4 +822,13 @@ impl<const WIDTH: usize, const HEIGHT: usize, const SCALE: u16> ActorId
     // TODO SparseChunkMap
     type Map<T> = SortedVecMap<Self, T>;
     type SparseMap<T> = BTreeMap<Self, T>;
+
+    fn in_view<T>(
+        map: &Self::DenseMap<T>,
+        viewpoint: SectorViewpoint2d,
+    ) -> impl Iterator<Item = Self> + '_ {
+        viewpoint
+            .iter()
+            .filter(move |&sector_id| Map::contains(map, sector_id))
+    }
 }
@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::{Efficient, Map, OrdIter, SortedVecMap, Sparse, Wrapper};
+use super::{Efficient, Map, OrdIter, SectorViewpoint2d, SortedVecMap, Sparse, Wrapper};
 use crate::bitcode::{self, *};
 use crate::{ArenaMap, CompatHasher, PlayerId, TeamId};
 use serde::{Deserialize, Serialize};
@@ -29,6 +29,16 @@ pub trait ActorId: Copy {
     /// - allocates memory proportional to its len
     /// E.g. a [`HashMap`][`std::collections::HashMap`].
     type SparseMap<T>: Map<Self, T> + Efficient + OrdIter + Sparse;
+
+    /// Ids of actors in `map` that a client sees from `viewpoint`, in addition to its
+    /// `Visibility`. Only [`SectorId2d`][`crate::SectorId2d`]s are seen this way.
+    fn in_view<T>(
+        map: &Self::DenseMap<T>,
+        viewpoint: SectorViewpoint2d,
+    ) -> impl Iterator<Item = Self> + '_ {
+        let _ = (map, viewpoint);
+        std::iter::empty()
+    }
 }
 
 impl ActorId for PlayerId {
@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
 use crate::bitcode::{self, *};
-use crate::{ArenaKey, ArenaMap};
+use crate::{ArenaKey, ArenaMap, GenerationalArenaMap, GenerationalKey};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
 use std::fmt::{Debug, Formatter};
@@ -556,3 +556,64 @@ impl<K: ArenaKey, V> Map<K, V> for ArenaMap<K, V> {
 
 impl<K, V> OrdIter for ArenaMap<K, V> {}
 impl<K, V> Efficient for ArenaMap<K, V> {}
+
+// GenerationalArenaMap
+impl<K: ArenaKey, V> FromIterator<(GenerationalKey<K>, V)> for GenerationalArenaMap<K, V> {
+    fn from_iter<T: IntoIterator<Item = (GenerationalKey<K>, V)>>(iter: T) -> Self {
+        let mut ret = Self::default();
+        for (k, v) in iter {
+            ret.insert_with_key(k, v);
+        }
+        ret
+    }
+}
+
+impl<K: ArenaKey, V> Map<GenerationalKey<K>, V> for GenerationalArenaMap<K, V> {
+    type Iter<'a> = impl Iterator<Item = (GenerationalKey<K>, &'a V)> where K: 'a, V: 'a;
+    type IterMut<'a> = impl Iterator<Item = (GenerationalKey<K>, &'a mut V)> where K: 'a, V: 'a;
+
+    fn get(&self, k: GenerationalKey<K>) -> Option<&V> {
+        GenerationalArenaMap::get(self, k)
+    }
+
+    fn get_mut(&mut self, k: GenerationalKey<K>) -> Option<&mut V> {
+        GenerationalArenaMap::get_mut(self, k)
+    }
+
+    fn insert(&mut self, k: GenerationalKey<K>, v: V) -> Option<V> {
+        GenerationalArenaMap::insert_with_key(self, k, v)
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        GenerationalArenaMap::iter(self)
+    }
+
+    fn iter_mut(&mut self) -> Self::IterMut<'_> {
+        GenerationalArenaMap::iter_mut(self)
+    }
+
+    fn len(&self) -> usize {
+        GenerationalArenaMap::len(self)
+    }
+
+    fn or_default(&mut self, k: GenerationalKey<K>) -> &mut V
+    where
+        V: Default,
+    {
+        if !self.contains(k) {
+            self.insert_with_key(k, V::default());
+        }
+        &mut self[k]
+    }
+
+    fn remove(&mut self, k: GenerationalKey<K>) -> Option<V> {
+        GenerationalArenaMap::remove(self, k)
+    }
+
+    fn retain(&mut self, f: impl FnMut(GenerationalKey<K>, &mut V) -> bool) {
+        GenerationalArenaMap::retain(self, f)
+    }
+}
+
+impl<K, V> OrdIter for GenerationalArenaMap<K, V> {}
+impl<K, V> Efficient for GenerationalArenaMap<K, V> {}
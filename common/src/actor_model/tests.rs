@@ -477,3 +477,270 @@ mod tests2 {
         }
     }
 }
+
+#[cfg(test)]
+mod sector_tests {
+    use crate::actor_model::*;
+    use crate::{define_actor_state, define_events, define_world};
+    use glam::Vec2;
+    use std::collections::BTreeSet;
+
+    type TileId = SectorId2d<16, 16, 10>;
+
+    #[derive(Clone, Debug, Hash)]
+    pub struct Tile(u8);
+
+    impl Actor for Tile {
+        type Id = TileId;
+
+        const KEEPALIVE: u8 = 2;
+    }
+
+    #[derive(Clone, Debug)]
+    pub enum TileInput {}
+
+    impl Message for TileInput {}
+
+    define_events!(Tile, Server, TileInput);
+    define_actor_state!(Tile, Server);
+    define_world!(u32, Tile);
+
+    impl<C> WorldTick<C> for World {
+        fn tick_before_inputs(&mut self, _: &mut C) {}
+
+        fn tick_client(&mut self, _: &mut C) {}
+    }
+
+    #[test]
+    fn viewpoint() {
+        let mut server = World::default();
+        for id in TileId::iter(TileId::new(0, 0), TileId::new(15, 15)) {
+            // Leave some sectors empty.
+            if (id.x + id.y) % 3 != 0 {
+                Map::insert(&mut server.tile, id, Tile(id.x ^ id.y).into());
+            }
+        }
+
+        let mut knowledge = Knowledge::default();
+        let mut client = World::default();
+        let visible = |server: &World, viewpoint: SectorViewpoint2d| -> BTreeSet<TileId> {
+            viewpoint
+                .iter()
+                .filter(|&id| Map::contains(&server.tile, id))
+                .collect()
+        };
+        let known = |client: &World| -> BTreeSet<TileId> {
+            Map::iter(&client.tile).map(|(id, _)| id).collect()
+        };
+
+        for i in 0..40 {
+            let viewpoint = SectorViewpoint2d::new(Vec2::new(i as f32 * 2.0 - 40.0, 5.0), 25.0);
+            knowledge.viewpoint = Some(viewpoint);
+            let update = server.get_update(&mut knowledge, Visibility { tile: |_: &_| None });
+            server.post_update();
+            client.apply_owned(update, &mut ());
+
+            let known = known(&client);
+            assert!(known.is_superset(&visible(&server, viewpoint)));
+            assert!(known.len() < Map::len(&server.tile));
+        }
+
+        // Sectors out of view expire after the keepalive.
+        let viewpoint = SectorViewpoint2d::new(Vec2::splat(70.0), 15.0);
+        knowledge.viewpoint = Some(viewpoint);
+        for _ in 0..=Tile::KEEPALIVE + 1 {
+            let update = server.get_update(&mut knowledge, Visibility { tile: |_: &_| None });
+            server.post_update();
+            client.apply_owned(update, &mut ());
+        }
+        assert_eq!(known(&client), visible(&server, viewpoint));
+        assert!(!known(&client).is_empty());
+
+        // Without a viewpoint, only `Visibility` applies.
+        knowledge.viewpoint = None;
+        for _ in 0..=Tile::KEEPALIVE + 1 {
+            let update = server.get_update(
+                &mut knowledge,
+                Visibility {
+                    tile: |_: &_| Some(TileId::new(0, 1)),
+                },
+            );
+            server.post_update();
+            client.apply_owned(update, &mut ());
+        }
+        assert_eq!(known(&client), BTreeSet::from([TileId::new(0, 1)]));
+    }
+}
+
+#[cfg(test)]
+mod entities_2d_tests {
+    use crate::{
+        Aabb2d, Angle, Circle, Collider2d, Entities2d, Entity2d, EntityIndex2d, RotatedRectangle,
+        SectorArray2d,
+    };
+    use glam::Vec2;
+    use rand::prelude::*;
+    use rand_chacha::ChaCha20Rng;
+
+    const WIDTH: usize = 16;
+    const HEIGHT: usize = 12;
+    const SCALE: u16 = 100;
+    const MAX_RADIUS: f32 = 150.0;
+
+    type Index = EntityIndex2d<WIDTH, HEIGHT, SCALE>;
+    type Sectors = SectorArray2d<Entities2d<Thing, WIDTH, HEIGHT, SCALE>, WIDTH, HEIGHT, SCALE>;
+
+    struct Thing {
+        collider: Collider2d,
+    }
+
+    impl Entity2d for Thing {
+        fn position(&self) -> Vec2 {
+            self.collider.center()
+        }
+    }
+
+    fn random_point(rng: &mut impl Rng, margin: f32) -> Vec2 {
+        let half = Vec2::new(WIDTH as f32, HEIGHT as f32) * SCALE as f32 * 0.5 + margin;
+        Vec2::new(
+            rng.gen_range(-half.x..half.x),
+            rng.gen_range(-half.y..half.y),
+        )
+    }
+
+    fn random_sectors(rng: &mut impl Rng, n: usize) -> Sectors {
+        let mut sectors = Sectors::default();
+        for _ in 0..n {
+            let center = random_point(rng, -1.0);
+            let collider = if rng.gen() {
+                Collider2d::Circle(Circle::new(center, rng.gen_range(1.0..MAX_RADIUS)))
+            } else {
+                let size = Vec2::new(rng.gen_range(1.0..200.0), rng.gen_range(1.0..200.0));
+                let rectangle = RotatedRectangle::new(center, size, Angle(rng.gen()));
+                Collider2d::RotatedRectangle(rectangle)
+            };
+            let thing = Thing { collider };
+            let sector_id = thing.sector_id().unwrap();
+            sectors.get_mut(sector_id).unwrap().push(thing).unwrap();
+        }
+        sectors
+    }
+
+    fn all(sectors: &Sectors) -> Vec<(Index, &Thing)> {
+        sectors
+            .iter()
+            .flat_map(|(sector_id, entities)| {
+                entities
+                    .iter()
+                    .map(move |(index, thing)| (Index::new(sector_id, index), thing))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn iter_aabb() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let sectors = random_sectors(&mut rng, 1000);
+        for _ in 0..200 {
+            let a = random_point(&mut rng, 200.0);
+            let b = a + Vec2::new(rng.gen_range(0.0..500.0), rng.gen_range(0.0..500.0));
+            let aabb = Aabb2d::new(a, b);
+            let mut actual: Vec<_> = Entities2d::iter_aabb(aabb, |id| sectors.get(id))
+                .map(|(index, _)| index)
+                .collect();
+            actual.sort();
+            let expected: Vec<_> = all(&sectors)
+                .into_iter()
+                .filter(|(_, thing)| aabb.contains_point(thing.position()))
+                .map(|(index, _)| index)
+                .collect();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn nearest() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        for n in [0, 10, 1000] {
+            let sectors = random_sectors(&mut rng, n);
+            for _ in 0..200 {
+                let center = random_point(&mut rng, 300.0);
+                let k = rng.gen_range(0..10);
+                let max_distance = rng.gen_range(0.0..2000.0);
+                let parity = rng.gen_range(0..2);
+                let filter = |index: Index, _: &Thing| index.index % 2 == parity;
+                let actual =
+                    Entities2d::nearest(center, k, max_distance, |id| sectors.get(id), filter);
+                let mut expected: Vec<_> = all(&sectors)
+                    .into_iter()
+                    .filter(|&(index, thing)| {
+                        filter(index, thing) && thing.position().distance(center) <= max_distance
+                    })
+                    .collect();
+                expected.sort_by(|(i1, t1), (i2, t2)| {
+                    let d1 = t1.position().distance_squared(center);
+                    let d2 = t2.position().distance_squared(center);
+                    d1.total_cmp(&d2).then(i1.cmp(i2))
+                });
+                expected.truncate(k);
+                assert_eq!(
+                    actual.iter().map(|(index, _)| index).collect::<Vec<_>>(),
+                    expected.iter().map(|(index, _)| index).collect::<Vec<_>>(),
+                    "{center} {k} {max_distance}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ray_cast() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let mut hits = 0;
+        for n in [0, 10, 1000] {
+            let sectors = random_sectors(&mut rng, n);
+            for _ in 0..300 {
+                let origin = random_point(&mut rng, 500.0);
+                let direction = Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU));
+                let max_distance = rng.gen_range(0.0..3000.0);
+                let collider =
+                    |index: Index, thing: &Thing| (index.index % 3 > 0).then_some(thing.collider);
+                let actual = Entities2d::ray_cast(
+                    origin,
+                    direction,
+                    max_distance,
+                    MAX_RADIUS,
+                    |id| sectors.get(id),
+                    collider,
+                );
+                let expected = all(&sectors)
+                    .into_iter()
+                    .filter_map(|(index, thing)| {
+                        let distance = collider(index, thing)?.ray_distance(
+                            origin,
+                            direction,
+                            max_distance,
+                        )?;
+                        Some((index, distance))
+                    })
+                    .min_by(|(i1, d1), (i2, d2)| d1.total_cmp(d2).then(i1.cmp(i2)));
+                let actual = actual.map(|(index, _, distance)| (index, distance));
+                assert_eq!(actual, expected, "{origin} {direction} {max_distance}");
+                hits += expected.is_some() as usize;
+
+                let end = origin + direction * max_distance;
+                let segment = Entities2d::segment_cast(
+                    origin,
+                    end,
+                    MAX_RADIUS,
+                    |id| sectors.get(id),
+                    collider,
+                );
+                assert_eq!(
+                    segment.map(|(index, _, _)| index),
+                    actual.map(|(index, _)| index)
+                );
+            }
+        }
+        assert!(hits > 100, "{hits}");
+    }
+}
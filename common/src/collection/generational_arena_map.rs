@@ -0,0 +1,266 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use crate::bitcode::{self, *};
+use crate::ArenaKey;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt::{Debug, Formatter};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut};
+
+/// An [`ArenaKey`] plus the generation of its slot. Unlike a plain [`ArenaKey`], it won't refer
+/// to a value inserted after its own value was removed.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct GenerationalKey<K> {
+    pub key: K,
+    pub generation: u32,
+}
+
+impl<K: ArenaKey> GenerationalKey<K> {
+    pub fn new(key: K, generation: u32) -> Self {
+        Self { key, generation }
+    }
+
+    fn index(&self) -> usize {
+        self.key.to_index()
+    }
+}
+
+// Ordered by index, as [`GenerationalArenaMap`] iterates, to satisfy [`OrdIter`].
+impl<K: ArenaKey + Eq> Ord for GenerationalKey<K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.index()
+            .cmp(&other.index())
+            .then_with(|| self.generation.cmp(&other.generation))
+    }
+}
+
+impl<K: ArenaKey + Eq> PartialOrd for GenerationalKey<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Clone, Hash, Serialize, Deserialize, Encode, Decode)]
+struct Slot<V> {
+    /// Incremented each time the slot's value is removed.
+    generation: u32,
+    value: Option<V>,
+}
+
+/// Like [`ArenaMap`][`crate::ArenaMap`], but allocates its own keys and checks their
+/// generation, so stale keys can be kept in game state without referring to new values.
+#[derive(Clone, Hash, Serialize, Deserialize, Encode, Decode)]
+pub struct GenerationalArenaMap<K, V> {
+    /// Invariant: Slots are never removed, to remember their generation.
+    slots: Vec<Slot<V>>,
+    /// Indices of vacant slots, reused last in first out. May contain indices that aren't vacant
+    /// (or don't exist) if decoded from untrusted data, which are skipped.
+    free: Vec<u32>,
+    len: usize,
+    _spooky: PhantomData<K>,
+}
+
+impl<K: ArenaKey + Debug, V: Debug> Debug for GenerationalArenaMap<K, V> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// Can't derive since it would bound K + V: Default.
+impl<K, V> Default for GenerationalArenaMap<K, V> {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+            _spooky: PhantomData,
+        }
+    }
+}
+
+impl<K: ArenaKey, V> GenerationalArenaMap<K, V> {
+    /// Same as `Self::default()`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of items in the map.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true iff the map is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Test if the key exists in the map (with the same generation).
+    pub fn contains(&self, key: GenerationalKey<K>) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Gets a value, unless `key` is stale.
+    pub fn get(&self, key: GenerationalKey<K>) -> Option<&V> {
+        self.slots
+            .get(key.index())
+            .filter(|slot| slot.generation == key.generation)
+            .and_then(|slot| slot.value.as_ref())
+    }
+
+    /// Gets a value mutably, unless `key` is stale.
+    pub fn get_mut(&mut self, key: GenerationalKey<K>) -> Option<&mut V> {
+        self.slots
+            .get_mut(key.index())
+            .filter(|slot| slot.generation == key.generation)
+            .and_then(|slot| slot.value.as_mut())
+    }
+
+    /// Inserts a value with a new key, reusing the most recently vacated slot, if any.
+    pub fn insert(&mut self, value: V) -> GenerationalKey<K> {
+        let index = loop {
+            match self.free.pop() {
+                Some(index) => {
+                    let index = index as usize;
+                    if self.slots.get(index).is_some_and(|s| s.value.is_none()) {
+                        break index;
+                    }
+                }
+                None => {
+                    self.slots.push(Slot {
+                        generation: 0,
+                        value: None,
+                    });
+                    break self.slots.len() - 1;
+                }
+            }
+        };
+        let slot = &mut self.slots[index];
+        slot.value = Some(value);
+        self.len += 1;
+        GenerationalKey::new(K::from_index(index), slot.generation)
+    }
+
+    /// Inserts a `value` with a `key` allocated elsewhere, e.g. by the server, returning the old
+    /// value of the same generation. Invalidates any other generation of the same key. O(n) in
+    /// the number of vacant slots.
+    pub fn insert_with_key(&mut self, key: GenerationalKey<K>, value: V) -> Option<V> {
+        let index = key.index();
+        if index >= self.slots.len() {
+            self.free
+                .extend((self.slots.len()..index).map(|i| i as u32));
+            self.slots.resize_with(index + 1, || Slot {
+                generation: 0,
+                value: None,
+            });
+        } else if self.slots[index].value.is_none() {
+            self.free.retain(|&i| i as usize != index);
+        }
+
+        let slot = &mut self.slots[index];
+        let old = if slot.generation == key.generation {
+            slot.value.take()
+        } else {
+            slot.generation = key.generation;
+            None
+        };
+        if slot.value.replace(value).is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Removes a `key` from the map, invalidating it.
+    pub fn remove(&mut self, key: GenerationalKey<K>) -> Option<V> {
+        let index = key.index();
+        let slot = self
+            .slots
+            .get_mut(index)
+            .filter(|slot| slot.generation == key.generation)?;
+        let old = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(index as u32);
+        self.len = self.len.saturating_sub(1);
+        Some(old)
+    }
+
+    /// Like `HashMap` retain.
+    pub fn retain<F: FnMut(GenerationalKey<K>, &mut V) -> bool>(&mut self, mut f: F) {
+        for (i, slot) in self.slots.iter_mut().enumerate() {
+            if let Some(v) = &mut slot.value
+                && !f(GenerationalKey::new(K::from_index(i), slot.generation), v)
+            {
+                slot.value = None;
+                slot.generation = slot.generation.wrapping_add(1);
+                self.free.push(i as u32);
+                self.len = self.len.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Iterates key value pairs, in order of index.
+    pub fn iter(&self) -> impl Iterator<Item = (GenerationalKey<K>, &V)> {
+        self.slots.iter().enumerate().filter_map(|(i, slot)| {
+            slot.value
+                .as_ref()
+                .map(|v| (GenerationalKey::new(K::from_index(i), slot.generation), v))
+        })
+    }
+
+    /// Iterates keys and values mutably, in order of index.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (GenerationalKey<K>, &mut V)> {
+        self.slots.iter_mut().enumerate().filter_map(|(i, slot)| {
+            let generation = slot.generation;
+            slot.value
+                .as_mut()
+                .map(|v| (GenerationalKey::new(K::from_index(i), generation), v))
+        })
+    }
+
+    /// Iterates only keys.
+    pub fn keys(&self) -> impl Iterator<Item = GenerationalKey<K>> + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    /// Iterates only values.
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.slots.iter().filter_map(|slot| slot.value.as_ref())
+    }
+
+    /// Iterates only values mutably.
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> + '_ {
+        self.slots.iter_mut().filter_map(|slot| slot.value.as_mut())
+    }
+}
+
+impl<K: ArenaKey, V> Index<GenerationalKey<K>> for GenerationalArenaMap<K, V> {
+    type Output = V;
+
+    fn index(&self, k: GenerationalKey<K>) -> &Self::Output {
+        self.get(k).unwrap()
+    }
+}
+
+impl<K: ArenaKey, V> IndexMut<GenerationalKey<K>> for GenerationalArenaMap<K, V> {
+    fn index_mut(&mut self, k: GenerationalKey<K>) -> &mut Self::Output {
+        self.get_mut(k).unwrap()
+    }
+}
+
+impl<K: ArenaKey, V> IntoIterator for GenerationalArenaMap<K, V> {
+    type Item = (GenerationalKey<K>, V);
+
+    type IntoIter = impl Iterator<Item = Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.slots.into_iter().enumerate().filter_map(|(i, slot)| {
+            slot.value
+                .map(|v| (GenerationalKey::new(K::from_index(i), slot.generation), v))
+        })
+    }
+}
@@ -1,7 +1,11 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use glam::{uvec2, UVec2};
+use crate::bitcode::{self, *};
+use arrayvec::ArrayVec;
+use fxhash::{FxHashMap, FxHashSet};
+use glam::{ivec2, uvec2, IVec2, UVec2};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::Formatter;
 
@@ -110,11 +114,317 @@ impl Mask2d {
         me
     }
 
+    /// Returns the dimensions given to [`new`][`Self::new`].
+    pub fn dims(&self) -> UVec2 {
+        uvec2(self.original_x, self.dims.y)
+    }
+
+    /// Returns true if the [`Mask2d`] contains `pos`.
+    ///
+    /// **Panics**
+    ///
+    /// If `pos` is >= [`dims`][`Self::dims`].
+    #[inline]
+    pub fn get(&self, pos: UVec2) -> bool {
+        assert!(pos.cmplt(self.dims()).all());
+        index_2d(&self.mask, self.dims, pos.x, pos.y)
+    }
+
     #[inline]
     fn set(&mut self, pos: UVec2) {
         set_2d_mut(&mut self.mask, self.dims, pos.x, pos.y, true);
     }
 
+    /// Returns an iterator over the contained cells in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = UVec2> + '_ {
+        let dims = self.dims;
+        (0..dims.y).flat_map(move |y| {
+            let row = index_1d(&self.mask, dims, y);
+            let mut x = 0;
+            std::iter::from_fn(move || {
+                let found =
+                    first_one_starting_at(row, x).filter(|&found| found < self.original_x)?;
+                x = found + 1;
+                Some(uvec2(found, y))
+            })
+        })
+    }
+
+    /// Returns true if `pos` is in bounds and contained.
+    fn contains(&self, pos: IVec2) -> bool {
+        pos.cmpge(IVec2::ZERO).all()
+            && pos.as_uvec2().cmplt(self.dims()).all()
+            && self.get(pos.as_uvec2())
+    }
+
+    /// Clears the bits past `original_x`, which some constructors set.
+    fn clear_padding(&mut self) {
+        let dims = self.dims;
+        if self.original_x == dims.x {
+            return;
+        }
+        for y in 0..dims.y {
+            clear_bit_range(
+                index_1d_mut(&mut self.mask, dims, y),
+                self.original_x,
+                dims.x,
+            );
+        }
+    }
+
+    /// Returns a [`Mask2d`] that also contains every cell within `radius` cells (including
+    /// diagonally) of a contained cell.
+    pub fn dilate(&self, radius: u32) -> Self {
+        self.morph(radius, true)
+    }
+
+    /// Returns a [`Mask2d`] that only contains cells with every cell within `radius` cells
+    /// (including diagonally) contained. Cells out of bounds aren't contained.
+    pub fn erode(&self, radius: u32) -> Self {
+        self.morph(radius, false)
+    }
+
+    /// Erodes and then dilates, removing features smaller than the kernel.
+    pub fn open(&self, radius: u32) -> Self {
+        self.erode(radius).dilate(radius)
+    }
+
+    /// Dilates and then erodes, filling gaps smaller than the kernel. Unlike [`erode`][`Self::erode`],
+    /// the erosion treats cells out of bounds as contained, so that nothing is removed.
+    pub fn close(&self, radius: u32) -> Self {
+        self.dilate(radius).complement().dilate(radius).complement()
+    }
+
+    /// Returns a [`Mask2d`] that contains the cells this doesn't.
+    fn complement(&self) -> Self {
+        let mut complement = self.clone();
+        for v in complement.mask.iter_mut() {
+            *v = !*v;
+        }
+        complement.clear_padding();
+        complement
+    }
+
+    /// Combines shifted rows and then shifted columns with a square kernel, taking their union
+    /// to dilate or intersection to erode.
+    fn morph(&self, radius: u32, union: bool) -> Self {
+        let combine = |a: V, b: V| if union { a | b } else { a & b };
+        let dims = self.dims;
+        let mut source = self.clone();
+        source.clear_padding();
+        if radius == 0 {
+            return source;
+        }
+
+        let mut horizontal = source.clone();
+        for y in 0..dims.y {
+            let row = index_1d(&source.mask, dims, y);
+            let out = index_1d_mut(&mut horizontal.mask, dims, y);
+            for shift in 1..=radius as i64 {
+                for (i, v) in out.iter_mut().enumerate() {
+                    let shifted =
+                        combine(shifted_word(row, i, shift), shifted_word(row, i, -shift));
+                    *v = combine(*v, shifted);
+                }
+            }
+        }
+        horizontal.clear_padding();
+
+        let mut vertical = horizontal.clone();
+        for y in 0..dims.y {
+            let out = index_1d_mut(&mut vertical.mask, dims, y);
+            for offset in 1..=radius {
+                for other in [
+                    y.checked_sub(offset),
+                    Some(y + offset).filter(|&y| y < dims.y),
+                ] {
+                    if let Some(other) = other {
+                        let row = index_1d(&horizontal.mask, dims, other);
+                        for (v, &o) in out.iter_mut().zip(row) {
+                            *v = combine(*v, o);
+                        }
+                    } else if !union {
+                        out.fill(0);
+                    }
+                }
+            }
+        }
+        vertical
+    }
+
+    /// Returns the cells connected to `start` (including `start`) that are the same as it
+    /// (contained or not), moving orthogonally or also `diagonal`ly.
+    ///
+    /// **Panics**
+    ///
+    /// If `start` is >= [`dims`][`Self::dims`].
+    pub fn flood_fill(&self, start: UVec2, diagonal: bool) -> Self {
+        let value = self.get(start);
+        let mut filled = Self::empty(self.dims());
+        filled.set(start);
+        let mut stack = vec![start];
+        while let Some(pos) = stack.pop() {
+            for neighbor in neighbors(pos, self.dims(), diagonal) {
+                if self.get(neighbor) == value && !filled.get(neighbor) {
+                    filled.set(neighbor);
+                    stack.push(neighbor);
+                }
+            }
+        }
+        filled
+    }
+
+    /// Labels the groups of contained cells connected orthogonally or also `diagonal`ly.
+    /// Components are numbered in row-major order of their first cell.
+    pub fn components(&self, diagonal: bool) -> MaskComponents2d {
+        let dims = self.dims();
+        let mut labels = vec![NO_COMPONENT; (dims.x * dims.y) as usize].into_boxed_slice();
+        let mut sizes = Vec::new();
+        let mut stack = Vec::new();
+        for start in self.iter() {
+            let index = (start.y * dims.x + start.x) as usize;
+            if labels[index] != NO_COMPONENT {
+                continue;
+            }
+            let label = sizes.len() as u32;
+            labels[index] = label;
+            let mut size = 1;
+            stack.push(start);
+            while let Some(pos) = stack.pop() {
+                for neighbor in neighbors(pos, dims, diagonal) {
+                    let index = (neighbor.y * dims.x + neighbor.x) as usize;
+                    if labels[index] == NO_COMPONENT && self.get(neighbor) {
+                        labels[index] = label;
+                        size += 1;
+                        stack.push(neighbor);
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+        MaskComponents2d {
+            dims,
+            labels,
+            sizes,
+        }
+    }
+
+    /// Traces the edges between contained and not contained cells into closed polygons,
+    /// without repeating the first vertex. Vertices are on cell corners, i.e. cell `(x, y)` spans
+    /// from `(x, y)` to `(x + 1, y + 1)`, and only where the boundary turns. Outer boundaries are
+    /// counter-clockwise and holes are clockwise (if y is up). Cells that only touch diagonally
+    /// get separate boundaries.
+    pub fn boundaries(&self) -> Vec<Vec<UVec2>> {
+        // Directed unit edges with the contained cell on the left.
+        let mut edges = FxHashMap::<UVec2, ArrayVec<UVec2, 2>>::default();
+        let mut starts = Vec::new();
+        for pos in self.iter() {
+            let corners = [pos, pos + UVec2::X, pos + UVec2::ONE, pos + UVec2::Y];
+            for (i, direction) in [IVec2::NEG_Y, IVec2::X, IVec2::Y, IVec2::NEG_X]
+                .into_iter()
+                .enumerate()
+            {
+                if !self.contains(pos.as_ivec2() + direction) {
+                    let (from, to) = (corners[i], corners[(i + 1) % 4]);
+                    edges.entry(from).or_default().push(to);
+                    starts.push((from, to));
+                }
+            }
+        }
+
+        let mut used = FxHashSet::default();
+        let mut boundaries = Vec::new();
+        for start in starts {
+            if !used.insert(start) {
+                continue;
+            }
+            let mut vertices = vec![start.0];
+            let (mut from, mut to) = start;
+            loop {
+                // Prefer turning left, which keeps diagonally touching cells separate.
+                let direction = to.as_ivec2() - from.as_ivec2();
+                let next = [direction.perp(), direction, -direction.perp()]
+                    .into_iter()
+                    .map(|turn| (to.as_ivec2() + turn).as_uvec2())
+                    .find(|next| edges[&to].contains(next))
+                    .unwrap();
+                if (to, next) == start {
+                    break;
+                }
+                used.insert((to, next));
+                vertices.push(to);
+                (from, to) = (to, next);
+            }
+
+            // Remove vertices in the middle of straight lines.
+            let n = vertices.len();
+            let corners = (0..n)
+                .filter(|&i| {
+                    let previous = vertices[(i + n - 1) % n].as_ivec2();
+                    let next = vertices[(i + 1) % n].as_ivec2();
+                    let vertex = vertices[i].as_ivec2();
+                    vertex - previous != next - vertex
+                })
+                .map(|i| vertices[i])
+                .collect();
+            boundaries.push(corners);
+        }
+        boundaries
+    }
+
+    /// Returns the distance from the center of each cell to the center of the nearest cell that
+    /// is contained, minus the distance to the nearest cell that isn't, in row-major order. In
+    /// other words, negative inside and positive outside. Infinite if there are no such cells.
+    pub fn signed_distance_field(&self) -> Vec<f32> {
+        let outside = self.squared_distances(true);
+        let inside = self.squared_distances(false);
+        let distance = |squared: f64| {
+            if squared >= FAR {
+                f32::INFINITY
+            } else {
+                squared.sqrt() as f32
+            }
+        };
+        outside
+            .into_iter()
+            .zip(inside)
+            .map(|(outside, inside)| distance(outside) - distance(inside))
+            .collect()
+    }
+
+    /// Returns the squared distance from each cell to the nearest cell that is contained (or
+    /// isn't), or at least [`FAR`] if there isn't one. See:
+    /// https://cs.brown.edu/people/pfelzens/papers/dt-final.pdf
+    fn squared_distances(&self, contained: bool) -> Vec<f64> {
+        let dims = self.dims();
+        let (width, height) = (dims.x as usize, dims.y as usize);
+        let mut distances: Vec<f64> = (0..dims.y)
+            .flat_map(|y| (0..dims.x).map(move |x| uvec2(x, y)))
+            .map(|pos| if self.get(pos) == contained { 0.0 } else { FAR })
+            .collect();
+
+        let n = width.max(height);
+        let mut line = vec![0.0; n];
+        let mut out = vec![0.0; n];
+        let mut parabolas = vec![0; n];
+        let mut boundaries = vec![0.0; n + 1];
+        for x in 0..width {
+            for y in 0..height {
+                line[y] = distances[y * width + x];
+            }
+            distance_transform_1d(&line[..height], &mut out, &mut parabolas, &mut boundaries);
+            for y in 0..height {
+                distances[y * width + x] = out[y];
+            }
+        }
+        for row in distances.chunks_exact_mut(width.max(1)) {
+            line[..width].copy_from_slice(row);
+            distance_transform_1d(&line[..width], &mut out, &mut parabolas, &mut boundaries);
+            row.copy_from_slice(&out[..width]);
+        }
+        distances
+    }
+
     /// Like [`take_rects`][`Self::take_rects`] but more efficient since it doesn't have to
     /// allocate. Clears the [`Mask`].
     /// TODO find a way to efficiently implement an iterator version of this.
@@ -217,6 +527,143 @@ impl Mask2d {
     }
 }
 
+/// The value of [`MaskComponents2d::labels`] for cells that aren't contained.
+const NO_COMPONENT: u32 = u32::MAX;
+
+/// The connected components of a [`Mask2d`], from [`Mask2d::components`].
+#[derive(Clone, Debug)]
+pub struct MaskComponents2d {
+    dims: UVec2,
+    /// Component of each cell, or [`NO_COMPONENT`].
+    labels: Box<[u32]>,
+    /// Number of cells in each component.
+    sizes: Vec<u32>,
+}
+
+impl MaskComponents2d {
+    /// Returns the number of components.
+    pub fn len(&self) -> usize {
+        self.sizes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sizes.is_empty()
+    }
+
+    /// Returns the component containing `pos`, or `None` if it isn't contained.
+    ///
+    /// **Panics**
+    ///
+    /// If `pos` is >= the dimensions of the [`Mask2d`].
+    pub fn get(&self, pos: UVec2) -> Option<usize> {
+        assert!(pos.cmplt(self.dims).all());
+        let label = self.labels[(pos.y * self.dims.x + pos.x) as usize];
+        (label != NO_COMPONENT).then_some(label as usize)
+    }
+
+    /// Returns the number of cells in each component.
+    pub fn sizes(&self) -> &[u32] {
+        &self.sizes
+    }
+
+    /// Returns a [`Mask2d`] containing only the cells of `component`.
+    pub fn mask(&self, component: usize) -> Mask2d {
+        let dims = self.dims;
+        Mask2d::new(
+            (0..dims.y)
+                .flat_map(|y| (0..dims.x).map(move |x| uvec2(x, y)))
+                .filter(|&pos| self.labels[(pos.y * dims.x + pos.x) as usize] == component as u32),
+            dims,
+        )
+    }
+}
+
+/// A compact, serializable form of a [`Mask2d`]: the lengths of alternating runs of cells that
+/// aren't and are contained, in row-major order, starting with cells that aren't.
+#[derive(Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct RunLengthMask2d {
+    width: u32,
+    height: u32,
+    runs: Vec<u32>,
+}
+
+impl RunLengthMask2d {
+    /// Maximum width and height that can be converted back into a [`Mask2d`], to bound the
+    /// allocation for untrusted input.
+    pub const MAX_DIM: u32 = 4096;
+
+    pub fn dims(&self) -> UVec2 {
+        uvec2(self.width, self.height)
+    }
+
+    pub fn runs(&self) -> &[u32] {
+        &self.runs
+    }
+}
+
+impl From<&Mask2d> for RunLengthMask2d {
+    fn from(mask: &Mask2d) -> Self {
+        let width = mask.original_x;
+        let mut runs = Vec::new();
+        let mut run = 0;
+        let mut contained = false;
+        for y in 0..mask.dims.y {
+            let row = index_1d(&mask.mask, mask.dims, y);
+            let mut x = 0;
+            while x < width {
+                let end = first_bit_starting_at(row, x, !contained)
+                    .unwrap_or(width)
+                    .min(width);
+                run += end - x;
+                if end < width {
+                    runs.push(run);
+                    run = 0;
+                    contained = !contained;
+                }
+                x = end;
+            }
+        }
+        if width * mask.dims.y != 0 {
+            runs.push(run);
+        }
+        Self {
+            width,
+            height: mask.dims.y,
+            runs,
+        }
+    }
+}
+
+impl TryFrom<&RunLengthMask2d> for Mask2d {
+    type Error = &'static str;
+
+    fn try_from(encoded: &RunLengthMask2d) -> Result<Self, Self::Error> {
+        if encoded
+            .dims()
+            .cmpgt(UVec2::splat(RunLengthMask2d::MAX_DIM))
+            .any()
+        {
+            return Err("dims too large");
+        }
+        let total = encoded.width as u64 * encoded.height as u64;
+        if encoded.runs.iter().map(|&run| run as u64).sum::<u64>() != total {
+            return Err("runs don't match dims");
+        }
+        let mut me = Self::empty(encoded.dims());
+        let mut start = 0;
+        for (i, &run) in encoded.runs.iter().enumerate() {
+            let end = start + run;
+            if i % 2 == 1 {
+                for index in start..end {
+                    me.set(uvec2(index % encoded.width, index / encoded.width));
+                }
+            }
+            start = end;
+        }
+        Ok(me)
+    }
+}
+
 impl fmt::Debug for Mask2d {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         assert_eq!(self.mask.len() as u32 * V::BITS, self.dims.y * self.dims.x);
@@ -265,6 +712,92 @@ fn set_2d_mut(m: &mut [V], dims: UVec2, x: u32, y: u32, v: bool) {
     }
 }
 
+/// Returns the in bounds neighbors of `pos`, orthogonal and optionally `diagonal`.
+fn neighbors(pos: UVec2, dims: UVec2, diagonal: bool) -> impl Iterator<Item = UVec2> {
+    const ORTHOGONAL: [IVec2; 4] = [IVec2::X, IVec2::Y, IVec2::NEG_X, IVec2::NEG_Y];
+    const DIAGONAL: [IVec2; 4] = [ivec2(1, 1), ivec2(-1, 1), ivec2(-1, -1), ivec2(1, -1)];
+    let diagonals = if diagonal { &DIAGONAL[..] } else { &[] };
+    ORTHOGONAL
+        .iter()
+        .chain(diagonals)
+        .map(move |&offset| pos.as_ivec2() + offset)
+        .filter(move |p| p.cmpge(IVec2::ZERO).all() && p.as_uvec2().cmplt(dims).all())
+        .map(|p| p.as_uvec2())
+}
+
+/// Returns word `i` of `row` shifted `shift` bits towards higher x (or lower x if negative),
+/// shifting in zeros.
+fn shifted_word(row: &[V], i: usize, shift: i64) -> V {
+    let get = |j: i64| {
+        usize::try_from(j)
+            .ok()
+            .and_then(|j| row.get(j))
+            .copied()
+            .unwrap_or(0)
+    };
+    let words = (shift.unsigned_abs() / V::BITS as u64) as i64;
+    let bits = (shift.unsigned_abs() % V::BITS as u64) as u32;
+    let i = i as i64;
+    if shift >= 0 {
+        let v = get(i - words) << bits;
+        if bits == 0 {
+            v
+        } else {
+            v | get(i - words - 1) >> (V::BITS - bits)
+        }
+    } else {
+        let v = get(i + words) >> bits;
+        if bits == 0 {
+            v
+        } else {
+            v | get(i + words + 1) << (V::BITS - bits)
+        }
+    }
+}
+
+/// Squared distance standing in for infinity, so it can be added to.
+const FAR: f64 = 1e20;
+
+/// Computes the lower envelope of parabolas rooted at each of `f` into `out`.
+/// `parabolas` and `boundaries` are scratch space.
+fn distance_transform_1d(
+    f: &[f64],
+    out: &mut [f64],
+    parabolas: &mut [usize],
+    boundaries: &mut [f64],
+) {
+    let n = f.len();
+    if n == 0 {
+        return;
+    }
+    let intersection = |p: usize, q: usize| {
+        ((f[q] + (q * q) as f64) - (f[p] + (p * p) as f64)) / (2 * (q - p)) as f64
+    };
+    let mut k = 0;
+    parabolas[0] = 0;
+    boundaries[0] = f64::NEG_INFINITY;
+    boundaries[1] = f64::INFINITY;
+    for q in 1..n {
+        let mut s = intersection(parabolas[k], q);
+        while s <= boundaries[k] {
+            k -= 1;
+            s = intersection(parabolas[k], q);
+        }
+        k += 1;
+        parabolas[k] = q;
+        boundaries[k] = s;
+        boundaries[k + 1] = f64::INFINITY;
+    }
+    k = 0;
+    for (q, out) in out[..n].iter_mut().enumerate() {
+        while boundaries[k + 1] < q as f64 {
+            k += 1;
+        }
+        let p = parabolas[k];
+        *out = (q.abs_diff(p) * q.abs_diff(p)) as f64 + f[p];
+    }
+}
+
 fn round_x_up(x: u32) -> u32 {
     let rounded_up = x.next_multiple_of(V::BITS);
     debug_assert_eq!(rounded_up % V::BITS, 0);
@@ -3,9 +3,11 @@
 
 mod alloc;
 mod arena_map;
+mod generational_arena_map;
 mod mask;
 mod tests;
 
 pub use self::alloc::{arc_default_n, box_default_n};
 pub use self::arena_map::{ArenaEntry, ArenaKey, ArenaMap, OccupiedEntry, VacantEntry};
-pub use self::mask::{Mask2d, Rect};
+pub use self::generational_arena_map::{GenerationalArenaMap, GenerationalKey};
+pub use self::mask::{Mask2d, MaskComponents2d, Rect, RunLengthMask2d};
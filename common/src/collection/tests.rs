@@ -42,6 +42,115 @@ mod arenamap_tests {
     }
 }
 
+#[cfg(test)]
+mod generational_arena_map_tests {
+    use crate::actor_model::Map;
+    use crate::bitcode::{self, *};
+    use crate::{ArenaKey, GenerationalArenaMap, GenerationalKey, PlayerId};
+    use std::marker::PhantomData;
+
+    #[test]
+    fn stale_keys() {
+        let mut map = GenerationalArenaMap::<PlayerId, &str>::new();
+        let a = map.insert("a");
+        let b = map.insert("b");
+        assert_eq!(map[a], "a");
+        assert_eq!(map.len(), 2);
+
+        assert_eq!(map.remove(a), Some("a"));
+        assert_eq!(map.remove(a), None);
+        let c = map.insert("c");
+        assert_eq!(c.key, a.key);
+        assert_ne!(c, a);
+        assert_eq!(map.get(a), None);
+        assert_eq!(map.get_mut(a), None);
+        assert_eq!(map[c], "c");
+
+        map.retain(|_, v| *v != "c");
+        assert!(!map.contains(c));
+        let d = map.insert("d");
+        assert_eq!(d.key, c.key);
+        assert_eq!(map.keys().collect::<Vec<_>>(), [d, b]);
+        Map::verify_ord_iter(&map);
+    }
+
+    #[test]
+    fn insert_with_key() {
+        let mut server = GenerationalArenaMap::<PlayerId, u32>::new();
+        let keys = (0..5).map(|i| server.insert(i)).collect::<Vec<_>>();
+        server.remove(keys[1]);
+        server.remove(keys[3]);
+        let new = server.insert(5);
+
+        // E.g. a client replicating the server's keys.
+        let mut client: GenerationalArenaMap<PlayerId, u32> =
+            server.iter().map(|(k, &v)| (k, v)).collect();
+        assert_eq!(client.len(), server.len());
+        assert_eq!(client[new], 5);
+        assert_eq!(client.get(keys[3]), None);
+
+        let stale = GenerationalKey::new(PlayerId::from_index(4), 7);
+        assert_eq!(client.insert_with_key(stale, 42), None);
+        assert_eq!(client.get(keys[4]), None);
+        assert_eq!(client.len(), server.len());
+        assert!(!client.iter().any(|(k, _)| k.key == keys[1].key));
+    }
+
+    #[test]
+    fn encode_decode() {
+        let mut map = GenerationalArenaMap::<PlayerId, u8>::new();
+        let a = map.insert(1);
+        map.insert(2);
+        map.remove(a);
+
+        let mut decoded: GenerationalArenaMap<PlayerId, u8> =
+            bitcode::decode(&bitcode::encode(&map)).unwrap();
+        assert_eq!(decoded.get(a), None);
+        assert_eq!(decoded.insert(3), map.insert(3));
+        assert_eq!(
+            decoded.iter().collect::<Vec<_>>(),
+            map.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn decode_invalid_free() {
+        // Same encoding as `GenerationalArenaMap<PlayerId, u8>`.
+        #[derive(Encode)]
+        struct Slot {
+            generation: u32,
+            value: Option<u8>,
+        }
+        #[derive(Encode)]
+        struct Map {
+            slots: Vec<Slot>,
+            free: Vec<u32>,
+            len: usize,
+            _spooky: PhantomData<PlayerId>,
+        }
+
+        let invalid = Map {
+            slots: vec![Slot {
+                generation: 3,
+                value: Some(1),
+            }],
+            free: vec![0, 100],
+            len: 0,
+            _spooky: PhantomData,
+        };
+        let mut decoded: GenerationalArenaMap<PlayerId, u8> =
+            bitcode::decode(&bitcode::encode(&invalid)).unwrap();
+        let a = GenerationalKey::new(PlayerId::from_index(0), 3);
+        assert_eq!(decoded[a], 1);
+        let b = decoded.insert(2);
+        assert_eq!(b.key, PlayerId::from_index(1));
+        assert_eq!(decoded[a], 1);
+        assert_eq!(decoded.remove(a), Some(1));
+        assert_eq!(decoded.remove(b), Some(2));
+        assert!(decoded.is_empty());
+    }
+}
+
 #[cfg(test)]
 mod mask_tests {
     use super::*;
@@ -267,3 +376,293 @@ mod mask_tests {
         }
     }
 }
+
+#[cfg(test)]
+mod mask_2d_tests {
+    use crate::{bitcode, Mask2d, RunLengthMask2d};
+    use glam::{ivec2, uvec2, IVec2, UVec2};
+    use rand::prelude::*;
+    use rand_chacha::ChaCha20Rng;
+
+    fn cells(dims: UVec2) -> impl Iterator<Item = UVec2> {
+        (0..dims.y).flat_map(move |y| (0..dims.x).map(move |x| uvec2(x, y)))
+    }
+
+    fn random_mask(rng: &mut ChaCha20Rng, dims: UVec2, density: f32) -> Mask2d {
+        Mask2d::new(cells(dims).filter(|_| rng.gen_bool(density as f64)), dims)
+    }
+
+    fn contains(mask: &Mask2d, pos: IVec2) -> bool {
+        pos.cmpge(IVec2::ZERO).all()
+            && pos.as_uvec2().cmplt(mask.dims()).all()
+            && mask.get(pos.as_uvec2())
+    }
+
+    fn assert_same(a: &Mask2d, b: &Mask2d) {
+        assert_eq!(a.dims(), b.dims());
+        assert_eq!(format!("{a:?}"), format!("{b:?}"));
+        assert!(a.iter().eq(cells(a.dims()).filter(|&pos| a.get(pos))));
+    }
+
+    #[test]
+    fn dilate_erode() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        for i in 0..100 {
+            let dims = uvec2(rng.gen_range(1..150), rng.gen_range(1..20));
+            let mask = random_mask(&mut rng, dims, if i % 2 == 0 { 0.05 } else { 0.9 });
+            let radius = rng.gen_range(0..4);
+            let r = radius as i32;
+            let kernel = || (-r..=r).flat_map(|y| (-r..=r).map(move |x| ivec2(x, y)));
+
+            let dilated = Mask2d::new(
+                cells(dims).filter(|&pos| kernel().any(|o| contains(&mask, pos.as_ivec2() + o))),
+                dims,
+            );
+            assert_same(&mask.dilate(radius), &dilated);
+            assert_same(
+                &mask.dilate(radius),
+                &Mask2d::new_expanded(mask.iter(), dims, radius * 2 + 1),
+            );
+
+            let eroded = Mask2d::new(
+                cells(dims).filter(|&pos| kernel().all(|o| contains(&mask, pos.as_ivec2() + o))),
+                dims,
+            );
+            assert_same(&mask.erode(radius), &eroded);
+
+            assert_same(&mask.open(radius), &eroded.dilate(radius));
+            let closed = Mask2d::new(
+                cells(dims).filter(|&pos| {
+                    kernel().all(|o| {
+                        let pos = pos.as_ivec2() + o;
+                        let in_bounds =
+                            pos.cmpge(IVec2::ZERO).all() && pos.as_uvec2().cmplt(dims).all();
+                        !in_bounds || dilated.get(pos.as_uvec2())
+                    })
+                }),
+                dims,
+            );
+            assert_same(&mask.close(radius), &closed);
+            assert!(mask.iter().all(|pos| closed.get(pos)));
+        }
+    }
+
+    #[test]
+    fn open_close() {
+        let dims = uvec2(13, 8);
+        // A square with a notch, and a speck.
+        let mask = Mask2d::new(
+            cells(dims).filter(|&pos| {
+                let square = pos.cmpge(uvec2(2, 2)).all() && pos.cmple(uvec2(6, 5)).all();
+                (square && pos != uvec2(4, 5)) || pos == uvec2(10, 3)
+            }),
+            dims,
+        );
+        assert_eq!(
+            format!("{:?}", mask.open(1)),
+            "\
+            0000000000000\n\
+            0000000000000\n\
+            0011111000000\n\
+            0011111000000\n\
+            0011111000000\n\
+            0000000000000\n\
+            0000000000000\n\
+            0000000000000\n\
+            "
+        );
+        assert_eq!(
+            format!("{:?}", mask.close(1)),
+            "\
+            0000000000000\n\
+            0000000000000\n\
+            0011111000000\n\
+            0011111000100\n\
+            0011111000000\n\
+            0011111000000\n\
+            0000000000000\n\
+            0000000000000\n\
+            "
+        );
+    }
+
+    #[test]
+    fn components() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        for _ in 0..50 {
+            let dims = uvec2(rng.gen_range(1..40), rng.gen_range(1..40));
+            let mask = random_mask(&mut rng, dims, 0.45);
+            for diagonal in [false, true] {
+                let components = mask.components(diagonal);
+                assert_eq!(
+                    components.sizes().iter().sum::<u32>() as usize,
+                    mask.iter().count()
+                );
+                let mut seen = vec![false; components.len()];
+                for pos in cells(dims) {
+                    let Some(component) = components.get(pos) else {
+                        assert!(!mask.get(pos));
+                        continue;
+                    };
+                    if seen[component] {
+                        continue;
+                    }
+                    // First cell of each component in row-major order.
+                    assert!(seen.iter().take(component).all(|&seen| seen));
+                    seen[component] = true;
+                    let filled = mask.flood_fill(pos, diagonal);
+                    assert_same(&filled, &components.mask(component));
+                    assert_eq!(
+                        filled.iter().count(),
+                        components.sizes()[component] as usize
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn flood_fill() {
+        let dims = uvec2(4, 3);
+        let mask = Mask2d::new([uvec2(1, 0), uvec2(1, 1), uvec2(2, 2)], dims);
+        assert_eq!(
+            format!("{:?}", mask.flood_fill(uvec2(0, 0), false)),
+            "\
+            1000\n\
+            1000\n\
+            1100\n\
+            "
+        );
+        assert_eq!(
+            format!("{:?}", mask.flood_fill(uvec2(1, 0), true)),
+            "\
+            0100\n\
+            0100\n\
+            0010\n\
+            "
+        );
+        assert_eq!(mask.flood_fill(uvec2(3, 0), true).iter().count(), 9);
+    }
+
+    #[test]
+    fn boundaries() {
+        // Ring with a diagonal neighbor.
+        let dims = uvec2(5, 4);
+        let mask = Mask2d::new(
+            cells(uvec2(3, 3))
+                .filter(|&pos| pos != UVec2::ONE)
+                .chain([uvec2(3, 3)]),
+            dims,
+        );
+        assert_eq!(
+            mask.boundaries(),
+            vec![
+                vec![uvec2(0, 0), uvec2(3, 0), uvec2(3, 3), uvec2(0, 3)],
+                vec![uvec2(2, 1), uvec2(1, 1), uvec2(1, 2), uvec2(2, 2)],
+                vec![uvec2(3, 3), uvec2(4, 3), uvec2(4, 4), uvec2(3, 4)],
+            ]
+        );
+
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        for _ in 0..50 {
+            let dims = uvec2(rng.gen_range(1..30), rng.gen_range(1..30));
+            let mask = random_mask(&mut rng, dims, 0.5);
+            let boundaries = mask.boundaries();
+            // Counter-clockwise outer boundaries minus clockwise holes.
+            let area: i32 = boundaries
+                .iter()
+                .map(|polygon| {
+                    let n = polygon.len();
+                    (0..n)
+                        .map(|i| {
+                            polygon[i]
+                                .as_ivec2()
+                                .perp_dot(polygon[(i + 1) % n].as_ivec2())
+                        })
+                        .sum::<i32>()
+                })
+                .sum();
+            assert_eq!(area, mask.iter().count() as i32 * 2);
+            let outer = boundaries
+                .iter()
+                .filter(|polygon| {
+                    let n = polygon.len();
+                    (0..n)
+                        .map(|i| {
+                            polygon[i]
+                                .as_ivec2()
+                                .perp_dot(polygon[(i + 1) % n].as_ivec2())
+                        })
+                        .sum::<i32>()
+                        > 0
+                })
+                .count();
+            assert_eq!(outer, mask.components(false).len());
+            for polygon in &boundaries {
+                assert!(polygon.len() >= 4);
+                for i in 0..polygon.len() {
+                    let edge = polygon[(i + 1) % polygon.len()].as_ivec2() - polygon[i].as_ivec2();
+                    assert!(edge.x == 0 || edge.y == 0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn signed_distance_field() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        for i in 0..30 {
+            let dims = uvec2(rng.gen_range(1..30), rng.gen_range(1..30));
+            let mask = random_mask(&mut rng, dims, [0.0, 1.0, 0.03, 0.97, 0.5][i % 5]);
+            let field = mask.signed_distance_field();
+            let nearest = |pos: UVec2, contained: bool| {
+                cells(dims)
+                    .filter(|&other| mask.get(other) == contained)
+                    .map(|other| other.as_vec2().distance(pos.as_vec2()))
+                    .min_by(f32::total_cmp)
+                    .unwrap_or(f32::INFINITY)
+            };
+            for (pos, distance) in cells(dims).zip(field) {
+                let expected = if mask.get(pos) {
+                    -nearest(pos, false)
+                } else {
+                    nearest(pos, true)
+                };
+                if expected.is_infinite() {
+                    assert_eq!(distance, expected);
+                } else {
+                    assert!((distance - expected).abs() < 1e-4, "{distance} {expected}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn run_length() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        for i in 0..100 {
+            let dims = uvec2(rng.gen_range(0..200), rng.gen_range(0..10));
+            let mask = random_mask(&mut rng, dims, [0.0, 1.0, 0.01, 0.5][i % 4]);
+            let encoded = RunLengthMask2d::from(&mask);
+            assert_eq!(encoded.dims(), dims);
+            assert!(encoded.runs().iter().skip(1).all(|&run| run > 0));
+            let decoded: RunLengthMask2d = bitcode::decode(&bitcode::encode(&encoded)).unwrap();
+            assert_eq!(decoded, encoded);
+            assert_same(&Mask2d::try_from(&decoded).unwrap(), &mask);
+        }
+
+        // Padding left by expanding near the edge isn't encoded.
+        let expanded = Mask2d::new_expanded([uvec2(2, 0)], uvec2(3, 2), 3);
+        assert_eq!(RunLengthMask2d::from(&expanded).runs(), [1, 2, 1, 2]);
+
+        let invalid: RunLengthMask2d =
+            serde_json::from_str(r#"{"width":2,"height":2,"runs":[3,2]}"#).unwrap();
+        assert!(Mask2d::try_from(&invalid).is_err());
+
+        let dim = RunLengthMask2d::MAX_DIM + 1;
+        let too_large: RunLengthMask2d =
+            serde_json::from_str(&format!(r#"{{"width":{dim},"height":1,"runs":[{dim}]}}"#))
+                .unwrap();
+        assert_eq!(Mask2d::try_from(&too_large).err(), Some("dims too large"));
+    }
+}
@@ -35,6 +35,7 @@ mod collection;
 mod game_constants;
 mod lockstep;
 mod math;
+mod pathfinding;
 mod protocol;
 mod team;
 mod time;
@@ -42,12 +43,14 @@ mod time;
 // Not all `actor_model` symbols are exported directly.
 pub use actor_model::{
     Entities2d, Entity2d, EntityIndex2d, OutOfBounds, SectorArray2d, SectorId2d, SectorMap2d,
+    SectorViewpoint2d,
 };
 pub use alloc::*;
 pub use collection::*;
 pub use game_constants::*;
 pub use lockstep::*;
 pub use math::*;
+pub use pathfinding::*;
 pub use protocol::*;
 #[allow(unused)]
 pub use team::*;
@@ -2,10 +2,12 @@
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
 use super::phase::LockstepPhaseInner;
+use super::transfer::LockstepTransferProgress;
 use super::{
-    Lockstep, LockstepInputId, LockstepInputQueue, LockstepInputWindow, LockstepRequest,
-    LockstepTick, LockstepUpdate, LockstepWorld,
+    Lockstep, LockstepInputId, LockstepInputQueue, LockstepInputWindow, LockstepJitter,
+    LockstepRequest, LockstepTick, LockstepUpdate, LockstepWorld,
 };
+use crate::bitcode::DecodeOwned;
 use crate::lockstep::phase::LockstepPhase;
 use crate::{ArenaMap, PlayerId};
 use heapless::HistoryBuffer;
@@ -41,12 +43,31 @@ where
     pub smoothed_normalized_ticks_since_real: f32,
     /// If server has buffered too many inputs then throttle sending.
     pub server_buffered_inputs: usize,
+    /// Jitter of `server_buffered_inputs` in ticks, measured by the server.
+    pub server_buffered_inputs_jitter: f32,
+    /// Jitter of the round trip time in ticks, from `ping_latencies`. The round trip time itself
+    /// includes the input delay, so it can't inform it.
+    pub round_trip_jitter: LockstepJitter,
+    /// Ticks of inputs the server should buffer, smoothly approaching
+    /// [`Self::target_input_delay`]. `None` until first update.
+    input_delay: Option<f32>,
     /// Diagnostic.
     pub ping_latencies: HistoryBuffer<u32, { W::TPS }>,
     /// Diagnostic.
     pub total_latencies: HistoryBuffer<u32, { W::TPS }>,
     /// A buffer of `Info` (e.g. sound) events that game hasn't yet consumed.
     pub(crate) info: Vec<W::Info>,
+    /// Initial state being received in chunks, if too big for one message.
+    transfer: Option<LockstepTransferProgress<W>>,
+    /// Recently sent inputs, oldest first, for desync diagnostics.
+    #[cfg(feature = "desync")]
+    sent: std::collections::VecDeque<(LockstepInputId, W::Input)>,
+    /// A detected desync that hasn't been uploaded yet.
+    #[cfg(feature = "desync")]
+    desync: Option<Box<super::LockstepDesync<W>>>,
+    /// Stops ticking after a desync, since everything afterwards is garbage.
+    #[cfg(feature = "desync")]
+    desynced: bool,
 }
 
 impl<W: LockstepWorld + Default> Default for LockstepClient<W>
@@ -67,9 +88,19 @@ where
             since_predicted_tick: 0.0,
             smoothed_normalized_ticks_since_real: 0.0,
             server_buffered_inputs: 0,
+            server_buffered_inputs_jitter: 0.0,
+            round_trip_jitter: Default::default(),
+            input_delay: None,
             ping_latencies: Default::default(),
             total_latencies: Default::default(),
             info: Default::default(),
+            transfer: None,
+            #[cfg(feature = "desync")]
+            sent: Default::default(),
+            #[cfg(feature = "desync")]
+            desync: None,
+            #[cfg(feature = "desync")]
+            desynced: false,
         }
     }
 }
@@ -82,21 +113,38 @@ where
     [(); W::TPS]:,
 {
     /// Returns ping latency and total latency.
-    pub fn receive(&mut self, update: LockstepUpdate<W>) -> (Option<u32>, Option<u32>) {
+    ///
+    /// Requires [`DecodeOwned`] in case the state is transferred in chunks, which a networked
+    /// [`LockstepUpdate`] implies anyway.
+    pub fn receive(&mut self, update: LockstepUpdate<W>) -> (Option<u32>, Option<u32>)
+    where
+        Lockstep<W>: DecodeOwned,
+    {
         if let Some((player_id, initialization)) = update.initialization {
-            self.player_id = Some(player_id);
-            self.real.clone_from(&initialization);
-            self.predicted.clone_from(&initialization);
-            self.predicted_next.clone_from(&initialization);
-            self.interpolated = initialization;
-            // Kludge.
-            //self.input_queue = Default::default();
-
-            if let Some(info) = W::on_complete() {
-                self.info.push(info);
-            }
+            self.transfer = None;
+            self.initialize(player_id, initialization);
         }
         self.server_buffered_inputs = update.buffered_inputs;
+        self.server_buffered_inputs_jitter = update.buffered_inputs_jitter as f32 * (1.0 / 16.0);
+        if let Some(chunk) = update.transfer {
+            let transfer = match &mut self.transfer {
+                Some(transfer) if transfer.is_same(&chunk) => transfer,
+                transfer => transfer.insert(LockstepTransferProgress::new(&chunk)),
+            };
+            transfer.receive(chunk);
+        }
+        if let Some(transfer) = &mut self.transfer
+            && !transfer.done
+        {
+            // Buffer ticks until the state they apply to is available.
+            if !transfer.failed {
+                transfer.ticks.push(update.tick);
+            }
+            if transfer.is_complete() {
+                self.finish_transfer();
+            }
+            return (None, None);
+        }
         let (ping_latency, total_latency) = self.tick(
             update.tick,
             update.last_applied_input_id,
@@ -106,6 +154,7 @@ where
         //log::info!("ping = {ping_latency:?} total = {total_latency:?}");
         if let Some(ping_latency) = ping_latency {
             self.ping_latencies.write(ping_latency);
+            self.round_trip_jitter.sample(ping_latency as f32);
         }
         if let Some(total_latency) = total_latency {
             self.total_latencies.write(total_latency);
@@ -113,6 +162,84 @@ where
         (ping_latency, total_latency)
     }
 
+    fn initialize(&mut self, player_id: PlayerId, initialization: Lockstep<W>) {
+        self.player_id = Some(player_id);
+        self.real.clone_from(&initialization);
+        self.predicted.clone_from(&initialization);
+        self.predicted_next.clone_from(&initialization);
+        self.interpolated = initialization;
+        // Kludge.
+        //self.input_queue = Default::default();
+
+        if let Some(info) = W::on_complete() {
+            self.info.push(info);
+        }
+    }
+
+    /// Decodes and verifies a completely transferred state, then fast-forwards it through the
+    /// ticks buffered during the transfer to catch up with the server. If that fails, the
+    /// transfer is discarded and requested again.
+    fn finish_transfer(&mut self)
+    where
+        Lockstep<W>: DecodeOwned,
+    {
+        let transfer = self.transfer.as_mut().unwrap();
+        let bytes = std::mem::take(&mut transfer.bytes);
+        let ticks = std::mem::take(&mut transfer.ticks);
+        match Self::decode_transfer(&bytes, transfer.checksum, ticks) {
+            Ok(initialization) => {
+                transfer.done = true;
+                transfer.acknowledge = true;
+                let player_id = transfer.player_id;
+                self.initialize(player_id, initialization);
+                self.heard_from_server = true;
+            }
+            Err(_e) => {
+                #[cfg(feature = "log")]
+                log::warn!("discarding transfer: {_e}");
+                transfer.fail();
+            }
+        }
+    }
+
+    fn decode_transfer(
+        bytes: &[u8],
+        checksum: u32,
+        ticks: Vec<LockstepTick<W>>,
+    ) -> Result<Lockstep<W>, &'static str>
+    where
+        Lockstep<W>: DecodeOwned,
+    {
+        let mut initialization: Lockstep<W> =
+            crate::bitcode::decode(bytes).map_err(|_| "invalid state")?;
+        if initialization.checksum() != checksum {
+            return Err("state checksum mismatch");
+        }
+        for tick in ticks {
+            if let Some(checksum) = tick.checksum
+                && initialization.checksum() != checksum
+            {
+                return Err("tick checksum mismatch");
+            }
+            initialization.tick(
+                tick,
+                &LockstepPhase {
+                    inner: LockstepPhaseInner::GroundTruth,
+                },
+                &mut |_| {},
+            );
+        }
+        Ok(initialization)
+    }
+
+    /// Progress of receiving the initial state (0..=1), if it is being sent in chunks.
+    pub fn transfer_progress(&self) -> Option<f32> {
+        self.transfer
+            .as_ref()
+            .filter(|t| !t.done)
+            .map(|t| t.progress())
+    }
+
     /// Advances the real world by one `tick` from the server. Also corrects any miss predictions
     /// we made with [`Self::tick_predicted`]. Called by `receive`
     ///
@@ -125,6 +252,10 @@ where
         last_applied_id: LockstepInputId,
         last_received_id: LockstepInputId,
     ) -> (Option<u32>, Option<u32>) {
+        #[cfg(feature = "desync")]
+        if self.desynced {
+            return (None, None);
+        }
         assert!(
             last_received_id < self.input_queue.end && last_applied_id < self.input_queue.end,
             "server received/applied unsent command {last_received_id}/{last_applied_id} >= {}",
@@ -134,16 +265,25 @@ where
         if let Some(checksum) = tick.checksum {
             let real = self.real.checksum();
             if real != checksum {
+                #[cfg(feature = "log")]
+                log::error!("desync {real} {checksum}");
+                // Dump the state for the server to compare against instead of panicking.
                 #[cfg(feature = "desync")]
                 {
-                    log::warn!("client = {:?}", self.real);
-                    log::warn!("server = {:?}", tick.complete);
+                    self.desynced = true;
+                    self.desync = Some(Box::new(super::LockstepDesync {
+                        tick_id: self.real.context.tick_id,
+                        client_checksum: real,
+                        server_checksum: checksum,
+                        client: self.real.clone(),
+                        server: None,
+                        inputs: self.sent.iter().cloned().collect(),
+                    }));
+                    return (None, None);
                 }
-                #[cfg(feature = "log")]
-                log::error!("desync {real} {checksum}");
+                #[cfg(not(feature = "desync"))]
                 panic!("desync {real} {checksum}");
             }
-            assert_eq!(real, checksum, "desync");
         }
         self.input_queue.acknowledged(last_applied_id);
         self.heard_from_server = true;
@@ -242,16 +382,60 @@ where
         W::Input: std::fmt::Debug,
         W: std::fmt::Debug,
     {
+        #[cfg(feature = "desync")]
+        if let Some(desync) = self.desync.take() {
+            send_with_reliable(
+                LockstepRequest {
+                    inputs: LockstepInputWindow {
+                        sliding_window: Default::default(),
+                        last_input_id: self.input_queue.end.saturating_sub(1),
+                    },
+                    transferred: None,
+                    retransfer: false,
+                    desync: Some(desync),
+                },
+                true,
+            );
+        }
+        #[cfg(feature = "desync")]
+        if self.desynced {
+            return self.info.drain(..);
+        }
+        if let Some(transfer) = &mut self.transfer
+            && std::mem::take(&mut transfer.acknowledge)
+        {
+            send_with_reliable(
+                LockstepRequest {
+                    inputs: LockstepInputWindow {
+                        sliding_window: Default::default(),
+                        last_input_id: self.input_queue.end.saturating_sub(1),
+                    },
+                    transferred: Some(transfer.transferred()),
+                    retransfer: transfer.failed,
+                    #[cfg(feature = "desync")]
+                    desync: None,
+                },
+                transfer.failed,
+            );
+        }
         if !self.loaded() {
             return self.info.drain(..);
         }
         let server_buffer_usage = self.server_buffer_usage();
-        let client_buffer_usage = self.client_buffer_usage();
-        let buffer_usage = server_buffer_usage * 0.5 + client_buffer_usage * 0.5;
+        let (buffer_usage, target_buffer) = if W::ADAPTIVE_INPUT_DELAY {
+            self.update_input_delay(elapsed_seconds, supports_unreliable);
+            (server_buffer_usage, self.target_buffer(supports_unreliable))
+        } else {
+            let client_buffer_usage = self.client_buffer_usage();
+            (
+                server_buffer_usage * 0.5 + client_buffer_usage * 0.5,
+                W::target_buffer(supports_unreliable),
+            )
+        };
         // https://www.desmos.com/calculator/dgsdlh0yng
         let tick_scale = 1.0
             + 0.25
-                * (2.0 * (buffer_usage - W::target_buffer(supports_unreliable)))
+                * (2.0 * (buffer_usage - target_buffer))
                     .clamp(-1.0, 1.0)
                     .tan();
         let adjusted_tick_period_secs = W::TICK_PERIOD_SECS * tick_scale;
@@ -266,7 +450,23 @@ where
             let input = input(true);
 
             if let Ok(inputs) = self.tick_predicted(input, supports_unreliable) {
-                send_with_reliable(LockstepRequest { inputs }, false);
+                #[cfg(feature = "desync")]
+                {
+                    if self.sent.len() >= super::LockstepDesync::<W>::MAX_INPUTS {
+                        self.sent.pop_front();
+                    }
+                    self.sent.push_back((inputs.last_input_id, input));
+                }
+                send_with_reliable(
+                    LockstepRequest {
+                        inputs,
+                        transferred: None,
+                        retransfer: false,
+                        #[cfg(feature = "desync")]
+                        desync: None,
+                    },
+                    false,
+                );
             } else {
                 //#[cfg(feature = "log")]
                 //log::warn!("unable to predict");
@@ -352,6 +552,54 @@ where
         (self.average_ping_latency_secs() * 1000.0) as u32
     }
 
+    /// Minimum and maximum input delay in ticks.
+    fn input_delay_bounds(supports_unreliable: bool) -> (f32, f32) {
+        let max = W::target_buffer(supports_unreliable) * W::BUFFERED_TICKS as f32;
+        ((W::MIN_INPUT_DELAY as f32).min(max), max)
+    }
+
+    /// Input delay in ticks that would absorb the measured jitter. Errs on the side of the
+    /// maximum until measured.
+    pub fn target_input_delay(&self, supports_unreliable: bool) -> f32 {
+        // Covers the vast majority of deviations.
+        const JITTER_MULTIPLE: f32 = 3.0;
+
+        let (min, max) = Self::input_delay_bounds(supports_unreliable);
+        if !self.round_trip_jitter.is_measured() {
+            return max;
+        }
+        let jitter = self
+            .round_trip_jitter
+            .jitter()
+            .max(self.server_buffered_inputs_jitter);
+        (min + JITTER_MULTIPLE * jitter).clamp(min, max)
+    }
+
+    /// Current input delay in ticks, i.e. how many inputs the server should be buffering.
+    pub fn input_delay(&self, supports_unreliable: bool) -> f32 {
+        self.input_delay
+            .unwrap_or_else(|| self.target_input_delay(supports_unreliable))
+    }
+
+    /// 0..=1, the target for [`Self::server_buffer_usage`] if [`LockstepWorld::ADAPTIVE_INPUT_DELAY`].
+    pub fn target_buffer(&self, supports_unreliable: bool) -> f32 {
+        self.input_delay(supports_unreliable) * (1.0 / W::BUFFERED_TICKS as f32)
+    }
+
+    /// Moves the input delay towards the target gradually. Since input delay is achieved by
+    /// adjusting the tick rate, inputs are never dropped, but sudden changes would be noticeable.
+    fn update_input_delay(&mut self, elapsed_seconds: f32, supports_unreliable: bool) {
+        // Ticks of input delay per second.
+        const MAX_CHANGE_RATE: f32 = 2.0;
+
+        let target = self.target_input_delay(supports_unreliable);
+        let (min, max) = Self::input_delay_bounds(supports_unreliable);
+        let input_delay = self.input_delay.get_or_insert(target);
+        let max_change = elapsed_seconds * MAX_CHANGE_RATE;
+        *input_delay =
+            (*input_delay + (target - *input_delay).clamp(-max_change, max_change)).clamp(min, max);
+    }
+
     /// 0..=1
     pub fn client_buffer_usage(&self) -> f32 {
         self.input_queue.len() as f32 * (1.0 / W::MAX_PREDICTION as f32)
@@ -362,6 +610,12 @@ where
         self.server_buffered_inputs as f32 * (1.0 / W::BUFFERED_TICKS as f32)
     }
 
+    /// Whether a desync was detected, after which the client stops ticking.
+    #[cfg(feature = "desync")]
+    pub fn desynced(&self) -> bool {
+        self.desynced
+    }
+
     /// Has the server sent a complete.
     pub fn loaded(&self) -> bool {
         self.player_id.is_some()
@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::{LockstepInput, LockstepInputId, LockstepWorld};
+use super::{LockstepInput, LockstepInputId, LockstepJitter, LockstepTransfer, LockstepWorld};
 use arrayvec::ArrayVec;
 use std::fmt::{self, Debug, Formatter};
 
@@ -18,6 +18,10 @@ where
     pub last_received_command_id: LockstepInputId,
     /// Client inputs received by server but not yet applied.
     pub receive_buffer: ArrayVec<LockstepInput<W::Input>, { W::BUFFERED_TICKS }>,
+    /// Level of `receive_buffer` each tick, whose jitter is reported to the client.
+    pub buffer_level: LockstepJitter,
+    /// Initial state being sent in chunks, if too big for one message.
+    pub transfer: Option<LockstepTransfer>,
 }
 
 impl<W: LockstepWorld> Default for LockstepClientData<W>
@@ -30,6 +34,8 @@ where
             last_applied_command_id: Default::default(),
             last_received_command_id: Default::default(),
             receive_buffer: Default::default(),
+            buffer_level: Default::default(),
+            transfer: None,
         }
     }
 }
@@ -45,12 +51,16 @@ where
             last_applied_command_id,
             last_received_command_id,
             receive_buffer,
+            buffer_level,
+            transfer,
         } = self;
         f.debug_struct("LockstepClientData")
             .field("initialized", initialized)
             .field("last_applied_command_id", last_applied_command_id)
             .field("last_received_command_id", last_received_command_id)
             .field("receive_buffer", receive_buffer)
+            .field("buffer_level", buffer_level)
+            .field("transfer", transfer)
             .finish()
     }
 }
@@ -0,0 +1,375 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{Lockstep, LockstepContext, LockstepInputId, LockstepPlayer, LockstepWorld};
+use crate::bitcode::{self, *};
+use crate::{ArenaKey, ArenaMap, PlayerId};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt::{self, Debug, Display, Formatter, Write};
+
+/// Diagnostic dump of a checksum mismatch, created by [`LockstepClient`][`super::LockstepClient`]
+/// and uploaded to [`LockstepServer`][`super::LockstepServer`], which fills in `server`.
+///
+/// Persist it with [`bitcode::encode`] and inspect it later with [`Self::diff`] or [`Display`],
+/// which require [`LockstepDiff`].
+#[derive(Clone, Encode, Decode)]
+pub struct LockstepDesync<W: LockstepWorld>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    /// The tick at which the states were compared, before it was applied.
+    pub tick_id: u32,
+    pub client_checksum: u32,
+    pub server_checksum: u32,
+    pub client: Lockstep<W>,
+    /// `None` if the server no longer had the state for `tick_id`.
+    pub server: Option<Lockstep<W>>,
+    /// The most recent inputs the client sent, with their ids, from oldest to newest.
+    pub inputs: Vec<(LockstepInputId, W::Input)>,
+}
+
+impl<W: LockstepWorld> LockstepDesync<W>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    /// Most [`Self::inputs`] a client sends.
+    pub const MAX_INPUTS: usize = W::TPS * 2;
+
+    /// Fields that differ between the client and server states.
+    pub fn diff(&self) -> Vec<LockstepDifference>
+    where
+        W: LockstepDiff,
+        W::Player: LockstepDiff,
+        W::Input: LockstepDiff,
+    {
+        let Some(server) = &self.server else {
+            return Vec::new();
+        };
+        let mut differ = LockstepDiffer::default();
+        differ.field("context", &self.client.context, &server.context);
+        differ.field("world", &self.client.world, &server.world);
+        differ.differences
+    }
+}
+
+impl<W: LockstepWorld> Debug for LockstepDesync<W>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            tick_id,
+            client_checksum,
+            server_checksum,
+            client,
+            server,
+            inputs,
+        } = self;
+        f.debug_struct("LockstepDesync")
+            .field("tick_id", tick_id)
+            .field("client_checksum", client_checksum)
+            .field("server_checksum", server_checksum)
+            .field("client", client)
+            .field("server", server)
+            .field("inputs", inputs)
+            .finish()
+    }
+}
+
+/// Human readable report of differences followed by input history.
+impl<W: LockstepWorld + LockstepDiff> Display for LockstepDesync<W>
+where
+    W::Player: LockstepDiff,
+    W::Input: LockstepDiff,
+    [(); W::LAG_COMPENSATION]:,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "desync at tick {}: client {} server {}",
+            self.tick_id, self.client_checksum, self.server_checksum
+        )?;
+        if self.server.is_some() {
+            let diff = self.diff();
+            if diff.is_empty() {
+                // E.g. non-determinism in `Hash`.
+                writeln!(f, "no differences in encoded state")?;
+            }
+            for difference in diff {
+                writeln!(f, "{difference}")?;
+            }
+        } else {
+            writeln!(f, "server state unavailable")?;
+        }
+        writeln!(f, "inputs:")?;
+        for (input_id, input) in &self.inputs {
+            writeln!(f, "{input_id} {input:?}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A field that differs between the client and server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockstepDifference {
+    /// E.g. `.context.players[PlayerId(5)].inner.position.x`.
+    pub path: String,
+    /// `None` if the field only exists on the server.
+    pub client: Option<String>,
+    /// `None` if the field only exists on the client.
+    pub server: Option<String>,
+}
+
+impl LockstepDifference {
+    /// Differences between `client` and `server`, each [`Self::path`] starting with `path`.
+    pub fn diff<T: LockstepDiff + ?Sized>(path: &str, client: &T, server: &T) -> Vec<Self> {
+        let mut differ = LockstepDiffer {
+            path: path.to_owned(),
+            differences: Vec::new(),
+        };
+        client.diff(server, &mut differ);
+        differ.differences
+    }
+}
+
+impl Display for LockstepDifference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let missing = "<missing>";
+        write!(
+            f,
+            "{}: client = {} server = {}",
+            if self.path.is_empty() {
+                "."
+            } else {
+                &self.path
+            },
+            self.client.as_deref().unwrap_or(missing),
+            self.server.as_deref().unwrap_or(missing)
+        )
+    }
+}
+
+/// Locates the differences between client and server states in a [`LockstepDesync`].
+///
+/// `#[derive(LockstepDiff)]` compares each field separately (fields marked
+/// `#[lockstep_diff(whole)]` only need [`Encode`] and [`Debug`]), while an empty `impl` compares
+/// the value as a whole.
+pub trait LockstepDiff: Encode + Debug {
+    /// Reports how `self` (the client's value) differs from `server` to `differ`.
+    fn diff(&self, server: &Self, differ: &mut LockstepDiffer) {
+        differ.whole(self, server);
+    }
+}
+
+/// Collects [`LockstepDifference`]s on behalf of [`LockstepDiff`].
+#[derive(Debug, Default)]
+pub struct LockstepDiffer {
+    path: String,
+    differences: Vec<LockstepDifference>,
+}
+
+impl LockstepDiffer {
+    /// Compares a field named `name`.
+    pub fn field<T: LockstepDiff + ?Sized>(&mut self, name: &str, client: &T, server: &T) {
+        self.nested(format_args!(".{name}"), |differ| {
+            client.diff(server, differ)
+        });
+    }
+
+    /// Compares a field named `name` as a whole.
+    pub fn field_whole<T: Encode + Debug + ?Sized>(&mut self, name: &str, client: &T, server: &T) {
+        self.nested(format_args!(".{name}"), |differ| {
+            differ.whole(client, server)
+        });
+    }
+
+    /// Compares an element of a collection, which may only exist on one side.
+    pub fn index<T: LockstepDiff + ?Sized>(
+        &mut self,
+        index: impl Debug,
+        client: Option<&T>,
+        server: Option<&T>,
+    ) {
+        self.nested(format_args!("[{index:?}]"), |differ| {
+            match (client, server) {
+                (Some(client), Some(server)) => client.diff(server, differ),
+                (None, None) => {}
+                (client, server) => differ.push(
+                    client.map(|c| format!("{c:?}")),
+                    server.map(|s| format!("{s:?}")),
+                ),
+            }
+        });
+    }
+
+    /// Compares the [`bitcode`] encodings of `client` and `server`, which unlike [`Debug`] output
+    /// include every field. If they differ, reports their [`Debug`] representations, or if those
+    /// are identical, the first differing byte.
+    pub fn whole<T: Encode + Debug + ?Sized>(&mut self, client: &T, server: &T) {
+        let client_bytes = bitcode::encode(client);
+        let server_bytes = bitcode::encode(server);
+        if client_bytes == server_bytes {
+            return;
+        }
+        let (client, server) = (format!("{client:?}"), format!("{server:?}"));
+        if client != server {
+            self.push(Some(client), Some(server));
+            return;
+        }
+        let index = client_bytes
+            .iter()
+            .zip(&server_bytes)
+            .position(|(c, s)| c != s)
+            .unwrap_or(client_bytes.len().min(server_bytes.len()));
+        let byte = |bytes: &[u8]| bytes.get(index).map(|b| format!("{b:#04x}"));
+        self.nested(format_args!(" (byte {index})"), |differ| {
+            differ.push(byte(&client_bytes), byte(&server_bytes))
+        });
+    }
+
+    fn push(&mut self, client: Option<String>, server: Option<String>) {
+        self.differences.push(LockstepDifference {
+            path: self.path.clone(),
+            client,
+            server,
+        });
+    }
+
+    fn nested(&mut self, segment: fmt::Arguments, f: impl FnOnce(&mut Self)) {
+        let len = self.path.len();
+        let _ = self.path.write_fmt(segment);
+        f(self);
+        self.path.truncate(len);
+    }
+}
+
+macro_rules! impl_lockstep_diff_whole {
+    ($($t:ty),*) => {
+        $(impl LockstepDiff for $t {})*
+    };
+}
+
+impl_lockstep_diff_whole!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    str,
+    String,
+    PlayerId
+);
+
+macro_rules! impl_lockstep_diff_tuple {
+    ($($t:ident $i:tt),+) => {
+        impl<$($t: LockstepDiff),+> LockstepDiff for ($($t,)+) {
+            fn diff(&self, server: &Self, differ: &mut LockstepDiffer) {
+                $(differ.field(stringify!($i), &self.$i, &server.$i);)+
+            }
+        }
+    };
+}
+
+impl_lockstep_diff_tuple!(A 0);
+impl_lockstep_diff_tuple!(A 0, B 1);
+impl_lockstep_diff_tuple!(A 0, B 1, C 2);
+impl_lockstep_diff_tuple!(A 0, B 1, C 2, D 3);
+
+impl<T: LockstepDiff> LockstepDiff for Option<T> {
+    fn diff(&self, server: &Self, differ: &mut LockstepDiffer) {
+        match (self, server) {
+            (Some(client), Some(server)) => client.diff(server, differ),
+            _ => differ.whole(self, server),
+        }
+    }
+}
+
+impl<T: LockstepDiff> LockstepDiff for Box<T> {
+    fn diff(&self, server: &Self, differ: &mut LockstepDiffer) {
+        T::diff(self, server, differ);
+    }
+}
+
+impl<T: LockstepDiff> LockstepDiff for [T] {
+    fn diff(&self, server: &Self, differ: &mut LockstepDiffer) {
+        for i in 0..self.len().max(server.len()) {
+            differ.index(i, self.get(i), server.get(i));
+        }
+    }
+}
+
+impl<T: LockstepDiff, const N: usize> LockstepDiff for [T; N] {
+    fn diff(&self, server: &Self, differ: &mut LockstepDiffer) {
+        self.as_slice().diff(server, differ);
+    }
+}
+
+impl<T: LockstepDiff> LockstepDiff for Vec<T> {
+    fn diff(&self, server: &Self, differ: &mut LockstepDiffer) {
+        self.as_slice().diff(server, differ);
+    }
+}
+
+impl<T: LockstepDiff> LockstepDiff for VecDeque<T> {
+    fn diff(&self, server: &Self, differ: &mut LockstepDiffer) {
+        for i in 0..self.len().max(server.len()) {
+            differ.index(i, self.get(i), server.get(i));
+        }
+    }
+}
+
+impl<K: Encode + Debug + Ord, V: LockstepDiff> LockstepDiff for BTreeMap<K, V> {
+    fn diff(&self, server: &Self, differ: &mut LockstepDiffer) {
+        let keys: BTreeSet<&K> = self.keys().chain(server.keys()).collect();
+        for key in keys {
+            differ.index(key, self.get(key), server.get(key));
+        }
+    }
+}
+
+impl<K: ArenaKey + Encode + Debug, V: LockstepDiff> LockstepDiff for ArenaMap<K, V> {
+    fn diff(&self, server: &Self, differ: &mut LockstepDiffer) {
+        let mut keys: Vec<K> = self.keys().chain(server.keys()).collect();
+        keys.sort_unstable_by_key(|key| key.to_index());
+        keys.dedup_by_key(|key| key.to_index());
+        for key in keys {
+            differ.index(key, self.get(key), server.get(key));
+        }
+    }
+}
+
+impl<W: LockstepWorld + LockstepDiff> LockstepDiff for LockstepContext<W>
+where
+    [(); W::LAG_COMPENSATION]:,
+    W::Player: LockstepDiff,
+    W::Input: LockstepDiff,
+{
+    fn diff(&self, server: &Self, differ: &mut LockstepDiffer) {
+        differ.field("tick_id", &self.tick_id, &server.tick_id);
+        differ.field("players", &self.players, &server.players);
+    }
+}
+
+impl<W: LockstepWorld + LockstepDiff> LockstepDiff for LockstepPlayer<W>
+where
+    [(); W::LAG_COMPENSATION]:,
+    W::Player: LockstepDiff,
+    W::Input: LockstepDiff,
+{
+    fn diff(&self, server: &Self, differ: &mut LockstepDiffer) {
+        differ.field("input", &self.input, &server.input);
+        differ.field("inner", &self.inner, &server.inner);
+    }
+}
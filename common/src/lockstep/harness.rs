@@ -0,0 +1,276 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{
+    Lockstep, LockstepClient, LockstepClientData, LockstepRequest, LockstepServer, LockstepUpdate,
+    LockstepWorld,
+};
+use crate::bitcode::{DecodeOwned, Encode};
+use crate::{HashRng, PlayerId};
+use rand::Rng;
+use std::collections::{BTreeMap, VecDeque};
+use std::hash::Hash;
+use std::time::Duration;
+
+/// Simulated network conditions between a client and the server.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LockstepLink {
+    /// One way latency.
+    pub latency: Duration,
+    /// Extra one way latency, uniformly distributed.
+    pub jitter: Duration,
+    /// Probability (0..=1) of losing each message. Lost inputs are dropped, since they are sent
+    /// unreliably, while lost updates are retransmitted (delaying all subsequent updates).
+    pub loss: f32,
+}
+
+struct HarnessClient<W: LockstepWorld>
+where
+    [(); W::LAG_COMPENSATION]:,
+    [(); W::MAX_PREDICTION]:,
+    [(); W::INPUTS_PER_EFFICIENT_PACKET]:,
+    [(); W::BUFFERED_TICKS]:,
+    [(); W::TPS]:,
+{
+    client: LockstepClient<W>,
+    data: LockstepClientData<W>,
+    link: LockstepLink,
+    /// Scripted input, given the harness tick.
+    input: Box<dyn FnMut(u32) -> W::Input>,
+    /// Arrival frame and message, in order of arrival.
+    to_server: VecDeque<(u64, LockstepRequest<W>)>,
+    to_client: VecDeque<(u64, LockstepUpdate<W>)>,
+}
+
+/// Runs a [`LockstepServer`] and any number of [`LockstepClient`]s in-process, deterministically,
+/// for testing that a [`LockstepWorld`] is actually deterministic.
+///
+/// Clients panic as soon as their checksum disagrees with the server, or with the `desync`
+/// feature, stop and upload a [`LockstepDesync`][`super::LockstepDesync`] to
+/// `LockstepServer::desyncs`. Either way, [`Self::assert_converged`] checks their final states.
+pub struct LockstepHarness<W: LockstepWorld>
+where
+    [(); W::LAG_COMPENSATION]:,
+    [(); W::MAX_PREDICTION]:,
+    [(); W::INPUTS_PER_EFFICIENT_PACKET]:,
+    [(); W::BUFFERED_TICKS]:,
+    [(); W::TPS]:,
+{
+    pub server: LockstepServer<W>,
+    clients: BTreeMap<PlayerId, HarnessClient<W>>,
+    /// Server checksums by tick id, oldest first.
+    checksums: VecDeque<(u32, u32)>,
+    rng: HashRng,
+    next_client: usize,
+    frame: u64,
+}
+
+impl<W: LockstepWorld + Default> LockstepHarness<W>
+where
+    [(); W::LAG_COMPENSATION]:,
+    [(); W::MAX_PREDICTION]:,
+    [(); W::INPUTS_PER_EFFICIENT_PACKET]:,
+    [(); W::BUFFERED_TICKS]:,
+    [(); W::TPS]:,
+    Lockstep<W>: Encode + DecodeOwned,
+{
+    /// Server checksums to remember, limiting the latency of clients.
+    const CHECKSUMS: usize = W::TPS * 10;
+    /// Client frames per server tick.
+    pub const FRAMES_PER_TICK: u64 = 4;
+
+    /// `seed` determines latency, jitter, and loss.
+    pub fn new(world: W, seed: &impl Hash) -> Self {
+        Self {
+            server: LockstepServer::new(world),
+            clients: Default::default(),
+            checksums: Default::default(),
+            rng: HashRng::new(seed),
+            next_client: 0,
+            frame: 0,
+        }
+    }
+
+    /// Server ticks elapsed.
+    pub fn tick(&self) -> u32 {
+        (self.frame / Self::FRAMES_PER_TICK) as u32
+    }
+
+    /// Adds a player with a new client, which will receive the current state. `input` is called
+    /// with [`Self::tick`] every time the client needs an input.
+    pub fn join(
+        &mut self,
+        player: W::Player,
+        link: LockstepLink,
+        input: impl FnMut(u32) -> W::Input + 'static,
+    ) -> PlayerId {
+        let player_id = PlayerId::nth_client(self.next_client).unwrap();
+        self.next_client += 1;
+        *self.server.player_mut(player_id) = Some(player);
+        self.clients.insert(
+            player_id,
+            HarnessClient {
+                client: Default::default(),
+                data: Default::default(),
+                link,
+                input: Box::new(input),
+                to_server: Default::default(),
+                to_client: Default::default(),
+            },
+        );
+        player_id
+    }
+
+    /// Removes a player and their client.
+    pub fn leave(&mut self, player_id: PlayerId) {
+        self.server.player_left(player_id);
+        self.clients.remove(&player_id);
+    }
+
+    pub fn client(&self, player_id: PlayerId) -> Option<&LockstepClient<W>> {
+        self.clients.get(&player_id).map(|c| &c.client)
+    }
+
+    /// The server's data about a client.
+    pub fn client_data(&self, player_id: PlayerId) -> Option<&LockstepClientData<W>> {
+        self.clients.get(&player_id).map(|c| &c.data)
+    }
+
+    pub fn player_ids(&self) -> impl Iterator<Item = PlayerId> + '_ {
+        self.clients.keys().copied()
+    }
+
+    /// Runs for `ticks` server ticks.
+    pub fn run(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            self.step();
+        }
+    }
+
+    /// Runs one server tick and the client frames until the next.
+    pub fn step(&mut self) {
+        self.server_tick();
+        for _ in 0..Self::FRAMES_PER_TICK {
+            self.client_frame();
+            self.frame += 1;
+        }
+    }
+
+    fn server_tick(&mut self) {
+        let frame = self.frame;
+        for (&player_id, c) in &mut self.clients {
+            while c
+                .to_server
+                .front()
+                .is_some_and(|(arrival, _)| *arrival <= frame)
+            {
+                let (_, request) = c.to_server.pop_front().unwrap();
+                self.server
+                    .request(player_id, request, Some(&mut c.data), true);
+            }
+        }
+        self.server
+            .update(self.clients.iter_mut().map(|(&id, c)| (id, &mut c.data)));
+        for (&player_id, c) in &mut self.clients {
+            let update = self.server.client_update(player_id, &mut c.data);
+            let mut arrival = frame + Self::delay(&mut self.rng, &c.link);
+            // Retransmit until not lost.
+            while self.rng.gen_bool(c.link.loss.clamp(0.0, 0.99) as f64) {
+                arrival += 2 * Self::delay(&mut self.rng, &c.link) + 1;
+            }
+            // In order delivery.
+            if let Some(&(last, _)) = c.to_client.back() {
+                arrival = arrival.max(last);
+            }
+            c.to_client.push_back((arrival, update));
+        }
+        self.server.post_update(&mut |_| {});
+
+        if self.checksums.len() >= Self::CHECKSUMS {
+            self.checksums.pop_front();
+        }
+        self.checksums.push_back((
+            self.server.real.context.tick_id,
+            self.server.real.checksum(),
+        ));
+    }
+
+    fn client_frame(&mut self) {
+        let frame = self.frame;
+        let tick = self.tick();
+        let elapsed_seconds = W::TICK_PERIOD_SECS / Self::FRAMES_PER_TICK as f32;
+        for c in self.clients.values_mut() {
+            while c
+                .to_client
+                .front()
+                .is_some_and(|(arrival, _)| *arrival <= frame)
+            {
+                let (_, update) = c.to_client.pop_front().unwrap();
+                c.client.receive(update);
+            }
+            let mut requests = Vec::new();
+            let input = &mut c.input;
+            let _ = c.client.update(
+                elapsed_seconds,
+                true,
+                |_| input(tick),
+                |request, _| requests.push(request),
+            );
+            for request in requests {
+                if self.rng.gen_bool(c.link.loss.clamp(0.0, 1.0) as f64) {
+                    continue;
+                }
+                let mut arrival = frame + Self::delay(&mut self.rng, &c.link);
+                if let Some(&(last, _)) = c.to_server.back() {
+                    // Not strictly necessary for unreliable messages.
+                    arrival = arrival.max(last);
+                }
+                c.to_server.push_back((arrival, request));
+            }
+        }
+    }
+
+    /// One way delay in frames.
+    fn delay(rng: &mut HashRng, link: &LockstepLink) -> u64 {
+        let secs =
+            link.latency.as_secs_f32() + link.jitter.as_secs_f32() * rng.gen_range(0.0..=1.0);
+        (secs * Self::FRAMES_PER_TICK as f32 / W::TICK_PERIOD_SECS).round() as u64
+    }
+
+    /// Panics unless every loaded client's state matches the server's state at the same tick.
+    pub fn assert_converged(&self) {
+        for (player_id, c) in &self.clients {
+            let client = &c.client;
+            if !client.loaded() {
+                continue;
+            }
+            let tick_id = client.real.context.tick_id;
+            #[cfg(feature = "desync")]
+            assert!(!client.desynced(), "{player_id:?} desynced at {tick_id}");
+            let server = self
+                .checksums
+                .iter()
+                .find(|(t, _)| *t == tick_id)
+                .map(|(_, checksum)| *checksum)
+                .unwrap_or_else(|| panic!("{player_id:?} too far behind at {tick_id}"));
+            assert_eq!(
+                client.real.checksum(),
+                server,
+                "{player_id:?} diverged at {tick_id}"
+            );
+        }
+    }
+
+    /// Runs until every client has loaded, then calls [`Self::assert_converged`]. Panics if
+    /// loading takes more than `max_ticks`.
+    pub fn settle(&mut self, max_ticks: u32) {
+        for _ in 0..max_ticks {
+            if self.clients.values().all(|c| c.client.loaded()) {
+                self.assert_converged();
+                return;
+            }
+            self.step();
+        }
+        panic!("clients didn't load within {max_ticks} ticks");
+    }
+}
@@ -0,0 +1,37 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+/// Smoothed mean deviation of a noisy measurement such as round trip time or buffer level, in
+/// ticks. Like TCP's RTTVAR.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LockstepJitter {
+    mean: f32,
+    deviation: f32,
+    samples: u32,
+}
+
+impl LockstepJitter {
+    const DEVIATION_GAIN: f32 = 1.0 / 4.0;
+    const MEAN_GAIN: f32 = 1.0 / 8.0;
+
+    pub fn sample(&mut self, ticks: f32) {
+        if self.samples == 0 {
+            self.mean = ticks;
+            self.deviation = ticks * 0.5;
+        } else {
+            self.deviation += ((ticks - self.mean).abs() - self.deviation) * Self::DEVIATION_GAIN;
+            self.mean += (ticks - self.mean) * Self::MEAN_GAIN;
+        }
+        self.samples = self.samples.saturating_add(1);
+    }
+
+    /// Smoothed mean deviation from the smoothed mean.
+    pub fn jitter(&self) -> f32 {
+        self.deviation
+    }
+
+    /// Whether there are any samples yet.
+    pub fn is_measured(&self) -> bool {
+        self.samples > 0
+    }
+}
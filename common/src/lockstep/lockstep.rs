@@ -76,6 +76,17 @@ pub trait LockstepWorld: Hash + Debug + Clone + Sized {
     const BUFFERED_TICKS: usize = Self::MAX_PREDICTION;
     /// Do not overwrite.
     const MAX_LATENCY: u8 = Self::LAG_COMPENSATION as u8 - 1;
+    /// Adjust each player's input delay (see [`Self::target_buffer`]) to their measured jitter.
+    const ADAPTIVE_INPUT_DELAY: bool = false;
+    /// Minimum input delay in ticks, if [`Self::ADAPTIVE_INPUT_DELAY`].
+    const MIN_INPUT_DELAY: usize = 1;
+    /// Late joiners are sent the world in chunks of this many bytes per tick, if it is bigger.
+    const TRANSFER_BYTES_PER_TICK: usize = 16 * 1024;
+    /// Overrides the version stored in [`LockstepRecording`][`super::LockstepRecording`]s, which
+    /// can only be played by a world with the same version. The default, see
+    /// [`LockstepRecording::world_version`][`super::LockstepRecording::world_version`], can't see
+    /// game logic changes, so set (and bump) this when those must invalidate old recordings.
+    const REPLAY_VERSION: Option<u32> = None;
     /// When server gives update, interpolate from the old prediction to the new one.
     ///
     /// Do not use if there are physics discontinuities.
@@ -96,6 +107,9 @@ pub trait LockstepWorld: Hash + Debug + Clone + Sized {
         [(); Self::LAG_COMPENSATION]:;
 
     /// 0..1
+    ///
+    /// The maximum fraction of [`Self::BUFFERED_TICKS`] to buffer if
+    /// [`Self::ADAPTIVE_INPUT_DELAY`].
     fn target_buffer(_supports_unreliable: bool) -> f32 {
         0.5
     }
@@ -4,33 +4,47 @@
 mod client;
 mod client_data;
 mod context;
+#[cfg(feature = "desync")]
+mod desync;
+mod harness;
 mod input;
 mod input_queue;
 mod input_window;
+mod jitter;
 mod lag_compensation;
 mod lockstep;
 mod phase;
 mod player;
 mod player_peers;
+mod replay;
 mod request;
 mod server;
+mod spectator;
 #[cfg(test)]
 mod tests;
 mod tick;
+mod transfer;
 mod update;
 
 pub use client::LockstepClient;
 pub use client_data::LockstepClientData;
 pub use context::LockstepContext;
+#[cfg(feature = "desync")]
+pub use desync::{LockstepDesync, LockstepDiff, LockstepDiffer, LockstepDifference};
+pub use harness::{LockstepHarness, LockstepLink};
 pub use input::{LockstepInput, LockstepInputId};
 pub use input_queue::LockstepInputQueue;
 pub use input_window::LockstepInputWindow;
+pub use jitter::LockstepJitter;
 pub use lag_compensation::LagCompensation;
 pub use lockstep::{Lockstep, LockstepWorld};
 pub use phase::LockstepPhase;
 pub use player::LockstepPlayer;
 pub use player_peers::LockstepPeers;
+pub use replay::{LockstepRecording, LockstepReplay, LockstepReplayError};
 pub use request::LockstepRequest;
 pub use server::{lockstep_get, lockstep_mut, LockstepServer};
+pub use spectator::{LockstepSpectator, LockstepSpectatorData, LockstepSpectatorUpdate};
 pub use tick::LockstepTick;
+pub use transfer::{LockstepTransfer, LockstepTransferChunk};
 pub use update::LockstepUpdate;
@@ -0,0 +1,185 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::phase::{LockstepPhase, LockstepPhaseInner};
+use super::{Lockstep, LockstepTick, LockstepWorld};
+use crate::bitcode::{self, *};
+use crate::CompatHasher;
+use std::any::type_name;
+use std::hash::{Hash, Hasher};
+
+/// A complete match, recorded by [`LockstepServer`][`super::LockstepServer`]. Since lockstep is
+/// deterministic, the initial state and every tick's inputs are sufficient to reproduce it.
+///
+/// Save it with [`bitcode::encode`] and play it with [`LockstepReplay`].
+#[derive(Clone, Encode, Decode)]
+pub struct LockstepRecording<W: LockstepWorld>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    /// [`Self::world_version`] when recorded, which guards against playing a recording of a
+    /// different or incompatible world.
+    pub replay_version: u32,
+    pub initial: Lockstep<W>,
+    /// Overwrites, inputs, and checksums applied to `initial`, in order.
+    pub ticks: Vec<LockstepTick<W>>,
+}
+
+impl<W: LockstepWorld> LockstepRecording<W>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    pub(crate) fn new(initial: Lockstep<W>) -> Self {
+        Self {
+            replay_version: Self::world_version(),
+            initial,
+            ticks: Vec::new(),
+        }
+    }
+
+    /// [`LockstepWorld::REPLAY_VERSION`] if set, otherwise a hash of the world's type names and
+    /// the encodings of its default input and tick, which changes when they are renamed or
+    /// their layout changes.
+    pub fn world_version() -> u32 {
+        if let Some(version) = W::REPLAY_VERSION {
+            return version;
+        }
+        let mut h = CompatHasher::default();
+        type_name::<W>().hash(&mut h);
+        type_name::<W::Player>().hash(&mut h);
+        type_name::<W::Input>().hash(&mut h);
+        type_name::<W::Tick>().hash(&mut h);
+        type_name::<W::Info>().hash(&mut h);
+        bitcode::encode(&W::Input::default()).hash(&mut h);
+        bitcode::encode(&W::Tick::default()).hash(&mut h);
+        h.finish() as u32
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum LockstepReplayError {
+    /// Not a valid recording.
+    Decode,
+    /// Recorded with a different [`LockstepRecording::world_version`].
+    WrongWorld,
+    /// Replaying produced a different state than recorded, e.g. the game logic changed.
+    Desync { tick_id: u32 },
+}
+
+/// Plays a [`LockstepRecording`], with seeking.
+pub struct LockstepReplay<W: LockstepWorld>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    recording: LockstepRecording<W>,
+    /// State after `position` ticks.
+    state: Lockstep<W>,
+    position: usize,
+    /// State after `i * KEYFRAME_INTERVAL` ticks, filled in as they are reached.
+    keyframes: Vec<Lockstep<W>>,
+}
+
+impl<W: LockstepWorld> LockstepReplay<W>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    /// Ticks between keyframes, bounding the cost of seeking backwards.
+    pub(crate) const KEYFRAME_INTERVAL: usize = W::TPS * 10;
+
+    pub fn new(recording: LockstepRecording<W>) -> Result<Self, LockstepReplayError> {
+        if recording.replay_version != LockstepRecording::<W>::world_version() {
+            return Err(LockstepReplayError::WrongWorld);
+        }
+        Ok(Self {
+            state: recording.initial.clone(),
+            keyframes: vec![recording.initial.clone()],
+            recording,
+            position: 0,
+        })
+    }
+
+    /// Decodes a recording encoded with [`bitcode::encode`].
+    pub fn decode(bytes: &[u8]) -> Result<Self, LockstepReplayError>
+    where
+        LockstepRecording<W>: DecodeOwned,
+    {
+        let recording = bitcode::decode::<LockstepRecording<W>>(bytes)
+            .map_err(|_| LockstepReplayError::Decode)?;
+        Self::new(recording)
+    }
+
+    /// Current state.
+    pub fn state(&self) -> &Lockstep<W> {
+        &self.state
+    }
+
+    /// Ticks applied so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Total number of ticks.
+    pub fn len(&self) -> usize {
+        self.recording.ticks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recording.ticks.is_empty()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position == self.len()
+    }
+
+    /// Applies one tick, if not finished. Returns whether a tick was applied.
+    pub fn step(&mut self, on_info: &mut dyn FnMut(W::Info)) -> Result<bool, LockstepReplayError> {
+        let Some(tick) = self.recording.ticks.get(self.position) else {
+            return Ok(false);
+        };
+        if let Some(checksum) = tick.checksum
+            && checksum != self.state.checksum()
+        {
+            return Err(LockstepReplayError::Desync {
+                tick_id: self.state.context.tick_id,
+            });
+        }
+        self.state.tick(
+            tick.clone(),
+            &LockstepPhase {
+                inner: LockstepPhaseInner::GroundTruth,
+            },
+            on_info,
+        );
+        self.position += 1;
+        if self.position % Self::KEYFRAME_INTERVAL == 0
+            && self.keyframes.len() == self.position / Self::KEYFRAME_INTERVAL
+        {
+            self.keyframes.push(self.state.clone());
+        }
+        Ok(true)
+    }
+
+    /// Applies up to `ticks` ticks, discarding info. Returns how many were applied.
+    pub fn fast_forward(&mut self, ticks: usize) -> Result<usize, LockstepReplayError> {
+        for i in 0..ticks {
+            if !self.step(&mut |_| {})? {
+                return Ok(i);
+            }
+        }
+        Ok(ticks)
+    }
+
+    /// Moves to the state after `position` ticks (clamped to [`Self::len`]), starting from the
+    /// nearest keyframe that isn't after it.
+    pub fn seek(&mut self, position: usize) -> Result<(), LockstepReplayError> {
+        let position = position.min(self.len());
+        let keyframe = (position / Self::KEYFRAME_INTERVAL).min(self.keyframes.len() - 1);
+        let keyframe_position = keyframe * Self::KEYFRAME_INTERVAL;
+        if position < self.position || keyframe_position > self.position {
+            self.state.clone_from(&self.keyframes[keyframe]);
+            self.position = keyframe_position;
+        }
+        self.fast_forward(position - self.position)?;
+        Ok(())
+    }
+}
@@ -9,13 +9,23 @@ use std::fmt::{self, Debug, Formatter};
 #[derive(Clone, Encode, Decode)]
 pub struct LockstepRequest<W: LockstepWorld>
 where
+    [(); W::LAG_COMPENSATION]:,
     [(); W::INPUTS_PER_EFFICIENT_PACKET]:,
 {
     pub inputs: LockstepInputWindow<W>,
+    /// Contiguous bytes of [`LockstepUpdate::transfer`][`super::LockstepUpdate::transfer`]
+    /// received so far.
+    pub transferred: Option<u32>,
+    /// Set if the transferred state couldn't be decoded or verified, to restart the transfer.
+    pub retransfer: bool,
+    /// Uploaded once after the client detects a desync.
+    #[cfg(feature = "desync")]
+    pub desync: Option<Box<super::LockstepDesync<W>>>,
 }
 
 impl<W: LockstepWorld> LockstepRequest<W>
 where
+    [(); W::LAG_COMPENSATION]:,
     [(); W::INPUTS_PER_EFFICIENT_PACKET]:,
 {
     pub fn bot(input: W::Input) -> Self {
@@ -24,19 +34,34 @@ where
                 inner: input,
                 input_id: 0,
             }),
+            transferred: None,
+            retransfer: false,
+            #[cfg(feature = "desync")]
+            desync: None,
         }
     }
 }
 
 impl<W: LockstepWorld + Debug> Debug for LockstepRequest<W>
 where
+    [(); W::LAG_COMPENSATION]:,
     [(); W::INPUTS_PER_EFFICIENT_PACKET]:,
     W::Input: Debug,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let Self { inputs } = self;
-        f.debug_struct("LockstepRequest")
-            .field("inputs", &inputs)
-            .finish()
+        let Self {
+            inputs,
+            transferred,
+            retransfer,
+            #[cfg(feature = "desync")]
+            desync,
+        } = self;
+        let mut f = f.debug_struct("LockstepRequest");
+        f.field("inputs", &inputs);
+        f.field("transferred", transferred);
+        f.field("retransfer", retransfer);
+        #[cfg(feature = "desync")]
+        f.field("desync", desync);
+        f.finish()
     }
 }
@@ -2,10 +2,13 @@
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
 use super::phase::{LockstepPhase, LockstepPhaseInner};
+use super::spectator::LockstepSpectating;
 use super::{
-    Lockstep, LockstepClientData, LockstepInput, LockstepRequest, LockstepTick, LockstepUpdate,
+    Lockstep, LockstepClientData, LockstepInput, LockstepRecording, LockstepRequest,
+    LockstepSpectatorData, LockstepSpectatorUpdate, LockstepTick, LockstepTransfer, LockstepUpdate,
     LockstepWorld,
 };
+use crate::bitcode::Encode;
 use crate::{ArenaKey, ArenaMap, PlayerId};
 use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
@@ -21,6 +24,18 @@ where
     pub real: Lockstep<W>,
     /// Pending inputs not yet applied to `real`
     pub current: LockstepTick<W>,
+    /// Every tick since [`Self::start_recording`], for replays.
+    recording: Option<LockstepRecording<W>>,
+    /// Ticks after which `recording` stops growing.
+    max_recording_ticks: usize,
+    /// Delayed stream for spectators, if enabled.
+    spectating: Option<LockstepSpectating<W>>,
+    /// Recent values of `real`, oldest first, to compare against uploaded desyncs.
+    #[cfg(feature = "desync")]
+    history: std::collections::VecDeque<Lockstep<W>>,
+    /// Desyncs uploaded by clients, completed with the server's state.
+    #[cfg(feature = "desync")]
+    pub desyncs: Vec<(PlayerId, super::LockstepDesync<W>)>,
 }
 
 impl<W: LockstepWorld + Default> Default for LockstepServer<W>
@@ -54,7 +69,17 @@ where
     W::Tick: Debug,
 {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        let Self { real, current } = self;
+        let Self {
+            real,
+            current,
+            recording: _,
+            max_recording_ticks: _,
+            spectating: _,
+            #[cfg(feature = "desync")]
+                history: _,
+            #[cfg(feature = "desync")]
+                desyncs: _,
+        } = self;
         f.debug_struct("LockstepServer")
             .field("real", real)
             .field("current", current)
@@ -94,10 +119,24 @@ where
     [(); W::INPUTS_PER_EFFICIENT_PACKET]:,
     [(); W::BUFFERED_TICKS]:,
 {
+    /// Ticks of `history` to keep, enough to cover the client's latency plus the upload.
+    #[cfg(feature = "desync")]
+    const DESYNC_HISTORY: usize = W::TPS * 5;
+    /// Largest client state to accept in an uploaded desync, since clients control its size.
+    #[cfg(feature = "desync")]
+    const MAX_DESYNC_BYTES: usize = 1 << 22;
+
     pub fn new(world: W) -> Self {
         Self {
             real: Lockstep::new(world),
             current: Default::default(),
+            recording: None,
+            max_recording_ticks: 0,
+            spectating: None,
+            #[cfg(feature = "desync")]
+            history: Default::default(),
+            #[cfg(feature = "desync")]
+            desyncs: Default::default(),
         }
     }
 
@@ -131,15 +170,33 @@ where
         self.current.inputs.remove(player_id);
     }
 
+    /// Requires [`Encode`] to limit the size of uploaded desyncs, which a networked
+    /// [`LockstepRequest`] implies anyway.
     pub fn request(
         &mut self,
         player_id: PlayerId,
         request: LockstepRequest<W>,
         client_data: Option<&mut LockstepClientData<W>>,
         supports_unreliable: bool,
-    ) {
+    ) where
+        Lockstep<W>: Encode,
+    {
         let reliable = !supports_unreliable;
+        #[cfg(feature = "desync")]
+        if let Some(desync) = request.desync {
+            self.receive_desync(player_id, *desync);
+        }
         if let Some(client) = client_data {
+            if request.retransfer {
+                // Start over from the current state.
+                client.initialized = false;
+                client.transfer = None;
+            } else if let Some(transferred) = request.transferred
+                && let Some(transfer) = &mut client.transfer
+                && transfer.acknowledge(transferred)
+            {
+                client.transfer = None;
+            }
             //println!("received {} init={} last={}", request.inputs.last_input_id, client.initialized, client.last_applied_command_id);
             if !client.initialized {
                 // Likely situation: client switched to this server but sent a message intended
@@ -214,6 +271,9 @@ where
                 .all(|c| c.input_id > client.last_applied_command_id));
             debug_assert!(client.receive_buffer.is_sorted_by_key(|c| c.input_id));
 
+            client
+                .buffer_level
+                .sample(client.receive_buffer.len() as f32);
             if client.receive_buffer.is_empty() {
                 continue;
             }
@@ -226,32 +286,134 @@ where
         self.current.checksum = Some(self.checksum());
         #[cfg(feature = "desync")]
         {
-            self.current.complete = Some(self.real.clone());
+            if self.history.len() >= Self::DESYNC_HISTORY {
+                self.history.pop_front();
+            }
+            self.history.push_back(self.real.clone());
         }
     }
 
+    #[cfg(feature = "desync")]
+    fn receive_desync(&mut self, player_id: PlayerId, mut desync: super::LockstepDesync<W>)
+    where
+        Lockstep<W>: Encode,
+    {
+        if self.desyncs.iter().any(|(p, _)| *p == player_id) {
+            // Only need one per client.
+            return;
+        }
+        if desync.inputs.len() > super::LockstepDesync::<W>::MAX_INPUTS
+            || crate::bitcode::encode(&desync.client).len() > Self::MAX_DESYNC_BYTES
+        {
+            #[cfg(feature = "log")]
+            log::warn!("{player_id:?} uploaded oversized desync");
+            return;
+        }
+        desync.server = self
+            .history
+            .iter()
+            .find(|real| real.context.tick_id == desync.tick_id)
+            .cloned();
+        #[cfg(feature = "log")]
+        log::error!("{player_id:?} desync at tick {}", desync.tick_id);
+        self.desyncs.push((player_id, desync));
+    }
+
+    /// Requires [`Encode`] in case the state has to be transferred in chunks, which a networked
+    /// [`LockstepUpdate`] implies anyway.
     pub fn client_update(
         &self,
         player_id: PlayerId,
         client_data: &mut LockstepClientData<W>,
-    ) -> LockstepUpdate<W> {
+    ) -> LockstepUpdate<W>
+    where
+        Lockstep<W>: Encode,
+    {
         assert!(player_id.is_client());
         let initialize = !std::mem::replace(&mut client_data.initialized, true);
+        let mut initialization = None;
+        if initialize {
+            client_data.last_applied_command_id = 0;
+            // New.
+            client_data.last_received_command_id = 0;
+            let transfer = LockstepTransfer::new(&self.real);
+            if transfer.len() > W::TRANSFER_BYTES_PER_TICK {
+                // Too big for one message, the client buffers ticks until it has it all.
+                client_data.transfer = Some(transfer);
+            } else {
+                client_data.transfer = None;
+                initialization = Some((player_id, self.real.clone()));
+            }
+        }
+        let transfer = client_data
+            .transfer
+            .as_mut()
+            .and_then(|t| t.next_chunk::<W>(player_id));
         LockstepUpdate {
-            initialization: initialize.then(|| {
-                client_data.last_applied_command_id = 0;
-                // New.
-                client_data.last_received_command_id = 0;
-                (player_id, self.real.clone())
-            }),
+            initialization,
+            transfer,
             last_applied_input_id: client_data.last_applied_command_id,
             last_received_input_id: client_data.last_received_command_id,
             tick: self.current.clone(),
             buffered_inputs: client_data.receive_buffer.len(),
+            buffered_inputs_jitter: (client_data.buffer_level.jitter() * 16.0).min(u8::MAX as f32)
+                as u8,
         }
     }
 
+    /// Starts recording a replay from the current state, discarding any previous recording.
+    /// Recording stops after `max_ticks` ticks, bounding its memory usage; take it and start
+    /// another to keep going.
+    pub fn start_recording(&mut self, max_ticks: usize) {
+        self.recording = Some(LockstepRecording::new(self.real.clone()));
+        self.max_recording_ticks = max_ticks;
+    }
+
+    /// Whether ticks are still being recorded, i.e. recording started and isn't full.
+    pub fn is_recording(&self) -> bool {
+        self.recording
+            .as_ref()
+            .is_some_and(|r| r.ticks.len() < self.max_recording_ticks)
+    }
+
+    /// Stops recording and returns the recording, if any.
+    pub fn take_recording(&mut self) -> Option<LockstepRecording<W>> {
+        self.recording.take()
+    }
+
+    /// Starts streaming the match to spectators, `delay` ticks behind. Spectators can be served
+    /// once `delay` ticks have elapsed. Any existing spectators must be reinitialized.
+    pub fn enable_spectators(&mut self, delay: usize) {
+        self.spectating = Some(LockstepSpectating::new(&self.real, delay));
+    }
+
+    pub fn disable_spectators(&mut self) {
+        self.spectating = None;
+    }
+
+    /// Spectator delay in ticks, if spectators are enabled.
+    pub fn spectator_delay(&self) -> Option<usize> {
+        self.spectating.as_ref().map(|s| s.delay())
+    }
+
+    /// Call once per tick for each spectator, after [`Self::post_update`]. Returns [`None`] if
+    /// spectators are disabled or not ready to be served.
+    pub fn spectator_update(
+        &self,
+        spectator_data: &mut LockstepSpectatorData,
+    ) -> Option<LockstepSpectatorUpdate<W>> {
+        self.spectating.as_ref()?.update(spectator_data)
+    }
+
     pub fn post_update(&mut self, on_info: &mut dyn FnMut(W::Info)) {
+        if let Some(recording) = &mut self.recording
+            && recording.ticks.len() < self.max_recording_ticks
+        {
+            recording.ticks.push(self.current.clone());
+        }
+        if let Some(spectating) = &mut self.spectating {
+            spectating.push(&self.current);
+        }
         self.real.tick(
             std::mem::take(&mut self.current),
             &LockstepPhase {
@@ -0,0 +1,257 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::phase::{LockstepPhase, LockstepPhaseInner};
+use super::{Lockstep, LockstepTick, LockstepWorld};
+use crate::bitcode::{self, *};
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
+
+/// Server side state for streaming a delayed copy of the match to spectators, who don't have a
+/// [`LockstepPlayer`][`super::LockstepPlayer`] and therefore don't appear in
+/// [`LockstepPeers`][`super::LockstepPeers`] or contribute inputs.
+pub(crate) struct LockstepSpectating<W: LockstepWorld>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    /// Ticks behind the match, to prevent relaying information to players (ghosting).
+    delay: usize,
+    /// The match as of `delay` ticks ago, once `pending` is full.
+    delayed: Lockstep<W>,
+    /// Ticks not yet applied to `delayed`, oldest first.
+    pending: VecDeque<LockstepTick<W>>,
+    /// The tick most recently applied to `delayed`, and the tick id it was applied to.
+    last: Option<(u32, LockstepTick<W>)>,
+}
+
+impl<W: LockstepWorld> LockstepSpectating<W>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    pub(crate) fn new(real: &Lockstep<W>, delay: usize) -> Self {
+        Self {
+            delay,
+            delayed: real.clone(),
+            pending: VecDeque::with_capacity(delay + 1),
+            last: None,
+        }
+    }
+
+    /// Call with each tick as it is applied to the match.
+    pub(crate) fn push(&mut self, tick: &LockstepTick<W>) {
+        self.pending.push_back(tick.clone());
+        self.last = None;
+        if self.pending.len() > self.delay {
+            let tick = self.pending.pop_front().unwrap();
+            let tick_id = self.delayed.context.tick_id;
+            self.delayed.tick(
+                tick.clone(),
+                &LockstepPhase {
+                    inner: LockstepPhaseInner::GroundTruth,
+                },
+                &mut |_| {},
+            );
+            self.last = Some((tick_id, tick));
+        }
+    }
+
+    /// Returns [`None`] if spectators can't be served yet because the delay hasn't elapsed since
+    /// spectating was enabled.
+    pub(crate) fn update(
+        &self,
+        data: &mut LockstepSpectatorData,
+    ) -> Option<LockstepSpectatorUpdate<W>> {
+        if self.pending.len() < self.delay {
+            return None;
+        }
+        if !std::mem::replace(&mut data.initialized, true) {
+            Some(LockstepSpectatorUpdate {
+                initialization: Some(self.delayed.clone()),
+                tick_id: self.delayed.context.tick_id,
+                tick: None,
+            })
+        } else if let Some((tick_id, tick)) = &self.last {
+            Some(LockstepSpectatorUpdate {
+                initialization: None,
+                tick_id: *tick_id,
+                tick: Some(tick.clone()),
+            })
+        } else {
+            Some(LockstepSpectatorUpdate {
+                initialization: None,
+                tick_id: self.delayed.context.tick_id,
+                tick: None,
+            })
+        }
+    }
+
+    pub(crate) fn delay(&self) -> usize {
+        self.delay
+    }
+}
+
+/// The data about a spectator that is known by the server.
+#[derive(Debug, Default)]
+pub struct LockstepSpectatorData {
+    /// Reset to reinitialize the spectator, e.g. if [`LockstepSpectator::take_resync`].
+    pub initialized: bool,
+}
+
+/// Message sent from lockstep model on game server to a spectator, once per tick.
+#[derive(Clone, Encode, Decode)]
+pub struct LockstepSpectatorUpdate<W: LockstepWorld>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    /// Complete state, sent when the spectator joins, possibly mid-match.
+    pub initialization: Option<Lockstep<W>>,
+    /// The tick id `tick` applies to, so spectators can drop duplicates and detect gaps.
+    pub tick_id: u32,
+    /// The next confirmed tick, if any.
+    pub tick: Option<LockstepTick<W>>,
+}
+
+impl<W: LockstepWorld + Debug> Debug for LockstepSpectatorUpdate<W>
+where
+    [(); W::LAG_COMPENSATION]:,
+    W::Player: Debug,
+    W::Input: Debug,
+    W::Tick: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Self {
+            initialization,
+            tick_id,
+            tick,
+        } = self;
+        f.debug_struct("LockstepSpectatorUpdate")
+            .field("initialization", initialization)
+            .field("tick_id", tick_id)
+            .field("tick", tick)
+            .finish()
+    }
+}
+
+/// Implements lockstep model for a game client that is spectating. Unlike [`LockstepClient`]
+/// there is nothing to predict, only to interpolate.
+///
+/// [`LockstepClient`]: super::LockstepClient
+pub struct LockstepSpectator<W: LockstepWorld>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    /// The state before `real`, for interpolation.
+    pub previous: Lockstep<W>,
+    /// The latest (delayed) server state.
+    pub real: Lockstep<W>,
+    /// A linear interpolation between `previous` and `real`.
+    pub interpolated: Lockstep<W>,
+    /// Fractional ticks since `real` was received.
+    pub since_real: f32,
+    loaded: bool,
+    /// Missed or misapplied a tick, so ticks are ignored until reinitialized.
+    desynced: bool,
+    /// Whether to ask the server to reinitialize, see [`Self::take_resync`].
+    resync: bool,
+    /// A buffer of `Info` (e.g. sound) events that game hasn't yet consumed.
+    info: Vec<W::Info>,
+}
+
+impl<W: LockstepWorld + Default> Default for LockstepSpectator<W>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    fn default() -> Self {
+        Self {
+            previous: Default::default(),
+            real: Default::default(),
+            interpolated: Default::default(),
+            since_real: 0.0,
+            loaded: false,
+            desynced: false,
+            resync: false,
+            info: Default::default(),
+        }
+    }
+}
+
+impl<W: LockstepWorld> LockstepSpectator<W>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    pub fn receive(&mut self, update: LockstepSpectatorUpdate<W>) {
+        if let Some(initialization) = update.initialization {
+            self.previous.clone_from(&initialization);
+            self.real.clone_from(&initialization);
+            self.interpolated = initialization;
+            self.loaded = true;
+            self.desynced = false;
+            if let Some(info) = W::on_complete() {
+                self.info.push(info);
+            }
+        }
+        if let Some(tick) = update.tick
+            && self.loaded
+            && !self.desynced
+        {
+            let expected = self.real.context.tick_id;
+            if (update.tick_id.wrapping_sub(expected) as i32) < 0 {
+                // Already applied.
+                return;
+            }
+            if update.tick_id != expected
+                || tick
+                    .checksum
+                    .is_some_and(|checksum| checksum != self.real.checksum())
+            {
+                #[cfg(feature = "log")]
+                log::error!(
+                    "spectator desync at {expected} (received {})",
+                    update.tick_id
+                );
+                self.desynced = true;
+                self.resync = true;
+                return;
+            }
+            self.previous.clone_from(&self.real);
+            self.real.tick(
+                tick,
+                &LockstepPhase {
+                    inner: LockstepPhaseInner::GroundTruth,
+                },
+                &mut |info| self.info.push(info),
+            );
+            self.since_real = 0.0;
+        }
+    }
+
+    /// Called every frame by game to interpolate world state and get `Info` events.
+    pub fn update(&mut self, elapsed_seconds: f32) -> impl Iterator<Item = W::Info> + '_ {
+        if self.loaded {
+            self.since_real = (self.since_real + elapsed_seconds / W::TICK_PERIOD_SECS).min(1.0);
+            self.interpolated = self.previous.lerp(
+                &self.real,
+                self.since_real,
+                &LockstepPhase {
+                    inner: LockstepPhaseInner::LerpingCurrentPredictionToNextPrediction {
+                        perspective: None,
+                        smoothed_normalized_ticks_since_real: 0.0,
+                    },
+                },
+            );
+        }
+        self.info.drain(..)
+    }
+
+    /// Has the server sent a complete.
+    pub fn loaded(&self) -> bool {
+        self.loaded
+    }
+
+    /// Returns `true` once after missing a tick or diverging from the server. The state is
+    /// frozen until reinitialized, so relay it to the server, which should reset
+    /// [`LockstepSpectatorData::initialized`].
+    pub fn take_resync(&mut self) -> bool {
+        std::mem::take(&mut self.resync)
+    }
+}
@@ -1,64 +1,85 @@
+use super::phase::LockstepPhase;
+use super::{
+    LockstepClient, LockstepContext, LockstepRecording, LockstepReplay, LockstepReplayError,
+    LockstepSpectator, LockstepSpectatorData,
+};
+use crate::bitcode::{self, *};
+use crate::{
+    ArenaMap, LockstepClientData, LockstepHarness, LockstepLink, LockstepServer, LockstepWorld,
+    PlayerId,
+};
+use kodiak_macros::HbHash;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 use std::sync::mpsc::TryRecvError;
 use std::time::{Duration, Instant};
+extern crate self as kodiak_common;
 
-// clear && RUST_LOG=info cargo test lockstep --features log -- --nocapture
-#[test]
-fn lockstep() {
-    let _ = env_logger::try_init();
-
-    use super::LockstepClient;
-    use crate::bitcode::{self, *};
-    use crate::{LockstepClientData, LockstepServer, LockstepWorld, PlayerId};
-    use kodiak_macros::HbHash;
-    extern crate self as kodiak_common;
-    use rand::{thread_rng, Rng};
-
-    #[derive(Clone, Default, Debug, Hash, Encode, Decode)]
-    struct World;
-    #[derive(Clone, Debug, HbHash, Encode, Decode)]
-    struct Player {
-        #[hb_hash]
-        number: f32,
-        #[hb_hash]
-        velocity: f32,
-    }
-    #[derive(Clone, Copy, Debug, Default, HbHash, Encode, Decode)]
-    struct Input {
-        #[hb_hash]
-        target: f32,
+#[derive(Clone, Default, Debug, Hash, Encode, Decode)]
+#[cfg_attr(feature = "desync", derive(kodiak_macros::LockstepDiff))]
+struct World;
+#[derive(Clone, Debug, HbHash, Encode, Decode)]
+#[cfg_attr(feature = "desync", derive(kodiak_macros::LockstepDiff))]
+struct Player {
+    #[hb_hash]
+    number: f32,
+    #[hb_hash]
+    velocity: f32,
+}
+#[derive(Clone, Copy, Debug, Default, HbHash, Encode, Decode)]
+#[cfg_attr(feature = "desync", derive(kodiak_macros::LockstepDiff))]
+struct Input {
+    #[hb_hash]
+    target: f32,
+}
+impl Player {
+    fn tick(&mut self, input: &Input) {
+        let velocity = ((input.target - self.number) * 0.4).clamp(-1.0, 1.0);
+        self.velocity += (velocity - self.velocity) * 0.8;
+        self.number += self.velocity * World::TICK_PERIOD_SECS;
     }
-    impl LockstepWorld for World {
-        type Input = Input;
-        type Player = Player;
+}
+impl LockstepWorld for World {
+    type Input = Input;
+    type Player = Player;
 
-        const BUFFERED_TICKS: usize = Self::TPS;
-        const MAX_PREDICTION: usize = Self::TPS;
-        const TPS: usize = 10;
+    const BUFFERED_TICKS: usize = Self::TPS;
+    const MAX_PREDICTION: usize = Self::TPS;
+    const TPS: usize = 10;
 
-        fn tick(
-            &mut self,
-            _tick: Self::Tick,
-            context: &mut crate::lockstep::LockstepContext<Self>,
-            _predicting: Option<kodiak_common::PlayerId>,
-            _interpolation_prediction: bool,
-            _on_info: &mut dyn FnMut(Self::Info),
-        ) where
-            [(); Self::LAG_COMPENSATION]:,
-        {
-            for (_, player) in context.players.iter_mut() {
-                let velocity = ((player.input.target - player.number) * 0.4).clamp(-1.0, 1.0);
-                player.velocity += (velocity - player.velocity) * 0.8;
-                player.number += player.velocity * World::TICK_PERIOD_SECS;
-            }
+    fn tick(
+        &mut self,
+        _tick: Self::Tick,
+        context: &mut LockstepContext<Self>,
+        _phase: &LockstepPhase,
+        _on_info: &mut dyn FnMut(Self::Info),
+    ) where
+        [(); Self::LAG_COMPENSATION]:,
+    {
+        for (_, player) in context.players.iter_mut() {
+            let input = player.input;
+            player.tick(&input);
         }
+    }
 
-        fn lerp_player(player: &Self::Player, next: &Self::Player, t: f32) -> Self::Player {
-            Player {
-                number: player.number + (next.number - player.number) * t,
-                velocity: player.velocity + (next.velocity - player.velocity) * t,
-            }
+    fn lerp_player(
+        _player_id: PlayerId,
+        player: &Self::Player,
+        next: &Self::Player,
+        t: f32,
+        _phase: &LockstepPhase,
+    ) -> Self::Player {
+        Player {
+            number: player.number + (next.number - player.number) * t,
+            velocity: player.velocity + (next.velocity - player.velocity) * t,
         }
     }
+}
+
+// clear && RUST_LOG=info cargo test lockstep --features log -- --nocapture
+#[test]
+fn lockstep() {
+    let _ = env_logger::try_init();
 
     let player_id = PlayerId::nth_client(0).unwrap();
     const TICKS: usize = 200;
@@ -67,7 +88,7 @@ fn lockstep() {
     let (send_to_client, client_receive) = std::sync::mpsc::channel();
     let (send_to_server, server_receive) = std::sync::mpsc::channel();
     let server = std::thread::spawn(move || {
-        let mut rng = thread_rng();
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
         let mut client_data = LockstepClientData::<World>::default();
         let mut server = LockstepServer::<World>::default();
         *server.player_mut(player_id) = Some(Player {
@@ -82,7 +103,7 @@ fn lockstep() {
             }
             server.update(std::iter::once((player_id, &mut client_data)));
             let client_update = server.client_update(player_id, &mut client_data);
-            server.post_update();
+            server.post_update(&mut |_| {});
             if send_to_client.send(client_update).is_err() {
                 break;
             }
@@ -92,7 +113,7 @@ fn lockstep() {
         }
     });
     let client = std::thread::spawn(move || {
-        let mut rng = thread_rng();
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
         let mut client = LockstepClient::<World>::default();
         let mut last_time = Instant::now();
         let mut time = 0f32;
@@ -165,3 +186,544 @@ fn lockstep() {
     server.join().unwrap();
     client.join().unwrap();
 }
+
+/// [`World`] with [`LockstepWorld::ADAPTIVE_INPUT_DELAY`].
+#[derive(Clone, Default, Debug, Hash, Encode, Decode)]
+struct AdaptiveWorld;
+impl LockstepWorld for AdaptiveWorld {
+    type Input = Input;
+    type Player = Player;
+
+    const ADAPTIVE_INPUT_DELAY: bool = true;
+    const BUFFERED_TICKS: usize = Self::TPS;
+    const MAX_PREDICTION: usize = Self::TPS;
+    const TPS: usize = World::TPS;
+
+    fn tick(
+        &mut self,
+        _tick: Self::Tick,
+        context: &mut LockstepContext<Self>,
+        _phase: &LockstepPhase,
+        _on_info: &mut dyn FnMut(Self::Info),
+    ) where
+        [(); Self::LAG_COMPENSATION]:,
+    {
+        for (_, player) in context.players.iter_mut() {
+            let input = player.input;
+            player.tick(&input);
+        }
+    }
+}
+
+/// Simulates one client over a network with `jitter` ticks of jitter, returning the client's
+/// input delay in ticks once settled.
+fn simulate_input_delay(jitter: f32) -> f32 {
+    const TICKS: u32 = 600;
+    let tick = Duration::from_secs_f32(AdaptiveWorld::TICK_PERIOD_SECS);
+
+    let mut harness = LockstepHarness::new(AdaptiveWorld, &jitter.to_bits());
+    let link = LockstepLink {
+        latency: tick,
+        jitter: tick.mul_f32(jitter),
+        loss: 0.0,
+    };
+    let player = Player {
+        number: 0.0,
+        velocity: 0.0,
+    };
+    let player_id = harness.join(player, link, |tick| Input {
+        target: (tick as f32 * 0.04).sin(),
+    });
+    let mut last_applied = 0;
+    for _ in 0..TICKS {
+        harness.step();
+        // Adjusting input delay must never skip inputs.
+        let applied = harness
+            .client_data(player_id)
+            .unwrap()
+            .last_applied_command_id;
+        assert!(
+            applied == last_applied || applied == last_applied + 1,
+            "{last_applied} -> {applied}"
+        );
+        last_applied = applied;
+    }
+    assert!(last_applied > TICKS / 2, "{last_applied}");
+    harness.client(player_id).unwrap().input_delay(true)
+}
+
+#[test]
+fn adaptive_input_delay() {
+    let max = AdaptiveWorld::target_buffer(true) * AdaptiveWorld::BUFFERED_TICKS as f32;
+    let stable = simulate_input_delay(0.0);
+    let jittery = simulate_input_delay(3.0);
+    assert!(stable < max, "{stable} {max}");
+    assert!(jittery > stable, "{jittery} {stable}");
+    assert!(jittery <= max, "{jittery} {max}");
+}
+
+#[test]
+fn spectator() {
+    const DELAY: usize = 5;
+    let player_id = PlayerId::nth_client(0).unwrap();
+    let mut server = LockstepServer::<World>::default();
+    *server.player_mut(player_id) = Some(Player {
+        number: 0.0,
+        velocity: 0.0,
+    });
+    server.enable_spectators(DELAY);
+    let mut spectator_data = LockstepSpectatorData::default();
+    let mut spectator = LockstepSpectator::<World>::default();
+    let mut history = Vec::new();
+
+    for i in 0..50 {
+        server.update(std::iter::empty());
+        server
+            .current
+            .inputs
+            .insert(player_id, Input { target: i as f32 });
+        server.post_update(&mut |_| {});
+        history.push(server.real.clone());
+
+        // Join mid-match.
+        if i < 20 {
+            continue;
+        }
+        let update = server.spectator_update(&mut spectator_data).unwrap();
+        assert_eq!(update.initialization.is_some(), i == 20 || i == 42);
+        // Lose an update.
+        if i == 40 {
+            continue;
+        }
+        // Duplicates are ignored.
+        if i == 30 {
+            spectator.receive(update.clone());
+        }
+        spectator.receive(update);
+        let _ = spectator.update(World::TICK_PERIOD_SECS);
+
+        // The gap freezes the spectator until it is reinitialized.
+        if i == 41 {
+            assert!(spectator.take_resync());
+            assert!(!spectator.take_resync());
+            assert_eq!(
+                spectator.real.context.tick_id,
+                history[i - DELAY - 2].context.tick_id
+            );
+            spectator_data.initialized = false;
+            continue;
+        }
+        assert!(!spectator.take_resync());
+        let delayed = &history[i - DELAY];
+        assert_eq!(spectator.real.context.tick_id, delayed.context.tick_id);
+        assert_eq!(spectator.real.checksum(), delayed.checksum());
+        // Spectators aren't players.
+        assert_eq!(spectator.real.context.players.len(), 1);
+    }
+}
+
+#[test]
+fn transfer() {
+    #[derive(Clone, Debug, Hash, Encode, Decode)]
+    struct BigWorld {
+        data: Vec<u32>,
+    }
+    impl Default for BigWorld {
+        fn default() -> Self {
+            Self {
+                data: (0..1000).collect(),
+            }
+        }
+    }
+    impl LockstepWorld for BigWorld {
+        type Input = u8;
+        type Player = ();
+
+        const TRANSFER_BYTES_PER_TICK: usize = 256;
+
+        fn tick(
+            &mut self,
+            _tick: Self::Tick,
+            context: &mut LockstepContext<Self>,
+            _phase: &LockstepPhase,
+            _on_info: &mut dyn FnMut(Self::Info),
+        ) where
+            [(); Self::LAG_COMPENSATION]:,
+        {
+            let i = context.tick_id as usize % self.data.len();
+            for (_, player) in context.players.iter() {
+                self.data[i] = self.data[i]
+                    .wrapping_mul(7)
+                    .wrapping_add(player.input as u32);
+            }
+        }
+    }
+
+    for corrupt in [false, true] {
+        let veteran = PlayerId::nth_client(0).unwrap();
+        let joiner = PlayerId::nth_client(1).unwrap();
+        let mut server = LockstepServer::<BigWorld>::default();
+        *server.player_mut(veteran) = Some(());
+        for i in 0..10 {
+            server.update(std::iter::empty());
+            server.current.inputs.insert(veteran, i);
+            server.post_update(&mut |_| {});
+        }
+
+        *server.player_mut(joiner) = Some(());
+        let mut client_data = LockstepClientData::<BigWorld>::default();
+        let mut client = LockstepClient::<BigWorld>::default();
+        let mut requests = Vec::new();
+        let mut progress = Vec::new();
+        for i in 0..150u32 {
+            for request in std::mem::take(&mut requests) {
+                server.request(joiner, request, Some(&mut client_data), false);
+            }
+            server.update(std::iter::once((joiner, &mut client_data)));
+            server.current.inputs.insert(veteran, i as u8);
+            let mut update = server.client_update(joiner, &mut client_data);
+            server.post_update(&mut |_| {});
+            if i == 0 {
+                assert!(update.initialization.is_none(), "should be chunked");
+            }
+            if i == 3 {
+                // Lost chunk must be resent.
+                assert!(update.transfer.take().is_some());
+            }
+            if corrupt && i == 1 {
+                // Corrupt state must be discarded and transferred again.
+                update.transfer.as_mut().unwrap().bytes[0] ^= 1;
+            }
+            client.receive(update);
+            progress.extend(client.transfer_progress());
+            let _ = client.update(
+                BigWorld::TICK_PERIOD_SECS,
+                false,
+                |_| 1,
+                |request, _| requests.push(request),
+            );
+        }
+
+        assert!(client.loaded());
+        assert!(client.transfer_progress().is_none());
+        assert!(client_data.transfer.is_none());
+        assert_eq!(
+            progress.windows(2).all(|w| w[0] <= w[1]),
+            !corrupt,
+            "{progress:?}"
+        );
+        assert!(progress.len() > 5, "{progress:?}");
+        // Caught up to the live tick.
+        assert_eq!(client.real.context.tick_id, server.real.context.tick_id);
+        assert_eq!(client.real.checksum(), server.real.checksum());
+        assert_eq!(client.real.context.players.len(), 2);
+    }
+}
+
+#[test]
+fn replay() {
+    #[derive(Clone, Default, Debug, Hash, Encode, Decode)]
+    struct ReplayWorld {
+        sum: u32,
+    }
+
+    impl LockstepWorld for ReplayWorld {
+        type Info = u32;
+        type Input = u8;
+        type Player = ();
+
+        fn tick(
+            &mut self,
+            _tick: Self::Tick,
+            context: &mut LockstepContext<Self>,
+            _phase: &LockstepPhase,
+            on_info: &mut dyn FnMut(Self::Info),
+        ) where
+            [(); Self::LAG_COMPENSATION]:,
+        {
+            for (_, player) in context.players.iter() {
+                self.sum = self.sum.wrapping_mul(31).wrapping_add(player.input as u32);
+            }
+            on_info(self.sum);
+        }
+    }
+
+    fn record(ticks: usize) -> (LockstepRecording<ReplayWorld>, Vec<u32>) {
+        let mut server = LockstepServer::<ReplayWorld>::default();
+        assert!(!server.is_recording());
+        server.start_recording(ticks);
+        let player_id = PlayerId::nth_client(0).unwrap();
+        *server.player_mut(player_id) = Some(());
+        let mut sums = Vec::new();
+        // Ticks past the limit aren't recorded.
+        for i in 0..ticks + 3 {
+            assert_eq!(server.is_recording(), i < ticks);
+            server.update(std::iter::empty());
+            let mut inputs = ArenaMap::new();
+            inputs.insert(player_id, i as u8);
+            server.current.inputs = inputs;
+            server.post_update(&mut |_| {});
+            sums.push(server.real.world.sum);
+        }
+        (server.take_recording().unwrap(), sums)
+    }
+
+    let keyframe_interval = LockstepReplay::<ReplayWorld>::KEYFRAME_INTERVAL;
+    let ticks = keyframe_interval * 2 + 5;
+    let (recording, sums) = record(ticks);
+    assert_eq!(
+        recording.replay_version,
+        LockstepRecording::<ReplayWorld>::world_version()
+    );
+    assert_ne!(
+        recording.replay_version,
+        LockstepRecording::<World>::world_version()
+    );
+    let bytes = bitcode::encode(&recording);
+    let mut replay = LockstepReplay::<ReplayWorld>::decode(&bytes).unwrap();
+    assert_eq!(replay.len(), ticks);
+
+    let mut info = Vec::new();
+    assert!(replay.step(&mut |i| info.push(i)).unwrap());
+    assert_eq!(info, [sums[0]]);
+
+    replay.seek(ticks - 1).unwrap();
+    assert_eq!(replay.state().world.sum, sums[ticks - 2]);
+    replay.seek(3).unwrap();
+    assert_eq!(replay.state().world.sum, sums[2]);
+    replay.seek(keyframe_interval + 1).unwrap();
+    assert_eq!(replay.position(), keyframe_interval + 1);
+    assert_eq!(replay.state().world.sum, sums[keyframe_interval]);
+
+    let remaining = ticks - replay.position();
+    assert_eq!(replay.fast_forward(usize::MAX).unwrap(), remaining);
+    assert!(replay.is_finished());
+    assert!(!replay.step(&mut |_| {}).unwrap());
+
+    // Tampering is detected.
+    let mut tampered = recording.clone();
+    tampered.ticks[1]
+        .inputs
+        .values_mut()
+        .for_each(|input| *input += 1);
+    let mut replay = LockstepReplay::new(tampered).unwrap();
+    assert_eq!(
+        replay.fast_forward(ticks),
+        Err(LockstepReplayError::Desync { tick_id: 2 })
+    );
+
+    let mut wrong = recording;
+    wrong.replay_version ^= 1;
+    assert!(matches!(
+        LockstepReplay::new(wrong),
+        Err(LockstepReplayError::WrongWorld)
+    ));
+}
+
+#[test]
+fn harness() {
+    let player = || Player {
+        number: 0.0,
+        velocity: 0.0,
+    };
+    let mut harness = LockstepHarness::new(World, &"harness");
+    let fast = LockstepLink::default();
+    let slow = LockstepLink {
+        latency: Duration::from_millis(150),
+        jitter: Duration::from_millis(50),
+        loss: 0.1,
+    };
+    let a = harness.join(player(), fast, |tick| Input {
+        target: (tick as f32 * 0.1).sin(),
+    });
+    harness.run(20);
+    let b = harness.join(player(), slow, |tick| Input {
+        target: (tick % 7) as f32,
+    });
+    harness.run(50);
+    harness.settle(100);
+    let c = harness.join(player(), slow, |_| Input { target: -1.0 });
+    harness.run(20);
+    harness.leave(a);
+    harness.run(50);
+    harness.settle(100);
+    assert_eq!(harness.player_ids().collect::<Vec<_>>(), [b, c]);
+    assert_eq!(harness.server.real.context.players.len(), 2);
+}
+
+#[cfg(feature = "desync")]
+#[test]
+fn desync_diff() {
+    use crate::{LockstepDiff, LockstepDifference};
+    use std::collections::BTreeMap;
+    use std::fmt::{self, Debug, Formatter};
+
+    #[derive(Debug, Encode, LockstepDiff)]
+    enum Mode {
+        Walk { speed: u8 },
+        Fly(u8),
+    }
+
+    #[derive(Debug, Encode, LockstepDiff)]
+    struct Player {
+        position: (f32, f32),
+        weapon: Option<u8>,
+        mode: Mode,
+    }
+
+    #[derive(Debug, Encode, LockstepDiff)]
+    struct World {
+        tick_id: u32,
+        players: BTreeMap<u8, Player>,
+        projectiles: Vec<u16>,
+    }
+
+    let client = World {
+        tick_id: 5,
+        players: BTreeMap::from([
+            (
+                1,
+                Player {
+                    position: (1.0, 2.0),
+                    weapon: None,
+                    mode: Mode::Walk { speed: 1 },
+                },
+            ),
+            (
+                2,
+                Player {
+                    position: (3.0, 4.0),
+                    weapon: Some(7),
+                    mode: Mode::Walk { speed: 2 },
+                },
+            ),
+        ]),
+        projectiles: vec![1, 2],
+    };
+    let server = World {
+        tick_id: 5,
+        players: BTreeMap::from([
+            (
+                1,
+                Player {
+                    position: (1.0, 2.5),
+                    weapon: Some(3),
+                    mode: Mode::Fly(1),
+                },
+            ),
+            (
+                2,
+                Player {
+                    position: (3.0, 4.0),
+                    weapon: Some(7),
+                    mode: Mode::Walk { speed: 3 },
+                },
+            ),
+        ]),
+        projectiles: vec![1, 2, 3],
+    };
+    assert!(LockstepDifference::diff(".world", &client, &client).is_empty());
+
+    let difference = |path: &str, client: Option<&str>, server: Option<&str>| LockstepDifference {
+        path: path.to_owned(),
+        client: client.map(str::to_owned),
+        server: server.map(str::to_owned),
+    };
+    assert_eq!(
+        LockstepDifference::diff(".world", &client, &server),
+        vec![
+            difference(".world.players[1].position.1", Some("2.0"), Some("2.5")),
+            difference(".world.players[1].weapon", Some("None"), Some("Some(3)")),
+            difference(
+                ".world.players[1].mode",
+                Some("Walk { speed: 1 }"),
+                Some("Fly(1)")
+            ),
+            difference(".world.players[2].mode.speed", Some("2"), Some("3")),
+            difference(".world.projectiles[2]", None, Some("3")),
+        ]
+    );
+
+    // Differences that aren't visible to `Debug` are still found.
+    #[derive(Encode)]
+    struct Opaque(u16);
+
+    impl Debug for Opaque {
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            f.write_str("Opaque")
+        }
+    }
+
+    impl LockstepDiff for Opaque {}
+
+    #[derive(Debug, Encode, LockstepDiff)]
+    struct Wrapper(u8, #[lockstep_diff(whole)] Opaque);
+
+    assert!(LockstepDifference::diff(".opaque", &Opaque(1), &Opaque(1)).is_empty());
+    let diff = LockstepDifference::diff(".opaque", &Opaque(1), &Opaque(2));
+    assert_eq!(diff.len(), 1, "{diff:?}");
+    assert!(diff[0].path.starts_with(".opaque (byte "), "{diff:?}");
+    assert_ne!(diff[0].client, diff[0].server);
+
+    let diff = LockstepDifference::diff("", &Wrapper(1, Opaque(1)), &Wrapper(2, Opaque(2)));
+    assert_eq!(diff.len(), 2, "{diff:?}");
+    assert_eq!(diff[0], difference(".0", Some("1"), Some("2")));
+    assert!(diff[1].path.starts_with(".1 (byte "), "{diff:?}");
+}
+
+#[cfg(feature = "desync")]
+#[test]
+fn desync_upload() {
+    let player_id = PlayerId::nth_client(0).unwrap();
+    let mut server = LockstepServer::<World>::default();
+    *server.player_mut(player_id) = Some(Player {
+        number: 0.0,
+        velocity: 0.0,
+    });
+    let mut client_data = LockstepClientData::<World>::default();
+    let mut client = LockstepClient::<World>::default();
+    let mut requests = Vec::new();
+    for i in 0..50 {
+        for request in std::mem::take(&mut requests) {
+            // As if it went over the network.
+            let request = bitcode::decode(&bitcode::encode(&request)).unwrap();
+            server.request(player_id, request, Some(&mut client_data), false);
+        }
+        server.update(std::iter::once((player_id, &mut client_data)));
+        let update = server.client_update(player_id, &mut client_data);
+        server.post_update(&mut |_| {});
+        client.receive(update);
+        if i == 20 {
+            assert!(client.loaded());
+            assert!(server.desyncs.is_empty());
+            // Simulate non-determinism.
+            client
+                .real
+                .context
+                .players
+                .get_mut(player_id)
+                .unwrap()
+                .number += 1.0;
+        }
+        let _ = client.update(
+            World::TICK_PERIOD_SECS,
+            false,
+            |_| Input { target: 1.0 },
+            |request, _| requests.push(request),
+        );
+    }
+
+    assert!(client.desynced());
+    let [(desync_player_id, desync)] = &server.desyncs[..] else {
+        panic!("{:?}", server.desyncs);
+    };
+    assert_eq!(*desync_player_id, player_id);
+    assert_ne!(desync.client_checksum, desync.server_checksum);
+    let server_state = desync.server.as_ref().expect("server state");
+    assert_eq!(server_state.context.tick_id, desync.tick_id);
+    let path = format!(".context.players[{player_id:?}].inner.number");
+    let diff = desync.diff();
+    assert_eq!(diff.len(), 1, "{diff:?}");
+    assert_eq!(diff[0].path, path);
+    assert!(desync.to_string().contains(&path));
+}
@@ -13,8 +13,6 @@ where
     [(); W::LAG_COMPENSATION]:,
 {
     pub checksum: Option<u32>,
-    #[cfg(feature = "desync")]
-    pub complete: Option<super::Lockstep<W>>,
     pub overwrites: BTreeMap<PlayerId, Option<W::Player>>,
     /// An input for all `players`.
     pub inputs: ArenaMap<PlayerId, W::Input>,
@@ -28,8 +26,6 @@ where
     fn default() -> Self {
         Self {
             checksum: Default::default(),
-            #[cfg(feature = "desync")]
-            complete: Default::default(),
             overwrites: Default::default(),
             inputs: Default::default(),
             inner: Default::default(),
@@ -47,8 +43,6 @@ where
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let Self {
             checksum,
-            #[cfg(feature = "desync")]
-                complete: _,
             overwrites,
             inputs,
             inner,
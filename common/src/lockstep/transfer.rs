@@ -0,0 +1,191 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{Lockstep, LockstepTick, LockstepWorld};
+use crate::bitcode::{self, *};
+use crate::PlayerId;
+use std::fmt::{self, Debug, Formatter};
+
+/// Part of a bitcode-encoded [`Lockstep`], for initializing late joiners whose world is too big
+/// to send in one message.
+#[derive(Clone, Debug, Encode, Decode)]
+pub struct LockstepTransferChunk {
+    pub player_id: PlayerId,
+    /// Identifies the transfer, along with `checksum`.
+    pub tick_id: u32,
+    /// Checksum of the decoded state.
+    pub checksum: u32,
+    /// Total length of the encoded state.
+    pub len: u32,
+    pub offset: u32,
+    pub bytes: Vec<u8>,
+}
+
+/// Server side progress of sending a [`Lockstep`] in chunks.
+pub struct LockstepTransfer {
+    tick_id: u32,
+    checksum: u32,
+    bytes: Box<[u8]>,
+    /// Bytes sent so far.
+    sent: usize,
+    /// Bytes the client has received so far, all of which were contiguous.
+    acknowledged: usize,
+    /// Ticks since `acknowledged` increased.
+    stalled: usize,
+}
+
+impl Debug for LockstepTransfer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LockstepTransfer")
+            .field("tick_id", &self.tick_id)
+            .field("len", &self.bytes.len())
+            .field("sent", &self.sent)
+            .field("acknowledged", &self.acknowledged)
+            .finish()
+    }
+}
+
+impl LockstepTransfer {
+    pub(crate) fn new<W: LockstepWorld>(real: &Lockstep<W>) -> Self
+    where
+        [(); W::LAG_COMPENSATION]:,
+        Lockstep<W>: Encode,
+    {
+        Self {
+            tick_id: real.context.tick_id,
+            checksum: real.checksum(),
+            bytes: bitcode::encode(real).into(),
+            sent: 0,
+            acknowledged: 0,
+            stalled: 0,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Returns the next chunk, of at most [`LockstepWorld::TRANSFER_BYTES_PER_TICK`], if any. Resends from the last
+    /// acknowledged byte if the client stops making progress, e.g. due to packet loss.
+    pub(crate) fn next_chunk<W: LockstepWorld>(
+        &mut self,
+        player_id: PlayerId,
+    ) -> Option<LockstepTransferChunk> {
+        self.stalled += 1;
+        if self.sent > self.acknowledged && self.stalled > W::TPS {
+            self.sent = self.acknowledged;
+            self.stalled = 0;
+        }
+        if self.sent == self.bytes.len() {
+            return None;
+        }
+        let end = (self.sent + W::TRANSFER_BYTES_PER_TICK).min(self.bytes.len());
+        let chunk = LockstepTransferChunk {
+            player_id,
+            tick_id: self.tick_id,
+            checksum: self.checksum,
+            len: self.bytes.len() as u32,
+            offset: self.sent as u32,
+            bytes: self.bytes[self.sent..end].to_vec(),
+        };
+        self.sent = end;
+        Some(chunk)
+    }
+
+    /// Returns true if the transfer is complete.
+    pub(crate) fn acknowledge(&mut self, received: u32) -> bool {
+        let received = (received as usize).min(self.bytes.len());
+        if received > self.acknowledged {
+            self.acknowledged = received;
+            self.stalled = 0;
+        }
+        self.acknowledged == self.bytes.len()
+    }
+}
+
+/// Client side progress of receiving a [`Lockstep`] in chunks.
+pub(crate) struct LockstepTransferProgress<W: LockstepWorld>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    pub(crate) player_id: PlayerId,
+    pub(crate) tick_id: u32,
+    pub(crate) checksum: u32,
+    pub(crate) len: usize,
+    pub(crate) bytes: Vec<u8>,
+    /// Ticks to apply after the transferred state, in order.
+    pub(crate) ticks: Vec<LockstepTick<W>>,
+    /// Whether the server needs to hear about new `bytes`.
+    pub(crate) acknowledge: bool,
+    /// Whether the state was decoded and fast-forwarded, after which `bytes` and `ticks` are
+    /// empty. Kept to ignore chunks that were resent.
+    pub(crate) done: bool,
+    /// Whether the state couldn't be decoded or verified, so the server should restart the
+    /// transfer. Chunks of this transfer are ignored in the meantime.
+    pub(crate) failed: bool,
+}
+
+impl<W: LockstepWorld> LockstepTransferProgress<W>
+where
+    [(); W::LAG_COMPENSATION]:,
+{
+    pub(crate) fn new(chunk: &LockstepTransferChunk) -> Self {
+        Self {
+            player_id: chunk.player_id,
+            tick_id: chunk.tick_id,
+            checksum: chunk.checksum,
+            len: chunk.len as usize,
+            bytes: Vec::with_capacity(chunk.len as usize),
+            ticks: Vec::new(),
+            acknowledge: false,
+            done: false,
+            failed: false,
+        }
+    }
+
+    pub(crate) fn is_same(&self, chunk: &LockstepTransferChunk) -> bool {
+        self.tick_id == chunk.tick_id && self.checksum == chunk.checksum
+    }
+
+    /// Ignores chunks that aren't contiguous, which will be resent.
+    pub(crate) fn receive(&mut self, chunk: LockstepTransferChunk) {
+        if self.failed {
+            return;
+        }
+        if !self.done && chunk.offset as usize == self.bytes.len() {
+            self.bytes.extend_from_slice(&chunk.bytes);
+            self.bytes.truncate(self.len);
+        }
+        self.acknowledge = true;
+    }
+
+    pub(crate) fn is_complete(&self) -> bool {
+        self.done || (!self.failed && self.bytes.len() == self.len)
+    }
+
+    /// Discards everything received, and asks the server to restart the transfer.
+    pub(crate) fn fail(&mut self) {
+        self.bytes = Vec::new();
+        self.ticks = Vec::new();
+        self.acknowledge = true;
+        self.failed = true;
+    }
+
+    /// Contiguous bytes received so far.
+    pub(crate) fn transferred(&self) -> u32 {
+        if self.done {
+            self.len as u32
+        } else {
+            self.bytes.len() as u32
+        }
+    }
+
+    /// 0..=1
+    pub(crate) fn progress(&self) -> f32 {
+        if self.len == 0 {
+            1.0
+        } else {
+            self.transferred() as f32 / self.len as f32
+        }
+    }
+}
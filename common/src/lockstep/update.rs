@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::{Lockstep, LockstepInputId, LockstepTick, LockstepWorld};
+use super::{Lockstep, LockstepInputId, LockstepTick, LockstepTransferChunk, LockstepWorld};
 use crate::bitcode::{self, *};
 use crate::PlayerId;
 use std::fmt::{self, Debug, Formatter};
@@ -12,10 +12,14 @@ where
     [(); W::LAG_COMPENSATION]:,
 {
     pub initialization: Option<(PlayerId, Lockstep<W>)>,
+    /// Like `initialization` but in chunks, for big worlds.
+    pub transfer: Option<LockstepTransferChunk>,
     pub last_applied_input_id: LockstepInputId,
     pub last_received_input_id: LockstepInputId,
     pub tick: LockstepTick<W>,
     pub buffered_inputs: usize,
+    /// Jitter of `buffered_inputs` in sixteenths of a tick.
+    pub buffered_inputs_jitter: u8,
 }
 
 impl<W: LockstepWorld + Debug> Debug for LockstepUpdate<W>
@@ -28,17 +32,21 @@ where
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let Self {
             initialization,
+            transfer,
             last_applied_input_id,
             last_received_input_id,
             tick,
             buffered_inputs,
+            buffered_inputs_jitter,
         } = self;
         f.debug_struct("LockstepUpdate")
             .field("initialization", &initialization)
+            .field("transfer", transfer)
             .field("last_applied_input_id", last_applied_input_id)
             .field("last_received_input_id", last_received_input_id)
             .field("tick", &tick)
             .field("buffered_inputs", &buffered_inputs)
+            .field("buffered_inputs_jitter", &buffered_inputs_jitter)
             .finish()
     }
 }
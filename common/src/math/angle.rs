@@ -1,6 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
+use super::{Curve, Easing, Hermite};
 use crate::bitcode::{self, *};
 use glam::{IVec2, Mat2, Mat3, Mat4, Quat, Vec2, Vec3, Vec3Swizzles};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -171,6 +172,29 @@ impl Angle {
         self + (other - self) * value
     }
 
+    /// Like [`Self::lerp`], but with `easing` applied to `value`. Only deterministic if `easing`
+    /// is.
+    pub fn ease(self, other: Self, value: f32, easing: Easing) -> Self {
+        self.lerp(other, easing.apply(value))
+    }
+
+    /// Rotates towards `target` the shorter way around, by at most `max_delta`.
+    pub fn rotate_towards(self, target: Self, max_delta: Self) -> Self {
+        self + (target - self).clamp_magnitude(max_delta)
+    }
+
+    /// Smoothly interpolates from `from` to `to` as `value` goes from 0 to 1, with angular
+    /// velocity at each end determined by `before` and `after` (Catmull-Rom). Adjacent angles
+    /// are connected the shorter way around.
+    pub fn catmull_rom(before: Self, from: Self, to: Self, after: Self, value: f32) -> Self {
+        // Unwrap relative to `from`.
+        let before = (before - from).0 as f32;
+        let to_delta = (to - from).0 as f32;
+        let after = to_delta + (after - to).0 as f32;
+        let delta = Hermite::new(0.0, (to_delta - before) * 0.5, to_delta, after * 0.5);
+        from + Self(delta.sample(value) as i32 as AngleRepr)
+    }
+
     /// Increases clockwise with straight up being 0. Output always 0..=359, never 360.
     pub fn to_bearing(self) -> u16 {
         ((Self::PI_2 - self).0 as u16 as u32 * 360 / (u16::MAX as u32 + 1)) as u16
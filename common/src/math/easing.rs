@@ -0,0 +1,209 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+/// Maps linear progress in `[0, 1]` to eased progress, which starts at 0 and ends at 1, but may
+/// overshoot in between (e.g. [`Self::BackOut`] and [`Self::ElasticOut`]).
+///
+/// `In` variants start slow, `Out` variants end slow, and `InOut` variants do both. Polynomial
+/// easings (including back and bounce) are deterministic across platforms, whereas sine, expo,
+/// and elastic use transcendental functions so are only suitable for client animations.
+///
+/// See <https://easings.net> for plots.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    QuartIn,
+    QuartOut,
+    QuartInOut,
+    QuintIn,
+    QuintOut,
+    QuintInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    CircIn,
+    CircOut,
+    CircInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+    /// Cubic with zero slope at both ends.
+    SmoothStep,
+    /// Quintic with zero slope and curvature at both ends.
+    SmootherStep,
+}
+
+impl Easing {
+    pub const ALL: [Self; 33] = [
+        Self::Linear,
+        Self::QuadIn,
+        Self::QuadOut,
+        Self::QuadInOut,
+        Self::CubicIn,
+        Self::CubicOut,
+        Self::CubicInOut,
+        Self::QuartIn,
+        Self::QuartOut,
+        Self::QuartInOut,
+        Self::QuintIn,
+        Self::QuintOut,
+        Self::QuintInOut,
+        Self::SineIn,
+        Self::SineOut,
+        Self::SineInOut,
+        Self::ExpoIn,
+        Self::ExpoOut,
+        Self::ExpoInOut,
+        Self::CircIn,
+        Self::CircOut,
+        Self::CircInOut,
+        Self::BackIn,
+        Self::BackOut,
+        Self::BackInOut,
+        Self::ElasticIn,
+        Self::ElasticOut,
+        Self::ElasticInOut,
+        Self::BounceIn,
+        Self::BounceOut,
+        Self::BounceInOut,
+        Self::SmoothStep,
+        Self::SmootherStep,
+    ];
+
+    /// Eases `t`, which is clamped to `[0, 1]`.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::QuadIn => quad(t),
+            Self::QuadOut => out(quad, t),
+            Self::QuadInOut => in_out(quad, t),
+            Self::CubicIn => cubic(t),
+            Self::CubicOut => out(cubic, t),
+            Self::CubicInOut => in_out(cubic, t),
+            Self::QuartIn => quart(t),
+            Self::QuartOut => out(quart, t),
+            Self::QuartInOut => in_out(quart, t),
+            Self::QuintIn => quint(t),
+            Self::QuintOut => out(quint, t),
+            Self::QuintInOut => in_out(quint, t),
+            Self::SineIn => sine(t),
+            Self::SineOut => out(sine, t),
+            Self::SineInOut => in_out(sine, t),
+            Self::ExpoIn => expo(t),
+            Self::ExpoOut => out(expo, t),
+            Self::ExpoInOut => in_out(expo, t),
+            Self::CircIn => circ(t),
+            Self::CircOut => out(circ, t),
+            Self::CircInOut => in_out(circ, t),
+            Self::BackIn => back(t),
+            Self::BackOut => out(back, t),
+            Self::BackInOut => in_out(back, t),
+            Self::ElasticIn => elastic(t),
+            Self::ElasticOut => out(elastic, t),
+            Self::ElasticInOut => in_out(elastic, t),
+            Self::BounceIn => bounce(t),
+            Self::BounceOut => out(bounce, t),
+            Self::BounceInOut => in_out(bounce, t),
+            Self::SmoothStep => t * t * (3.0 - 2.0 * t),
+            Self::SmootherStep => t * t * t * (t * (t * 6.0 - 15.0) + 10.0),
+        }
+    }
+}
+
+fn out(ease_in: fn(f32) -> f32, t: f32) -> f32 {
+    1.0 - ease_in(1.0 - t)
+}
+
+fn in_out(ease_in: fn(f32) -> f32, t: f32) -> f32 {
+    if t < 0.5 {
+        ease_in(2.0 * t) * 0.5
+    } else {
+        1.0 - ease_in(2.0 - 2.0 * t) * 0.5
+    }
+}
+
+fn quad(t: f32) -> f32 {
+    t * t
+}
+
+fn cubic(t: f32) -> f32 {
+    t * t * t
+}
+
+fn quart(t: f32) -> f32 {
+    quad(quad(t))
+}
+
+fn quint(t: f32) -> f32 {
+    quart(t) * t
+}
+
+fn sine(t: f32) -> f32 {
+    1.0 - (t * (PI * 0.5)).cos()
+}
+
+fn expo(t: f32) -> f32 {
+    if t == 0.0 {
+        0.0
+    } else {
+        2f32.powf(10.0 * t - 10.0)
+    }
+}
+
+fn circ(t: f32) -> f32 {
+    1.0 - (1.0 - t * t).sqrt()
+}
+
+fn back(t: f32) -> f32 {
+    // Overshoots by 10%.
+    const C: f32 = 1.70158;
+    t * t * ((C + 1.0) * t - C)
+}
+
+fn elastic(t: f32) -> f32 {
+    if t == 0.0 || t == 1.0 {
+        t
+    } else {
+        -(2f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * (2.0 * PI / 3.0)).sin()
+    }
+}
+
+fn bounce(t: f32) -> f32 {
+    // Defined as bouncing in reverse, since that's more intuitive.
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    let t = 1.0 - t;
+    let out = if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    };
+    1.0 - out
+}
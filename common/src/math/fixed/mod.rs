@@ -0,0 +1,9 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+mod scalar;
+mod tests;
+mod vec2;
+
+pub use self::scalar::{Fixed32, Fixed64};
+pub use self::vec2::{Fixed32Vec2, Fixed64Vec2};
@@ -0,0 +1,471 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use crate::bitcode::{self, *};
+use crate::Angle;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{
+    Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign, Sub, SubAssign,
+};
+
+/// Q32.32 bits of PI, rounded.
+const PI_Q32: i64 = 13493037705;
+/// Q32.32 bits of PI / 2, rounded.
+const FRAC_PI_2_Q32: i64 = 6746518852;
+/// Q32.32 bits of PI / 4, rounded.
+const FRAC_PI_4_Q32: i64 = 3373259426;
+/// Q32.32 bits of 2 * PI, rounded.
+const TAU_Q32: i64 = 26986075409;
+const ONE_Q32: i64 = 1 << 32;
+
+macro_rules! fixed {
+    ($name:ident, $repr:ty, $wide:ty, $unsigned_wide:ty, $frac:literal, $doc:literal) => {
+        #[doc = $doc]
+        ///
+        /// All methods and trait `impl`s are cross-platform deterministic, so it can be used
+        /// instead of `f32` in lockstep games. Arithmetic panics on overflow in debug mode, like
+        /// integers.
+        #[derive(
+            Copy,
+            Clone,
+            Default,
+            Hash,
+            Eq,
+            PartialEq,
+            Ord,
+            PartialOrd,
+            Serialize,
+            Deserialize,
+            Encode,
+            Decode,
+        )]
+        pub struct $name(pub $repr);
+
+        impl $name {
+            pub const EPSILON: Self = Self(1);
+            /// Number of fractional bits.
+            pub const FRAC_BITS: u32 = $frac;
+            pub const FRAC_PI_2: Self = Self::from_q32(FRAC_PI_2_Q32);
+            pub const FRAC_PI_4: Self = Self::from_q32(FRAC_PI_4_Q32);
+            pub const HALF: Self = Self(1 << ($frac - 1));
+            pub const MAX: Self = Self(<$repr>::MAX);
+            pub const MIN: Self = Self(<$repr>::MIN);
+            pub const NEG_ONE: Self = Self(-1 << $frac);
+            pub const ONE: Self = Self(1 << $frac);
+            pub const PI: Self = Self::from_q32(PI_Q32);
+            pub const TAU: Self = Self::from_q32(TAU_Q32);
+            pub const ZERO: Self = Self(0);
+
+            /// Rounds Q32.32 bits to this precision.
+            const fn from_q32(bits: i64) -> Self {
+                let shift = 32 - $frac;
+                Self(((bits + ((1 << shift) >> 1)) >> shift) as $repr)
+            }
+
+            /// Converts to Q32.32 bits.
+            const fn to_q32(self) -> i64 {
+                (self.0 as i64) << (32 - $frac)
+            }
+
+            pub const fn from_bits(bits: $repr) -> Self {
+                Self(bits)
+            }
+
+            pub const fn to_bits(self) -> $repr {
+                self.0
+            }
+
+            /// **Panics**
+            ///
+            /// In debug mode if `i` is out of range.
+            pub const fn from_int(i: $repr) -> Self {
+                Self(i << $frac)
+            }
+
+            /// Returns `numerator / denominator`, rounded toward zero.
+            pub const fn from_ratio(numerator: $repr, denominator: $repr) -> Self {
+                Self((((numerator as $wide) << $frac) / denominator as $wide) as $repr)
+            }
+
+            /// Rounds to the nearest representable value, saturating. Deterministic, although
+            /// whatever computed `f` might not be.
+            pub fn from_f32(f: f32) -> Self {
+                Self::from_f64(f as f64)
+            }
+
+            /// Rounds to the nearest representable value, saturating. Deterministic, although
+            /// whatever computed `f` might not be.
+            pub fn from_f64(f: f64) -> Self {
+                Self((f * (1u64 << $frac) as f64).round() as $repr)
+            }
+
+            pub fn to_f32(self) -> f32 {
+                self.to_f64() as f32
+            }
+
+            pub fn to_f64(self) -> f64 {
+                self.0 as f64 * (1.0 / (1u64 << $frac) as f64)
+            }
+
+            /// Largest integer less than or equal to `self`.
+            pub const fn to_int(self) -> $repr {
+                self.0 >> $frac
+            }
+
+            pub const fn floor(self) -> Self {
+                Self(self.0 & !((1 << $frac) - 1))
+            }
+
+            pub const fn ceil(self) -> Self {
+                Self(self.0.wrapping_add((1 << $frac) - 1) & !((1 << $frac) - 1))
+            }
+
+            /// Rounds half way cases up.
+            pub const fn round(self) -> Self {
+                Self(self.0.wrapping_add(1 << ($frac - 1)) & !((1 << $frac) - 1))
+            }
+
+            /// Always positive, i.e. `self - self.floor()`.
+            pub const fn fract(self) -> Self {
+                Self(self.0 & ((1 << $frac) - 1))
+            }
+
+            pub const fn abs(self) -> Self {
+                Self(self.0.abs())
+            }
+
+            pub const fn signum(self) -> Self {
+                Self::from_int(self.0.signum())
+            }
+
+            pub const fn is_negative(self) -> bool {
+                self.0 < 0
+            }
+
+            pub const fn saturating_add(self, rhs: Self) -> Self {
+                Self(self.0.saturating_add(rhs.0))
+            }
+
+            pub const fn saturating_sub(self, rhs: Self) -> Self {
+                Self(self.0.saturating_sub(rhs.0))
+            }
+
+            pub const fn saturating_mul(self, rhs: Self) -> Self {
+                let wide = (self.0 as $wide * rhs.0 as $wide) >> $frac;
+                if wide > <$repr>::MAX as $wide {
+                    Self::MAX
+                } else if wide < <$repr>::MIN as $wide {
+                    Self::MIN
+                } else {
+                    Self(wide as $repr)
+                }
+            }
+
+            pub const fn checked_div(self, rhs: Self) -> Option<Self> {
+                if rhs.0 == 0 {
+                    None
+                } else {
+                    Some(Self(
+                        (((self.0 as $wide) << $frac) / rhs.0 as $wide) as $repr,
+                    ))
+                }
+            }
+
+            pub fn recip(self) -> Self {
+                Self::ONE / self
+            }
+
+            /// Returns zero if `self` is negative.
+            pub fn sqrt(self) -> Self {
+                if self.0 <= 0 {
+                    debug_assert!(self.0 == 0, "sqrt of negative");
+                    return Self::ZERO;
+                }
+                Self((((self.0 as $unsigned_wide) << $frac).isqrt()) as $repr)
+            }
+
+            pub fn lerp(self, other: Self, t: Self) -> Self {
+                self + (other - self) * t
+            }
+
+            /// Replacement for [`f32::sin_cos`], with an error of less than 1e-8 (plus rounding).
+            pub fn sin_cos(self) -> (Self, Self) {
+                let (sin, cos) = sin_cos_turns(radians_q32_to_turns(self.to_q32()));
+                (Self::from_q32(sin), Self::from_q32(cos))
+            }
+
+            pub fn sin(self) -> Self {
+                self.sin_cos().0
+            }
+
+            pub fn cos(self) -> Self {
+                self.sin_cos().1
+            }
+
+            /// Returns [`Self::MAX`] or [`Self::MIN`] if `cos` is zero.
+            pub fn tan(self) -> Self {
+                let (sin, cos) = sin_cos_turns(radians_q32_to_turns(self.to_q32()));
+                if cos == 0 {
+                    return if sin < 0 { Self::MIN } else { Self::MAX };
+                }
+                let q32 = ((sin as i128) << 32) / cos as i128;
+                let (min, max) = (Self::MIN.to_q32() as i128, Self::MAX.to_q32() as i128);
+                Self::from_q32(q32.clamp(min, max) as i64)
+            }
+
+            /// Replacement for [`f32::atan2`], returning radians in the range [-PI, PI], with an
+            /// error of less than 1e-8 (plus rounding). Returns zero if both are zero.
+            pub fn atan2(self, x: Self) -> Self {
+                Self::from_q32(atan2_q32(self.to_q32(), x.to_q32()))
+            }
+
+            /// Converts an [`Angle`] to radians.
+            pub fn from_angle(angle: Angle) -> Self {
+                Self::from_q32((angle.0 as i64 * TAU_Q32) >> 16)
+            }
+
+            /// Converts radians to an [`Angle`], wrapping.
+            pub fn to_angle(self) -> Angle {
+                turns_to_angle(radians_q32_to_turns(self.to_q32()))
+            }
+        }
+
+        impl From<Angle> for $name {
+            fn from(angle: Angle) -> Self {
+                Self::from_angle(angle)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&self.to_f64(), f)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.to_f64(), f)
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self::Output {
+                Self(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self::Output {
+                Self(self.0 - rhs.0)
+            }
+        }
+
+        impl Mul for $name {
+            type Output = Self;
+
+            /// Rounds toward negative infinity.
+            fn mul(self, rhs: Self) -> Self::Output {
+                let wide = (self.0 as $wide * rhs.0 as $wide) >> $frac;
+                debug_assert!(
+                    wide >= <$repr>::MIN as $wide && wide <= <$repr>::MAX as $wide,
+                    "multiply with overflow"
+                );
+                Self(wide as $repr)
+            }
+        }
+
+        impl Div for $name {
+            type Output = Self;
+
+            /// Rounds toward zero.
+            fn div(self, rhs: Self) -> Self::Output {
+                let wide = ((self.0 as $wide) << $frac) / rhs.0 as $wide;
+                debug_assert!(
+                    wide >= <$repr>::MIN as $wide && wide <= <$repr>::MAX as $wide,
+                    "divide with overflow"
+                );
+                Self(wide as $repr)
+            }
+        }
+
+        impl Rem for $name {
+            type Output = Self;
+
+            fn rem(self, rhs: Self) -> Self::Output {
+                Self(self.0 % rhs.0)
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self::Output {
+                Self(-self.0)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: Self) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: Self) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl MulAssign for $name {
+            fn mul_assign(&mut self, rhs: Self) {
+                *self = *self * rhs;
+            }
+        }
+
+        impl DivAssign for $name {
+            fn div_assign(&mut self, rhs: Self) {
+                *self = *self / rhs;
+            }
+        }
+
+        impl RemAssign for $name {
+            fn rem_assign(&mut self, rhs: Self) {
+                *self = *self % rhs;
+            }
+        }
+
+        impl std::iter::Sum for $name {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                iter.fold(Self::ZERO, Add::add)
+            }
+        }
+    };
+}
+
+fixed!(
+    Fixed32,
+    i32,
+    i64,
+    u64,
+    16,
+    "A Q16.16 fixed-point number, with a range of about ±32768 and a precision of 1/65536."
+);
+fixed!(
+    Fixed64,
+    i64,
+    i128,
+    u128,
+    32,
+    "A Q32.32 fixed-point number, with a range of about ±2 billion and a precision of 1/2^32."
+);
+
+impl From<Fixed32> for Fixed64 {
+    fn from(f: Fixed32) -> Self {
+        Self(f.to_q32())
+    }
+}
+
+impl From<Fixed64> for Fixed32 {
+    /// Rounds to the nearest [`Fixed32`].
+    fn from(f: Fixed64) -> Self {
+        Self::from_q32(f.0)
+    }
+}
+
+/// Multiplies Q32.32 bits, rounding toward negative infinity.
+fn mul_q32(a: i64, b: i64) -> i64 {
+    ((a as i128 * b as i128) >> 32) as i64
+}
+
+/// Converts Q32.32 radians to a fraction of a turn, wrapping.
+fn radians_q32_to_turns(radians: i64) -> u32 {
+    (((radians as i128) << 32).div_euclid(TAU_Q32 as i128) as u64) as u32
+}
+
+/// Rounds a fraction of a turn to the nearest [`Angle`].
+pub(crate) fn turns_to_angle(turns: u32) -> Angle {
+    Angle((turns.wrapping_add(1 << 15) >> 16) as u16 as i16)
+}
+
+/// Converts an [`Angle`] to a fraction of a turn.
+pub(crate) fn angle_to_turns(angle: Angle) -> u32 {
+    (angle.0 as u16 as u32) << 16
+}
+
+/// Returns Q32.32 bits of (sin, cos) of a fraction of a turn.
+pub(crate) fn sin_cos_turns(turns: u32) -> (i64, i64) {
+    const QUARTER_BITS: u32 = 30;
+    let quadrant = turns >> QUARTER_BITS;
+    let remainder = turns & ((1 << QUARTER_BITS) - 1);
+    // [0, PI / 2)
+    let theta = ((remainder as i128 * FRAC_PI_2_Q32 as i128) >> QUARTER_BITS) as i64;
+    let theta2 = mul_q32(theta, theta);
+
+    // Taylor series, evaluated with Horner's method.
+    let mut sin = ONE_Q32;
+    for n in [156, 110, 72, 42, 20, 6] {
+        sin = ONE_Q32 - mul_q32(theta2, sin) / n;
+    }
+    let sin = mul_q32(theta, sin);
+    let mut cos = ONE_Q32;
+    for n in [182, 132, 90, 56, 30, 12, 2] {
+        cos = ONE_Q32 - mul_q32(theta2, cos) / n;
+    }
+
+    match quadrant {
+        0 => (sin, cos),
+        1 => (cos, -sin),
+        2 => (-sin, -cos),
+        _ => (-cos, sin),
+    }
+}
+
+/// Returns Q32.32 bits of atan(a) for `a` in [0, 1].
+fn atan_unit_q32(a: i64) -> i64 {
+    // tan(PI / 8)
+    const TAN_FRAC_PI_8_Q32: i64 = 1779033704;
+    debug_assert!((0..=ONE_Q32).contains(&a));
+    // atan(a) = PI / 4 + atan((a - 1) / (a + 1)), bringing `a` within ±tan(PI / 8).
+    let (offset, a) = if a > TAN_FRAC_PI_8_Q32 {
+        (
+            FRAC_PI_4_Q32,
+            (((a - ONE_Q32) as i128) << 32).div_euclid((a + ONE_Q32) as i128) as i64,
+        )
+    } else {
+        (0, a)
+    };
+    let a2 = mul_q32(a, a);
+
+    // Taylor series, evaluated with Horner's method.
+    let mut sum = 0;
+    for n in [19, 17, 15, 13, 11, 9, 7, 5, 3] {
+        sum = ONE_Q32 / n - mul_q32(a2, sum);
+    }
+    sum = ONE_Q32 - mul_q32(a2, sum);
+    offset + mul_q32(a, sum)
+}
+
+/// Returns Q32.32 bits of atan2(y, x).
+pub(crate) fn atan2_q32(y: i64, x: i64) -> i64 {
+    let (ax, ay) = (x.unsigned_abs(), y.unsigned_abs());
+    if ax == 0 && ay == 0 {
+        return 0;
+    }
+    let a = ((ax.min(ay) as u128) << 32) / ax.max(ay) as u128;
+    let mut r = atan_unit_q32(a as i64);
+    if ay > ax {
+        r = FRAC_PI_2_Q32 - r;
+    }
+    if x < 0 {
+        r = PI_Q32 - r;
+    }
+    if y < 0 {
+        r = -r;
+    }
+    r
+}
@@ -0,0 +1,215 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+#[cfg(test)]
+mod fixed_tests {
+    use crate::{Angle, AngleRepr, Fixed32, Fixed32Vec2, Fixed64, Fixed64Vec2, FixedCollider2d};
+
+    // Expected bits were computed on x86_64 and must match on every platform, e.g. wasm32.
+
+    #[test]
+    fn arithmetic() {
+        let a = Fixed32::from_f32(1.5);
+        let b = Fixed32::from_f32(-2.25);
+        assert_eq!((a * b).0, -221184);
+        assert_eq!((a / b).0, -43690);
+        assert_eq!(b.floor(), Fixed32::from_int(-3));
+        assert_eq!(b.ceil(), Fixed32::from_int(-2));
+        assert_eq!(b.round(), Fixed32::from_int(-2));
+        assert_eq!(b.fract(), Fixed32::from_f32(0.75));
+        assert_eq!(b.to_int(), -3);
+        assert_eq!(Fixed32::from_ratio(1, 3).0, 21845);
+        assert_eq!(Fixed32::MAX.saturating_mul(a), Fixed32::MAX);
+        assert_eq!(a.checked_div(Fixed32::ZERO), None);
+        assert_eq!(Fixed64::from(a), Fixed64::from_f32(1.5));
+        assert_eq!(Fixed32::from(Fixed64::from_f64(1.0 / 3.0)).0, 21845);
+    }
+
+    #[test]
+    fn constants() {
+        assert_eq!(Fixed32::PI.0, 205887);
+        assert_eq!(Fixed64::PI.0, 13493037705);
+        assert_eq!(Fixed32::HALF, Fixed32::from_ratio(1, 2));
+        assert_eq!(Fixed64::TAU, Fixed64::PI + Fixed64::PI - Fixed64::EPSILON);
+    }
+
+    #[test]
+    fn sqrt() {
+        assert_eq!(Fixed32::from_int(2).sqrt().0, 92681);
+        assert_eq!(Fixed64::from_int(2).sqrt().0, 6074000999);
+        assert_eq!(Fixed32::from_int(16).sqrt(), Fixed32::from_int(4));
+        assert_eq!(Fixed64::ZERO.sqrt(), Fixed64::ZERO);
+    }
+
+    #[test]
+    fn trig() {
+        assert_eq!(Fixed32::ONE.sin().0, 55147);
+        assert_eq!(Fixed32::ONE.cos().0, 35409);
+        assert_eq!(Fixed32::ONE.tan().0, 102066);
+        assert_eq!(Fixed32::ONE.atan2(Fixed32::NEG_ONE).0, 154416);
+        assert_eq!(Fixed64::ONE.sin().0, 3614090358);
+        assert_eq!(Fixed64::ONE.cos().0, 2320580737);
+        assert_eq!(Fixed64::ONE.tan().0, 6689015230);
+        assert_eq!(Fixed64::ONE.atan2(Fixed64::NEG_ONE).0, 10119778279);
+        assert_eq!(Fixed64::ZERO.atan2(Fixed64::ZERO), Fixed64::ZERO);
+
+        for i in -5000..5000 {
+            let x = Fixed64::from_f64(i as f64 * 0.00731);
+            let (sin, cos) = x.sin_cos();
+            assert!((sin.to_f64() - x.to_f64().sin()).abs() < 1e-8, "sin({x})");
+            assert!((cos.to_f64() - x.to_f64().cos()).abs() < 1e-8, "cos({x})");
+            for y in [-3.0, -1.0, -0.3, 0.7, 2.0] {
+                let y = Fixed64::from_f64(y);
+                let atan2 = y.atan2(x).to_f64();
+                let expected = y.to_f64().atan2(x.to_f64());
+                assert!((atan2 - expected).abs() < 1e-8, "atan2({y}, {x})");
+            }
+        }
+    }
+
+    #[test]
+    fn angle() {
+        let vec = Fixed32Vec2::from_angle(Angle(5461));
+        assert_eq!((vec.x.0, vec.y.0), (56757, 32766));
+        let vec = Fixed64Vec2::from_angle(Angle(8192));
+        assert_eq!((vec.x.0, vec.y.0), (3037000501, 3037000500));
+        assert_eq!(Fixed32::from_angle(Angle(-16384)).0, -102944);
+
+        for i in AngleRepr::MIN..=AngleRepr::MAX {
+            let angle = Angle(i);
+            assert_eq!(Fixed32Vec2::from_angle(angle).to_angle(), angle);
+            assert_eq!(Fixed64Vec2::from_angle(angle).to_angle(), angle);
+            assert_eq!(Fixed64::from_angle(angle).to_angle(), angle);
+        }
+    }
+
+    #[test]
+    fn vec2() {
+        let v = Fixed32Vec2::new(Fixed32::from_int(3), Fixed32::from_int(4));
+        assert_eq!(v.length(), Fixed32::from_int(5));
+        assert_eq!(
+            v.normalize_or_zero(),
+            Fixed32Vec2::new(Fixed32(39321), Fixed32(52428))
+        );
+        assert_eq!(Fixed32Vec2::ZERO.normalize_or_zero(), Fixed32Vec2::ZERO);
+        assert_eq!(
+            Fixed32Vec2::splat(Fixed32::from_int(30000)).length(),
+            Fixed32::MAX
+        );
+        assert_eq!(v.perp_dot(v.perp()), v.length_squared());
+    }
+
+    /// A small simulation, to detect any platform differences.
+    #[test]
+    fn simulation() {
+        fn hash(h: u64, bits: i64) -> u64 {
+            h.wrapping_mul(31).wrapping_add(bits as u64)
+        }
+
+        let mut pos = Fixed32Vec2::ZERO;
+        let mut vel = Fixed32Vec2::new(Fixed32::from_ratio(1, 3), Fixed32::ZERO);
+        let mut h = 0;
+        for i in 0..1000 {
+            let dir = Fixed32Vec2::from_angle(Angle((i * 997) as AngleRepr));
+            vel = (vel + dir * Fixed32::from_ratio(1, 60)) * Fixed32::from_ratio(99, 100);
+            pos += vel;
+            h = hash(h, pos.x.0 as u32 as i64);
+            h = hash(h, pos.y.0 as u32 as i64);
+            h = hash(h, pos.to_angle().0 as u16 as i64);
+            h = h.wrapping_add(pos.length().sqrt().0 as u64);
+        }
+        assert_eq!(h, 11079061948956881679);
+
+        let mut pos = Fixed64Vec2::ZERO;
+        let mut vel = Fixed64Vec2::new(Fixed64::from_ratio(1, 3), Fixed64::ZERO);
+        let mut h = 0;
+        for i in 0..1000 {
+            let dir = Fixed64Vec2::from_angle(Angle((i * 997) as AngleRepr));
+            vel = (vel + dir * Fixed64::from_ratio(1, 60)) * Fixed64::from_ratio(99, 100);
+            pos += vel;
+            h = hash(h, pos.x.0);
+            h = hash(h, pos.y.0);
+            h = hash(h, pos.to_angle().0 as u16 as i64);
+            h = h.wrapping_add(pos.length().sqrt().0 as u64);
+        }
+        assert_eq!(h, 1075756622256892445);
+    }
+
+    #[test]
+    fn collider() {
+        let int = |x: i64, y: i64| Fixed64Vec2::new(Fixed64::from_int(x), Fixed64::from_int(y));
+        let circle = FixedCollider2d::circle(int(0, 0), Fixed64::from_int(2));
+        let rectangle = FixedCollider2d::rotated_rectangle(int(3, 0), int(2, 2), Angle::PI_4);
+        assert!(circle.collides(&rectangle));
+        assert!(rectangle.collides(&circle));
+        assert!(!circle.collides(&FixedCollider2d::circle(int(5, 0), Fixed64::from_int(2))));
+
+        let far = FixedCollider2d::rotated_rectangle(int(100000, 0), int(2, 2), Angle::ZERO);
+        assert!(!rectangle.collides(&far));
+        let near = FixedCollider2d::rotated_rectangle(int(5, 0), int(2, 2), Angle::ZERO);
+        // Rotated 45 degrees, so reaches sqrt(2) from its center.
+        assert!(rectangle.collides(&near));
+        let not_near = FixedCollider2d::rotated_rectangle(int(6, 1), int(2, 2), Angle::ZERO);
+        assert!(!rectangle.collides(&not_near));
+
+        let float = rectangle.to_collider_2d();
+        assert_eq!(float.center(), glam::Vec2::new(3.0, 0.0));
+        assert!(float.collides(&circle.to_collider_2d()));
+    }
+
+    #[test]
+    fn convex_collider() {
+        use crate::collider_2d_test_helpers::{moved, random_collider};
+        use crate::{Collider2d, ConvexPolygon};
+        use glam::{vec2, Vec2};
+        use rand::prelude::*;
+        use rand_chacha::ChaCha20Rng;
+
+        let int = |x: i64, y: i64| Fixed64Vec2::new(Fixed64::from_int(x), Fixed64::from_int(y));
+        let capsule = FixedCollider2d::capsule(int(0, 0), int(4, 0), Fixed64::ONE);
+        assert_eq!(capsule.center(), int(2, 0));
+        assert!(capsule.collides(&FixedCollider2d::circle(int(2, 2), Fixed64::ONE)));
+        assert!(!capsule.collides(&FixedCollider2d::circle(int(2, 3), Fixed64::ONE)));
+        assert!(capsule.collides(&FixedCollider2d::circle(int(6, 0), Fixed64::ONE)));
+
+        let triangle = Collider2d::ConvexPolygon(
+            ConvexPolygon::new([vec2(0.0, 0.0), vec2(3.0, 0.0), vec2(0.0, 3.0)]).unwrap(),
+        );
+        let fixed_triangle = FixedCollider2d::from(triangle);
+        assert_eq!(fixed_triangle.center(), int(1, 1));
+        assert!(fixed_triangle.collides(&capsule));
+        assert_eq!(fixed_triangle.to_collider_2d().center(), Vec2::ONE);
+
+        // Invalid, e.g. decoded from untrusted data, but doesn't panic.
+        let mut vertices = [int(0, 0); ConvexPolygon::MAX_VERTICES];
+        vertices[..3].copy_from_slice(&[int(0, 0), int(3, 0), int(0, 3)]);
+        for len in [0, 1, 2, 3, u8::MAX] {
+            let polygon = FixedCollider2d::ConvexPolygon { vertices, len };
+            assert_eq!(polygon.collides(&capsule), len > 0);
+            assert_eq!(
+                polygon.to_collider_2d().center(),
+                polygon.center().to_vec2()
+            );
+        }
+
+        // Agrees with floating point, except when barely touching.
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let mut checked = 0;
+        for _ in 0..5000 {
+            let [a, b] = [(); 2].map(|_| random_collider(&mut rng, 5.0));
+            let expected = a.collides(&b);
+            let barely = (0..8).any(|i| {
+                let offset = Vec2::from_angle(i as f32 * std::f32::consts::FRAC_PI_4) * 0.01;
+                a.collides(&moved(b, offset)) != expected
+            });
+            if barely {
+                continue;
+            }
+            checked += 1;
+            let (a, b) = (FixedCollider2d::from(a), FixedCollider2d::from(b));
+            assert_eq!(a.collides(&b), expected);
+            assert_eq!(b.collides(&a), expected);
+        }
+        assert!(checked > 4500, "{checked}");
+    }
+}
@@ -0,0 +1,230 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::scalar::{angle_to_turns, atan2_q32, sin_cos_turns};
+use super::{Fixed32, Fixed64};
+use crate::bitcode::{self, *};
+use crate::Angle;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+macro_rules! fixed_vec2 {
+    ($name:ident, $scalar:ident, $repr:ty, $wide:ty, $unsigned_wide:ty) => {
+        /// A 2D vector of
+        #[doc = concat!("[`", stringify!($scalar), "`]s.")]
+        #[derive(
+            Copy, Clone, Default, Hash, Eq, PartialEq, Serialize, Deserialize, Encode, Decode,
+        )]
+        pub struct $name {
+            pub x: $scalar,
+            pub y: $scalar,
+        }
+
+        impl $name {
+            pub const NEG_X: Self = Self::new($scalar::NEG_ONE, $scalar::ZERO);
+            pub const NEG_Y: Self = Self::new($scalar::ZERO, $scalar::NEG_ONE);
+            pub const ONE: Self = Self::splat($scalar::ONE);
+            pub const X: Self = Self::new($scalar::ONE, $scalar::ZERO);
+            pub const Y: Self = Self::new($scalar::ZERO, $scalar::ONE);
+            pub const ZERO: Self = Self::splat($scalar::ZERO);
+
+            pub const fn new(x: $scalar, y: $scalar) -> Self {
+                Self { x, y }
+            }
+
+            pub const fn splat(v: $scalar) -> Self {
+                Self { x: v, y: v }
+            }
+
+            /// Rounds each component to the nearest representable value, saturating.
+            pub fn from_vec2(v: Vec2) -> Self {
+                Self::new($scalar::from_f32(v.x), $scalar::from_f32(v.y))
+            }
+
+            pub fn to_vec2(self) -> Vec2 {
+                Vec2::new(self.x.to_f32(), self.y.to_f32())
+            }
+
+            /// Unit vector pointing in the direction of `angle`. Replacement for
+            /// [`Angle::to_vec`].
+            pub fn from_angle(angle: Angle) -> Self {
+                let (sin, cos) = sin_cos_turns(angle_to_turns(angle));
+                Self::new($scalar::from(Fixed64(cos)), $scalar::from(Fixed64(sin)))
+            }
+
+            /// Replacement for [`Angle::from_vec`]. Returns [`Angle::ZERO`] for [`Self::ZERO`].
+            pub fn to_angle(self) -> Angle {
+                Fixed64(atan2_q32(Fixed64::from(self.y).0, Fixed64::from(self.x).0)).to_angle()
+            }
+
+            pub fn dot(self, rhs: Self) -> $scalar {
+                self.x * rhs.x + self.y * rhs.y
+            }
+
+            /// Rotated 90 degrees counter-clockwise.
+            pub fn perp(self) -> Self {
+                Self::new(-self.y, self.x)
+            }
+
+            /// The z component of the 3D cross product.
+            pub fn perp_dot(self, rhs: Self) -> $scalar {
+                self.x * rhs.y - self.y * rhs.x
+            }
+
+            /// Rotates `rhs` by the angle of `self`, scaling it by the length of `self`.
+            /// Like [`Vec2::rotate`].
+            pub fn rotate(self, rhs: Self) -> Self {
+                Self::new(
+                    self.x * rhs.x - self.y * rhs.y,
+                    self.y * rhs.x + self.x * rhs.y,
+                )
+            }
+
+            /// May overflow for long vectors, unlike [`Self::length`].
+            pub fn length_squared(self) -> $scalar {
+                self.dot(self)
+            }
+
+            /// Computed without intermediate overflow. Saturates at [`
+            #[doc = concat!(stringify!($scalar), "::MAX`].")]
+            pub fn length(self) -> $scalar {
+                let x = self.x.0 as $wide;
+                let y = self.y.0 as $wide;
+                let squared = (x * x) as $unsigned_wide + (y * y) as $unsigned_wide;
+                $scalar(squared.isqrt().min(<$repr>::MAX as $unsigned_wide) as $repr)
+            }
+
+            pub fn distance_squared(self, rhs: Self) -> $scalar {
+                (self - rhs).length_squared()
+            }
+
+            pub fn distance(self, rhs: Self) -> $scalar {
+                (self - rhs).length()
+            }
+
+            /// Returns [`Self::ZERO`] if the length is zero.
+            pub fn normalize_or_zero(self) -> Self {
+                let length = self.length();
+                if length == $scalar::ZERO {
+                    Self::ZERO
+                } else {
+                    self / length
+                }
+            }
+
+            pub fn abs(self) -> Self {
+                Self::new(self.x.abs(), self.y.abs())
+            }
+
+            pub fn min(self, rhs: Self) -> Self {
+                Self::new(self.x.min(rhs.x), self.y.min(rhs.y))
+            }
+
+            pub fn max(self, rhs: Self) -> Self {
+                Self::new(self.x.max(rhs.x), self.y.max(rhs.y))
+            }
+
+            pub fn clamp(self, min: Self, max: Self) -> Self {
+                Self::new(self.x.clamp(min.x, max.x), self.y.clamp(min.y, max.y))
+            }
+
+            pub fn lerp(self, rhs: Self, t: $scalar) -> Self {
+                self + (rhs - self) * t
+            }
+        }
+
+        impl From<Angle> for $name {
+            fn from(angle: Angle) -> Self {
+                Self::from_angle(angle)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "[{:?}, {:?}]", self.x, self.y)
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self::Output {
+                Self::new(self.x + rhs.x, self.y + rhs.y)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self::Output {
+                Self::new(self.x - rhs.x, self.y - rhs.y)
+            }
+        }
+
+        impl Mul<$scalar> for $name {
+            type Output = Self;
+
+            fn mul(self, rhs: $scalar) -> Self::Output {
+                Self::new(self.x * rhs, self.y * rhs)
+            }
+        }
+
+        impl Div<$scalar> for $name {
+            type Output = Self;
+
+            fn div(self, rhs: $scalar) -> Self::Output {
+                Self::new(self.x / rhs, self.y / rhs)
+            }
+        }
+
+        impl Neg for $name {
+            type Output = Self;
+
+            fn neg(self) -> Self::Output {
+                Self::new(-self.x, -self.y)
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: Self) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, rhs: Self) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl MulAssign<$scalar> for $name {
+            fn mul_assign(&mut self, rhs: $scalar) {
+                *self = *self * rhs;
+            }
+        }
+
+        impl DivAssign<$scalar> for $name {
+            fn div_assign(&mut self, rhs: $scalar) {
+                *self = *self / rhs;
+            }
+        }
+    };
+}
+
+fixed_vec2!(Fixed32Vec2, Fixed32, i32, i64, u64);
+fixed_vec2!(Fixed64Vec2, Fixed64, i64, i128, u128);
+
+impl From<Fixed32Vec2> for Fixed64Vec2 {
+    fn from(v: Fixed32Vec2) -> Self {
+        Self::new(v.x.into(), v.y.into())
+    }
+}
+
+impl From<Fixed64Vec2> for Fixed32Vec2 {
+    /// Rounds to the nearest [`Fixed32Vec2`].
+    fn from(v: Fixed64Vec2) -> Self {
+        Self::new(v.x.into(), v.y.into())
+    }
+}
@@ -0,0 +1,85 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use glam::Vec2;
+
+/// Axis-aligned bounding box.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Aabb2d {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Aabb2d {
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        debug_assert!(min.cmple(max).all());
+        Self { min, max }
+    }
+
+    pub fn from_center_half_size(center: Vec2, half_size: Vec2) -> Self {
+        Self::new(center - half_size, center + half_size)
+    }
+
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_size(&self) -> Vec2 {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn perimeter(&self) -> f32 {
+        let size = self.max - self.min;
+        (size.x + size.y) * 2.0
+    }
+
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// Returns true if `other` is entirely inside `self`.
+    pub fn contains(&self, other: &Self) -> bool {
+        other.min.cmpge(self.min).all() && other.max.cmple(self.max).all()
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+
+    /// Returns the smallest [`Aabb2d`] containing both.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Grows each side by `margin`.
+    pub fn expand(&self, margin: f32) -> Self {
+        Self {
+            min: self.min - margin,
+            max: self.max + margin,
+        }
+    }
+
+    /// Returns the distance along a ray, starting at `origin` in the normalized `direction`, to
+    /// where it enters the box (zero if it starts inside), if it does so within `max_distance`.
+    pub fn ray_distance(&self, origin: Vec2, direction: Vec2, max_distance: f32) -> Option<f32> {
+        let mut enter = 0f32;
+        let mut exit = max_distance;
+        for i in 0..2 {
+            if direction[i] == 0.0 {
+                if origin[i] < self.min[i] || origin[i] > self.max[i] {
+                    return None;
+                }
+            } else {
+                let inverse = direction[i].recip();
+                let a = (self.min[i] - origin[i]) * inverse;
+                let b = (self.max[i] - origin[i]) * inverse;
+                enter = enter.max(a.min(b));
+                exit = exit.min(a.max(b));
+            }
+        }
+        (enter <= exit).then_some(enter)
+    }
+}
@@ -0,0 +1,415 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{Aabb2d, Collider2d};
+use glam::Vec2;
+
+const NULL: u32 = u32::MAX;
+
+/// Identifies a collider within a [`Broadphase2d`]. Keys of removed colliders may be reused.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct BroadphaseKey2d(u32);
+
+/// A dynamic AABB tree of [`Collider2d`]s, for finding potential collisions without comparing
+/// every pair of colliders.
+///
+/// Each collider's [`Aabb2d`] is expanded by a margin, so colliders that move slightly don't
+/// have to be reinserted. All results are deterministic, given the same sequence of operations.
+#[derive(Clone)]
+pub struct Broadphase2d<T> {
+    nodes: Vec<Node<T>>,
+    root: u32,
+    free: Vec<u32>,
+    len: usize,
+    margin: f32,
+}
+
+#[derive(Clone)]
+struct Node<T> {
+    /// Expanded by the margin, if a leaf.
+    aabb: Aabb2d,
+    parent: u32,
+    /// [`NULL`] if a leaf.
+    children: [u32; 2],
+    /// Zero if a leaf.
+    height: u32,
+    leaf: Option<Leaf<T>>,
+}
+
+#[derive(Clone)]
+struct Leaf<T> {
+    collider: Collider2d,
+    /// Not expanded by the margin.
+    aabb: Aabb2d,
+    value: T,
+}
+
+impl<T> Broadphase2d<T> {
+    /// Colliders may move up to `margin` in any direction before being reinserted.
+    pub fn new(margin: f32) -> Self {
+        debug_assert!(margin >= 0.0);
+        Self {
+            nodes: Vec::new(),
+            root: NULL,
+            free: Vec::new(),
+            len: 0,
+            margin,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.root = NULL;
+        self.free.clear();
+        self.len = 0;
+    }
+
+    pub fn insert(&mut self, collider: Collider2d, value: T) -> BroadphaseKey2d {
+        let aabb = collider.aabb();
+        let node = Node {
+            aabb: aabb.expand(self.margin),
+            parent: NULL,
+            children: [NULL; 2],
+            height: 0,
+            leaf: Some(Leaf {
+                collider,
+                aabb,
+                value,
+            }),
+        };
+        let index = self.allocate(node);
+        self.insert_leaf(index);
+        self.len += 1;
+        BroadphaseKey2d(index)
+    }
+
+    pub fn remove(&mut self, key: BroadphaseKey2d) -> Option<T> {
+        self.leaf(key)?;
+        self.remove_leaf(key.0);
+        self.len -= 1;
+        self.free.push(key.0);
+        self.nodes[key.0 as usize]
+            .leaf
+            .take()
+            .map(|leaf| leaf.value)
+    }
+
+    /// Moves a collider, returning true if it had to be reinserted because it moved beyond its
+    /// margin.
+    ///
+    /// **Panics**
+    ///
+    /// If `key` doesn't exist.
+    pub fn update(&mut self, key: BroadphaseKey2d, collider: Collider2d) -> bool {
+        let aabb = collider.aabb();
+        let node = &mut self.nodes[key.0 as usize];
+        let leaf = node.leaf.as_mut().expect("doesn't exist");
+        leaf.collider = collider;
+        leaf.aabb = aabb;
+        if node.aabb.contains(&aabb) {
+            return false;
+        }
+        self.remove_leaf(key.0);
+        self.nodes[key.0 as usize].aabb = aabb.expand(self.margin);
+        self.insert_leaf(key.0);
+        true
+    }
+
+    pub fn get(&self, key: BroadphaseKey2d) -> Option<(&Collider2d, &T)> {
+        self.leaf(key).map(|leaf| (&leaf.collider, &leaf.value))
+    }
+
+    pub fn get_mut(&mut self, key: BroadphaseKey2d) -> Option<&mut T> {
+        self.nodes
+            .get_mut(key.0 as usize)?
+            .leaf
+            .as_mut()
+            .map(|leaf| &mut leaf.value)
+    }
+
+    /// Iterates colliders in order of keys.
+    pub fn iter(&self) -> impl Iterator<Item = (BroadphaseKey2d, &Collider2d, &T)> + '_ {
+        self.nodes.iter().enumerate().filter_map(|(index, node)| {
+            node.leaf
+                .as_ref()
+                .map(|leaf| (BroadphaseKey2d(index as u32), &leaf.collider, &leaf.value))
+        })
+    }
+
+    /// Returns every pair of colliders whose [`Aabb2d`]s intersect, sorted, with the lesser key
+    /// first. Use [`Collider2d::collides`] to check whether they actually collide.
+    pub fn pairs(&self) -> Vec<(BroadphaseKey2d, BroadphaseKey2d)> {
+        let mut pairs = Vec::new();
+        // Pairs of nodes whose descendants may intersect each other, or the same node twice for
+        // descendants that may intersect each other.
+        let mut stack = Vec::new();
+        if self.root != NULL {
+            stack.push((self.root, self.root));
+        }
+        while let Some((a, b)) = stack.pop() {
+            if a == b {
+                if let [c, d] = self.nodes[a as usize].children
+                    && c != NULL
+                {
+                    stack.extend([(c, c), (d, d), (c, d)]);
+                }
+                continue;
+            }
+            let (node_a, node_b) = (&self.nodes[a as usize], &self.nodes[b as usize]);
+            if !node_a.aabb.intersects(&node_b.aabb) {
+                continue;
+            }
+            match (&node_a.leaf, &node_b.leaf) {
+                (Some(leaf_a), Some(leaf_b)) => {
+                    if leaf_a.aabb.intersects(&leaf_b.aabb) {
+                        pairs.push((BroadphaseKey2d(a.min(b)), BroadphaseKey2d(a.max(b))));
+                    }
+                }
+                // Descend into the larger node.
+                (Some(_), None) => stack.extend(node_b.children.map(|child| (a, child))),
+                (None, Some(_)) => stack.extend(node_a.children.map(|child| (child, b))),
+                (None, None) => {
+                    if node_a.aabb.perimeter() < node_b.aabb.perimeter() {
+                        stack.extend(node_b.children.map(|child| (a, child)));
+                    } else {
+                        stack.extend(node_a.children.map(|child| (child, b)));
+                    }
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs
+    }
+
+    /// Iterates colliders whose [`Aabb2d`]s intersect `aabb`.
+    pub fn query_aabb(
+        &self,
+        aabb: Aabb2d,
+    ) -> impl Iterator<Item = (BroadphaseKey2d, &Collider2d, &T)> + '_ {
+        let mut stack = Vec::new();
+        if self.root != NULL {
+            stack.push(self.root);
+        }
+        std::iter::from_fn(move || {
+            while let Some(index) = stack.pop() {
+                let node = &self.nodes[index as usize];
+                if !node.aabb.intersects(&aabb) {
+                    continue;
+                }
+                if let Some(leaf) = &node.leaf {
+                    if leaf.aabb.intersects(&aabb) {
+                        return Some((BroadphaseKey2d(index), &leaf.collider, &leaf.value));
+                    }
+                } else {
+                    stack.extend(node.children);
+                }
+            }
+            None
+        })
+    }
+
+    /// Iterates colliders that collide with `collider`.
+    pub fn query_collider<'a>(
+        &'a self,
+        collider: &'a Collider2d,
+    ) -> impl Iterator<Item = (BroadphaseKey2d, &'a Collider2d, &'a T)> + 'a {
+        self.query_aabb(collider.aabb())
+            .filter(move |(_, other, _)| other.collides(collider))
+    }
+
+    /// Returns the nearest collider, and the distance to it, along a ray starting at `origin` in
+    /// the normalized `direction`, ignoring colliders for which `filter` returns false.
+    pub fn ray_cast(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        max_distance: f32,
+        mut filter: impl FnMut(BroadphaseKey2d, &T) -> bool,
+    ) -> Option<(BroadphaseKey2d, f32)> {
+        let mut nearest = None;
+        let mut max_distance = max_distance;
+        let mut stack = Vec::new();
+        if self.root != NULL {
+            stack.push(self.root);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index as usize];
+            if node
+                .aabb
+                .ray_distance(origin, direction, max_distance)
+                .is_none()
+            {
+                continue;
+            }
+            if let Some(leaf) = &node.leaf {
+                let key = BroadphaseKey2d(index);
+                if let Some(distance) = leaf.collider.ray_distance(origin, direction, max_distance)
+                    && filter(key, &leaf.value)
+                    && (nearest.is_none() || distance < max_distance)
+                {
+                    nearest = Some((key, distance));
+                    max_distance = distance;
+                }
+            } else {
+                stack.extend(node.children);
+            }
+        }
+        nearest
+    }
+
+    fn leaf(&self, key: BroadphaseKey2d) -> Option<&Leaf<T>> {
+        self.nodes.get(key.0 as usize)?.leaf.as_ref()
+    }
+
+    fn allocate(&mut self, node: Node<T>) -> u32 {
+        if let Some(index) = self.free.pop() {
+            self.nodes[index as usize] = node;
+            index
+        } else {
+            let index = self.nodes.len() as u32;
+            self.nodes.push(node);
+            index
+        }
+    }
+
+    fn insert_leaf(&mut self, leaf: u32) {
+        if self.root == NULL {
+            self.root = leaf;
+            self.nodes[leaf as usize].parent = NULL;
+            return;
+        }
+
+        // Descend towards the sibling that least increases the total perimeter.
+        let aabb = self.nodes[leaf as usize].aabb;
+        let mut index = self.root;
+        while self.nodes[index as usize].leaf.is_none() {
+            let node = &self.nodes[index as usize];
+            let perimeter = node.aabb.perimeter();
+            let combined = node.aabb.union(&aabb).perimeter();
+            // Cost of making a new parent for this node and the leaf.
+            let cost = 2.0 * combined;
+            // Minimum cost of pushing the leaf further down.
+            let inheritance = 2.0 * (combined - perimeter);
+            let child_cost = |child: u32| {
+                let child = &self.nodes[child as usize];
+                let combined = child.aabb.union(&aabb).perimeter();
+                if child.leaf.is_some() {
+                    combined + inheritance
+                } else {
+                    combined - child.aabb.perimeter() + inheritance
+                }
+            };
+            let [a, b] = node.children;
+            let (cost_a, cost_b) = (child_cost(a), child_cost(b));
+            if cost < cost_a && cost < cost_b {
+                break;
+            }
+            index = if cost_a <= cost_b { a } else { b };
+        }
+
+        let sibling = index;
+        let parent = self.nodes[sibling as usize].parent;
+        let new_parent = self.allocate(Node {
+            aabb: aabb.union(&self.nodes[sibling as usize].aabb),
+            parent,
+            children: [sibling, leaf],
+            height: self.nodes[sibling as usize].height + 1,
+            leaf: None,
+        });
+        self.replace_child(parent, sibling, new_parent);
+        self.nodes[sibling as usize].parent = new_parent;
+        self.nodes[leaf as usize].parent = new_parent;
+        self.refit(new_parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: u32) {
+        if leaf == self.root {
+            self.root = NULL;
+            return;
+        }
+        let parent = self.nodes[leaf as usize].parent;
+        let grandparent = self.nodes[parent as usize].parent;
+        let [a, b] = self.nodes[parent as usize].children;
+        let sibling = if a == leaf { b } else { a };
+        self.replace_child(grandparent, parent, sibling);
+        self.nodes[sibling as usize].parent = grandparent;
+        self.free.push(parent);
+        self.refit(grandparent);
+    }
+
+    /// Replaces `parent`'s child `old` with `new`, or the root if `parent` is [`NULL`].
+    fn replace_child(&mut self, parent: u32, old: u32, new: u32) {
+        if parent == NULL {
+            self.root = new;
+        } else {
+            let children = &mut self.nodes[parent as usize].children;
+            let i = (children[1] == old) as usize;
+            debug_assert_eq!(children[i], old);
+            children[i] = new;
+        }
+    }
+
+    /// Rebalances and recomputes the [`Aabb2d`]s and heights of `index` and its ancestors.
+    fn refit(&mut self, mut index: u32) {
+        while index != NULL {
+            index = self.balance(index);
+            self.fix(index);
+            index = self.nodes[index as usize].parent;
+        }
+    }
+
+    fn fix(&mut self, index: u32) {
+        let [a, b] = self.nodes[index as usize].children;
+        let (a, b) = (&self.nodes[a as usize], &self.nodes[b as usize]);
+        let aabb = a.aabb.union(&b.aabb);
+        let height = a.height.max(b.height) + 1;
+        let node = &mut self.nodes[index as usize];
+        node.aabb = aabb;
+        node.height = height;
+    }
+
+    /// If one child of `index` is more than one taller than the other, rotates it up to replace
+    /// `index`. Returns the node now in the place of `index`.
+    fn balance(&mut self, index: u32) -> u32 {
+        let node = &self.nodes[index as usize];
+        if node.height < 2 {
+            return index;
+        }
+        let [a, b] = node.children;
+        let difference =
+            self.nodes[b as usize].height as i32 - self.nodes[a as usize].height as i32;
+        let (up, side) = if difference > 1 {
+            (b, 1)
+        } else if difference < -1 {
+            (a, 0)
+        } else {
+            return index;
+        };
+
+        // `index` becomes a child of `up`, adopting the shorter of `up`'s children.
+        let [c, d] = self.nodes[up as usize].children;
+        let (keep, give) = if self.nodes[c as usize].height > self.nodes[d as usize].height {
+            (c, d)
+        } else {
+            (d, c)
+        };
+        let parent = self.nodes[index as usize].parent;
+        self.replace_child(parent, index, up);
+        self.nodes[up as usize].parent = parent;
+        self.nodes[up as usize].children = [index, keep];
+        self.nodes[index as usize].parent = up;
+        self.nodes[index as usize].children[side] = give;
+        self.nodes[give as usize].parent = index;
+        self.fix(index);
+        self.fix(up);
+        up
+    }
+}
@@ -0,0 +1,49 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::Aabb2d;
+use glam::Vec2;
+
+/// The points within `radius` of the segment from `a` to `b`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Capsule {
+    pub a: Vec2,
+    pub b: Vec2,
+    pub radius: f32,
+}
+
+impl Capsule {
+    pub fn new(a: Vec2, b: Vec2, radius: f32) -> Self {
+        Self { a, b, radius }
+    }
+
+    pub fn center(&self) -> Vec2 {
+        (self.a + self.b) * 0.5
+    }
+
+    pub fn area(&self) -> f32 {
+        self.a.distance(self.b) * self.radius * 2.0 + self.radius.powi(2) * std::f32::consts::PI
+    }
+
+    pub fn aabb(&self) -> Aabb2d {
+        Aabb2d::new(
+            self.a.min(self.b) - self.radius,
+            self.a.max(self.b) + self.radius,
+        )
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        closest_point_on_segment(self.a, self.b, point).distance_squared(point)
+            <= self.radius.powi(2)
+    }
+}
+
+/// Returns the point on the segment from `a` to `b` closest to `point`.
+pub(crate) fn closest_point_on_segment(a: Vec2, b: Vec2, point: Vec2) -> Vec2 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared == 0.0 {
+        return a;
+    }
+    a + ab * ((point - a).dot(ab) / length_squared).clamp(0.0, 1.0)
+}
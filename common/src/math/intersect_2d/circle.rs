@@ -30,4 +30,48 @@ impl Circle {
         let clamped = center.clamp(-rect.half_size, rect.half_size);
         center.distance_squared(clamped) <= self.radius.powi(2)
     }
+
+    /// Returns (normal, depth), where normal points from `self` towards `other`.
+    pub fn collides_normal_depth(&self, other: &Self) -> Option<(Vec2, f32)> {
+        let delta = other.center - self.center;
+        let radii = self.radius + other.radius;
+        let distance_squared = delta.length_squared();
+        if distance_squared > radii.powi(2) {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        let normal = if distance > 0.0 {
+            delta / distance
+        } else {
+            Vec2::X
+        };
+        Some((normal, radii - distance))
+    }
+
+    /// Returns (normal, depth), where normal points from `self` towards `rect`.
+    pub fn rotated_rectangle_normal_depth(&self, rect: &RotatedRectangle) -> Option<(Vec2, f32)> {
+        let relative = self.center - rect.center;
+        let local = Vec2::new(relative.dot(rect.normal), relative.dot(rect.normal.perp()));
+        let clamped = local.clamp(-rect.half_size, rect.half_size);
+        // Local space, pointing from `rect` towards `self`.
+        let (local_normal, depth) = if local != clamped {
+            let delta = local - clamped;
+            let distance_squared = delta.length_squared();
+            if distance_squared > self.radius.powi(2) {
+                return None;
+            }
+            let distance = distance_squared.sqrt();
+            (delta / distance, self.radius - distance)
+        } else {
+            // Center is inside `rect`, so push out via the nearest edge.
+            let inside = rect.half_size - local.abs();
+            if inside.x < inside.y {
+                (Vec2::new(local.x.signum(), 0.0), self.radius + inside.x)
+            } else {
+                (Vec2::new(0.0, local.y.signum()), self.radius + inside.y)
+            }
+        };
+        let normal = rect.normal * local_normal.x + rect.normal.perp() * local_normal.y;
+        Some((-normal, depth))
+    }
 }
@@ -3,19 +3,28 @@
 
 use glam::Vec2;
 
-use super::{Circle, RotatedRectangle};
+use super::capsule::closest_point_on_segment;
+use super::{Aabb2d, Capsule, Circle, ConvexPolygon, OriginAabb2d, RotatedRectangle};
+use arrayvec::ArrayVec;
 
 #[derive(Copy, Clone)]
 pub enum Collider2d {
     Circle(Circle),
     RotatedRectangle(RotatedRectangle),
+    Capsule(Capsule),
+    ConvexPolygon(ConvexPolygon),
 }
 
+/// The vertices of a point, segment or convex polygon.
+type Core = ArrayVec<Vec2, { ConvexPolygon::MAX_VERTICES }>;
+
 impl Collider2d {
     pub fn center(&self) -> Vec2 {
         match self {
             Self::Circle(circle) => circle.center,
             Self::RotatedRectangle(rectangle) => rectangle.center,
+            Self::Capsule(capsule) => capsule.center(),
+            Self::ConvexPolygon(polygon) => polygon.centroid(),
         }
     }
 
@@ -25,6 +34,86 @@ impl Collider2d {
             Self::RotatedRectangle(rectangle) => {
                 rectangle.half_size.x * rectangle.half_size.y * 4.0
             }
+            Self::Capsule(capsule) => capsule.area(),
+            Self::ConvexPolygon(polygon) => polygon.area(),
+        }
+    }
+
+    /// Returns the smallest [`Aabb2d`] containing the collider.
+    pub fn aabb(&self) -> Aabb2d {
+        match self {
+            Self::Circle(circle) => {
+                Aabb2d::from_center_half_size(circle.center, Vec2::splat(circle.radius))
+            }
+            Self::RotatedRectangle(rectangle) => {
+                let Vec2 { x: cos, y: sin } = rectangle.normal.abs();
+                let Vec2 { x, y } = rectangle.half_size;
+                let half_size = Vec2::new(cos * x + sin * y, sin * x + cos * y);
+                Aabb2d::from_center_half_size(rectangle.center, half_size)
+            }
+            Self::Capsule(capsule) => capsule.aabb(),
+            Self::ConvexPolygon(polygon) => polygon.aabb(),
+        }
+    }
+
+    /// Returns the smallest [`OriginAabb2d`] containing the collider, relative to its
+    /// [`center`][`Self::center`].
+    pub fn origin_aabb(&self) -> OriginAabb2d {
+        let aabb = self.aabb();
+        let center = self.center();
+        OriginAabb2d {
+            radii: (aabb.max - center).max(center - aabb.min),
+        }
+    }
+
+    /// Returns the distance along a ray, starting at `origin` in the normalized `direction`, to
+    /// where it enters the collider (zero if it starts inside), if it does so within
+    /// `max_distance`.
+    pub fn ray_distance(&self, origin: Vec2, direction: Vec2, max_distance: f32) -> Option<f32> {
+        debug_assert!(direction.is_normalized());
+        match self {
+            Self::Circle(circle) => {
+                let relative = origin - circle.center;
+                let b = relative.dot(direction);
+                let c = relative.length_squared() - circle.radius.powi(2);
+                if c > 0.0 && b > 0.0 {
+                    // Outside and pointing away.
+                    return None;
+                }
+                let discriminant = b * b - c;
+                if discriminant < 0.0 {
+                    return None;
+                }
+                let distance = (-b - discriminant.sqrt()).max(0.0);
+                (distance <= max_distance).then_some(distance)
+            }
+            Self::RotatedRectangle(rectangle) => {
+                let local =
+                    |v: Vec2| Vec2::new(v.dot(rectangle.normal), v.dot(rectangle.normal.perp()));
+                Aabb2d::from_center_half_size(Vec2::ZERO, rectangle.half_size).ray_distance(
+                    local(origin - rectangle.center),
+                    local(direction),
+                    max_distance,
+                )
+            }
+            Self::Capsule(capsule) => {
+                // The first of the parts that make up the capsule that the ray enters.
+                let ends = [capsule.a, capsule.b]
+                    .map(|center| Self::Circle(Circle::new(center, capsule.radius)));
+                let delta = capsule.b - capsule.a;
+                let side = delta.try_normalize().map(|normal| {
+                    Self::RotatedRectangle(RotatedRectangle::with_normal(
+                        capsule.center(),
+                        Vec2::new(delta.length(), capsule.radius * 2.0),
+                        normal,
+                    ))
+                });
+                ends.iter()
+                    .chain(&side)
+                    .filter_map(|part| part.ray_distance(origin, direction, max_distance))
+                    .min_by(f32::total_cmp)
+            }
+            Self::ConvexPolygon(polygon) => polygon.ray_distance(origin, direction, max_distance),
         }
     }
 
@@ -34,6 +123,174 @@ impl Collider2d {
             (Self::Circle(s), Self::RotatedRectangle(o)) => s.rotated_rectangle(o),
             (Self::RotatedRectangle(s), Self::Circle(o)) => s.circle(o),
             (Self::RotatedRectangle(s), Self::RotatedRectangle(o)) => s.collides(o),
+            _ => self.rounded_normal_depth(other).is_some(),
         }
     }
+
+    /// Returns (normal, depth), where normal points from `self` towards `other`. Unlike
+    /// [`RotatedRectangle::collides_normal_depth`], the normal is never zero.
+    pub fn collides_normal_depth(&self, other: &Self) -> Option<(Vec2, f32)> {
+        match (self, other) {
+            (Self::Circle(s), Self::Circle(o)) => s.collides_normal_depth(o),
+            (Self::Circle(s), Self::RotatedRectangle(o)) => s.rotated_rectangle_normal_depth(o),
+            (Self::RotatedRectangle(s), Self::Circle(o)) => o
+                .rotated_rectangle_normal_depth(s)
+                .map(|(normal, depth)| (-normal, depth)),
+            (Self::RotatedRectangle(s), Self::RotatedRectangle(o)) => {
+                s.collides_normal_depth(o).map(|(normal, depth)| {
+                    if normal == Vec2::ZERO {
+                        // Deeply overlapping, so estimate.
+                        let delta = o.center - s.center;
+                        let depth = s.half_size.min_element() + o.half_size.min_element();
+                        (delta.try_normalize().unwrap_or(Vec2::X), depth)
+                    } else {
+                        (normal, depth)
+                    }
+                })
+            }
+            _ => self.rounded_normal_depth(other),
+        }
+    }
+
+    /// Returns the farthest point of the collider in `direction`.
+    pub fn support(&self, direction: Vec2) -> Vec2 {
+        let (core, radius) = self.rounded();
+        let farthest = core
+            .iter()
+            .copied()
+            .max_by(|a, b| a.dot(direction).total_cmp(&b.dot(direction)))
+            .unwrap();
+        farthest + direction.normalize_or_zero() * radius
+    }
+
+    /// Returns the collider as a point, segment or convex polygon, expanded by a radius.
+    fn rounded(&self) -> (Core, f32) {
+        let mut core = Core::new();
+        let radius = match self {
+            Self::Circle(circle) => {
+                core.push(circle.center);
+                circle.radius
+            }
+            Self::RotatedRectangle(rectangle) => {
+                let x = rectangle.normal * rectangle.half_size.x;
+                let y = rectangle.normal.perp() * rectangle.half_size.y;
+                let center = rectangle.center;
+                core.extend([
+                    center + x + y,
+                    center - x + y,
+                    center - x - y,
+                    center + x - y,
+                ]);
+                0.0
+            }
+            Self::Capsule(capsule) => {
+                core.push(capsule.a);
+                if capsule.b != capsule.a {
+                    core.push(capsule.b);
+                }
+                capsule.radius
+            }
+            Self::ConvexPolygon(polygon) => {
+                core.extend(polygon.vertices().iter().copied());
+                0.0
+            }
+        };
+        (core, radius)
+    }
+
+    /// Works for any pair of colliders by separating the closest points of their cores or, if
+    /// the cores overlap, by the separating axis theorem.
+    fn rounded_normal_depth(&self, other: &Self) -> Option<(Vec2, f32)> {
+        let (a, a_radius) = self.rounded();
+        let (b, b_radius) = other.rounded();
+        let radii = a_radius + b_radius;
+
+        // Candidate separating axes are the edge normals, plus the directions of segments in
+        // case they are collinear.
+        let mut axes = ArrayVec::<Vec2, { ConvexPolygon::MAX_VERTICES * 2 + 2 }>::new();
+        for core in [&a, &b] {
+            for (start, end) in edges(core) {
+                axes.extend((end - start).perp().try_normalize());
+            }
+            if core.len() == 2 {
+                axes.extend((core[1] - core[0]).try_normalize());
+            }
+        }
+        let project = |core: &Core, axis: Vec2| {
+            core.iter()
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| {
+                    let dot = v.dot(axis);
+                    (min.min(dot), max.max(dot))
+                })
+        };
+        // (normal, overlap) with the least overlap, or `None` if separated.
+        let mut least: Option<(Vec2, f32)> = None;
+        let mut separated = false;
+        for &axis in &axes {
+            let (a_min, a_max) = project(&a, axis);
+            let (b_min, b_max) = project(&b, axis);
+            // Pushing `other` in the direction of the axis or the opposite.
+            for (normal, overlap) in [(axis, a_max - b_min), (-axis, b_max - a_min)] {
+                if overlap < 0.0 {
+                    separated = true;
+                } else if !matches!(least, Some((_, least)) if least <= overlap) {
+                    least = Some((normal, overlap));
+                }
+            }
+        }
+
+        let (a_closest, b_closest) = closest_points(&a, &b);
+        let delta = b_closest - a_closest;
+        if separated || (axes.is_empty() && delta != Vec2::ZERO) {
+            let distance = delta.length();
+            if distance > radii {
+                return None;
+            }
+            let normal = delta.try_normalize().unwrap_or(Vec2::X);
+            Some((normal, radii - distance))
+        } else if let Some((normal, overlap)) = least {
+            Some((normal, overlap + radii))
+        } else {
+            // Coincident points.
+            let normal = (other.center() - self.center())
+                .try_normalize()
+                .unwrap_or(Vec2::X);
+            Some((normal, radii))
+        }
+    }
+}
+
+/// Returns the edges of a point, segment or convex polygon, as (start, end). A segment only has
+/// one edge.
+fn edges(core: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    let count = match core.len() {
+        0 | 1 => 0,
+        2 => 1,
+        n => n,
+    };
+    (0..count).map(|i| (core[i], core[(i + 1) % core.len()]))
+}
+
+/// Returns the closest points of two non-overlapping points, segments or convex polygons.
+fn closest_points(a: &[Vec2], b: &[Vec2]) -> (Vec2, Vec2) {
+    // The closest points always include a vertex of one or the other.
+    let nearest = |vertices: &[Vec2], core: &[Vec2]| {
+        vertices
+            .iter()
+            .flat_map(|&v| {
+                let single = (core.len() == 1).then(|| (v, core[0]));
+                edges(core)
+                    .map(move |(start, end)| (v, closest_point_on_segment(start, end, v)))
+                    .chain(single)
+            })
+            .min_by(|(a, b), (c, d)| a.distance_squared(*b).total_cmp(&c.distance_squared(*d)))
+            .unwrap()
+    };
+    let (a_vertex, b_point) = nearest(a, b);
+    let (b_vertex, a_point) = nearest(b, a);
+    if a_vertex.distance_squared(b_point) <= b_vertex.distance_squared(a_point) {
+        (a_vertex, b_point)
+    } else {
+        (a_point, b_vertex)
+    }
 }
@@ -0,0 +1,168 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::Aabb2d;
+use crate::Angle;
+use glam::Vec2;
+
+/// A convex polygon with up to [`MAX_VERTICES`][`Self::MAX_VERTICES`] vertices, stored inline
+/// so it's [`Copy`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ConvexPolygon {
+    /// Counter-clockwise, with unused vertices zeroed.
+    vertices: [Vec2; Self::MAX_VERTICES],
+    len: u8,
+}
+
+impl ConvexPolygon {
+    pub const MAX_VERTICES: usize = 8;
+
+    /// Returns the convex hull of `points`, or `None` if it has no area or more than
+    /// [`MAX_VERTICES`][`Self::MAX_VERTICES`] vertices.
+    pub fn new(points: impl IntoIterator<Item = Vec2>) -> Option<Self> {
+        let mut points: Vec<Vec2> = points.into_iter().collect();
+        points.sort_unstable_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+        points.dedup();
+
+        // Andrew's monotone chain, lower half then upper half.
+        let n = points.len();
+        let mut hull: Vec<Vec2> = Vec::with_capacity(n + 1);
+        let mut floor = 2;
+        for (i, &point) in points.iter().chain(points.iter().rev().skip(1)).enumerate() {
+            if i == n {
+                // Don't remove any of the lower half.
+                floor = hull.len() + 1;
+            }
+            while hull.len() >= floor
+                && (hull[hull.len() - 1] - hull[hull.len() - 2])
+                    .perp_dot(point - hull[hull.len() - 2])
+                    <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        // The last is the same as the first.
+        hull.pop();
+        if hull.len() < 3 || hull.len() > Self::MAX_VERTICES {
+            return None;
+        }
+        Some(Self::from_vertices(hull))
+    }
+
+    /// Like [`new`][`Self::new`] but trusts that `vertices` are already convex and
+    /// counter-clockwise.
+    pub(crate) fn from_vertices(vertices: impl IntoIterator<Item = Vec2>) -> Self {
+        let mut me = Self {
+            vertices: [Vec2::ZERO; Self::MAX_VERTICES],
+            len: 0,
+        };
+        for (vertex, v) in me.vertices.iter_mut().zip(vertices) {
+            *vertex = v;
+            me.len += 1;
+        }
+        debug_assert!(me.len >= 3);
+        me
+    }
+
+    /// Returns a polygon with `sides` equal sides, with its first vertex `radius` from `center`
+    /// in `direction`.
+    ///
+    /// **Panics**
+    ///
+    /// If `sides` is less than 3 or more than [`MAX_VERTICES`][`Self::MAX_VERTICES`].
+    pub fn regular(center: Vec2, radius: f32, sides: usize, direction: Angle) -> Self {
+        assert!((3..=Self::MAX_VERTICES).contains(&sides));
+        let rotation = direction.to_vec();
+        Self::from_vertices((0..sides).map(|i| {
+            let angle = i as f32 * (std::f32::consts::TAU / sides as f32);
+            center + Vec2::from_angle(angle).rotate(rotation) * radius
+        }))
+    }
+
+    /// Returns the vertices in counter-clockwise order.
+    pub fn vertices(&self) -> &[Vec2] {
+        &self.vertices[..self.len as usize]
+    }
+
+    /// Returns each edge as (start, end), in counter-clockwise order.
+    pub fn edges(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        let vertices = self.vertices();
+        vertices
+            .iter()
+            .zip(vertices.iter().cycle().skip(1))
+            .map(|(&start, &end)| (start, end))
+    }
+
+    /// Returns the polygon rotated by `direction` around the origin and then moved by `offset`,
+    /// e.g. from local to world space.
+    pub fn transformed(&self, offset: Vec2, direction: Angle) -> Self {
+        let rotation = direction.to_vec();
+        let mut transformed = *self;
+        for vertex in &mut transformed.vertices[..self.len as usize] {
+            *vertex = vertex.rotate(rotation) + offset;
+        }
+        transformed
+    }
+
+    pub fn area(&self) -> f32 {
+        self.edges().map(|(a, b)| a.perp_dot(b)).sum::<f32>() * 0.5
+    }
+
+    /// Returns the center of mass.
+    pub fn centroid(&self) -> Vec2 {
+        // Relative to the first vertex, for precision.
+        let origin = self.vertices[0];
+        let (sum, double_area) = self.edges().map(|(a, b)| (a - origin, b - origin)).fold(
+            (Vec2::ZERO, 0.0),
+            |(sum, double_area), (a, b)| {
+                let cross = a.perp_dot(b);
+                (sum + (a + b) * cross, double_area + cross)
+            },
+        );
+        origin + sum / (double_area * 3.0)
+    }
+
+    pub fn aabb(&self) -> Aabb2d {
+        let vertices = self.vertices();
+        let (min, max) = vertices[1..]
+            .iter()
+            .fold((vertices[0], vertices[0]), |(min, max), &v| {
+                (min.min(v), max.max(v))
+            });
+        Aabb2d::new(min, max)
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.edges()
+            .all(|(a, b)| (b - a).perp_dot(point - a) >= 0.0)
+    }
+
+    /// Like [`Collider2d::ray_distance`][`super::Collider2d::ray_distance`].
+    pub fn ray_distance(&self, origin: Vec2, direction: Vec2, max_distance: f32) -> Option<f32> {
+        // Clip the ray to the inside of each edge.
+        let (mut enter, mut exit) = (0.0f32, max_distance);
+        for (a, b) in self.edges() {
+            let outward = -(b - a).perp();
+            let denominator = outward.dot(direction);
+            let distance = outward.dot(a - origin);
+            if denominator == 0.0 {
+                if distance < 0.0 {
+                    // Parallel and outside.
+                    return None;
+                }
+                continue;
+            }
+            let t = distance / denominator;
+            if denominator < 0.0 {
+                enter = enter.max(t);
+            } else {
+                exit = exit.min(t);
+            }
+            if enter > exit {
+                return None;
+            }
+        }
+        Some(enter)
+    }
+}
@@ -0,0 +1,344 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{Capsule, Circle, Collider2d, ConvexPolygon, RotatedRectangle};
+use crate::bitcode::{self, *};
+use crate::{Angle, Fixed64, Fixed64Vec2};
+use arrayvec::ArrayVec;
+use glam::Vec2;
+
+/// Like [`Collider2d`] but with fixed-point math, so collisions are cross-platform
+/// deterministic.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq, Encode, Decode)]
+pub enum FixedCollider2d {
+    Circle {
+        center: Fixed64Vec2,
+        radius: Fixed64,
+    },
+    RotatedRectangle {
+        center: Fixed64Vec2,
+        /// Unit vector.
+        normal: Fixed64Vec2,
+        half_size: Fixed64Vec2,
+    },
+    Capsule {
+        a: Fixed64Vec2,
+        b: Fixed64Vec2,
+        radius: Fixed64,
+    },
+    ConvexPolygon {
+        /// Counter-clockwise, with unused vertices zeroed.
+        vertices: [Fixed64Vec2; ConvexPolygon::MAX_VERTICES],
+        /// Clamped to [`ConvexPolygon::MAX_VERTICES`], and fewer than 3 vertices act as a point
+        /// or segment, since a decoded polygon may be invalid.
+        len: u8,
+    },
+}
+
+/// The vertices of a point, segment or convex polygon.
+type Core = ArrayVec<Fixed64Vec2, { ConvexPolygon::MAX_VERTICES }>;
+
+impl FixedCollider2d {
+    pub fn circle(center: Fixed64Vec2, radius: Fixed64) -> Self {
+        Self::Circle { center, radius }
+    }
+
+    pub fn rotated_rectangle(center: Fixed64Vec2, size: Fixed64Vec2, direction: Angle) -> Self {
+        Self::RotatedRectangle {
+            center,
+            normal: Fixed64Vec2::from_angle(direction),
+            half_size: size / Fixed64::from_int(2),
+        }
+    }
+
+    pub fn capsule(a: Fixed64Vec2, b: Fixed64Vec2, radius: Fixed64) -> Self {
+        Self::Capsule { a, b, radius }
+    }
+
+    pub fn center(&self) -> Fixed64Vec2 {
+        match *self {
+            Self::Circle { center, .. } | Self::RotatedRectangle { center, .. } => center,
+            Self::Capsule { a, b, .. } => (a + b) / Fixed64::from_int(2),
+            Self::ConvexPolygon { ref vertices, len } => {
+                // Centroid, relative to the first vertex for range and precision.
+                let vertices = polygon_vertices(vertices, len);
+                let Some(&origin) = vertices.first() else {
+                    return Fixed64Vec2::ZERO;
+                };
+                let mut sum = Fixed64Vec2::ZERO;
+                let mut double_area = Fixed64::ZERO;
+                for (i, &a) in vertices.iter().enumerate() {
+                    let (a, b) = (a - origin, vertices[(i + 1) % vertices.len()] - origin);
+                    let cross = a.perp_dot(b);
+                    sum += (a + b) * cross;
+                    double_area += cross;
+                }
+                if double_area == Fixed64::ZERO {
+                    // Degenerate, so use the mean instead.
+                    let sum = vertices
+                        .iter()
+                        .fold(Fixed64Vec2::ZERO, |sum, &v| sum + (v - origin));
+                    return origin + sum / Fixed64::from_int(vertices.len() as i64);
+                }
+                origin + sum / (double_area * Fixed64::from_int(3))
+            }
+        }
+    }
+
+    pub fn collides(&self, other: &Self) -> bool {
+        match (*self, *other) {
+            (
+                Self::Circle { center, radius },
+                Self::Circle {
+                    center: other_center,
+                    radius: other_radius,
+                },
+            ) => within(center - other_center, radius + other_radius),
+            (
+                Self::Circle { center, radius },
+                Self::RotatedRectangle {
+                    center: rectangle_center,
+                    normal,
+                    half_size,
+                },
+            )
+            | (
+                Self::RotatedRectangle {
+                    center: rectangle_center,
+                    normal,
+                    half_size,
+                },
+                Self::Circle { center, radius },
+            ) => {
+                let relative = center - rectangle_center;
+                let local = Fixed64Vec2::new(relative.dot(normal), relative.dot(normal.perp()));
+                within(local - local.clamp(-half_size, half_size), radius)
+            }
+            (
+                a @ Self::RotatedRectangle {
+                    normal: a_normal, ..
+                },
+                b @ Self::RotatedRectangle {
+                    normal: b_normal, ..
+                },
+            ) => [a_normal, a_normal.perp(), b_normal, b_normal.perp()]
+                .into_iter()
+                .all(|axis| {
+                    let (a_min, a_max) = a.project(axis);
+                    let (b_min, b_max) = b.project(axis);
+                    a_min <= b_max && b_min <= a_max
+                }),
+            _ => {
+                let (a, a_radius) = self.rounded();
+                let (b, b_radius) = other.rounded();
+                let radii = a_radius + b_radius;
+                cores_overlap(&a, &b)
+                    || [(&a, &b), (&b, &a)].into_iter().any(|(vertices, core)| {
+                        vertices.iter().any(|&v| {
+                            if let [point] = core[..] {
+                                within(v - point, radii)
+                            } else {
+                                edges(core).any(|(start, end)| within_segment(v, start, end, radii))
+                            }
+                        })
+                    })
+            }
+        }
+    }
+
+    /// Returns the collider as a point, segment or convex polygon, expanded by a radius.
+    fn rounded(&self) -> (Core, Fixed64) {
+        let mut core = Core::new();
+        let radius = match *self {
+            Self::Circle { center, radius } => {
+                core.push(center);
+                radius
+            }
+            Self::RotatedRectangle {
+                center,
+                normal,
+                half_size,
+            } => {
+                let x = normal * half_size.x;
+                let y = normal.perp() * half_size.y;
+                core.extend([
+                    center + x + y,
+                    center - x + y,
+                    center - x - y,
+                    center + x - y,
+                ]);
+                Fixed64::ZERO
+            }
+            Self::Capsule { a, b, radius } => {
+                core.push(a);
+                if b != a {
+                    core.push(b);
+                }
+                radius
+            }
+            Self::ConvexPolygon { ref vertices, len } => {
+                core.extend(polygon_vertices(vertices, len).iter().copied());
+                Fixed64::ZERO
+            }
+        };
+        (core, radius)
+    }
+
+    /// Projects onto a unit `axis`, returning (min, max).
+    fn project(&self, axis: Fixed64Vec2) -> (Fixed64, Fixed64) {
+        let (center, extent) = match *self {
+            Self::Circle { center, radius } => (center, radius),
+            Self::RotatedRectangle {
+                center,
+                normal,
+                half_size,
+            } => (
+                center,
+                half_size.x * normal.dot(axis).abs() + half_size.y * normal.perp().dot(axis).abs(),
+            ),
+            Self::Capsule { .. } | Self::ConvexPolygon { .. } => {
+                let (core, radius) = self.rounded();
+                let (min, max) = core
+                    .iter()
+                    .fold((Fixed64::MAX, Fixed64::MIN), |(min, max), v| {
+                        let dot = v.dot(axis);
+                        (min.min(dot), max.max(dot))
+                    });
+                return (min - radius, max + radius);
+            }
+        };
+        let center = center.dot(axis);
+        (center - extent, center + extent)
+    }
+
+    /// Converts to a floating point [`Collider2d`], e.g. for rendering.
+    pub fn to_collider_2d(&self) -> Collider2d {
+        match *self {
+            Self::Circle { center, radius } => {
+                Collider2d::Circle(Circle::new(center.to_vec2(), radius.to_f32()))
+            }
+            Self::RotatedRectangle {
+                center,
+                normal,
+                half_size,
+            } => Collider2d::RotatedRectangle(RotatedRectangle::with_normal(
+                center.to_vec2(),
+                half_size.to_vec2() * 2.0,
+                normal.to_vec2().normalize(),
+            )),
+            Self::Capsule { a, b, radius } => {
+                Collider2d::Capsule(Capsule::new(a.to_vec2(), b.to_vec2(), radius.to_f32()))
+            }
+            Self::ConvexPolygon { ref vertices, len } => match *polygon_vertices(vertices, len) {
+                [] => Collider2d::Circle(Circle::new(Vec2::ZERO, 0.0)),
+                [a] => Collider2d::Capsule(Capsule::new(a.to_vec2(), a.to_vec2(), 0.0)),
+                [a, b] => Collider2d::Capsule(Capsule::new(a.to_vec2(), b.to_vec2(), 0.0)),
+                ref vertices => Collider2d::ConvexPolygon(ConvexPolygon::from_vertices(
+                    vertices.iter().map(|v| v.to_vec2()),
+                )),
+            },
+        }
+    }
+}
+
+impl From<Collider2d> for FixedCollider2d {
+    /// Rounds to the nearest representable values.
+    fn from(collider: Collider2d) -> Self {
+        match collider {
+            Collider2d::Circle(circle) => Self::Circle {
+                center: Fixed64Vec2::from_vec2(circle.center),
+                radius: Fixed64::from_f32(circle.radius),
+            },
+            Collider2d::RotatedRectangle(rectangle) => Self::RotatedRectangle {
+                center: Fixed64Vec2::from_vec2(rectangle.center),
+                normal: Fixed64Vec2::from_vec2(rectangle.normal),
+                half_size: Fixed64Vec2::from_vec2(rectangle.half_size),
+            },
+            Collider2d::Capsule(capsule) => Self::Capsule {
+                a: Fixed64Vec2::from_vec2(capsule.a),
+                b: Fixed64Vec2::from_vec2(capsule.b),
+                radius: Fixed64::from_f32(capsule.radius),
+            },
+            Collider2d::ConvexPolygon(polygon) => {
+                let mut vertices = [Fixed64Vec2::ZERO; ConvexPolygon::MAX_VERTICES];
+                for (fixed, &v) in vertices.iter_mut().zip(polygon.vertices()) {
+                    *fixed = Fixed64Vec2::from_vec2(v);
+                }
+                Self::ConvexPolygon {
+                    vertices,
+                    len: polygon.vertices().len() as u8,
+                }
+            }
+        }
+    }
+}
+
+/// Returns the used vertices of a [`FixedCollider2d::ConvexPolygon`].
+fn polygon_vertices(
+    vertices: &[Fixed64Vec2; ConvexPolygon::MAX_VERTICES],
+    len: u8,
+) -> &[Fixed64Vec2] {
+    &vertices[..(len as usize).min(ConvexPolygon::MAX_VERTICES)]
+}
+
+/// Returns the edges of a point, segment or convex polygon, as (start, end). A segment only has
+/// one edge.
+fn edges(core: &[Fixed64Vec2]) -> impl Iterator<Item = (Fixed64Vec2, Fixed64Vec2)> + '_ {
+    let count = match core.len() {
+        0 | 1 => 0,
+        2 => 1,
+        n => n,
+    };
+    (0..count).map(|i| (core[i], core[(i + 1) % core.len()]))
+}
+
+/// Returns true if no edge normal (or segment direction) separates two points, segments or
+/// convex polygons.
+fn cores_overlap(a: &[Fixed64Vec2], b: &[Fixed64Vec2]) -> bool {
+    let project = |core: &[Fixed64Vec2], axis: Fixed64Vec2| {
+        core.iter()
+            .fold((Fixed64::MAX, Fixed64::MIN), |(min, max), v| {
+                let dot = v.dot(axis);
+                (min.min(dot), max.max(dot))
+            })
+    };
+    let mut any = false;
+    for core in [a, b] {
+        let directions = (core.len() == 2).then(|| core[1] - core[0]);
+        // Axes don't need to be normalized to compare projections.
+        for axis in edges(core)
+            .map(|(start, end)| (end - start).perp())
+            .chain(directions)
+        {
+            any = true;
+            let (a_min, a_max) = project(a, axis);
+            let (b_min, b_max) = project(b, axis);
+            if a_max < b_min || b_max < a_min {
+                return false;
+            }
+        }
+    }
+    // Points only overlap if they're equal.
+    any || a == b
+}
+
+/// Returns true if `point` is no farther than `max` from the segment from `start` to `end`.
+fn within_segment(point: Fixed64Vec2, start: Fixed64Vec2, end: Fixed64Vec2, max: Fixed64) -> bool {
+    let segment = end - start;
+    let along = (point - start).dot(segment);
+    let length_squared = segment.length_squared();
+    let closest = if along <= Fixed64::ZERO {
+        start
+    } else if along >= length_squared {
+        end
+    } else {
+        start + segment * (along / length_squared)
+    };
+    within(point - closest, max)
+}
+
+/// Returns true if `v` is no longer than `max`, without overflowing.
+fn within(v: Fixed64Vec2, max: Fixed64) -> bool {
+    let squared = |f: Fixed64| (f.0 as i128) * (f.0 as i128);
+    squared(v.x) + squared(v.y) <= squared(max)
+}
@@ -1,13 +1,25 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
+mod aabb_2d;
+mod broadphase_2d;
+mod capsule;
 mod circle;
 mod collider_2d;
+mod convex_polygon;
+mod fixed_collider_2d;
 mod origin_aabb_2d;
 mod rotated_rectangle;
 mod tests;
 
+pub use aabb_2d::Aabb2d;
+pub use broadphase_2d::{Broadphase2d, BroadphaseKey2d};
+pub use capsule::Capsule;
 pub use circle::Circle;
 pub use collider_2d::Collider2d;
+pub use convex_polygon::ConvexPolygon;
+pub use fixed_collider_2d::FixedCollider2d;
 pub use origin_aabb_2d::OriginAabb2d;
 pub use rotated_rectangle::{RotatedRectangle, SatRect};
+#[cfg(test)]
+pub(crate) use tests::collider_2d_test_helpers;
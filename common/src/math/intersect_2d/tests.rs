@@ -32,3 +32,607 @@ mod intersect_2d_tests {
         bencher.iter(|| black_box(black_box(&a).collides_with(black_box(&b))))
     }
 }
+
+/// Helpers shared by tests of [`Collider2d`] and [`FixedCollider2d`][`crate::FixedCollider2d`].
+#[cfg(test)]
+pub(crate) mod collider_2d_test_helpers {
+    use crate::angle::Angle;
+    use crate::{Capsule, Circle, Collider2d, ConvexPolygon, RotatedRectangle};
+    use glam::{vec2, Vec2};
+    use rand::prelude::*;
+
+    pub(crate) fn random_point(rng: &mut impl Rng, radius: f32) -> Vec2 {
+        vec2(
+            rng.gen_range(-radius..radius),
+            rng.gen_range(-radius..radius),
+        )
+    }
+
+    /// Returns a collider of any kind, centered within `radius` of the origin on each axis.
+    pub(crate) fn random_collider(rng: &mut impl Rng, radius: f32) -> Collider2d {
+        let center = random_point(rng, radius);
+        match rng.gen_range(0..4) {
+            0 => Collider2d::Circle(Circle::new(center, rng.gen_range(0.5..3.0))),
+            1 => {
+                let size = vec2(rng.gen_range(1.0..5.0), rng.gen_range(1.0..5.0));
+                Collider2d::RotatedRectangle(RotatedRectangle::new(center, size, Angle(rng.gen())))
+            }
+            2 => Collider2d::Capsule(Capsule::new(
+                center,
+                center + random_point(rng, 3.0),
+                rng.gen_range(0.25..2.0),
+            )),
+            _ => loop {
+                let count = rng.gen_range(3..12);
+                if let Some(polygon) =
+                    ConvexPolygon::new((0..count).map(|_| center + random_point(rng, 3.0)))
+                {
+                    break Collider2d::ConvexPolygon(polygon);
+                }
+            },
+        }
+    }
+
+    pub(crate) fn moved(collider: Collider2d, offset: Vec2) -> Collider2d {
+        match collider {
+            Collider2d::Circle(circle) => {
+                Collider2d::Circle(Circle::new(circle.center + offset, circle.radius))
+            }
+            Collider2d::RotatedRectangle(mut rectangle) => {
+                rectangle.center += offset;
+                Collider2d::RotatedRectangle(rectangle)
+            }
+            Collider2d::Capsule(capsule) => Collider2d::Capsule(Capsule::new(
+                capsule.a + offset,
+                capsule.b + offset,
+                capsule.radius,
+            )),
+            Collider2d::ConvexPolygon(polygon) => {
+                Collider2d::ConvexPolygon(polygon.transformed(offset, Angle::ZERO))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod broadphase_2d_tests {
+    use super::collider_2d_test_helpers::{moved, random_collider};
+    use crate::angle::Angle;
+    use crate::{
+        Aabb2d, Broadphase2d, Circle, Collider2d, Entities2d, Entity2d, RotatedRectangle,
+        SectorArray2d,
+    };
+    use glam::Vec2;
+    use rand::prelude::*;
+    use rand_chacha::ChaCha20Rng;
+    use std::collections::BTreeSet;
+    use test::bench::{black_box, Bencher};
+
+    /// Checks `broadphase` against `colliders`, indexed by the broadphase's values.
+    fn check(broadphase: &Broadphase2d<usize>, colliders: &[Option<Collider2d>]) {
+        assert_eq!(broadphase.len(), colliders.iter().flatten().count());
+
+        let expected: BTreeSet<(usize, usize)> = (0..colliders.len())
+            .flat_map(|a| (a + 1..colliders.len()).map(move |b| (a, b)))
+            .filter(|&(a, b)| {
+                let (Some(a), Some(b)) = (&colliders[a], &colliders[b]) else {
+                    return false;
+                };
+                a.collides(b)
+            })
+            .collect();
+        let pairs = broadphase.pairs();
+        assert!(pairs.windows(2).all(|w| w[0] < w[1]));
+        let actual: BTreeSet<(usize, usize)> = pairs
+            .into_iter()
+            .filter_map(|(a, b)| {
+                let (a, &i) = broadphase.get(a).unwrap();
+                let (b, &j) = broadphase.get(b).unwrap();
+                a.collides(b).then_some((i.min(j), i.max(j)))
+            })
+            .collect();
+        assert_eq!(actual, expected);
+
+        let region = Aabb2d::new(Vec2::new(-20.0, -10.0), Vec2::new(30.0, 5.0));
+        let expected: BTreeSet<usize> = colliders
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| c.filter(|c| c.aabb().intersects(&region)).map(|_| i))
+            .collect();
+        let actual: BTreeSet<usize> = broadphase.query_aabb(region).map(|(_, _, &i)| i).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn broadphase_pairs_and_queries() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let mut broadphase = Broadphase2d::new(2.0);
+        let mut colliders = Vec::new();
+        let mut keys = Vec::new();
+        for i in 0..400 {
+            let collider = random_collider(&mut rng, 100.0);
+            keys.push(Some(broadphase.insert(collider, i)));
+            colliders.push(Some(collider));
+        }
+        check(&broadphase, &colliders);
+
+        for round in 0..5 {
+            for (i, key) in keys.iter_mut().enumerate() {
+                let Some(k) = *key else {
+                    continue;
+                };
+                if i % 7 == round {
+                    assert_eq!(broadphase.remove(k), Some(i));
+                    assert_eq!(broadphase.remove(k), None);
+                    *key = None;
+                    colliders[i] = None;
+                } else {
+                    let scale = if i % 2 == 0 { 1.0 } else { 20.0 };
+                    let offset = Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0));
+                    let collider = moved(colliders[i].unwrap(), offset * scale);
+                    broadphase.update(k, collider);
+                    colliders[i] = Some(collider);
+                }
+            }
+            for _ in 0..20 {
+                let collider = random_collider(&mut rng, 100.0);
+                keys.push(Some(broadphase.insert(collider, colliders.len())));
+                colliders.push(Some(collider));
+            }
+            check(&broadphase, &colliders);
+        }
+
+        let probe = Collider2d::Circle(Circle::new(Vec2::new(5.0, -3.0), 15.0));
+        let expected: BTreeSet<usize> = colliders
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| c.filter(|c| c.collides(&probe)).map(|_| i))
+            .collect();
+        let actual: BTreeSet<usize> = broadphase
+            .query_collider(&probe)
+            .map(|(_, _, &i)| i)
+            .collect();
+        assert_eq!(actual, expected);
+
+        broadphase.clear();
+        assert!(broadphase.is_empty());
+        assert!(broadphase.pairs().is_empty());
+    }
+
+    #[test]
+    fn broadphase_ray_cast() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let mut broadphase = Broadphase2d::new(2.0);
+        let colliders: Vec<Collider2d> = (0..200)
+            .map(|i| {
+                let collider = random_collider(&mut rng, 100.0);
+                broadphase.insert(collider, i);
+                collider
+            })
+            .collect();
+
+        for _ in 0..200 {
+            let origin = Vec2::new(rng.gen_range(-120.0..120.0), rng.gen_range(-120.0..120.0));
+            let direction = Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU));
+            let max_distance = rng.gen_range(0.0..100.0);
+            let ignore = rng.gen_range(0..colliders.len());
+
+            let expected = colliders
+                .iter()
+                .enumerate()
+                .filter(|&(i, _)| i != ignore)
+                .filter_map(|(_, c)| c.ray_distance(origin, direction, max_distance))
+                .min_by(f32::total_cmp);
+            let actual = broadphase
+                .ray_cast(origin, direction, max_distance, |_, &i| i != ignore)
+                .map(|(key, distance)| {
+                    let (collider, _) = broadphase.get(key).unwrap();
+                    assert_eq!(
+                        collider.ray_distance(origin, direction, max_distance),
+                        Some(distance)
+                    );
+                    distance
+                });
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn collider_aabb_and_ray_distance() {
+        let circle = Collider2d::Circle(Circle::new(Vec2::new(1.0, 2.0), 3.0));
+        assert_eq!(
+            circle.aabb(),
+            Aabb2d::new(Vec2::new(-2.0, -1.0), Vec2::new(4.0, 5.0))
+        );
+        assert_eq!(
+            circle.ray_distance(Vec2::new(-9.0, 2.0), Vec2::X, 100.0),
+            Some(7.0)
+        );
+        assert_eq!(
+            circle.ray_distance(Vec2::new(1.0, 2.0), Vec2::X, 1.0),
+            Some(0.0)
+        );
+        assert_eq!(
+            circle.ray_distance(Vec2::new(-9.0, 2.0), Vec2::X, 6.0),
+            None
+        );
+        assert_eq!(
+            circle.ray_distance(Vec2::new(-9.0, 2.0), Vec2::NEG_X, 100.0),
+            None
+        );
+
+        // Rotated by 45 degrees.
+        let diamond = Collider2d::RotatedRectangle(RotatedRectangle::new(
+            Vec2::ZERO,
+            Vec2::splat(2.0),
+            Angle(8192),
+        ));
+        let half_size = diamond.aabb().half_size();
+        assert!((half_size - Vec2::splat(2f32.sqrt())).length() < 0.001);
+        let distance = diamond
+            .ray_distance(Vec2::new(-5.0, 0.0), Vec2::X, 100.0)
+            .unwrap();
+        assert!((distance - (5.0 - 2f32.sqrt())).abs() < 0.001);
+        assert_eq!(
+            diamond.ray_distance(Vec2::new(-5.0, 1.5), Vec2::X, 100.0),
+            None
+        );
+    }
+
+    fn bench_colliders(world_radius: f32) -> Vec<Collider2d> {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        (0..2000)
+            .map(|_| random_collider(&mut rng, world_radius))
+            .collect()
+    }
+
+    /// Moves every collider, then finds every colliding pair.
+    fn bench_broadphase_pairs(b: &mut Bencher, world_radius: f32) {
+        let mut colliders = bench_colliders(world_radius);
+        let mut broadphase = Broadphase2d::new(1.0);
+        let keys: Vec<_> = colliders
+            .iter()
+            .map(|&collider| broadphase.insert(collider, ()))
+            .collect();
+        let mut tick = 0;
+        b.iter(|| {
+            tick += 1;
+            let offset = Vec2::from_angle(tick as f32) * 0.25;
+            for (collider, &key) in colliders.iter_mut().zip(&keys) {
+                *collider = moved(*collider, offset);
+                broadphase.update(key, *collider);
+            }
+            let count = broadphase
+                .pairs()
+                .into_iter()
+                .filter(|&(a, b)| {
+                    broadphase
+                        .get(a)
+                        .unwrap()
+                        .0
+                        .collides(broadphase.get(b).unwrap().0)
+                })
+                .count();
+            black_box(count)
+        });
+    }
+
+    struct BenchEntity {
+        index: usize,
+        collider: Collider2d,
+        radius: f32,
+    }
+
+    impl Entity2d for BenchEntity {
+        fn position(&self) -> Vec2 {
+            self.collider.center()
+        }
+    }
+
+    type BenchSectors = SectorArray2d<Entities2d<BenchEntity, 16, 16, 64>, 16, 16, 64>;
+
+    /// Like [`bench_broadphase_pairs`], but with sectors.
+    fn bench_sector_pairs(b: &mut Bencher, world_radius: f32) {
+        let mut colliders = bench_colliders(world_radius);
+        let max_radius = colliders
+            .iter()
+            .map(|c| c.aabb().half_size().length())
+            .fold(0.0, f32::max);
+        let mut sectors = BenchSectors::default();
+        let mut tick = 0;
+        b.iter(|| {
+            tick += 1;
+            let offset = Vec2::from_angle(tick as f32) * 0.25;
+            sectors.iter_mut().for_each(|(_, e)| e.inner.clear());
+            for (index, collider) in colliders.iter_mut().enumerate() {
+                *collider = moved(*collider, offset);
+                let entity = BenchEntity {
+                    index,
+                    collider: *collider,
+                    radius: collider.aabb().half_size().length(),
+                };
+                let sector_id = entity.sector_id().unwrap();
+                sectors.get_mut(sector_id).unwrap().push(entity);
+            }
+            let mut count = 0;
+            for (_, entities) in sectors.iter() {
+                for (_, entity) in entities.iter() {
+                    count += Entities2d::iter_radius(
+                        entity.position(),
+                        entity.radius + max_radius,
+                        |sector_id| sectors.get(sector_id),
+                    )
+                    .filter(|(_, other)| {
+                        other.index > entity.index && other.collider.collides(&entity.collider)
+                    })
+                    .count();
+                }
+            }
+            black_box(count)
+        });
+    }
+
+    #[bench]
+    fn bench_broadphase_pairs_spread(b: &mut Bencher) {
+        bench_broadphase_pairs(b, 500.0);
+    }
+
+    #[bench]
+    fn bench_sector_pairs_spread(b: &mut Bencher) {
+        bench_sector_pairs(b, 500.0);
+    }
+
+    #[bench]
+    fn bench_broadphase_pairs_clustered(b: &mut Bencher) {
+        bench_broadphase_pairs(b, 50.0);
+    }
+
+    #[bench]
+    fn bench_sector_pairs_clustered(b: &mut Bencher) {
+        bench_sector_pairs(b, 50.0);
+    }
+}
+
+#[cfg(test)]
+mod collider_2d_tests {
+    use super::collider_2d_test_helpers::{moved, random_collider, random_point};
+    use crate::angle::Angle;
+    use crate::{Capsule, Circle, Collider2d, ConvexPolygon};
+    use glam::{vec2, Vec2};
+    use rand::prelude::*;
+    use rand_chacha::ChaCha20Rng;
+    use test::bench::{black_box, Bencher};
+
+    fn contains(collider: &Collider2d, point: Vec2) -> bool {
+        collider.ray_distance(point, Vec2::X, 0.0) == Some(0.0)
+    }
+
+    #[test]
+    fn convex_polygon() {
+        let square = ConvexPolygon::new([
+            vec2(0.0, 0.0),
+            vec2(2.0, 2.0),
+            vec2(1.0, 1.0),
+            vec2(2.0, 0.0),
+            vec2(0.0, 2.0),
+            vec2(1.0, 0.0),
+            vec2(0.0, 2.0),
+        ])
+        .unwrap();
+        assert_eq!(
+            square.vertices(),
+            [
+                vec2(0.0, 0.0),
+                vec2(2.0, 0.0),
+                vec2(2.0, 2.0),
+                vec2(0.0, 2.0)
+            ]
+        );
+        assert_eq!(square.area(), 4.0);
+        assert_eq!(square.centroid(), Vec2::ONE);
+        assert!(square.contains(vec2(2.0, 1.0)));
+        assert!(!square.contains(vec2(2.1, 1.0)));
+
+        assert!(ConvexPolygon::new([Vec2::ZERO, Vec2::ONE, Vec2::splat(2.0)]).is_none());
+        assert!(ConvexPolygon::new([Vec2::ZERO, Vec2::X]).is_none());
+        let nonagon = (0..9).map(|i| Vec2::from_angle(i as f32));
+        assert!(ConvexPolygon::new(nonagon).is_none());
+
+        let hexagon = ConvexPolygon::regular(Vec2::ONE, 2.0, 6, Angle::ZERO);
+        assert_eq!(hexagon.vertices().len(), 6);
+        assert!((hexagon.area() - 6.0 * 3f32.sqrt()).abs() < 0.001);
+        assert!(hexagon.centroid().distance(Vec2::ONE) < 0.001);
+        let rotated = hexagon.transformed(Vec2::X, Angle::PI_2);
+        assert!(rotated.vertices()[0].distance(vec2(0.0, 3.0)) < 0.001);
+        assert!((rotated.area() - hexagon.area()).abs() < 0.001);
+    }
+
+    #[test]
+    fn capsule() {
+        let capsule = Collider2d::Capsule(Capsule::new(Vec2::ZERO, vec2(4.0, 0.0), 1.0));
+        assert_eq!(capsule.center(), vec2(2.0, 0.0));
+        assert!((capsule.area() - (8.0 + std::f32::consts::PI)).abs() < 0.001);
+        assert_eq!(
+            capsule.ray_distance(vec2(2.0, 5.0), Vec2::NEG_Y, 10.0),
+            Some(4.0)
+        );
+        assert_eq!(
+            capsule.ray_distance(vec2(-5.0, 0.0), Vec2::X, 10.0),
+            Some(4.0)
+        );
+        assert_eq!(capsule.ray_distance(vec2(-5.0, 1.5), Vec2::X, 10.0), None);
+        let origin_aabb = capsule.origin_aabb();
+        assert_eq!(origin_aabb.radii, vec2(3.0, 1.0));
+
+        let circle = Collider2d::Circle(Circle::new(vec2(2.0, 1.5), 1.0));
+        let (normal, depth) = capsule.collides_normal_depth(&circle).unwrap();
+        assert!(normal.distance(Vec2::Y) < 0.001);
+        assert!((depth - 0.5).abs() < 0.001);
+    }
+
+    /// Circles and rectangles as capsules and polygons should collide like they already did.
+    #[test]
+    fn equivalent_shapes() {
+        let as_general = |collider: Collider2d| match collider {
+            Collider2d::Circle(circle) => {
+                Collider2d::Capsule(Capsule::new(circle.center, circle.center, circle.radius))
+            }
+            Collider2d::RotatedRectangle(rectangle) => {
+                let x = rectangle.normal * rectangle.half_size.x;
+                let y = rectangle.normal.perp() * rectangle.half_size.y;
+                let c = rectangle.center;
+                Collider2d::ConvexPolygon(
+                    ConvexPolygon::new([c + x + y, c - x + y, c - x - y, c + x - y]).unwrap(),
+                )
+            }
+            collider => collider,
+        };
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let mut collisions = 0;
+        for _ in 0..10000 {
+            let [a, b] = [(); 2].map(|_| random_collider(&mut rng, 5.0));
+            if matches!(a, Collider2d::Capsule(_) | Collider2d::ConvexPolygon(_))
+                || matches!(b, Collider2d::Capsule(_) | Collider2d::ConvexPolygon(_))
+            {
+                continue;
+            }
+            let expected = a.collides_normal_depth(&b);
+            let actual = as_general(a).collides_normal_depth(&as_general(b));
+            assert_eq!(a.collides(&b), expected.is_some());
+            let (Some((expected_normal, expected_depth)), Some((normal, depth))) =
+                (expected, actual)
+            else {
+                // Allow disagreement when barely touching.
+                if let Some((_, depth)) = expected.or(actual) {
+                    assert!(depth < 0.001, "{depth}");
+                }
+                continue;
+            };
+            collisions += 1;
+            if matches!(a, Collider2d::RotatedRectangle(_))
+                && matches!(b, Collider2d::RotatedRectangle(_))
+            {
+                // Both are the least overlap of the separating axis theorem, but the original
+                // picks among ties differently.
+                assert!((depth - expected_depth).abs() < 0.001);
+            } else {
+                assert!((depth - expected_depth).abs() < 0.001);
+                assert!(normal.distance(expected_normal) < 0.001);
+            }
+        }
+        assert!(collisions > 500, "{collisions}");
+    }
+
+    #[test]
+    fn collides_normal_depth() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let mut collisions = 0;
+        for _ in 0..5000 {
+            let [a, b] = [(); 2].map(|_| random_collider(&mut rng, 5.0));
+            let result = a.collides_normal_depth(&b);
+            assert_eq!(a.collides(&b), result.is_some());
+            assert_eq!(b.collides(&a), result.is_some());
+
+            // Points inside both imply a collision.
+            let aabb = a.aabb();
+            for _ in 0..20 {
+                let point = aabb.min + (aabb.max - aabb.min) * vec2(rng.gen(), rng.gen());
+                if contains(&a, point) && contains(&b, point) {
+                    assert!(result.is_some());
+                }
+            }
+
+            let Some((normal, depth)) = result else {
+                continue;
+            };
+            collisions += 1;
+            assert!(normal.is_normalized());
+            assert!(depth >= 0.0);
+            // Moving `b` along the normal by the depth should separate them, but no less.
+            assert!(!a.collides(&moved(b, normal * (depth + 0.01))));
+            if depth > 0.01 {
+                assert!(a.collides(&moved(b, normal * (depth - 0.01))));
+            }
+            let (reverse_normal, reverse_depth) = b.collides_normal_depth(&a).unwrap();
+            assert!((reverse_depth - depth).abs() < 0.001);
+            if !matches!(
+                (a, b),
+                (
+                    Collider2d::RotatedRectangle(_),
+                    Collider2d::RotatedRectangle(_)
+                )
+            ) {
+                assert!(reverse_normal.distance(-normal) < 0.001);
+            }
+        }
+        assert!(collisions > 1000, "{collisions}");
+    }
+
+    #[test]
+    fn aabb_and_ray_distance() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        for _ in 0..1000 {
+            let collider = random_collider(&mut rng, 5.0);
+            let aabb = collider.aabb();
+            let center = collider.center();
+            for direction in [Vec2::X, Vec2::Y, Vec2::NEG_X, Vec2::NEG_Y] {
+                let support = collider.support(direction);
+                let bound = if direction.min_element() < 0.0 {
+                    aabb.min
+                } else {
+                    aabb.max
+                };
+                assert!((support.dot(direction) - bound.dot(direction)).abs() < 0.001);
+            }
+            let radii = collider.origin_aabb().radii;
+            assert!(aabb.min.cmpge(center - radii - 0.001).all());
+            assert!(aabb.max.cmple(center + radii + 0.001).all());
+            assert!(contains(&collider, center));
+
+            let origin = random_point(&mut rng, 15.0);
+            let direction = Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU));
+            let max_distance = rng.gen_range(0.0..20.0);
+            let along = |distance: f32| contains(&collider, origin + direction * distance);
+            match collider.ray_distance(origin, direction, max_distance) {
+                Some(distance) => {
+                    assert!(distance <= max_distance);
+                    assert!(along(distance + 0.001));
+                    if distance > 0.0 {
+                        assert!(!along(distance - 0.001));
+                    }
+                }
+                None => {
+                    for i in 0..=100 {
+                        assert!(!along(max_distance * i as f32 * 0.01));
+                    }
+                }
+            }
+        }
+    }
+
+    #[bench]
+    fn bench_convex_polygon_collides(b: &mut Bencher) {
+        let a = Collider2d::ConvexPolygon(ConvexPolygon::regular(
+            Vec2::ZERO,
+            2.0,
+            8,
+            Angle::from_degrees(10.0),
+        ));
+        let c = Collider2d::ConvexPolygon(ConvexPolygon::regular(
+            Vec2::ONE,
+            1.5,
+            5,
+            Angle::from_degrees(-85.0),
+        ));
+        assert!(a.collides(&c));
+        b.iter(|| black_box(black_box(&a).collides_normal_depth(black_box(&c))))
+    }
+
+    #[bench]
+    fn bench_capsule_collides(b: &mut Bencher) {
+        let a = Collider2d::Capsule(Capsule::new(Vec2::ZERO, vec2(3.0, 1.0), 0.5));
+        let c = Collider2d::Capsule(Capsule::new(vec2(1.0, 2.0), vec2(2.0, -1.0), 0.5));
+        assert!(a.collides(&c));
+        b.iter(|| black_box(black_box(&a).collides_normal_depth(black_box(&c))))
+    }
+}
@@ -0,0 +1,85 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{OriginAabb3d, Ray};
+use glam::Vec3;
+
+/// Axis-aligned bounding box.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb3d {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb3d {
+    /// Contains nothing, and is the identity of [`Self::union`].
+    pub const EMPTY: Self = Self {
+        min: Vec3::splat(f32::INFINITY),
+        max: Vec3::splat(f32::NEG_INFINITY),
+    };
+
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        debug_assert!(min.cmple(max).all());
+        Self { min, max }
+    }
+
+    pub fn from_center_half_size(center: Vec3, half_size: Vec3) -> Self {
+        Self::new(center - half_size, center + half_size)
+    }
+
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Self {
+        points.into_iter().fold(Self::EMPTY, |aabb, point| Self {
+            min: aabb.min.min(point),
+            max: aabb.max.max(point),
+        })
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_size(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let size = (self.max - self.min).max(Vec3::ZERO);
+        (size.x * size.y + size.y * size.z + size.z * size.x) * 2.0
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+
+    /// Returns the smallest [`Aabb3d`] containing both.
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Grows each side by `margin`.
+    pub fn expand(&self, margin: f32) -> Self {
+        Self {
+            min: self.min - margin,
+            max: self.max + margin,
+        }
+    }
+
+    /// Returns the `t` value along the ray where it enters the box, or zero if it starts inside.
+    pub fn ray(&self, ray: &Ray) -> Option<f32> {
+        OriginAabb3d {
+            radii: self.half_size(),
+        }
+        .ray(&Ray {
+            origin: ray.origin - self.center(),
+            direction: ray.direction,
+        })
+        .map(|t| t.max(0.0))
+    }
+}
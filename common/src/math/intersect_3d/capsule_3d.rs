@@ -0,0 +1,256 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::obb_3d::sat_normal_depth;
+use super::{Aabb3d, Obb3d, Ray, Sphere, Triangle3d};
+use glam::Vec3;
+
+/// A line segment from `a` to `b`, expanded by `radius`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Capsule3d {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub radius: f32,
+}
+
+impl Capsule3d {
+    pub fn new(a: Vec3, b: Vec3, radius: f32) -> Self {
+        Self { a, b, radius }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.a + self.b) * 0.5
+    }
+
+    pub fn aabb(&self) -> Aabb3d {
+        Aabb3d::from_points([self.a, self.b]).expand(self.radius)
+    }
+
+    /// Returns the point on the segment from `a` to `b` nearest to `point`.
+    pub fn closest_point_on_segment(&self, point: Vec3) -> Vec3 {
+        let ab = self.b - self.a;
+        let length_squared = ab.length_squared();
+        if length_squared == 0.0 {
+            return self.a;
+        }
+        let t = ((point - self.a).dot(ab) / length_squared).clamp(0.0, 1.0);
+        self.a + ab * t
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.closest_point_on_segment(point).distance_squared(point) <= self.radius.powi(2)
+    }
+
+    /// Returns the `t` value along the normalized ray where it enters the capsule, or zero if it
+    /// starts inside.
+    pub fn ray(&self, ray: &Ray) -> Option<f32> {
+        if self.contains_point(ray.origin) {
+            return Some(0.0);
+        }
+        // See: https://iquilezles.org/articles/intersectors/
+        let ba = self.b - self.a;
+        let oa = ray.origin - self.a;
+        let baba = ba.dot(ba);
+        let bard = ba.dot(ray.direction);
+        let baoa = ba.dot(oa);
+        let rdoa = ray.direction.dot(oa);
+        let oaoa = oa.dot(oa);
+        let a = baba - bard * bard;
+        let b = baba * rdoa - baoa * bard;
+        let c = baba * oaoa - baoa * baoa - self.radius.powi(2) * baba;
+        let h = b * b - a * c;
+        // Otherwise, the ray is parallel to the segment, and can only hit the ends.
+        if a > f32::EPSILON * baba && h >= 0.0 {
+            let t = (-b - h.sqrt()) / a;
+            let y = baoa + t * bard;
+            if y > 0.0 && y < baba {
+                return (t >= 0.0).then_some(t);
+            }
+        }
+        [self.a, self.b]
+            .into_iter()
+            .filter_map(|center| {
+                Sphere {
+                    center,
+                    radius: self.radius,
+                }
+                .ray(ray, true)
+            })
+            .filter(|&t| t >= 0.0)
+            .reduce(f32::min)
+    }
+
+    /// Returns (min, max) of the capsule projected onto `axis`.
+    pub(crate) fn project(&self, axis: Vec3) -> (f32, f32) {
+        let a = self.a.dot(axis);
+        let b = self.b.dot(axis);
+        (a.min(b) - self.radius, a.max(b) + self.radius)
+    }
+
+    pub fn collides(&self, other: &Self) -> bool {
+        self.collides_normal_depth(other).is_some()
+    }
+
+    /// Returns (normal, depth), where normal points from `self` towards `other`.
+    pub fn collides_normal_depth(&self, other: &Self) -> Option<(Vec3, f32)> {
+        let (s, o) = closest_points_segments(self.a, self.b, other.a, other.b);
+        let normal = (o - s).try_normalize().unwrap_or_else(|| {
+            // Segments intersect, so separate perpendicular to both.
+            let cross = (self.b - self.a).cross(other.b - other.a);
+            let normal = cross
+                .try_normalize()
+                .unwrap_or_else(|| perpendicular(self.b - self.a));
+            if normal.dot(other.center() - self.center()) < 0.0 {
+                -normal
+            } else {
+                normal
+            }
+        });
+        let depth = self.radius + other.radius - s.distance(o);
+        (depth >= 0.0).then_some((normal, depth))
+    }
+
+    /// Returns (normal, depth), where normal points from `self` towards `obb`.
+    pub fn obb_normal_depth(&self, obb: &Obb3d) -> Option<(Vec3, f32)> {
+        let (s, o) = closest_points_segment_convex(self.a, self.b, |p| obb.closest_point(p));
+        self.normal_depth(s, o).or_else(|| {
+            if s.distance_squared(o) > DEEP_SQUARED {
+                return None;
+            }
+            // The segment is inside the box.
+            let axes = obb.axes();
+            let direction = self.b - self.a;
+            sat_normal_depth(
+                axes.into_iter()
+                    .chain(axes.map(|axis| direction.cross(axis))),
+                |axis| self.project(axis),
+                |axis| obb.project(axis),
+            )
+        })
+    }
+
+    /// Returns (normal, depth), where normal points from `self` towards `triangle`.
+    pub fn triangle_normal_depth(&self, triangle: &Triangle3d) -> Option<(Vec3, f32)> {
+        let (s, o) = closest_points_segment_convex(self.a, self.b, |p| triangle.closest_point(p));
+        self.normal_depth(s, o).or_else(|| {
+            if s.distance_squared(o) > DEEP_SQUARED {
+                return None;
+            }
+            // The segment passes through the triangle.
+            let direction = self.b - self.a;
+            sat_normal_depth(
+                [triangle.normal()]
+                    .into_iter()
+                    .chain(triangle.edges().map(|edge| direction.cross(edge))),
+                |axis| self.project(axis),
+                |axis| triangle.project(axis),
+            )
+        })
+    }
+
+    /// Returns (normal, depth) given the closest points on the segment and another shape, if
+    /// they're far enough apart to compute a normal from.
+    fn normal_depth(&self, on_segment: Vec3, on_other: Vec3) -> Option<(Vec3, f32)> {
+        let distance_squared = on_segment.distance_squared(on_other);
+        if distance_squared <= DEEP_SQUARED || distance_squared > self.radius.powi(2) {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        Some(((on_other - on_segment) / distance, self.radius - distance))
+    }
+}
+
+/// Shapes closer than this are considered to overlap, so the normal is computed differently.
+const DEEP_SQUARED: f32 = 1e-8;
+
+/// Returns any normalized vector perpendicular to `v`.
+pub(crate) fn perpendicular(v: Vec3) -> Vec3 {
+    v.try_normalize()
+        .map(|v| v.any_orthonormal_vector())
+        .unwrap_or(Vec3::X)
+}
+
+/// Returns the closest points on segments `p1`-`q1` and `p2`-`q2`.
+pub(crate) fn closest_points_segments(p1: Vec3, q1: Vec3, p2: Vec3, q2: Vec3) -> (Vec3, Vec3) {
+    // See: Real-Time Collision Detection, 5.1.9.
+    let d1 = q1 - p1;
+    let d2 = q2 - p2;
+    let r = p1 - p2;
+    let a = d1.dot(d1);
+    let e = d2.dot(d2);
+    let f = d2.dot(r);
+    let (s, t) = if a <= f32::EPSILON && e <= f32::EPSILON {
+        (0.0, 0.0)
+    } else if a <= f32::EPSILON {
+        (0.0, (f / e).clamp(0.0, 1.0))
+    } else {
+        let c = d1.dot(r);
+        if e <= f32::EPSILON {
+            ((-c / a).clamp(0.0, 1.0), 0.0)
+        } else {
+            let b = d1.dot(d2);
+            let denominator = a * e - b * b;
+            let mut s = if denominator > 0.0 {
+                ((b * f - c * e) / denominator).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let mut t = (b * s + f) / e;
+            if t < 0.0 {
+                t = 0.0;
+                s = (-c / a).clamp(0.0, 1.0);
+            } else if t > 1.0 {
+                t = 1.0;
+                s = ((b - c) / a).clamp(0.0, 1.0);
+            }
+            (s, t)
+        }
+    };
+    (p1 + d1 * s, p2 + d2 * t)
+}
+
+/// Returns the closest points on segment `a`-`b` and a convex shape, given the shape's closest
+/// point function.
+fn closest_points_segment_convex(
+    a: Vec3,
+    b: Vec3,
+    closest_point: impl Fn(Vec3) -> Vec3,
+) -> (Vec3, Vec3) {
+    // The squared distance to a convex shape is convex, so a golden-section search finds its
+    // minimum along the segment.
+    const RATIO: f32 = 0.618034;
+    let distance_squared = |t: f32| {
+        let p = a.lerp(b, t);
+        p.distance_squared(closest_point(p))
+    };
+    let (mut low, mut high) = (0f32, 1f32);
+    let mut left = high - (high - low) * RATIO;
+    let mut right = low + (high - low) * RATIO;
+    let (mut left_distance, mut right_distance) = (distance_squared(left), distance_squared(right));
+    for _ in 0..32 {
+        if left_distance <= right_distance {
+            high = right;
+            right = left;
+            right_distance = left_distance;
+            left = high - (high - low) * RATIO;
+            left_distance = distance_squared(left);
+        } else {
+            low = left;
+            left = right;
+            left_distance = right_distance;
+            right = low + (high - low) * RATIO;
+            right_distance = distance_squared(right);
+        }
+    }
+    // The ends aren't tested by the search.
+    let p = [0.0, (low + high) * 0.5, 1.0]
+        .into_iter()
+        .map(|t| a.lerp(b, t))
+        .min_by(|&p1, &p2| {
+            let d1 = p1.distance_squared(closest_point(p1));
+            let d2 = p2.distance_squared(closest_point(p2));
+            d1.total_cmp(&d2)
+        })
+        .unwrap();
+    (p, closest_point(p))
+}
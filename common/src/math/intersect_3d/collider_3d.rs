@@ -0,0 +1,77 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{Aabb3d, Capsule3d, Obb3d, Ray, Sphere, Triangle3d};
+use glam::Vec3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Collider3d {
+    Sphere(Sphere),
+    Capsule(Capsule3d),
+    Obb(Obb3d),
+}
+
+impl Collider3d {
+    pub fn center(&self) -> Vec3 {
+        match self {
+            Self::Sphere(sphere) => sphere.center,
+            Self::Capsule(capsule) => capsule.center(),
+            Self::Obb(obb) => obb.center,
+        }
+    }
+
+    pub fn aabb(&self) -> Aabb3d {
+        match self {
+            Self::Sphere(sphere) => sphere.aabb(),
+            Self::Capsule(capsule) => capsule.aabb(),
+            Self::Obb(obb) => obb.aabb(),
+        }
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        match self {
+            Self::Sphere(sphere) => sphere.contains_point(point),
+            Self::Capsule(capsule) => capsule.contains_point(point),
+            Self::Obb(obb) => obb.contains_point(point),
+        }
+    }
+
+    /// Returns the `t` value along the normalized ray where it enters the collider, or zero if it
+    /// starts inside.
+    pub fn ray(&self, ray: &Ray) -> Option<f32> {
+        match self {
+            Self::Sphere(sphere) => sphere.ray(ray, true).map(|t| t.max(0.0)),
+            Self::Capsule(capsule) => capsule.ray(ray),
+            Self::Obb(obb) => obb.ray(ray),
+        }
+    }
+
+    pub fn collides(&self, other: &Self) -> bool {
+        self.collides_normal_depth(other).is_some()
+    }
+
+    /// Returns (normal, depth), where normal points from `self` towards `other`.
+    pub fn collides_normal_depth(&self, other: &Self) -> Option<(Vec3, f32)> {
+        let flip = |(normal, depth): (Vec3, f32)| (-normal, depth);
+        match (self, other) {
+            (Self::Sphere(s), Self::Sphere(o)) => s.collides_normal_depth(o),
+            (Self::Sphere(s), Self::Capsule(o)) => s.capsule_normal_depth(o),
+            (Self::Sphere(s), Self::Obb(o)) => s.obb_normal_depth(o),
+            (Self::Capsule(s), Self::Sphere(o)) => o.capsule_normal_depth(s).map(flip),
+            (Self::Capsule(s), Self::Capsule(o)) => s.collides_normal_depth(o),
+            (Self::Capsule(s), Self::Obb(o)) => s.obb_normal_depth(o),
+            (Self::Obb(s), Self::Sphere(o)) => o.obb_normal_depth(s).map(flip),
+            (Self::Obb(s), Self::Capsule(o)) => o.obb_normal_depth(s).map(flip),
+            (Self::Obb(s), Self::Obb(o)) => s.collides_normal_depth(o),
+        }
+    }
+
+    /// Returns (normal, depth), where normal points from `self` towards `triangle`.
+    pub fn triangle_normal_depth(&self, triangle: &Triangle3d) -> Option<(Vec3, f32)> {
+        match self {
+            Self::Sphere(sphere) => sphere.triangle_normal_depth(triangle),
+            Self::Capsule(capsule) => capsule.triangle_normal_depth(triangle),
+            Self::Obb(obb) => obb.triangle_normal_depth(triangle),
+        }
+    }
+}
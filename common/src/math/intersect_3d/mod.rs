@@ -3,13 +3,25 @@
 //
 // See: https://iquilezles.org/articles/intersectors/
 
+mod aabb_3d;
 mod any_box;
+mod capsule_3d;
+mod collider_3d;
+mod obb_3d;
 mod origin_aabb_3d;
 mod ray;
 mod sphere;
 mod tests;
+mod triangle_3d;
+mod triangle_mesh_3d;
 
+pub use aabb_3d::Aabb3d;
 pub use any_box::AnyBox;
+pub use capsule_3d::Capsule3d;
+pub use collider_3d::Collider3d;
+pub use obb_3d::Obb3d;
 pub use origin_aabb_3d::OriginAabb3d;
 pub use ray::Ray;
 pub use sphere::Sphere;
+pub use triangle_3d::Triangle3d;
+pub use triangle_mesh_3d::TriangleMesh3d;
@@ -0,0 +1,141 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{Aabb3d, OriginAabb3d, Ray, Triangle3d};
+use glam::{Mat3, Quat, Vec3};
+
+/// Oriented bounding box.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Obb3d {
+    pub center: Vec3,
+    pub rotation: Quat,
+    pub half_size: Vec3,
+}
+
+impl Obb3d {
+    pub fn new(center: Vec3, rotation: Quat, size: Vec3) -> Self {
+        debug_assert!(rotation.is_normalized());
+        Self {
+            center,
+            rotation,
+            half_size: size * 0.5,
+        }
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.half_size * 2.0
+    }
+
+    /// Returns the local x, y, and z axes in world space.
+    pub fn axes(&self) -> [Vec3; 3] {
+        let matrix = Mat3::from_quat(self.rotation);
+        [matrix.x_axis, matrix.y_axis, matrix.z_axis]
+    }
+
+    pub fn aabb(&self) -> Aabb3d {
+        let [x, y, z] = self.axes();
+        let half_size =
+            x.abs() * self.half_size.x + y.abs() * self.half_size.y + z.abs() * self.half_size.z;
+        Aabb3d::from_center_half_size(self.center, half_size)
+    }
+
+    /// Converts a point from world space to local space.
+    pub fn to_local(&self, point: Vec3) -> Vec3 {
+        self.rotation.inverse() * (point - self.center)
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.to_local(point).abs().cmple(self.half_size).all()
+    }
+
+    /// Returns the point in or on the box nearest to `point`.
+    pub fn closest_point(&self, point: Vec3) -> Vec3 {
+        let local = self.to_local(point).clamp(-self.half_size, self.half_size);
+        self.center + self.rotation * local
+    }
+
+    /// Returns the `t` value along the ray where it enters the box, or zero if it starts inside.
+    pub fn ray(&self, ray: &Ray) -> Option<f32> {
+        let inverse = self.rotation.inverse();
+        OriginAabb3d {
+            radii: self.half_size,
+        }
+        .ray(&Ray {
+            origin: inverse * (ray.origin - self.center),
+            direction: inverse * ray.direction,
+        })
+        .map(|t| t.max(0.0))
+    }
+
+    /// Returns (min, max) of the box projected onto `axis`.
+    pub(crate) fn project(&self, axis: Vec3) -> (f32, f32) {
+        let center = self.center.dot(axis);
+        let [x, y, z] = self.axes();
+        let radius = self.half_size.x * x.dot(axis).abs()
+            + self.half_size.y * y.dot(axis).abs()
+            + self.half_size.z * z.dot(axis).abs();
+        (center - radius, center + radius)
+    }
+
+    pub fn collides(&self, other: &Self) -> bool {
+        self.collides_normal_depth(other).is_some()
+    }
+
+    /// Returns (normal, depth), where normal points from `self` towards `other`.
+    pub fn collides_normal_depth(&self, other: &Self) -> Option<(Vec3, f32)> {
+        let a = self.axes();
+        let b = other.axes();
+        let edges = a.into_iter().flat_map(|a| b.map(|b| a.cross(b)));
+        sat_normal_depth(
+            a.into_iter().chain(b).chain(edges),
+            |axis| self.project(axis),
+            |axis| other.project(axis),
+        )
+    }
+
+    /// Returns (normal, depth), where normal points from `self` towards `triangle`.
+    pub fn triangle_normal_depth(&self, triangle: &Triangle3d) -> Option<(Vec3, f32)> {
+        let axes = self.axes();
+        let edges = triangle.edges();
+        let crosses = axes.into_iter().flat_map(|a| edges.map(|e| a.cross(e)));
+        sat_normal_depth(
+            axes.into_iter().chain([triangle.normal()]).chain(crosses),
+            |axis| self.project(axis),
+            |axis| triangle.project(axis),
+        )
+    }
+}
+
+/// Separating axis test. Returns the axis of least overlap, pointing from `a` towards `b`, and
+/// the overlap, or `None` if the (min, max) projections onto any of the axes don't overlap.
+/// Axes needn't be normalized, and (nearly) zero axes are skipped.
+pub(crate) fn sat_normal_depth(
+    axes: impl IntoIterator<Item = Vec3>,
+    a: impl Fn(Vec3) -> (f32, f32),
+    b: impl Fn(Vec3) -> (f32, f32),
+) -> Option<(Vec3, f32)> {
+    let mut min_overlap: Option<(Vec3, f32)> = None;
+    for axis in axes {
+        // e.g. cross product of parallel edges.
+        if axis.length_squared() < 1e-6 {
+            continue;
+        }
+        let axis = axis.normalize();
+        let (a_min, a_max) = a(axis);
+        let (b_min, b_max) = b(axis);
+        let forwards = a_max - b_min;
+        let backwards = b_max - a_min;
+        if forwards < 0.0 || backwards < 0.0 {
+            return None;
+        }
+        let overlap = if forwards <= backwards {
+            (axis, forwards)
+        } else {
+            (-axis, backwards)
+        };
+        if !matches!(min_overlap, Some((_, min)) if min <= overlap.1) {
+            min_overlap = Some(overlap);
+        }
+    }
+    min_overlap
+}
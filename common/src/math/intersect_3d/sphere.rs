@@ -3,14 +3,28 @@
 
 use glam::{Mat4, Vec2, Vec3};
 
-use super::Ray;
+use super::capsule_3d::perpendicular;
+use super::{Aabb3d, Capsule3d, Obb3d, Ray, Triangle3d};
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Sphere {
     pub center: Vec3,
     pub radius: f32,
 }
 
 impl Sphere {
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn aabb(&self) -> Aabb3d {
+        Aabb3d::from_center_half_size(self.center, Vec3::splat(self.radius))
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.center.distance_squared(point) <= self.radius.powi(2)
+    }
+
     pub fn mul_mat4(&self, matrix: &Mat4) -> Self {
         Self {
             center: matrix.transform_point3(self.center),
@@ -43,4 +57,88 @@ impl Sphere {
             Some(t.x)
         }
     }
+
+    pub fn collides(&self, other: &Self) -> bool {
+        self.center.distance_squared(other.center) <= (self.radius + other.radius).powi(2)
+    }
+
+    /// Returns (normal, depth), where normal points from `self` towards `other`.
+    pub fn collides_normal_depth(&self, other: &Self) -> Option<(Vec3, f32)> {
+        self.point_normal_depth(other.center, other.radius)
+    }
+
+    /// Returns (normal, depth), where normal points from `self` towards `capsule`.
+    pub fn capsule_normal_depth(&self, capsule: &Capsule3d) -> Option<(Vec3, f32)> {
+        let point = capsule.closest_point_on_segment(self.center);
+        self.point_normal_depth(point, capsule.radius)
+            .map(|(normal, depth)| {
+                if point == self.center {
+                    // Separate perpendicular to the capsule.
+                    (perpendicular(capsule.b - capsule.a), depth)
+                } else {
+                    (normal, depth)
+                }
+            })
+    }
+
+    /// Returns (normal, depth), where normal points from `self` towards `obb`.
+    pub fn obb_normal_depth(&self, obb: &Obb3d) -> Option<(Vec3, f32)> {
+        let local = obb.to_local(self.center);
+        let clamped = local.clamp(-obb.half_size, obb.half_size);
+        // Local space, pointing from `obb` towards `self`.
+        let (local_normal, depth) = if local != clamped {
+            let delta = local - clamped;
+            let distance_squared = delta.length_squared();
+            if distance_squared > self.radius.powi(2) {
+                return None;
+            }
+            let distance = distance_squared.sqrt();
+            (delta / distance, self.radius - distance)
+        } else {
+            // Center is inside `obb`, so push out via the nearest face.
+            let inside = obb.half_size - local.abs();
+            let axis = if inside.x <= inside.y && inside.x <= inside.z {
+                0
+            } else if inside.y <= inside.z {
+                1
+            } else {
+                2
+            };
+            let mut normal = Vec3::ZERO;
+            normal[axis] = if local[axis] < 0.0 { -1.0 } else { 1.0 };
+            (normal, self.radius + inside[axis])
+        };
+        Some((-(obb.rotation * local_normal), depth))
+    }
+
+    /// Returns (normal, depth), where normal points from `self` towards `triangle`.
+    pub fn triangle_normal_depth(&self, triangle: &Triangle3d) -> Option<(Vec3, f32)> {
+        let point = triangle.closest_point(self.center);
+        self.point_normal_depth(point, 0.0).map(|(normal, depth)| {
+            if point == self.center {
+                // Separate via the front face.
+                (-triangle.normal(), depth)
+            } else {
+                (normal, depth)
+            }
+        })
+    }
+
+    /// Returns (normal, depth) with another sphere, where normal points from `self` towards
+    /// `center`, which is [`Vec3::X`] if the centers coincide.
+    fn point_normal_depth(&self, center: Vec3, radius: f32) -> Option<(Vec3, f32)> {
+        let delta = center - self.center;
+        let radii = self.radius + radius;
+        let distance_squared = delta.length_squared();
+        if distance_squared > radii.powi(2) {
+            return None;
+        }
+        let distance = distance_squared.sqrt();
+        let normal = if distance > 0.0 {
+            delta / distance
+        } else {
+            Vec3::X
+        };
+        Some((normal, radii - distance))
+    }
 }
@@ -4,7 +4,7 @@
 #[cfg(test)]
 mod intersect_3d_tests {
     use super::{OriginAabb3d, Ray, Sphere};
-    use glam::{EulerRot, Mat4, Quat, Vec3, Vec3, Vec3Swizzles};
+    use glam::{EulerRot, Mat4, Quat, Vec3, Vec3Swizzles};
     use rand::{thread_rng, Rng};
 
     #[test]
@@ -59,3 +59,344 @@ mod intersect_3d_tests {
         }
     }
 }
+
+#[cfg(test)]
+mod collision_3d_tests {
+    use crate::{Aabb3d, Capsule3d, Collider3d, Obb3d, Ray, Sphere, Triangle3d, TriangleMesh3d};
+    use glam::{EulerRot, Quat, Vec3};
+    use rand::prelude::*;
+    use rand_chacha::ChaCha20Rng;
+    use std::f32::consts::PI;
+
+    fn random_vec3(rng: &mut impl Rng, radius: f32) -> Vec3 {
+        Vec3::new(
+            rng.gen_range(-radius..radius),
+            rng.gen_range(-radius..radius),
+            rng.gen_range(-radius..radius),
+        )
+    }
+
+    fn random_collider(rng: &mut impl Rng) -> Collider3d {
+        let center = random_vec3(rng, 2.0);
+        match rng.gen_range(0..3) {
+            0 => Collider3d::Sphere(Sphere::new(center, rng.gen_range(0.1..1.5))),
+            1 => {
+                let half = random_vec3(rng, 1.5);
+                Collider3d::Capsule(Capsule3d::new(
+                    center - half,
+                    center + half,
+                    rng.gen_range(0.1..1.0),
+                ))
+            }
+            _ => {
+                let rotation = Quat::from_euler(
+                    EulerRot::XYZ,
+                    rng.gen_range(-PI..PI),
+                    rng.gen_range(-PI..PI),
+                    rng.gen_range(-PI..PI),
+                );
+                let size = Vec3::new(
+                    rng.gen_range(0.2..3.0),
+                    rng.gen_range(0.2..3.0),
+                    rng.gen_range(0.2..3.0),
+                );
+                Collider3d::Obb(Obb3d::new(center, rotation, size))
+            }
+        }
+    }
+
+    fn random_triangle(rng: &mut impl Rng) -> Triangle3d {
+        let center = random_vec3(rng, 2.0);
+        Triangle3d::new(
+            center + random_vec3(rng, 2.0),
+            center + random_vec3(rng, 2.0),
+            center + random_vec3(rng, 2.0),
+        )
+    }
+
+    fn translated(collider: Collider3d, offset: Vec3) -> Collider3d {
+        match collider {
+            Collider3d::Sphere(mut sphere) => {
+                sphere.center += offset;
+                Collider3d::Sphere(sphere)
+            }
+            Collider3d::Capsule(mut capsule) => {
+                capsule.a += offset;
+                capsule.b += offset;
+                Collider3d::Capsule(capsule)
+            }
+            Collider3d::Obb(mut obb) => {
+                obb.center += offset;
+                Collider3d::Obb(obb)
+            }
+        }
+    }
+
+    #[test]
+    fn obb_normal_depth() {
+        let a = Obb3d::new(Vec3::ZERO, Quat::IDENTITY, Vec3::ONE);
+        let b = Obb3d::new(Vec3::new(0.9, 0.2, 0.0), Quat::IDENTITY, Vec3::ONE);
+        let (normal, depth) = a.collides_normal_depth(&b).unwrap();
+        assert!((normal - Vec3::X).length() < 0.001, "{normal}");
+        assert!((depth - 0.1).abs() < 0.001, "{depth}");
+
+        // Rotated 45 degrees around z, so its corner reaches x = 0.5 + 0.5 * 2.sqrt().
+        let rotation = Quat::from_rotation_z(PI / 4.0);
+        let c = Obb3d::new(Vec3::new(1.2, 0.0, 0.0), rotation, Vec3::ONE);
+        let (normal, depth) = a.collides_normal_depth(&c).unwrap();
+        assert!((normal - Vec3::X).length() < 0.001, "{normal}");
+        assert!(
+            (depth - (0.5 + 0.5 * 2f32.sqrt() - 1.2)).abs() < 0.001,
+            "{depth}"
+        );
+        assert!(!a.collides(&Obb3d::new(Vec3::new(1.8, 0.0, 0.0), rotation, Vec3::ONE)));
+    }
+
+    #[test]
+    fn triangle_closest_point() {
+        let triangle = Triangle3d::new(Vec3::ZERO, Vec3::X, Vec3::Y);
+        assert_eq!(triangle.normal(), Vec3::Z);
+        assert_eq!(
+            triangle.closest_point(Vec3::new(-1.0, -1.0, 1.0)),
+            Vec3::ZERO
+        );
+        assert_eq!(
+            triangle.closest_point(Vec3::new(0.25, 0.25, 5.0)),
+            Vec3::new(0.25, 0.25, 0.0)
+        );
+        assert_eq!(
+            triangle.closest_point(Vec3::new(0.5, -2.0, 0.0)),
+            Vec3::new(0.5, 0.0, 0.0)
+        );
+        let ray = Ray {
+            origin: Vec3::new(0.25, 0.25, 2.0),
+            direction: Vec3::NEG_Z,
+        };
+        assert_eq!(triangle.ray(&ray), Some(2.0));
+    }
+
+    #[test]
+    fn capsule_ray() {
+        let capsule = Capsule3d::new(Vec3::new(0.0, -1.0, 0.0), Vec3::new(0.0, 1.0, 0.0), 0.5);
+        let side = Ray {
+            origin: Vec3::new(-5.0, 0.5, 0.0),
+            direction: Vec3::X,
+        };
+        assert!((capsule.ray(&side).unwrap() - 4.5).abs() < 0.001);
+        let end = Ray {
+            origin: Vec3::new(0.0, 5.0, 0.0),
+            direction: Vec3::NEG_Y,
+        };
+        assert!((capsule.ray(&end).unwrap() - 3.5).abs() < 0.001);
+        let inside = Ray {
+            origin: Vec3::ZERO,
+            direction: Vec3::X,
+        };
+        assert_eq!(capsule.ray(&inside), Some(0.0));
+        let miss = Ray {
+            origin: Vec3::new(-5.0, 1.6, 0.0),
+            direction: Vec3::X,
+        };
+        assert_eq!(capsule.ray(&miss), None);
+    }
+
+    /// Moving `b` by the normal times the depth separates the colliders.
+    #[test]
+    fn collider_normal_depth_separates() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let mut collisions = 0;
+        for _ in 0..10000 {
+            let a = random_collider(&mut rng);
+            let b = random_collider(&mut rng);
+            let result = a.collides_normal_depth(&b);
+            let reverse = b.collides_normal_depth(&a);
+            assert_eq!(result.is_some(), reverse.is_some(), "{a:?} {b:?}");
+
+            let Some((normal, depth)) = result else {
+                let aabb = a.aabb();
+                for _ in 0..20 {
+                    let point = aabb.center() + random_vec3(&mut rng, 1.0) * aabb.half_size();
+                    assert!(
+                        !(a.contains_point(point) && b.contains_point(point)),
+                        "{a:?} {b:?} {point}"
+                    );
+                }
+                continue;
+            };
+            collisions += 1;
+            assert!(normal.is_normalized(), "{a:?} {b:?} {normal}");
+            assert!(depth >= 0.0, "{a:?} {b:?} {depth}");
+            assert!(
+                (reverse.unwrap().1 - depth).abs() < 0.01,
+                "{a:?} {b:?} {result:?} {reverse:?}"
+            );
+            let moved = translated(b, normal * (depth + 0.01));
+            assert!(!a.collides(&moved), "{a:?} {b:?} {normal} {depth}");
+        }
+        assert!(collisions > 1000, "{collisions}");
+    }
+
+    /// Moving the triangle by the normal times the depth separates it from the collider.
+    #[test]
+    fn triangle_normal_depth_separates() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let mut collisions = 0;
+        for _ in 0..10000 {
+            let collider = random_collider(&mut rng);
+            let triangle = random_triangle(&mut rng);
+            let Some((normal, depth)) = collider.triangle_normal_depth(&triangle) else {
+                for _ in 0..20 {
+                    let [u, v]: [f32; 2] = rng.gen();
+                    let (u, v) = if u + v > 1.0 {
+                        (1.0 - u, 1.0 - v)
+                    } else {
+                        (u, v)
+                    };
+                    let [a, b, c] = triangle.vertices;
+                    let point = a + (b - a) * u + (c - a) * v;
+                    assert!(!collider.contains_point(point), "{collider:?} {triangle:?}");
+                }
+                continue;
+            };
+            collisions += 1;
+            assert!(normal.is_normalized(), "{collider:?} {triangle:?} {normal}");
+            assert!(depth >= 0.0, "{collider:?} {triangle:?} {depth}");
+            let offset = normal * (depth + 0.01);
+            let moved = Triangle3d {
+                vertices: triangle.vertices.map(|v| v + offset),
+            };
+            assert_eq!(
+                collider.triangle_normal_depth(&moved),
+                None,
+                "{collider:?} {triangle:?} {normal} {depth}"
+            );
+        }
+        assert!(collisions > 1000, "{collisions}");
+    }
+
+    /// Rays hit the surface of colliders, and don't pass through them before that.
+    #[test]
+    fn collider_ray() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let mut hits = 0;
+        for _ in 0..2000 {
+            let collider = random_collider(&mut rng);
+            let origin = random_vec3(&mut rng, 1.0).normalize_or_zero() * 10.0;
+            let target = random_vec3(&mut rng, 3.0);
+            let ray = Ray {
+                origin,
+                direction: (target - origin).normalize(),
+            };
+            let result = collider.ray(&ray);
+            let end = result.unwrap_or(20.0);
+            for i in 0..1000 {
+                let t = end * i as f32 / 1000.0;
+                assert!(
+                    !collider.contains_point(ray.position(t)) || t > end - 0.01,
+                    "{collider:?} {ray:?} {result:?}"
+                );
+            }
+            if let Some(t) = result {
+                hits += 1;
+                let point = ray.position(t + 0.001);
+                assert!(
+                    collider.contains_point(point) || collider.contains_point(ray.position(t)),
+                    "{collider:?} {ray:?} {t}"
+                );
+            }
+        }
+        assert!(hits > 200, "{hits}");
+    }
+
+    fn random_mesh(rng: &mut impl Rng) -> TriangleMesh3d {
+        // Bumpy terrain, and some floating triangles.
+        let n = 16;
+        let mut vertices: Vec<Vec3> = (0..n * n)
+            .map(|i| {
+                let (x, z) = ((i % n) as f32, (i / n) as f32);
+                Vec3::new(
+                    x - n as f32 / 2.0,
+                    rng.gen_range(-0.5..0.5),
+                    z - n as f32 / 2.0,
+                )
+            })
+            .collect();
+        let mut triangles = Vec::new();
+        for z in 0..n - 1 {
+            for x in 0..n - 1 {
+                let i = z * n + x;
+                triangles.push([i, i + n, i + 1]);
+                triangles.push([i + 1, i + n, i + n + 1]);
+            }
+        }
+        for _ in 0..50 {
+            let triangle = random_triangle(rng);
+            let i = vertices.len() as u32;
+            vertices.extend(triangle.vertices.map(|v| v * 3.0 + Vec3::Y * 3.0));
+            triangles.push([i, i + 1, i + 2]);
+        }
+        TriangleMesh3d::new(vertices, triangles)
+    }
+
+    #[test]
+    fn triangle_mesh() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let mesh = random_mesh(&mut rng);
+        assert_eq!(mesh.len(), 15 * 15 * 2 + 50);
+        assert_eq!(
+            mesh.aabb(),
+            mesh.triangles()
+                .fold(Aabb3d::EMPTY, |aabb, triangle| aabb.union(&triangle.aabb()))
+        );
+
+        let mut hits = 0;
+        for _ in 0..500 {
+            let ray = Ray {
+                origin: random_vec3(&mut rng, 10.0) + Vec3::Y * 10.0,
+                direction: random_vec3(&mut rng, 1.0).normalize(),
+            };
+            let expected = mesh
+                .triangles()
+                .enumerate()
+                .filter_map(|(i, triangle)| Some((triangle.ray(&ray)?, i)))
+                .min_by(|a, b| a.partial_cmp(b).unwrap());
+            assert_eq!(mesh.ray(&ray), expected, "{ray:?}");
+            hits += expected.is_some() as usize;
+
+            let aabb = Aabb3d::from_center_half_size(ray.origin - Vec3::Y * 10.0, Vec3::splat(1.5));
+            let mut expected: Vec<usize> = mesh
+                .triangles()
+                .enumerate()
+                .filter(|(_, triangle)| triangle.aabb().intersects(&aabb))
+                .map(|(i, _)| i)
+                .collect();
+            let mut actual: Vec<usize> = mesh.query_aabb(aabb).collect();
+            actual.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(actual, expected);
+
+            let collider = translated(random_collider(&mut rng), ray.origin - Vec3::Y * 10.0);
+            let expected = mesh
+                .triangles()
+                .enumerate()
+                .filter_map(|(i, triangle)| {
+                    collider
+                        .triangle_normal_depth(&triangle)
+                        .map(|(normal, depth)| (normal, depth, i))
+                })
+                .reduce(|a, b| if b.1 > a.1 { b } else { a });
+            assert_eq!(mesh.collider_normal_depth(&collider), expected);
+        }
+        assert!(hits > 100, "{hits}");
+
+        let empty = TriangleMesh3d::new(Vec::new(), Vec::new());
+        assert!(empty.is_empty());
+        assert_eq!(
+            empty.ray(&Ray {
+                origin: Vec3::ZERO,
+                direction: Vec3::X
+            }),
+            None
+        );
+    }
+}
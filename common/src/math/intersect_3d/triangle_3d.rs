@@ -0,0 +1,119 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{Aabb3d, Ray};
+use glam::Vec3;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Triangle3d {
+    pub vertices: [Vec3; 3],
+}
+
+impl Triangle3d {
+    pub fn new(a: Vec3, b: Vec3, c: Vec3) -> Self {
+        Self {
+            vertices: [a, b, c],
+        }
+    }
+
+    pub fn centroid(&self) -> Vec3 {
+        let [a, b, c] = self.vertices;
+        (a + b + c) * (1.0 / 3.0)
+    }
+
+    /// Returns the normal of the front face (with counter-clockwise vertices), or zero if the
+    /// triangle is degenerate.
+    pub fn normal(&self) -> Vec3 {
+        let [a, b, c] = self.vertices;
+        (b - a).cross(c - a).normalize_or_zero()
+    }
+
+    /// Returns the edges, b - a, c - b, and a - c.
+    pub fn edges(&self) -> [Vec3; 3] {
+        let [a, b, c] = self.vertices;
+        [b - a, c - b, a - c]
+    }
+
+    pub fn aabb(&self) -> Aabb3d {
+        Aabb3d::from_points(self.vertices)
+    }
+
+    /// Returns the point on the triangle nearest to `point`.
+    pub fn closest_point(&self, point: Vec3) -> Vec3 {
+        // See: Real-Time Collision Detection, 5.1.5.
+        let [a, b, c] = self.vertices;
+        let ab = b - a;
+        let ac = c - a;
+        let ap = point - a;
+        let d1 = ab.dot(ap);
+        let d2 = ac.dot(ap);
+        if d1 <= 0.0 && d2 <= 0.0 {
+            return a;
+        }
+
+        let bp = point - b;
+        let d3 = ab.dot(bp);
+        let d4 = ac.dot(bp);
+        if d3 >= 0.0 && d4 <= d3 {
+            return b;
+        }
+
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+            return a + ab * (d1 / (d1 - d3));
+        }
+
+        let cp = point - c;
+        let d5 = ab.dot(cp);
+        let d6 = ac.dot(cp);
+        if d6 >= 0.0 && d5 <= d6 {
+            return c;
+        }
+
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+            return a + ac * (d2 / (d2 - d6));
+        }
+
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+            return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+        }
+
+        let denominator = 1.0 / (va + vb + vc);
+        a + ab * (vb * denominator) + ac * (vc * denominator)
+    }
+
+    /// Returns the `t` value along the ray where it hits either face of the triangle.
+    pub fn ray(&self, ray: &Ray) -> Option<f32> {
+        // Möller–Trumbore.
+        let [a, b, c] = self.vertices;
+        let ab = b - a;
+        let ac = c - a;
+        let p = ray.direction.cross(ac);
+        let determinant = ab.dot(p);
+        if determinant.abs() < f32::EPSILON {
+            // Parallel.
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+        let ao = ray.origin - a;
+        let u = ao.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = ao.cross(ab);
+        let v = ray.direction.dot(q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = ac.dot(q) * inverse;
+        (t >= 0.0).then_some(t)
+    }
+
+    /// Returns (min, max) of the triangle projected onto `axis`.
+    pub(crate) fn project(&self, axis: Vec3) -> (f32, f32) {
+        let [a, b, c] = self.vertices.map(|v| v.dot(axis));
+        (a.min(b).min(c), a.max(b).max(c))
+    }
+}
@@ -0,0 +1,216 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{Aabb3d, Collider3d, Ray, Triangle3d};
+use glam::Vec3;
+
+/// A static mesh of triangles, with a bounding volume hierarchy for fast queries.
+#[derive(Clone, Debug)]
+pub struct TriangleMesh3d {
+    vertices: Vec<Vec3>,
+    triangles: Vec<[u32; 3]>,
+    /// Triangle indices, ordered such that each leaf's triangles are contiguous.
+    order: Vec<u32>,
+    nodes: Vec<BvhNode>,
+}
+
+#[derive(Clone, Debug)]
+struct BvhNode {
+    aabb: Aabb3d,
+    /// Index into `order` if a leaf, otherwise index of the first of two consecutive children.
+    start: u32,
+    /// Zero if not a leaf.
+    len: u32,
+}
+
+impl TriangleMesh3d {
+    /// Leaves of the hierarchy have at most this many triangles.
+    const LEAF_TRIANGLES: usize = 4;
+
+    /// Creates a mesh from vertices and triangles of indices into the vertices.
+    ///
+    /// **Panics**
+    ///
+    /// If an index is out of bounds.
+    pub fn new(vertices: Vec<Vec3>, triangles: Vec<[u32; 3]>) -> Self {
+        assert!(
+            triangles
+                .iter()
+                .flatten()
+                .all(|&i| (i as usize) < vertices.len()),
+            "index out of bounds"
+        );
+        let mut mesh = Self {
+            vertices,
+            order: (0..triangles.len() as u32).collect(),
+            triangles,
+            nodes: Vec::new(),
+        };
+        if !mesh.triangles.is_empty() {
+            let centroids: Vec<Vec3> = (0..mesh.triangles.len())
+                .map(|i| mesh.triangle(i).centroid())
+                .collect();
+            mesh.nodes.push(BvhNode {
+                aabb: Aabb3d::EMPTY,
+                start: 0,
+                len: 0,
+            });
+            mesh.build(0, 0, mesh.triangles.len(), &centroids);
+        }
+        mesh
+    }
+
+    /// Subdivides `order[start..end]` into node `index`, splitting the longest axis at the median.
+    fn build(&mut self, index: usize, start: usize, end: usize, centroids: &[Vec3]) {
+        let aabb = self.order[start..end]
+            .iter()
+            .map(|&i| self.triangle(i as usize).aabb())
+            .fold(Aabb3d::EMPTY, |a, b| a.union(&b));
+        if end - start <= Self::LEAF_TRIANGLES {
+            self.nodes[index] = BvhNode {
+                aabb,
+                start: start as u32,
+                len: (end - start) as u32,
+            };
+            return;
+        }
+
+        let bounds = Aabb3d::from_points(
+            self.order[start..end]
+                .iter()
+                .map(|&i| centroids[i as usize]),
+        );
+        let size = bounds.max - bounds.min;
+        let axis = if size.x >= size.y && size.x >= size.z {
+            0
+        } else if size.y >= size.z {
+            1
+        } else {
+            2
+        };
+        let middle = (start + end) / 2;
+        self.order[start..end].select_nth_unstable_by(middle - start, |&a, &b| {
+            centroids[a as usize][axis]
+                .total_cmp(&centroids[b as usize][axis])
+                .then(a.cmp(&b))
+        });
+
+        let children = self.nodes.len();
+        self.nodes[index] = BvhNode {
+            aabb,
+            start: children as u32,
+            len: 0,
+        };
+        for _ in 0..2 {
+            self.nodes.push(BvhNode {
+                aabb: Aabb3d::EMPTY,
+                start: 0,
+                len: 0,
+            });
+        }
+        self.build(children, start, middle, centroids);
+        self.build(children + 1, middle, end, centroids);
+    }
+
+    pub fn len(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    pub fn vertices(&self) -> &[Vec3] {
+        &self.vertices
+    }
+
+    pub fn aabb(&self) -> Aabb3d {
+        self.nodes.first().map(|n| n.aabb).unwrap_or(Aabb3d::EMPTY)
+    }
+
+    /// Gets the triangle at `index`, in the order given to [`Self::new`].
+    pub fn triangle(&self, index: usize) -> Triangle3d {
+        Triangle3d {
+            vertices: self.triangles[index].map(|i| self.vertices[i as usize]),
+        }
+    }
+
+    /// Iterates the triangles in the order given to [`Self::new`].
+    pub fn triangles(&self) -> impl Iterator<Item = Triangle3d> + '_ {
+        (0..self.triangles.len()).map(|i| self.triangle(i))
+    }
+
+    /// Returns indices of triangles whose [`Aabb3d`]s intersect `aabb`.
+    pub fn query_aabb(&self, aabb: Aabb3d) -> impl Iterator<Item = usize> + '_ {
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push(0u32);
+        }
+        let mut leaf: &[u32] = &[];
+        std::iter::from_fn(move || loop {
+            if let Some((&i, rest)) = leaf.split_first() {
+                leaf = rest;
+                if self.triangle(i as usize).aabb().intersects(&aabb) {
+                    return Some(i as usize);
+                }
+                continue;
+            }
+            let node = &self.nodes[stack.pop()? as usize];
+            if !node.aabb.intersects(&aabb) {
+                continue;
+            }
+            if node.len == 0 {
+                stack.extend([node.start, node.start + 1]);
+            } else {
+                leaf = &self.order[node.start as usize..(node.start + node.len) as usize];
+            }
+        })
+    }
+
+    /// Returns the `t` value along the ray where it first hits a triangle, and that triangle's
+    /// index.
+    pub fn ray(&self, ray: &Ray) -> Option<(f32, usize)> {
+        let mut nearest: Option<(f32, usize)> = None;
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push(0u32);
+        }
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index as usize];
+            let Some(t) = node.aabb.ray(ray) else {
+                continue;
+            };
+            if matches!(nearest, Some((nearest, _)) if nearest < t) {
+                continue;
+            }
+            if node.len == 0 {
+                stack.extend([node.start, node.start + 1]);
+                continue;
+            }
+            for &i in &self.order[node.start as usize..(node.start + node.len) as usize] {
+                if let Some(t) = self.triangle(i as usize).ray(ray) {
+                    let i = i as usize;
+                    // Ties are broken by index, so the result doesn't depend on the hierarchy.
+                    if !matches!(nearest, Some(nearest) if nearest <= (t, i)) {
+                        nearest = Some((t, i));
+                    }
+                }
+            }
+        }
+        nearest
+    }
+
+    /// Returns the deepest (normal, depth, triangle index) of `collider` with any triangle,
+    /// where normal points from `collider` towards the triangle.
+    pub fn collider_normal_depth(&self, collider: &Collider3d) -> Option<(Vec3, f32, usize)> {
+        let mut deepest: Option<(Vec3, f32, usize)> = None;
+        for i in self.query_aabb(collider.aabb()) {
+            if let Some((normal, depth)) = collider.triangle_normal_depth(&self.triangle(i))
+                && !matches!(deepest, Some((_, d, j)) if d > depth || (d == depth && j < i))
+            {
+                deepest = Some((normal, depth, i));
+            }
+        }
+        deepest
+    }
+}
@@ -2,10 +2,16 @@
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
 mod angle;
+mod easing;
+mod fixed;
 mod intersect_2d;
 mod intersect_3d;
+mod noise;
+mod physics_2d;
 mod range;
 mod rng;
+mod sampling;
+mod spline;
 mod tests;
 mod x_vec2;
 mod mat;
@@ -14,9 +20,17 @@ pub use self::angle::{
     deterministic_atan2, mat3_to_translation_angle, translation_angle_to_mat3, vec_to_quat,
     vec_to_yaw_pitch, Angle, AngleRepr, Cardinal4,
 };
+pub use self::easing::Easing;
+pub use self::fixed::{Fixed32, Fixed32Vec2, Fixed64, Fixed64Vec2};
 pub use self::intersect_2d::*;
 pub use self::intersect_3d::*;
+pub use self::noise::{Noise2d, NoiseKind2d};
+pub use self::physics_2d::{
+    Contact2d, Physics2d, RigidBody2d, RigidBodyId2d, RigidBodyKey2d, RigidBodyKind2d, Shape2d,
+};
 pub use self::range::{gen_radius, lerp, map_ranges, map_ranges_fast};
 pub use self::rng::HashRng;
+pub use self::sampling::{poisson_disk_2d, WeightedTable};
+pub use self::spline::{ArcLength, CatmullRom, CubicBezier, Curve, Hermite, SplinePoint};
 pub use self::x_vec2::{I16Vec2, I8Vec2, U16Vec2, U8Vec2};
 pub use self::mat::normalize_scale_mat4;
\ No newline at end of file
@@ -0,0 +1,209 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::HashRng;
+use glam::{vec2, Vec2};
+use rand::RngCore;
+use std::hash::Hash;
+
+/// The kind of [`Noise2d`].
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq)]
+pub enum NoiseKind2d {
+    /// Smoothly interpolated gradients, with features aligned to a square grid.
+    #[default]
+    Perlin,
+    /// Like [`Self::Perlin`] but on a triangular grid, so there are fewer directional artifacts.
+    Simplex,
+    /// Smoothly interpolated random values, which is blobbier than gradient noise.
+    Value,
+    /// Distance to the nearest of randomly scattered points, which looks like cells.
+    Worley,
+}
+
+/// Seeded, infinite, 2D coherent noise for procedural generation.
+///
+/// Only uses basic floating point arithmetic (no transcendental functions or fused
+/// multiply-add), so results are identical on every platform, e.g. wasm32 and x86_64, allowing
+/// the server and clients to generate the same world from a seed.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct Noise2d {
+    kind: NoiseKind2d,
+    seed: u32,
+}
+
+impl Noise2d {
+    pub fn new<S: Hash>(seed: &S, kind: NoiseKind2d) -> Self {
+        Self {
+            kind,
+            seed: HashRng::new(seed).next_u32(),
+        }
+    }
+
+    pub fn kind(&self) -> NoiseKind2d {
+        self.kind
+    }
+
+    /// Returns noise in `[-1, 1]`, with features about 1 unit apart.
+    pub fn sample(&self, pos: Vec2) -> f32 {
+        self.sample_seeded(pos, self.seed)
+    }
+
+    fn sample_seeded(&self, pos: Vec2, seed: u32) -> f32 {
+        match self.kind {
+            NoiseKind2d::Perlin => perlin(pos, seed),
+            NoiseKind2d::Simplex => simplex(pos, seed),
+            NoiseKind2d::Value => value(pos, seed),
+            NoiseKind2d::Worley => worley(pos, seed),
+        }
+    }
+
+    /// Fractal Brownian motion: sums `octaves` layers of noise, each `lacunarity` times the
+    /// frequency and `gain` times the amplitude of the previous. Normalized to `[-1, 1]`.
+    /// Typically `lacunarity` is 2 and `gain` is 0.5.
+    pub fn fbm(&self, pos: Vec2, octaves: u32, lacunarity: f32, gain: f32) -> f32 {
+        let mut sum = 0.0;
+        let mut total_amplitude = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        for octave in 0..octaves {
+            // Different seeds per octave prevent them from lining up at the origin.
+            let seed = self.seed.wrapping_add(octave.wrapping_mul(0x9E3779B9));
+            sum += self.sample_seeded(pos * frequency, seed) * amplitude;
+            total_amplitude += amplitude;
+            amplitude *= gain;
+            frequency *= lacunarity;
+        }
+        if total_amplitude > 0.0 {
+            sum / total_amplitude
+        } else {
+            0.0
+        }
+    }
+
+    /// Domain warping: returns `pos` displaced by up to `strength` in each axis, according to
+    /// [`fbm`][`Self::fbm`] with `octaves`. Sampling noise at the result gives swirly, organic
+    /// shapes.
+    pub fn warp(&self, pos: Vec2, strength: f32, octaves: u32) -> Vec2 {
+        // Arbitrary offsets, so the axes are uncorrelated.
+        let x = self.fbm(pos + vec2(17.3, -41.9), octaves, 2.0, 0.5);
+        let y = self.fbm(pos + vec2(-93.1, 58.7), octaves, 2.0, 0.5);
+        pos + vec2(x, y) * strength
+    }
+}
+
+/// Hashes a lattice point.
+fn hash(x: i32, y: i32, seed: u32) -> u32 {
+    let mut h = seed ^ (x as u32).wrapping_mul(0x27D4EB2D) ^ (y as u32).wrapping_mul(0x165667B1);
+    h = (h ^ (h >> 15)).wrapping_mul(0x85EBCA6B);
+    h = (h ^ (h >> 13)).wrapping_mul(0xC2B2AE35);
+    h ^ (h >> 16)
+}
+
+/// Maps a hash to `[0, 1]`.
+fn unit(hash: u32) -> f32 {
+    // 24 bits are exactly representable.
+    (hash >> 8) as f32 * (1.0 / ((1 << 24) - 1) as f32)
+}
+
+/// One of 8 evenly spaced unit gradients.
+fn gradient(hash: u32) -> Vec2 {
+    const D: f32 = std::f32::consts::FRAC_1_SQRT_2;
+    const GRADIENTS: [Vec2; 8] = [
+        vec2(1.0, 0.0),
+        vec2(D, D),
+        vec2(0.0, 1.0),
+        vec2(-D, D),
+        vec2(-1.0, 0.0),
+        vec2(-D, -D),
+        vec2(0.0, -1.0),
+        vec2(D, -D),
+    ];
+    GRADIENTS[(hash >> 29) as usize]
+}
+
+/// Smoothstep with zero first and second derivatives at 0 and 1.
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+/// Deterministic linear interpolation, unlike [`super::lerp`] which may use fused multiply-add.
+fn mix(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Returns the cell containing `pos` and the position within it.
+fn cell(pos: Vec2) -> (i32, i32, Vec2) {
+    let floor = pos.floor();
+    (floor.x as i32, floor.y as i32, pos - floor)
+}
+
+/// Interpolates values at the 4 corners of the cell containing `pos`.
+fn interpolate(pos: Vec2, corner: impl Fn(i32, i32, Vec2) -> f32) -> f32 {
+    let (x, y, local) = cell(pos);
+    let v00 = corner(x, y, local);
+    let v10 = corner(x.wrapping_add(1), y, local - Vec2::X);
+    let v01 = corner(x, y.wrapping_add(1), local - Vec2::Y);
+    let v11 = corner(x.wrapping_add(1), y.wrapping_add(1), local - Vec2::ONE);
+    let (u, v) = (fade(local.x), fade(local.y));
+    mix(mix(v00, v10, u), mix(v01, v11, u), v)
+}
+
+fn value(pos: Vec2, seed: u32) -> f32 {
+    interpolate(pos, |x, y, _| unit(hash(x, y, seed)) * 2.0 - 1.0)
+}
+
+fn perlin(pos: Vec2, seed: u32) -> f32 {
+    // The extremes are +/- sqrt(0.5).
+    let n = interpolate(pos, |x, y, offset| gradient(hash(x, y, seed)).dot(offset));
+    (n * std::f32::consts::SQRT_2).clamp(-1.0, 1.0)
+}
+
+fn simplex(pos: Vec2, seed: u32) -> f32 {
+    // (sqrt(3) - 1) / 2 and (3 - sqrt(3)) / 6, to skew to and from the triangular grid.
+    const SKEW: f32 = 0.366_025_42;
+    const UNSKEW: f32 = 0.211_324_87;
+    let skewed = pos + (pos.x + pos.y) * SKEW;
+    let (x, y, _) = cell(skewed);
+    let origin = vec2(x as f32, y as f32);
+    let offset0 = pos - (origin - (origin.x + origin.y) * UNSKEW);
+    // Which triangle of the cell.
+    let (dx, dy) = if offset0.x > offset0.y {
+        (1, 0)
+    } else {
+        (0, 1)
+    };
+    let offset1 = offset0 - vec2(dx as f32, dy as f32) + UNSKEW;
+    let offset2 = offset0 - Vec2::ONE + 2.0 * UNSKEW;
+
+    let corner = |x: i32, y: i32, offset: Vec2| {
+        let falloff = 0.5 - offset.length_squared();
+        if falloff <= 0.0 {
+            0.0
+        } else {
+            let falloff = falloff * falloff;
+            falloff * falloff * gradient(hash(x, y, seed)).dot(offset)
+        }
+    };
+    let n = corner(x, y, offset0)
+        + corner(x.wrapping_add(dx), y.wrapping_add(dy), offset1)
+        + corner(x.wrapping_add(1), y.wrapping_add(1), offset2);
+    // Normalizes the extremes of the sum to +/- 1.
+    (n * 99.204_33).clamp(-1.0, 1.0)
+}
+
+fn worley(pos: Vec2, seed: u32) -> f32 {
+    let (x, y, local) = cell(pos);
+    let mut nearest = f32::INFINITY;
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let h = hash(x.wrapping_add(dx), y.wrapping_add(dy), seed);
+            let point = vec2(
+                dx as f32 + unit(h),
+                dy as f32 + unit(h.wrapping_mul(0x9E3779B9)),
+            );
+            nearest = nearest.min(point.distance_squared(local));
+        }
+    }
+    // The nearest point is rarely farther than 1, so that maps to 1.
+    (nearest.sqrt() * 2.0 - 1.0).clamp(-1.0, 1.0)
+}
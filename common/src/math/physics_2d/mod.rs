@@ -0,0 +1,9 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+mod physics;
+mod rigid_body_2d;
+mod tests;
+
+pub use physics::{Contact2d, Physics2d, RigidBodyId2d, RigidBodyKey2d};
+pub use rigid_body_2d::{RigidBody2d, RigidBodyKind2d, Shape2d};
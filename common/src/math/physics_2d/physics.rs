@@ -0,0 +1,601 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{RigidBody2d, RigidBodyKind2d};
+use crate::bitcode::{self, *};
+use crate::{
+    Angle, ArenaKey, Collider2d, GenerationalArenaMap, GenerationalKey, HbHash, RotatedRectangle,
+};
+use arrayvec::ArrayVec;
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+
+/// Index of a [`RigidBody2d`] within [`Physics2d`].
+#[derive(
+    Copy, Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Encode, Decode,
+)]
+pub struct RigidBodyId2d(pub u32);
+
+impl ArenaKey for RigidBodyId2d {
+    fn from_index(i: usize) -> Self {
+        Self(i as u32)
+    }
+
+    fn to_index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Refers to a [`RigidBody2d`] within [`Physics2d`], until it is removed.
+pub type RigidBodyKey2d = GenerationalKey<RigidBodyId2d>;
+
+/// Two touching bodies, reported by [`Physics2d::step`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Contact2d {
+    pub a: RigidBodyKey2d,
+    pub b: RigidBodyKey2d,
+    /// Unit vector pointing from `a` towards `b`.
+    pub normal: Vec2,
+    /// Approximate center of the overlap, in world space.
+    pub point: Vec2,
+    /// How far the bodies overlapped, before being pushed apart.
+    pub depth: f32,
+    /// Impulse (mass times velocity) applied along `normal`, e.g. for damage.
+    pub impulse: f32,
+}
+
+/// Simple, deterministic 2D physics, built on [`Collider2d`]. Only uses cross-platform
+/// deterministic float operations, so it is suitable for
+/// [`LockstepWorld::tick`][`crate::LockstepWorld::tick`], as well as server-side ticks.
+///
+/// Call [`Physics2d::step`] once per tick.
+#[derive(Clone, Debug, Default, Encode, Decode)]
+pub struct Physics2d {
+    bodies: GenerationalArenaMap<RigidBodyId2d, RigidBody2d>,
+    /// Units per second squared.
+    pub gravity: Vec2,
+    /// Impulses from the previous step, sorted by keys. Part of the simulation state, so must be
+    /// hashed and encoded.
+    warm_starts: Vec<WarmStart>,
+}
+
+impl Hash for Physics2d {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bodies.hash(state);
+        HbHash::hash(&self.gravity, state);
+        self.warm_starts.hash(state);
+    }
+}
+
+/// Accumulated impulses of a [`SolverContact`], which makes stacks converge much faster.
+#[derive(Clone, Debug, Encode, Decode)]
+struct WarmStart {
+    a: RigidBodyKey2d,
+    b: RigidBodyKey2d,
+    /// (normal, tangent) per point.
+    impulses: ArrayVec<[f32; 2], 2>,
+}
+
+impl Hash for WarmStart {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.a.hash(state);
+        self.b.hash(state);
+        self.impulses.len().hash(state);
+        for impulses in &self.impulses {
+            HbHash::hash(impulses, state);
+        }
+    }
+}
+
+/// Copy of a [`RigidBody2d`] while solving.
+struct SolverBody {
+    key: RigidBodyKey2d,
+    collider: Collider2d,
+    position: Vec2,
+    velocity: Vec2,
+    angular_velocity: f32,
+    inverse_mass: f32,
+    inverse_inertia: f32,
+    restitution: f32,
+    friction: f32,
+    kind: RigidBodyKind2d,
+    asleep: bool,
+    /// Wakes sleeping bodies it touches.
+    disturbs: bool,
+}
+
+impl SolverBody {
+    /// Is moved by collisions.
+    fn is_awake_dynamic(&self) -> bool {
+        self.kind == RigidBodyKind2d::Dynamic && !self.asleep
+    }
+
+    fn velocity_at(&self, offset: Vec2) -> Vec2 {
+        self.velocity + offset.perp() * self.angular_velocity
+    }
+
+    fn apply_impulse(&mut self, impulse: Vec2, offset: Vec2) {
+        self.velocity += impulse * self.inverse_mass;
+        self.angular_velocity += offset.perp_dot(impulse) * self.inverse_inertia;
+    }
+}
+
+/// Contact between [`SolverBody`]s.
+struct SolverContact {
+    a: usize,
+    b: usize,
+    /// Points from `a` towards `b`.
+    normal: Vec2,
+    friction: f32,
+    points: ArrayVec<SolverPoint, 2>,
+    /// Effective mass matrix (k11, k12, k22) if two points can be solved together.
+    block: Option<[f32; 3]>,
+}
+
+impl SolverContact {
+    fn prepare_block(&mut self, a: &SolverBody, b: &SolverBody) {
+        let [p1, p2] = &self.points[..] else {
+            return;
+        };
+        let rn1a = p1.offset_a.perp_dot(self.normal);
+        let rn1b = p1.offset_b.perp_dot(self.normal);
+        let rn2a = p2.offset_a.perp_dot(self.normal);
+        let rn2b = p2.offset_b.perp_dot(self.normal);
+        let inverse_mass = a.inverse_mass + b.inverse_mass;
+        let k11 = inverse_mass + a.inverse_inertia * rn1a * rn1a + b.inverse_inertia * rn1b * rn1b;
+        let k22 = inverse_mass + a.inverse_inertia * rn2a * rn2a + b.inverse_inertia * rn2b * rn2b;
+        let k12 = inverse_mass + a.inverse_inertia * rn1a * rn2a + b.inverse_inertia * rn1b * rn2b;
+        // Otherwise, the points are too close together to solve together.
+        if k11 * k11 < 1000.0 * (k11 * k22 - k12 * k12) {
+            self.block = Some([k11, k12, k22]);
+        }
+    }
+
+    /// Runs one iteration of the solver.
+    fn solve(&mut self, a: &mut SolverBody, b: &mut SolverBody) {
+        let normal = self.normal;
+        let tangent = normal.perp();
+        let relative = |a: &SolverBody, b: &SolverBody, point: &SolverPoint| {
+            b.velocity_at(point.offset_b) - a.velocity_at(point.offset_a)
+        };
+
+        for point in &mut self.points {
+            let max = self.friction * point.normal_impulse;
+            let total = (point.tangent_impulse
+                - relative(a, b, point).dot(tangent) * point.tangent_mass)
+                .clamp(-max, max);
+            let impulse = tangent * (total - point.tangent_impulse);
+            point.tangent_impulse = total;
+            a.apply_impulse(-impulse, point.offset_a);
+            b.apply_impulse(impulse, point.offset_b);
+        }
+
+        if let Some([k11, k12, k22]) = self.block
+            && let [p1, p2] = &mut self.points[..]
+        {
+            // Solve the linear complementarity problem of both points' impulses, instead of
+            // one at a time, which is much more stable for stacked rectangles.
+            let old = [p1.normal_impulse, p2.normal_impulse];
+            let b1 = relative(a, b, p1).dot(normal) - p1.target - (k11 * old[0] + k12 * old[1]);
+            let b2 = relative(a, b, p2).dot(normal) - p2.target - (k12 * old[0] + k22 * old[1]);
+            let det = k11 * k22 - k12 * k12;
+            let both = [(k12 * b2 - k22 * b1) / det, (k12 * b1 - k11 * b2) / det];
+            let first = -b1 / k11;
+            let second = -b2 / k22;
+            let new = if both[0] >= 0.0 && both[1] >= 0.0 {
+                both
+            } else if first >= 0.0 && k12 * first + b2 >= 0.0 {
+                [first, 0.0]
+            } else if second >= 0.0 && k12 * second + b1 >= 0.0 {
+                [0.0, second]
+            } else if b1 >= 0.0 && b2 >= 0.0 {
+                [0.0, 0.0]
+            } else {
+                // Degenerate, so leave as is.
+                old
+            };
+            for (point, new) in [p1, p2].into_iter().zip(new) {
+                let impulse = normal * (new - point.normal_impulse);
+                point.normal_impulse = new;
+                a.apply_impulse(-impulse, point.offset_a);
+                b.apply_impulse(impulse, point.offset_b);
+            }
+        } else {
+            for point in &mut self.points {
+                let total = (point.normal_impulse
+                    + (point.target - relative(a, b, point).dot(normal)) * point.normal_mass)
+                    .max(0.0);
+                let impulse = normal * (total - point.normal_impulse);
+                point.normal_impulse = total;
+                a.apply_impulse(-impulse, point.offset_a);
+                b.apply_impulse(impulse, point.offset_b);
+            }
+        }
+    }
+}
+
+struct SolverPoint {
+    point: Vec2,
+    depth: f32,
+    /// Offset of `point` from `a`.
+    offset_a: Vec2,
+    /// Offset of `point` from `b`.
+    offset_b: Vec2,
+    normal_mass: f32,
+    tangent_mass: f32,
+    /// Minimum separating velocity, positive due to restitution, or negative if the point isn't
+    /// touching yet.
+    target: f32,
+    normal_impulse: f32,
+    tangent_impulse: f32,
+}
+
+impl Physics2d {
+    /// Approaching slower than this doesn't bounce, which prevents jitter.
+    pub const BOUNCE_SPEED: f32 = 0.5;
+    /// Fraction of overlap (beyond [`Self::SLOP`]) corrected per step.
+    pub const CORRECTION: f32 = 0.4;
+    /// Velocity constraints are solved iteratively, more iterations are more accurate.
+    pub const ITERATIONS: usize = 16;
+    /// Bodies fall asleep after being slow for this many seconds.
+    pub const SLEEP_SECS: f32 = 0.5;
+    /// Bodies slower than this (in units or radians per second) may fall asleep.
+    pub const SLEEP_SPEED: f32 = 0.05;
+    /// Allowed overlap, which prevents jitter.
+    pub const SLOP: f32 = 0.01;
+
+    pub fn new(gravity: Vec2) -> Self {
+        Self {
+            bodies: GenerationalArenaMap::new(),
+            gravity,
+            warm_starts: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bodies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+    }
+
+    pub fn insert(&mut self, body: RigidBody2d) -> RigidBodyKey2d {
+        self.bodies.insert(body)
+    }
+
+    /// Wakes all bodies, in case they were resting on the removed body.
+    pub fn remove(&mut self, key: RigidBodyKey2d) -> Option<RigidBody2d> {
+        let ret = self.bodies.remove(key);
+        if ret.is_some() {
+            self.bodies.values_mut().for_each(RigidBody2d::wake);
+        }
+        ret
+    }
+
+    pub fn get(&self, key: RigidBodyKey2d) -> Option<&RigidBody2d> {
+        self.bodies.get(key)
+    }
+
+    /// Remember to [`RigidBody2d::wake`] a sleeping body after changing it.
+    pub fn get_mut(&mut self, key: RigidBodyKey2d) -> Option<&mut RigidBody2d> {
+        self.bodies.get_mut(key)
+    }
+
+    /// Iterates bodies in a deterministic order.
+    pub fn iter(&self) -> impl Iterator<Item = (RigidBodyKey2d, &RigidBody2d)> {
+        self.bodies.iter()
+    }
+
+    /// Iterates bodies mutably in a deterministic order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (RigidBodyKey2d, &mut RigidBody2d)> {
+        self.bodies.iter_mut()
+    }
+
+    /// Advances the simulation by `delta_seconds`, e.g. `TICK_PERIOD_SECS`, reporting each
+    /// [`Contact2d`] after it is resolved.
+    ///
+    /// Sleeping bodies act like static bodies until an awake body collides with them. Contacts
+    /// are detected in O(n^2) time.
+    pub fn step(&mut self, delta_seconds: f32, mut on_contact: impl FnMut(&Contact2d)) {
+        if delta_seconds <= 0.0 {
+            return;
+        }
+
+        let mut solver_bodies = Vec::with_capacity(self.bodies.len());
+        for (key, body) in self.bodies.iter_mut() {
+            let asleep = body.is_asleep();
+            let disturbs = match body.kind {
+                RigidBodyKind2d::Static => false,
+                RigidBodyKind2d::Kinematic => {
+                    body.velocity != Vec2::ZERO || body.angular_velocity != 0.0
+                }
+                // Before gravity, so bodies resting on sleeping bodies don't wake them.
+                RigidBodyKind2d::Dynamic => {
+                    !asleep && body.velocity.length_squared() >= Self::SLEEP_SPEED.powi(2)
+                }
+            };
+            if body.kind == RigidBodyKind2d::Dynamic && !asleep {
+                body.velocity += self.gravity * delta_seconds;
+            }
+            solver_bodies.push(SolverBody {
+                key,
+                collider: body.collider(),
+                position: body.position,
+                velocity: body.velocity,
+                angular_velocity: body.angular_velocity,
+                inverse_mass: body.inverse_mass(),
+                inverse_inertia: body.inverse_inertia(),
+                restitution: body.restitution,
+                friction: body.friction,
+                kind: body.kind,
+                asleep,
+                disturbs,
+            });
+        }
+
+        // Wake sleeping bodies that are touched by moving bodies.
+        for a in 0..solver_bodies.len() {
+            for b in 0..solver_bodies.len() {
+                if solver_bodies[a].asleep
+                    && solver_bodies[b].disturbs
+                    && solver_bodies[a]
+                        .collider
+                        .collides(&solver_bodies[b].collider)
+                {
+                    solver_bodies[a].asleep = false;
+                    self.bodies[solver_bodies[a].key].wake();
+                }
+            }
+        }
+        for body in &mut solver_bodies {
+            if body.asleep {
+                // Act like a static body.
+                body.inverse_mass = 0.0;
+                body.inverse_inertia = 0.0;
+            }
+        }
+
+        let mut contacts = Vec::new();
+        for a in 0..solver_bodies.len() {
+            for b in a + 1..solver_bodies.len() {
+                let body_a = &solver_bodies[a];
+                let body_b = &solver_bodies[b];
+                if !body_a.is_awake_dynamic() && !body_b.is_awake_dynamic() {
+                    continue;
+                }
+                let Some((normal, manifold)) = manifold(&body_a.collider, &body_b.collider) else {
+                    continue;
+                };
+                let restitution = body_a.restitution.max(body_b.restitution);
+                let points = manifold
+                    .into_iter()
+                    .map(|(point, depth)| {
+                        let offset_a = point - body_a.position;
+                        let offset_b = point - body_b.position;
+                        let effective_mass = |direction: Vec2| {
+                            let k = body_a.inverse_mass
+                                + body_b.inverse_mass
+                                + body_a.inverse_inertia * offset_a.perp_dot(direction).powi(2)
+                                + body_b.inverse_inertia * offset_b.perp_dot(direction).powi(2);
+                            if k > 0.0 {
+                                k.recip()
+                            } else {
+                                0.0
+                            }
+                        };
+                        let approach = (body_b.velocity_at(offset_b)
+                            - body_a.velocity_at(offset_a))
+                        .dot(normal);
+                        SolverPoint {
+                            point,
+                            depth,
+                            offset_a,
+                            offset_b,
+                            normal_mass: effective_mass(normal),
+                            tangent_mass: effective_mass(normal.perp()),
+                            target: if depth < 0.0 {
+                                depth / delta_seconds
+                            } else if approach < -Self::BOUNCE_SPEED {
+                                -approach * restitution
+                            } else {
+                                0.0
+                            },
+                            normal_impulse: 0.0,
+                            tangent_impulse: 0.0,
+                        }
+                    })
+                    .collect();
+                let mut contact = SolverContact {
+                    a,
+                    b,
+                    normal,
+                    friction: (body_a.friction * body_b.friction).sqrt(),
+                    points,
+                    block: None,
+                };
+                contact.prepare_block(body_a, body_b);
+                contacts.push(contact);
+            }
+        }
+
+        for contact in &mut contacts {
+            let [a, b] = solver_bodies.get_many_mut([contact.a, contact.b]).unwrap();
+            let Ok(i) = self
+                .warm_starts
+                .binary_search_by(|w| (w.a, w.b).cmp(&(a.key, b.key)))
+            else {
+                continue;
+            };
+            let warm_start = &self.warm_starts[i];
+            // Points don't correspond if their number changed.
+            if warm_start.impulses.len() == contact.points.len() {
+                for (point, &[normal, tangent]) in
+                    contact.points.iter_mut().zip(&warm_start.impulses)
+                {
+                    point.normal_impulse = normal;
+                    point.tangent_impulse = tangent;
+                    let impulse = contact.normal * normal + contact.normal.perp() * tangent;
+                    a.apply_impulse(-impulse, point.offset_a);
+                    b.apply_impulse(impulse, point.offset_b);
+                }
+            }
+        }
+
+        for _ in 0..Self::ITERATIONS {
+            for contact in &mut contacts {
+                let [a, b] = solver_bodies.get_many_mut([contact.a, contact.b]).unwrap();
+                contact.solve(a, b);
+            }
+        }
+
+        // Contacts were found in order of keys.
+        self.warm_starts = contacts
+            .iter()
+            .map(|contact| WarmStart {
+                a: solver_bodies[contact.a].key,
+                b: solver_bodies[contact.b].key,
+                impulses: contact
+                    .points
+                    .iter()
+                    .map(|p| [p.normal_impulse, p.tangent_impulse])
+                    .collect(),
+            })
+            .collect();
+
+        // Push overlapping bodies apart, in proportion to their inverse masses.
+        for contact in &contacts {
+            let [a, b] = solver_bodies.get_many_mut([contact.a, contact.b]).unwrap();
+            let inverse_mass = a.inverse_mass + b.inverse_mass;
+            if inverse_mass > 0.0 {
+                let depth = contact.points.iter().map(|p| p.depth).fold(0.0, f32::max);
+                let correction = contact.normal
+                    * ((depth - Self::SLOP).max(0.0) * Self::CORRECTION / inverse_mass);
+                a.position -= correction * a.inverse_mass;
+                b.position += correction * b.inverse_mass;
+            }
+        }
+
+        for contact in &contacts {
+            let n = contact.points.len() as f32;
+            on_contact(&Contact2d {
+                a: solver_bodies[contact.a].key,
+                b: solver_bodies[contact.b].key,
+                normal: contact.normal,
+                point: contact.points.iter().map(|p| p.point).sum::<Vec2>() / n,
+                depth: contact.points.iter().map(|p| p.depth).fold(0.0, f32::max),
+                impulse: contact.points.iter().map(|p| p.normal_impulse).sum(),
+            });
+        }
+
+        for solver_body in solver_bodies {
+            let body = &mut self.bodies[solver_body.key];
+            match body.kind {
+                RigidBodyKind2d::Static => continue,
+                RigidBodyKind2d::Dynamic if solver_body.asleep => continue,
+                RigidBodyKind2d::Dynamic => {
+                    body.velocity = solver_body.velocity;
+                    body.angular_velocity = solver_body.angular_velocity;
+                }
+                RigidBodyKind2d::Kinematic => {}
+            }
+            body.position = solver_body.position + body.velocity * delta_seconds;
+            body.rotation += Angle::from_radians(body.angular_velocity * delta_seconds);
+            if body.kind == RigidBodyKind2d::Dynamic {
+                body.update_sleep(delta_seconds, Self::SLEEP_SPEED, Self::SLEEP_SECS);
+            }
+        }
+    }
+}
+
+/// Returns the normal, pointing from `a` towards `b`, and up to two (point, depth) pairs.
+fn manifold(a: &Collider2d, b: &Collider2d) -> Option<(Vec2, ArrayVec<(Vec2, f32), 2>)> {
+    let (normal, depth) = a.collides_normal_depth(b)?;
+    let mut points = ArrayVec::new();
+    match (a, b) {
+        (Collider2d::Circle(a), _) => {
+            points.push((a.center + normal * (a.radius - depth * 0.5), depth));
+        }
+        (_, Collider2d::Circle(b)) => {
+            points.push((b.center - normal * (b.radius - depth * 0.5), depth));
+        }
+        (Collider2d::RotatedRectangle(a), Collider2d::RotatedRectangle(b)) => {
+            // The reference face is the face most aligned with the normal.
+            let flip = max_alignment(a, normal) < max_alignment(b, normal);
+            let (reference, incident, sign) = if flip { (b, a, -1.0) } else { (a, b, 1.0) };
+            let (face_normal, face_extent, side_extent) = snap_to_face(reference, normal * sign);
+            let tangent = face_normal.perp();
+
+            // The incident face is the face of the other rectangle most opposed to the normal.
+            let (incident_normal, incident_extent, incident_side_extent) =
+                snap_to_face(incident, -face_normal);
+            let incident_center = incident.center + incident_normal * incident_extent;
+            let incident_tangent = incident_normal.perp() * incident_side_extent;
+            let mut segment = [
+                incident_center + incident_tangent,
+                incident_center - incident_tangent,
+            ];
+
+            // Clip the incident face to the sides of the reference face.
+            let center = reference.center.dot(tangent);
+            for (sign, bound) in [(1.0, center + side_extent), (-1.0, center - side_extent)] {
+                let [p, q] = segment;
+                let p_outside = (p.dot(tangent) - bound) * sign;
+                let q_outside = (q.dot(tangent) - bound) * sign;
+                if p_outside > 0.0 && q_outside > 0.0 {
+                    return None;
+                }
+                if p_outside > 0.0 {
+                    segment[0] = p + (q - p) * (p_outside / (p_outside - q_outside));
+                } else if q_outside > 0.0 {
+                    segment[1] = q + (p - q) * (q_outside / (q_outside - p_outside));
+                }
+            }
+
+            let face = reference.center.dot(face_normal) + face_extent;
+            for point in segment {
+                let depth = face - point.dot(face_normal);
+                // Include points that are almost touching, so rectangles don't rock back and forth.
+                if depth >= -Physics2d::SLOP {
+                    points.push((point + face_normal * (depth * 0.5), depth));
+                }
+            }
+            if points.is_empty() {
+                // Rounding error.
+                let point = (segment[0] + segment[1]) * 0.5;
+                points.push((point, depth));
+            }
+            return Some((face_normal * sign, points));
+        }
+        _ => {
+            // The deepest point of `b`, moved halfway to the surface of `a`.
+            points.push((b.support(-normal) + normal * (depth * 0.5), depth));
+        }
+    }
+    Some((normal, points))
+}
+
+/// How closely `direction` aligns with the normal of one of `rect`'s faces.
+fn max_alignment(rect: &RotatedRectangle, direction: Vec2) -> f32 {
+    rect.normal
+        .dot(direction)
+        .abs()
+        .max(rect.normal.perp().dot(direction).abs())
+}
+
+/// Returns the outward normal of `rect`'s face most aligned with `direction`, the distance of
+/// that face from the center, and the half length of that face.
+fn snap_to_face(rect: &RotatedRectangle, direction: Vec2) -> (Vec2, f32, f32) {
+    let x = rect.normal.dot(direction);
+    let y = rect.normal.perp().dot(direction);
+    if x.abs() >= y.abs() {
+        (rect.normal * x.signum(), rect.half_size.x, rect.half_size.y)
+    } else {
+        (
+            rect.normal.perp() * y.signum(),
+            rect.half_size.y,
+            rect.half_size.x,
+        )
+    }
+}
@@ -0,0 +1,214 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use crate::bitcode::{self, *};
+use crate::{hash_f32, Angle, Circle, Collider2d, HbHash, RotatedRectangle};
+use glam::Vec2;
+use std::hash::{Hash, Hasher};
+
+/// How a [`RigidBody2d`] moves.
+#[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq, Encode, Decode)]
+pub enum RigidBodyKind2d {
+    /// Never moves, e.g. walls.
+    Static,
+    /// Moves according to its velocity, which only the game changes. Pushes dynamic bodies
+    /// without being pushed back, e.g. moving platforms.
+    Kinematic,
+    /// Moves according to its velocity, which gravity and collisions change.
+    #[default]
+    Dynamic,
+}
+
+/// The shape of a [`RigidBody2d`], relative to its position and rotation.
+#[derive(Copy, Clone, Debug, PartialEq, Encode, Decode)]
+pub enum Shape2d {
+    Circle { radius: f32 },
+    Rectangle { half_size: Vec2 },
+}
+
+impl Shape2d {
+    pub fn circle(radius: f32) -> Self {
+        Self::Circle { radius }
+    }
+
+    pub fn rectangle(size: Vec2) -> Self {
+        Self::Rectangle {
+            half_size: size * 0.5,
+        }
+    }
+
+    pub fn area(&self) -> f32 {
+        match *self {
+            Self::Circle { radius } => radius.powi(2) * std::f32::consts::PI,
+            Self::Rectangle { half_size } => half_size.x * half_size.y * 4.0,
+        }
+    }
+
+    /// Moment of inertia divided by mass.
+    fn inertia_per_mass(&self) -> f32 {
+        match *self {
+            Self::Circle { radius } => radius.powi(2) * 0.5,
+            Self::Rectangle { half_size } => half_size.length_squared() * (1.0 / 3.0),
+        }
+    }
+}
+
+impl Hash for Shape2d {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Self::Circle { radius } => {
+                0u8.hash(state);
+                hash_f32(*radius, state);
+            }
+            Self::Rectangle { half_size } => {
+                1u8.hash(state);
+                HbHash::hash(half_size, state);
+            }
+        }
+    }
+}
+
+/// A body simulated by [`Physics2d`][`crate::Physics2d`].
+#[derive(Clone, Debug, PartialEq, Encode, Decode)]
+pub struct RigidBody2d {
+    pub kind: RigidBodyKind2d,
+    pub shape: Shape2d,
+    pub position: Vec2,
+    pub rotation: Angle,
+    /// Units per second.
+    pub velocity: Vec2,
+    /// Radians per second, counter-clockwise.
+    pub angular_velocity: f32,
+    /// Mass per unit area.
+    pub density: f32,
+    /// Bounciness, from 0 (none) to 1 (elastic).
+    pub restitution: f32,
+    /// Coulomb friction coefficient.
+    pub friction: f32,
+    /// Collisions don't rotate the body, e.g. for player characters.
+    pub fixed_rotation: bool,
+    /// Seconds spent nearly still.
+    still_secs: f32,
+    asleep: bool,
+}
+
+impl RigidBody2d {
+    pub fn new(kind: RigidBodyKind2d, shape: Shape2d, position: Vec2) -> Self {
+        Self {
+            kind,
+            shape,
+            position,
+            rotation: Angle::ZERO,
+            velocity: Vec2::ZERO,
+            angular_velocity: 0.0,
+            density: 1.0,
+            restitution: 0.0,
+            friction: 0.5,
+            fixed_rotation: false,
+            still_secs: 0.0,
+            asleep: false,
+        }
+    }
+
+    /// The shape in world space.
+    pub fn collider(&self) -> Collider2d {
+        match self.shape {
+            Shape2d::Circle { radius } => Collider2d::Circle(Circle::new(self.position, radius)),
+            Shape2d::Rectangle { half_size } => Collider2d::RotatedRectangle(
+                RotatedRectangle::new(self.position, half_size * 2.0, self.rotation),
+            ),
+        }
+    }
+
+    /// Infinite unless [`RigidBodyKind2d::Dynamic`].
+    pub fn mass(&self) -> f32 {
+        if self.kind == RigidBodyKind2d::Dynamic {
+            self.density * self.shape.area()
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    pub(crate) fn inverse_mass(&self) -> f32 {
+        let mass = self.mass();
+        if mass > 0.0 && mass.is_finite() {
+            mass.recip()
+        } else {
+            0.0
+        }
+    }
+
+    pub(crate) fn inverse_inertia(&self) -> f32 {
+        if self.fixed_rotation {
+            0.0
+        } else {
+            self.inverse_mass() / self.shape.inertia_per_mass()
+        }
+    }
+
+    /// Velocity of a `point` (in world space) attached to the body.
+    pub fn velocity_at(&self, point: Vec2) -> Vec2 {
+        self.velocity + (point - self.position).perp() * self.angular_velocity
+    }
+
+    /// Applies an `impulse` (mass times velocity) at a `point` (in world space), waking the body.
+    pub fn apply_impulse(&mut self, impulse: Vec2, point: Vec2) {
+        self.wake();
+        self.velocity += impulse * self.inverse_mass();
+        self.angular_velocity += (point - self.position).perp_dot(impulse) * self.inverse_inertia();
+    }
+
+    /// Sleeping bodies don't move, and act like static bodies, until something wakes them.
+    pub fn is_asleep(&self) -> bool {
+        self.asleep
+    }
+
+    /// Call after changing a sleeping body's velocity or position.
+    pub fn wake(&mut self) {
+        self.still_secs = 0.0;
+        self.asleep = false;
+    }
+
+    /// Falls asleep after moving slower than `speed` for `secs`.
+    pub(crate) fn update_sleep(&mut self, delta_seconds: f32, speed: f32, secs: f32) {
+        if self.velocity.length_squared() < speed.powi(2) && self.angular_velocity.abs() < speed {
+            self.still_secs += delta_seconds;
+        } else {
+            self.still_secs = 0.0;
+        }
+        if self.still_secs >= secs {
+            self.asleep = true;
+            self.velocity = Vec2::ZERO;
+            self.angular_velocity = 0.0;
+        }
+    }
+}
+
+impl Hash for RigidBody2d {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let Self {
+            kind,
+            shape,
+            position,
+            rotation,
+            velocity,
+            angular_velocity,
+            density,
+            restitution,
+            friction,
+            fixed_rotation,
+            still_secs,
+            asleep,
+        } = self;
+        kind.hash(state);
+        shape.hash(state);
+        HbHash::hash(position, state);
+        rotation.hash(state);
+        HbHash::hash(velocity, state);
+        for f in [angular_velocity, density, restitution, friction, still_secs] {
+            hash_f32(*f, state);
+        }
+        fixed_rotation.hash(state);
+        asleep.hash(state);
+    }
+}
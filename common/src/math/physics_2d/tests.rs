@@ -0,0 +1,207 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+#[cfg(test)]
+mod physics_2d_tests {
+    use crate::{
+        bitcode, Angle, CompatHasher, Physics2d, RigidBody2d, RigidBodyKey2d, RigidBodyKind2d,
+        Shape2d,
+    };
+    use glam::Vec2;
+    use std::hash::{Hash, Hasher};
+
+    const DELTA_SECONDS: f32 = 1.0 / 16.0;
+    const GRAVITY: Vec2 = Vec2::new(0.0, -10.0);
+
+    fn world() -> Physics2d {
+        let mut physics = Physics2d::new(GRAVITY);
+        physics.insert(RigidBody2d::new(
+            RigidBodyKind2d::Static,
+            Shape2d::rectangle(Vec2::new(20.0, 1.0)),
+            Vec2::new(0.0, -0.5),
+        ));
+        physics
+    }
+
+    fn dynamic(shape: Shape2d, position: Vec2) -> RigidBody2d {
+        RigidBody2d::new(RigidBodyKind2d::Dynamic, shape, position)
+    }
+
+    fn steps(physics: &mut Physics2d, n: usize) {
+        for _ in 0..n {
+            physics.step(DELTA_SECONDS, |_| {});
+        }
+    }
+
+    fn hash(physics: &Physics2d) -> u64 {
+        let mut hasher = CompatHasher::default();
+        physics.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn rest_on_ground() {
+        let mut physics = world();
+        let circle = physics.insert(dynamic(Shape2d::circle(0.5), Vec2::new(-2.0, 5.0)));
+        let mut tilted = dynamic(Shape2d::rectangle(Vec2::ONE), Vec2::new(2.0, 3.0));
+        tilted.rotation = Angle(5000);
+        let rectangle = physics.insert(tilted);
+        steps(&mut physics, 64);
+
+        let circle = physics.get(circle).unwrap();
+        assert!(circle.is_asleep());
+        assert!((circle.position.y - 0.5).abs() < 0.05, "{circle:?}");
+
+        let rectangle = physics.get(rectangle).unwrap();
+        assert!(rectangle.is_asleep());
+        assert!((rectangle.position.y - 0.5).abs() < 0.05, "{rectangle:?}");
+        // Landed on a face.
+        assert!(
+            rectangle.rotation.to_radians().abs() < 0.05
+                || (rectangle.rotation.to_radians().abs() - std::f32::consts::FRAC_PI_2).abs()
+                    < 0.05,
+            "{rectangle:?}"
+        );
+    }
+
+    #[test]
+    fn stack() {
+        let mut physics = world();
+        let keys: Vec<RigidBodyKey2d> = (0..5)
+            .map(|i| {
+                physics.insert(dynamic(
+                    Shape2d::rectangle(Vec2::ONE),
+                    Vec2::new(i as f32 * 0.1, 0.5 + i as f32),
+                ))
+            })
+            .collect();
+        steps(&mut physics, 96);
+
+        for (i, key) in keys.into_iter().enumerate() {
+            let body = physics.get(key).unwrap();
+            assert!(body.is_asleep(), "{i} {body:?}");
+            assert!(
+                (body.position.y - (0.5 + i as f32)).abs() < 0.2,
+                "{i} {body:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn kinematic_pushes_dynamic() {
+        let mut physics = world();
+        let rectangle = physics.insert(dynamic(Shape2d::rectangle(Vec2::ONE), Vec2::new(0.0, 0.5)));
+        steps(&mut physics, 32);
+        assert!(physics.get(rectangle).unwrap().is_asleep());
+
+        let mut pusher = RigidBody2d::new(
+            RigidBodyKind2d::Kinematic,
+            Shape2d::rectangle(Vec2::ONE),
+            Vec2::new(-3.0, 0.5),
+        );
+        pusher.velocity = Vec2::new(2.0, 0.0);
+        let pusher = physics.insert(pusher);
+        steps(&mut physics, 48);
+
+        let pusher = physics.get(pusher).unwrap();
+        let rectangle = physics.get(rectangle).unwrap();
+        assert_eq!(pusher.position, Vec2::new(3.0, 0.5));
+        assert!(!rectangle.is_asleep());
+        assert!(rectangle.position.x > pusher.position.x, "{rectangle:?}");
+    }
+
+    #[test]
+    fn restitution() {
+        let bounce = |restitution: f32| {
+            let mut physics = world();
+            let mut ball = dynamic(Shape2d::circle(0.5), Vec2::new(0.0, 5.0));
+            ball.restitution = restitution;
+            let ball = physics.insert(ball);
+            let mut landed = false;
+            let mut max_height = 0f32;
+            for _ in 0..64 {
+                physics.step(DELTA_SECONDS, |_| {});
+                let y = physics.get(ball).unwrap().position.y;
+                landed |= y < 0.55;
+                if landed {
+                    max_height = max_height.max(y);
+                }
+            }
+            max_height
+        };
+        assert!(bounce(0.0) < 0.55);
+        assert!(bounce(0.8) > 2.0);
+    }
+
+    #[test]
+    fn momentum() {
+        let mut physics = Physics2d::new(Vec2::ZERO);
+        let mut a = dynamic(Shape2d::circle(0.5), Vec2::new(-2.0, 0.0));
+        a.velocity = Vec2::new(2.0, 0.0);
+        a.restitution = 1.0;
+        let a = physics.insert(a);
+        let b = physics.insert(dynamic(Shape2d::circle(0.5), Vec2::new(0.0, 0.0)));
+        steps(&mut physics, 32);
+
+        assert!(
+            physics.get(a).unwrap().velocity.length() < 0.01,
+            "{:?}",
+            physics.get(a).unwrap()
+        );
+        assert!(
+            (physics.get(b).unwrap().velocity - Vec2::new(2.0, 0.0)).length() < 0.01,
+            "{:?}",
+            physics.get(b).unwrap()
+        );
+    }
+
+    #[test]
+    fn contacts() {
+        let mut physics = world();
+        let ground = physics.iter().next().unwrap().0;
+        let circle = physics.insert(dynamic(Shape2d::circle(0.5), Vec2::new(0.0, 0.45)));
+        let mut contacts = Vec::new();
+        physics.step(DELTA_SECONDS, |contact| contacts.push(contact.clone()));
+
+        assert_eq!(contacts.len(), 1);
+        let contact = &contacts[0];
+        assert_eq!((contact.a, contact.b), (ground, circle));
+        assert!((contact.normal - Vec2::Y).length() < 0.001);
+        assert!((contact.depth - 0.05).abs() < 0.001);
+        assert!(contact.impulse > 0.0);
+
+        physics.remove(circle);
+        physics.step(DELTA_SECONDS, |_| panic!("no contacts"));
+    }
+
+    #[test]
+    fn deterministic() {
+        let scene = || {
+            let mut physics = world();
+            for i in 0..6 {
+                let position = Vec2::new(i as f32 * 0.3 - 1.0, 1.0 + i as f32 * 1.2);
+                let shape = if i % 2 == 0 {
+                    Shape2d::circle(0.4)
+                } else {
+                    Shape2d::rectangle(Vec2::new(1.0, 0.6))
+                };
+                physics.insert(dynamic(shape, position));
+            }
+            physics
+        };
+
+        let mut a = scene();
+        let mut b = scene();
+        steps(&mut a, 20);
+        steps(&mut b, 20);
+        assert_eq!(hash(&a), hash(&b));
+
+        // Decoding mid-simulation, including warm starts, continues identically.
+        let mut decoded: Physics2d = bitcode::decode(&bitcode::encode(&a)).unwrap();
+        assert_eq!(hash(&decoded), hash(&a));
+        steps(&mut a, 20);
+        steps(&mut decoded, 20);
+        assert_eq!(hash(&decoded), hash(&a));
+        assert_eq!(decoded.iter().count(), a.len());
+    }
+}
@@ -3,7 +3,7 @@
 
 use crate::CompatHasher;
 use rand::rngs::StdRng;
-use rand::{RngCore, SeedableRng};
+use rand::{Rng, RngCore, SeedableRng};
 use std::hash::{Hash, Hasher};
 
 pub struct HashRng {
@@ -18,6 +18,32 @@ impl HashRng {
             inner: StdRng::seed_from_u64(hasher.finish()),
         }
     }
+
+    /// Returns a random index in `0..len`, consistent between 32 and 64 bit platforms (unlike
+    /// `gen_range` on `usize`).
+    ///
+    /// **Panics**
+    ///
+    /// If `len` is zero or doesn't fit in a `u32`.
+    pub fn gen_index(&mut self, len: usize) -> usize {
+        let len = u32::try_from(len).expect("len too large");
+        self.gen_range(0..len) as usize
+    }
+
+    /// Returns a random element of `slice`, or `None` if it is empty. Portable, like
+    /// [`Self::gen_index`].
+    pub fn choose<'a, T>(&mut self, slice: &'a [T]) -> Option<&'a T> {
+        (!slice.is_empty()).then(|| &slice[self.gen_index(slice.len())])
+    }
+
+    /// Shuffles `slice` in place. Portable, like [`Self::gen_index`].
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        // Fisher-Yates.
+        for i in (1..slice.len()).rev() {
+            let j = self.gen_index(i + 1);
+            slice.swap(i, j);
+        }
+    }
 }
 
 impl RngCore for HashRng {
@@ -0,0 +1,142 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::HashRng;
+use glam::{vec2, IVec2, UVec2, Vec2};
+use rand::Rng;
+
+/// Chooses items with probability proportional to their weights.
+#[derive(Clone, Debug)]
+pub struct WeightedTable<T> {
+    items: Vec<T>,
+    /// Running total of weights, ending with each item.
+    cumulative: Vec<u64>,
+}
+
+impl<T> Default for WeightedTable<T> {
+    fn default() -> Self {
+        Self {
+            items: Vec::new(),
+            cumulative: Vec::new(),
+        }
+    }
+}
+
+impl<T> WeightedTable<T> {
+    /// Items with zero weight are never chosen.
+    pub fn new(items: impl IntoIterator<Item = (T, u32)>) -> Self {
+        let mut ret = Self::default();
+        let mut total = 0u64;
+        for (item, weight) in items {
+            if weight == 0 {
+                continue;
+            }
+            total += weight as u64;
+            ret.items.push(item);
+            ret.cumulative.push(total);
+        }
+        ret
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn total_weight(&self) -> u64 {
+        self.cumulative.last().copied().unwrap_or(0)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.items.iter()
+    }
+
+    /// Returns `None` iff the table is empty. Portable between platforms.
+    pub fn choose(&self, rng: &mut HashRng) -> Option<&T> {
+        if self.is_empty() {
+            return None;
+        }
+        let target = rng.gen_range(0..self.total_weight());
+        let index = self.cumulative.partition_point(|&total| total <= target);
+        self.items.get(index)
+    }
+}
+
+/// Returns randomly scattered points in `[0, size)`, no two closer than `min_distance`, such
+/// that there is little room for more (Bridson's algorithm). Higher `attempts` (typically 30)
+/// pack points more tightly, at the cost of performance. Portable between platforms.
+///
+/// **Panics**
+///
+/// If `min_distance` isn't positive.
+pub fn poisson_disk_2d(
+    rng: &mut HashRng,
+    size: Vec2,
+    min_distance: f32,
+    attempts: u32,
+) -> Vec<Vec2> {
+    assert!(min_distance > 0.0, "min_distance must be positive");
+    if !(size.x > 0.0 && size.y > 0.0) {
+        return Vec::new();
+    }
+
+    // Each grid cell can contain at most one point.
+    let cell_size = min_distance * std::f32::consts::FRAC_1_SQRT_2;
+    let dims = (size / cell_size).ceil().as_uvec2().max(UVec2::ONE);
+    let mut grid = vec![u32::MAX; dims.x as usize * dims.y as usize];
+    let cell = |point: Vec2| (point / cell_size).as_uvec2().min(dims - 1);
+    let index = |cell: UVec2| cell.x as usize + cell.y as usize * dims.x as usize;
+
+    let first = vec2(rng.gen::<f32>(), rng.gen::<f32>()) * size;
+    grid[index(cell(first))] = 0;
+    let mut points = vec![first];
+    let mut active = vec![0u32];
+
+    let min_distance_squared = min_distance * min_distance;
+    while !active.is_empty() {
+        let active_index = rng.gen_index(active.len());
+        let center = points[active[active_index] as usize];
+        let mut found = false;
+        for _ in 0..attempts {
+            // Uniform in the annulus between `min_distance` and twice that, by rejection
+            // sampling (avoids trigonometry, which isn't portable).
+            let offset = loop {
+                let offset = (vec2(rng.gen::<f32>(), rng.gen::<f32>()) * 4.0 - 2.0) * min_distance;
+                let distance_squared = offset.length_squared();
+                if distance_squared >= min_distance_squared
+                    && distance_squared <= 4.0 * min_distance_squared
+                {
+                    break offset;
+                }
+            };
+            let candidate = center + offset;
+            if !(candidate.cmpge(Vec2::ZERO).all() && candidate.cmplt(size).all()) {
+                continue;
+            }
+            let candidate_cell = cell(candidate).as_ivec2();
+            let min = (candidate_cell - 2).max(IVec2::ZERO).as_uvec2();
+            let max = (candidate_cell + 2).as_uvec2().min(dims - 1);
+            let too_close = (min.y..=max.y).any(|y| {
+                (min.x..=max.x).any(|x| {
+                    let other = grid[index(UVec2::new(x, y))];
+                    other != u32::MAX
+                        && points[other as usize].distance_squared(candidate) < min_distance_squared
+                })
+            });
+            if !too_close {
+                grid[index(cell(candidate))] = points.len() as u32;
+                active.push(points.len() as u32);
+                points.push(candidate);
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            active.swap_remove(active_index);
+        }
+    }
+    points
+}
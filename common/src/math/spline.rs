@@ -0,0 +1,292 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use glam::{Vec2, Vec3};
+use std::ops::{Add, Mul, Sub};
+
+/// A value that can be interpolated by a [`Curve`], such as [`Vec2`] or [`Vec3`].
+pub trait SplinePoint:
+    Copy + Add<Output = Self> + Sub<Output = Self> + Mul<f32, Output = Self>
+{
+    /// Distance from zero.
+    fn length(self) -> f32;
+}
+
+impl SplinePoint for f32 {
+    fn length(self) -> f32 {
+        self.abs()
+    }
+}
+
+impl SplinePoint for Vec2 {
+    fn length(self) -> f32 {
+        Vec2::length(self)
+    }
+}
+
+impl SplinePoint for Vec3 {
+    fn length(self) -> f32 {
+        Vec3::length(self)
+    }
+}
+
+/// A parametric curve, defined for `t` in `[0, 1]` (which is clamped).
+///
+/// Curves only use basic floating point arithmetic, so they are deterministic across platforms
+/// and suitable for server-side movement.
+pub trait Curve<P: SplinePoint> {
+    /// Returns the point at `t`.
+    fn sample(&self, t: f32) -> P;
+
+    /// Returns the rate of change of the point with respect to `t`, i.e. the tangent.
+    fn derivative(&self, t: f32) -> P;
+}
+
+/// A cubic Bézier curve, which passes through `start` and `end`, and is pulled towards the
+/// control points.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CubicBezier<P> {
+    pub start: P,
+    pub control1: P,
+    pub control2: P,
+    pub end: P,
+}
+
+impl<P: SplinePoint> CubicBezier<P> {
+    pub fn new(start: P, control1: P, control2: P, end: P) -> Self {
+        Self {
+            start,
+            control1,
+            control2,
+            end,
+        }
+    }
+}
+
+impl<P: SplinePoint> Curve<P> for CubicBezier<P> {
+    fn sample(&self, t: f32) -> P {
+        let t = t.clamp(0.0, 1.0);
+        let u = 1.0 - t;
+        self.start * (u * u * u)
+            + self.control1 * (3.0 * u * u * t)
+            + self.control2 * (3.0 * u * t * t)
+            + self.end * (t * t * t)
+    }
+
+    fn derivative(&self, t: f32) -> P {
+        let t = t.clamp(0.0, 1.0);
+        let u = 1.0 - t;
+        (self.control1 - self.start) * (3.0 * u * u)
+            + (self.control2 - self.control1) * (6.0 * u * t)
+            + (self.end - self.control2) * (3.0 * t * t)
+    }
+}
+
+/// A cubic Hermite curve, which passes through `start` and `end` with the given tangents.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Hermite<P> {
+    pub start: P,
+    pub start_tangent: P,
+    pub end: P,
+    pub end_tangent: P,
+}
+
+impl<P: SplinePoint> Hermite<P> {
+    pub fn new(start: P, start_tangent: P, end: P, end_tangent: P) -> Self {
+        Self {
+            start,
+            start_tangent,
+            end,
+            end_tangent,
+        }
+    }
+
+    /// Converts to the equivalent [`CubicBezier`].
+    pub fn to_bezier(&self) -> CubicBezier<P> {
+        CubicBezier::new(
+            self.start,
+            self.start + self.start_tangent * (1.0 / 3.0),
+            self.end - self.end_tangent * (1.0 / 3.0),
+            self.end,
+        )
+    }
+}
+
+impl<P: SplinePoint> Curve<P> for Hermite<P> {
+    fn sample(&self, t: f32) -> P {
+        let t = t.clamp(0.0, 1.0);
+        let t2 = t * t;
+        let t3 = t2 * t;
+        self.start * (2.0 * t3 - 3.0 * t2 + 1.0)
+            + self.start_tangent * (t3 - 2.0 * t2 + t)
+            + self.end * (3.0 * t2 - 2.0 * t3)
+            + self.end_tangent * (t3 - t2)
+    }
+
+    fn derivative(&self, t: f32) -> P {
+        let t = t.clamp(0.0, 1.0);
+        let t2 = t * t;
+        self.start * (6.0 * t2 - 6.0 * t)
+            + self.start_tangent * (3.0 * t2 - 4.0 * t + 1.0)
+            + self.end * (6.0 * t - 6.0 * t2)
+            + self.end_tangent * (3.0 * t2 - 2.0 * t)
+    }
+}
+
+/// A uniform Catmull-Rom spline, which smoothly passes through all of its points.
+///
+/// Each pair of adjacent points is connected by a segment that takes an equal share of `t`, so
+/// the speed varies if the points are unevenly spaced (see [`ArcLength`]).
+#[derive(Clone, Debug, PartialEq)]
+pub struct CatmullRom<P> {
+    points: Vec<P>,
+    closed: bool,
+}
+
+impl<P: SplinePoint> CatmullRom<P> {
+    /// If `closed`, the last point connects back to the first. Returns `None` if there are
+    /// fewer than 2 points.
+    pub fn new(points: Vec<P>, closed: bool) -> Option<Self> {
+        (points.len() >= 2).then_some(Self { points, closed })
+    }
+
+    pub fn points(&self) -> &[P] {
+        &self.points
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.points.len() - !self.closed as usize
+    }
+
+    /// Returns the `index`th segment, from `points[index]` to the following point.
+    ///
+    /// **Panics**
+    ///
+    /// If `index` isn't less than [`Self::segment_count`].
+    pub fn segment(&self, index: usize) -> Hermite<P> {
+        assert!(index < self.segment_count(), "segment out of bounds");
+        let len = self.points.len();
+        let point = |i: isize| {
+            if self.closed {
+                self.points[i.rem_euclid(len as isize) as usize]
+            } else {
+                // Extrapolate beyond the ends, so the end tangents point at their neighbors.
+                self.points[i.clamp(0, len as isize - 1) as usize]
+            }
+        };
+        let i = index as isize;
+        let (before, start, end, after) = (point(i - 1), point(i), point(i + 1), point(i + 2));
+        Hermite::new(start, (end - before) * 0.5, end, (after - start) * 0.5)
+    }
+
+    /// Returns the segment index and `t` within it.
+    fn locate(&self, t: f32) -> (usize, f32) {
+        let segments = self.segment_count();
+        let scaled = t.clamp(0.0, 1.0) * segments as f32;
+        let index = (scaled as usize).min(segments - 1);
+        (index, scaled - index as f32)
+    }
+}
+
+impl<P: SplinePoint> Curve<P> for CatmullRom<P> {
+    fn sample(&self, t: f32) -> P {
+        let (index, t) = self.locate(t);
+        self.segment(index).sample(t)
+    }
+
+    fn derivative(&self, t: f32) -> P {
+        let (index, t) = self.locate(t);
+        self.segment(index).derivative(t) * self.segment_count() as f32
+    }
+}
+
+/// Reparameterizes a [`Curve`] by arc length, so `t` is proportional to distance along it,
+/// i.e. sampling at evenly spaced `t` moves at constant speed.
+#[derive(Clone, Debug)]
+pub struct ArcLength<C> {
+    curve: C,
+    /// Distance along the curve at evenly spaced original `t`, starting with 0.
+    distances: Vec<f32>,
+}
+
+impl<C> ArcLength<C> {
+    /// Approximates the curve with `samples` line segments; more are slower but more accurate.
+    ///
+    /// **Panics**
+    ///
+    /// If `samples` is zero.
+    pub fn new<P: SplinePoint>(curve: C, samples: usize) -> Self
+    where
+        C: Curve<P>,
+    {
+        assert!(samples > 0, "samples must be positive");
+        let mut distances = Vec::with_capacity(samples + 1);
+        let mut previous = curve.sample(0.0);
+        let mut distance = 0.0;
+        distances.push(distance);
+        for i in 1..=samples {
+            let point = curve.sample(i as f32 / samples as f32);
+            distance += (point - previous).length();
+            distances.push(distance);
+            previous = point;
+        }
+        Self { curve, distances }
+    }
+
+    pub fn curve(&self) -> &C {
+        &self.curve
+    }
+
+    /// Total (approximate) length of the curve.
+    pub fn length(&self) -> f32 {
+        *self.distances.last().unwrap()
+    }
+
+    /// Returns the original curve's `t` at `distance` along it, clamped to the ends.
+    pub fn t_at_distance(&self, distance: f32) -> f32 {
+        let samples = self.distances.len() - 1;
+        let distance = distance.clamp(0.0, self.length());
+        let after = self
+            .distances
+            .partition_point(|&d| d < distance)
+            .clamp(1, samples);
+        let (start, end) = (self.distances[after - 1], self.distances[after]);
+        let fraction = if end > start {
+            (distance - start) / (end - start)
+        } else {
+            0.0
+        };
+        ((after - 1) as f32 + fraction) / samples as f32
+    }
+
+    /// Returns the point at `distance` along the curve, clamped to the ends.
+    pub fn sample_distance<P: SplinePoint>(&self, distance: f32) -> P
+    where
+        C: Curve<P>,
+    {
+        self.curve.sample(self.t_at_distance(distance))
+    }
+}
+
+impl<P: SplinePoint, C: Curve<P>> Curve<P> for ArcLength<C> {
+    fn sample(&self, t: f32) -> P {
+        self.sample_distance(t.clamp(0.0, 1.0) * self.length())
+    }
+
+    /// Has a magnitude of [`Self::length`], except where the original curve is stationary.
+    fn derivative(&self, t: f32) -> P {
+        let derivative = self
+            .curve
+            .derivative(self.t_at_distance(t.clamp(0.0, 1.0) * self.length()));
+        let speed = derivative.length();
+        if speed > 0.0 {
+            derivative * (self.length() / speed)
+        } else {
+            derivative
+        }
+    }
+}
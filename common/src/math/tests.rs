@@ -244,3 +244,304 @@ mod range_tests {
         assert_eq!(map_ranges_fast(10.0, 0.0..1.0, 2.0..3.0, true, true), 3.0);
     }
 }
+
+#[cfg(test)]
+mod noise_tests {
+    use crate::{HashRng, Noise2d, NoiseKind2d};
+    use glam::{vec2, Vec2};
+    use rand::prelude::*;
+    use test::bench::{black_box, Bencher};
+
+    const KINDS: [NoiseKind2d; 4] = [
+        NoiseKind2d::Perlin,
+        NoiseKind2d::Simplex,
+        NoiseKind2d::Value,
+        NoiseKind2d::Worley,
+    ];
+
+    #[test]
+    fn range_and_continuity() {
+        let mut rng = HashRng::new(&0u32);
+        for kind in KINDS {
+            let noise = Noise2d::new(&"range", kind);
+            let (mut min, mut max) = (f32::INFINITY, f32::NEG_INFINITY);
+            for _ in 0..100000 {
+                let pos = vec2(
+                    rng.gen_range(-1000.0..1000.0),
+                    rng.gen_range(-1000.0..1000.0),
+                );
+                let n = noise.sample(pos);
+                assert!((-1.0..=1.0).contains(&n), "{kind:?} {pos} {n}");
+                min = min.min(n);
+                max = max.max(n);
+
+                let nearby = noise.sample(pos + Vec2::splat(0.001));
+                assert!((n - nearby).abs() < 0.05, "{kind:?} {pos} {n} {nearby}");
+
+                let fbm = noise.fbm(pos, 5, 2.0, 0.5);
+                assert!((-1.0..=1.0).contains(&fbm), "{kind:?} {pos} {fbm}");
+            }
+            // Uses most of the range.
+            assert!(min < -0.6 && max > 0.6, "{kind:?} {min} {max}");
+        }
+    }
+
+    #[test]
+    fn deterministic() {
+        for kind in KINDS {
+            let a = Noise2d::new(&42u64, kind);
+            let b = Noise2d::new(&42u64, kind);
+            let c = Noise2d::new(&43u64, kind);
+            assert_eq!(a, b);
+            assert_ne!(a, c);
+            let pos = vec2(12.3, -45.6);
+            assert_eq!(a.sample(pos), b.sample(pos));
+            assert_ne!(a.sample(pos), c.sample(pos));
+            assert_eq!(a.warp(pos, 2.0, 3), b.warp(pos, 2.0, 3));
+            assert!(a.warp(pos, 2.0, 3).distance(pos) <= 2.0 * 2f32.sqrt());
+        }
+    }
+
+    #[test]
+    fn golden() {
+        // Expected bits were computed on x86_64, and must match all other platforms so worlds
+        // generate identically.
+        let pos = vec2(3.7, -8.2);
+        let expected = [
+            [0x3e173ab1, 0x3d0155f8, 0x4062d91f],
+            [0x3f1d7ecb, 0x3e667e33, 0x400c0eb2],
+            [0x3d92fb08, 0x3d202b5c, 0x407bed7c],
+            [0xbf745d7d, 0xbf2aa1f4, 0x4091ca52],
+        ];
+        for (kind, expected) in KINDS.into_iter().zip(expected) {
+            let noise = Noise2d::new(&"golden", kind);
+            let actual = [
+                noise.sample(pos).to_bits(),
+                noise.fbm(pos, 4, 2.0, 0.5).to_bits(),
+                noise.warp(pos, 3.0, 2).x.to_bits(),
+            ];
+            assert_eq!(actual, expected, "{kind:?}");
+        }
+    }
+
+    #[bench]
+    fn bench_fbm(b: &mut Bencher) {
+        for kind in KINDS {
+            let noise = Noise2d::new(&0u8, kind);
+            b.iter(|| {
+                let mut sum = 0.0;
+                for i in 0..100 {
+                    sum += noise.fbm(black_box(vec2(i as f32 * 0.37, 1.5)), 4, 2.0, 0.5);
+                }
+                sum
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod sampling_tests {
+    use crate::{poisson_disk_2d, HashRng, WeightedTable};
+    use glam::{vec2, Vec2};
+
+    #[test]
+    fn poisson_disk() {
+        let mut rng = HashRng::new(&"poisson");
+        let size = vec2(50.0, 30.0);
+        let points = poisson_disk_2d(&mut rng, size, 2.0, 30);
+        for (i, a) in points.iter().enumerate() {
+            assert!(a.cmpge(Vec2::ZERO).all() && a.cmplt(size).all(), "{a}");
+            for b in &points[i + 1..] {
+                assert!(a.distance(*b) >= 2.0, "{a} {b}");
+            }
+        }
+        // Reasonably dense (the theoretical maximum is about 430).
+        assert!(points.len() > 200, "{}", points.len());
+
+        let again = poisson_disk_2d(&mut HashRng::new(&"poisson"), size, 2.0, 30);
+        assert_eq!(points, again);
+        assert!(poisson_disk_2d(&mut rng, Vec2::ZERO, 1.0, 30).is_empty());
+    }
+
+    #[test]
+    fn weighted_table() {
+        let table = WeightedTable::new([('a', 1), ('b', 0), ('c', 3)]);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.total_weight(), 4);
+        assert_eq!(table.iter().copied().collect::<String>(), "ac");
+
+        let mut rng = HashRng::new(&1u8);
+        let mut counts = [0u32; 3];
+        for _ in 0..10000 {
+            counts[(*table.choose(&mut rng).unwrap() as u8 - b'a') as usize] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!((2300..2700).contains(&counts[0]), "{counts:?}");
+
+        let empty = WeightedTable::<char>::new([('z', 0)]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.choose(&mut rng), None);
+    }
+
+    #[test]
+    fn shuffle_and_choose() {
+        let mut rng = HashRng::new(&"shuffle");
+        let mut items: Vec<u32> = (0..100).collect();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..100).collect::<Vec<_>>());
+        let mut sorted = items.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..100).collect::<Vec<_>>());
+
+        // Expected order was computed on x86_64, and must match all other platforms.
+        let mut small = [1, 2, 3, 4, 5];
+        HashRng::new(&"shuffle").shuffle(&mut small);
+        assert_eq!(small, [3, 5, 2, 1, 4]);
+
+        assert!(rng.choose::<u32>(&[]).is_none());
+        assert!(items.contains(rng.choose(&items).unwrap()));
+    }
+}
+
+#[cfg(test)]
+mod spline_tests {
+    use crate::{Angle, ArcLength, CatmullRom, CubicBezier, Curve, Easing, Hermite};
+    use glam::{vec2, vec3, Vec2};
+
+    fn assert_close(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 0.001, "{a} {b}");
+    }
+
+    #[test]
+    fn bezier_and_hermite() {
+        let bezier = CubicBezier::new(
+            vec2(0.0, 0.0),
+            vec2(1.0, 2.0),
+            vec2(3.0, 2.0),
+            vec2(4.0, 0.0),
+        );
+        assert_eq!(bezier.sample(0.0), bezier.start);
+        assert_eq!(bezier.sample(1.0), bezier.end);
+        assert_close(bezier.sample(0.5), vec2(2.0, 1.5));
+        assert_close(bezier.derivative(0.0), vec2(3.0, 6.0));
+        assert_close(bezier.derivative(1.0), vec2(3.0, -6.0));
+
+        let hermite = Hermite::new(
+            vec2(0.0, 0.0),
+            vec2(3.0, 6.0),
+            vec2(4.0, 0.0),
+            vec2(3.0, -6.0),
+        );
+        assert_eq!(hermite.to_bezier(), bezier);
+        for i in 0..=10 {
+            let t = i as f32 / 10.0;
+            assert_close(hermite.sample(t), bezier.sample(t));
+            assert_close(hermite.derivative(t), bezier.derivative(t));
+        }
+        // Clamped.
+        assert_eq!(hermite.sample(2.0), hermite.end);
+    }
+
+    #[test]
+    fn catmull_rom() {
+        assert!(CatmullRom::new(vec![Vec2::ZERO], false).is_none());
+
+        let points = vec![
+            vec2(0.0, 0.0),
+            vec2(1.0, 1.0),
+            vec2(3.0, 1.0),
+            vec2(4.0, 0.0),
+        ];
+        let open = CatmullRom::new(points.clone(), false).unwrap();
+        assert_eq!(open.segment_count(), 3);
+        for (i, point) in points.iter().enumerate() {
+            assert_close(open.sample(i as f32 / 3.0), *point);
+        }
+        // Tangent at an interior point is parallel to its neighbors.
+        assert_close(open.segment(1).start_tangent, vec2(1.5, 0.5));
+        // Continuous between segments.
+        assert_close(
+            open.sample(1.0 / 3.0 - 0.0001),
+            open.sample(1.0 / 3.0 + 0.0001),
+        );
+
+        let closed = CatmullRom::new(points.clone(), true).unwrap();
+        assert_eq!(closed.segment_count(), 4);
+        assert_close(closed.sample(0.0), closed.sample(1.0));
+        assert_close(closed.derivative(0.0), closed.derivative(1.0));
+
+        let vec3s = CatmullRom::new(vec![vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 2.0)], false).unwrap();
+        assert_eq!(vec3s.sample(0.5), vec3(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn arc_length() {
+        // Uneven control points, so uniform t doesn't have uniform speed.
+        let bezier = CubicBezier::new(Vec2::ZERO, vec2(0.1, 0.0), vec2(1.0, 0.0), vec2(10.0, 0.0));
+        assert!(bezier.sample(0.5).x < 2.0);
+        let arc = ArcLength::new(bezier, 256);
+        assert!((arc.length() - 10.0).abs() < 0.01, "{}", arc.length());
+        for i in 0..=20 {
+            let distance = i as f32 * 0.5;
+            let point: Vec2 = arc.sample_distance(distance);
+            assert!((point.x - distance).abs() < 0.05, "{distance} {point}");
+            assert_close(arc.sample(distance / 10.0), point);
+            if i > 0 && i < 20 {
+                assert!((arc.derivative(distance / 10.0).length() - 10.0).abs() < 0.01);
+            }
+        }
+        assert_eq!(arc.t_at_distance(-1.0), 0.0);
+        assert_eq!(arc.t_at_distance(100.0), 1.0);
+    }
+
+    #[test]
+    fn easing() {
+        for easing in Easing::ALL {
+            assert!(easing.apply(0.0).abs() < 0.001, "{easing:?}");
+            assert!((easing.apply(1.0) - 1.0).abs() < 0.001, "{easing:?}");
+            assert_eq!(easing.apply(-1.0), easing.apply(0.0), "{easing:?}");
+            assert_eq!(easing.apply(2.0), easing.apply(1.0), "{easing:?}");
+            let mut previous = 0.0;
+            for i in 1..=100 {
+                let eased = easing.apply(i as f32 / 100.0);
+                assert!((-0.5..=1.5).contains(&eased), "{easing:?}");
+                // Continuous.
+                assert!((eased - previous).abs() < 0.2, "{easing:?} {i}");
+                previous = eased;
+            }
+        }
+        assert_eq!(Easing::QuadIn.apply(0.5), 0.25);
+        assert_eq!(Easing::QuadOut.apply(0.5), 0.75);
+        assert_eq!(Easing::CubicInOut.apply(0.5), 0.5);
+        assert_eq!(Easing::SmoothStep.apply(0.5), 0.5);
+        assert!(Easing::BackIn.apply(0.2) < 0.0);
+        assert!(Easing::BackOut.apply(0.8) > 1.0);
+    }
+
+    #[test]
+    fn angle() {
+        let a = Angle::from_degrees(170.0);
+        let b = Angle::from_degrees(-170.0);
+        // The shorter way around.
+        let middle = a.ease(b, 0.5, Easing::SmoothStep);
+        assert!(
+            (middle.to_degrees().abs() - 180.0).abs() < 0.1,
+            "{middle:?}"
+        );
+
+        assert_eq!(
+            a.rotate_towards(b, Angle::from_degrees(5.0)),
+            a + Angle::from_degrees(5.0)
+        );
+        assert_eq!(a.rotate_towards(b, Angle::from_degrees(30.0)), b);
+
+        let points = [150.0, 170.0, -170.0, -150.0].map(Angle::from_degrees);
+        let [before, from, to, after] = points;
+        assert_eq!(Angle::catmull_rom(before, from, to, after, 0.0), from);
+        assert_eq!(Angle::catmull_rom(before, from, to, after, 1.0), to);
+        for i in 0..=10 {
+            let angle = Angle::catmull_rom(before, from, to, after, i as f32 / 10.0);
+            assert!(angle.to_degrees().abs() > 165.0, "{angle:?}");
+        }
+    }
+}
@@ -0,0 +1,228 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::path_grid::{move_cost, PathGrid, DIAGONAL_COST, DIRECTIONS};
+use fxhash::FxHashSet;
+use glam::{IVec2, UVec2};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+const UNREACHABLE: u32 = u32::MAX;
+
+/// The distance from every cell of a [`PathGrid`] to the nearest of some goals, and therefore
+/// the direction to move towards them, shared by any number of agents.
+///
+/// Computation can be spread over multiple ticks with [`Self::step`]. When cells change
+/// passability, [`Self::update`] repairs only the affected distances.
+#[derive(Clone, Debug)]
+pub struct FlowField {
+    dims: UVec2,
+    goals: Vec<UVec2>,
+    /// Cost to the nearest goal, or [`UNREACHABLE`] (so far).
+    distances: Box<[u32]>,
+    /// (distance, index) of cells whose neighbors need to be relaxed.
+    open: BinaryHeap<Reverse<(u32, u32)>>,
+}
+
+impl FlowField {
+    /// Starts computing a flow field towards `goals`, without doing any work yet. Impassable
+    /// goals are ignored until they become passable.
+    ///
+    /// **Panics**
+    ///
+    /// If any one of `goals` is out of bounds.
+    pub fn new(grid: &PathGrid, goals: impl IntoIterator<Item = UVec2>) -> Self {
+        let goals: Vec<UVec2> = goals.into_iter().collect();
+        assert!(
+            goals.iter().all(|goal| goal.cmplt(grid.dims()).all()),
+            "out of bounds"
+        );
+        let mut me = Self {
+            dims: grid.dims(),
+            goals,
+            distances: Box::default(),
+            open: BinaryHeap::new(),
+        };
+        me.restart(grid);
+        me
+    }
+
+    fn restart(&mut self, grid: &PathGrid) {
+        self.dims = grid.dims();
+        self.distances = vec![UNREACHABLE; (self.dims.x * self.dims.y) as usize].into();
+        self.open.clear();
+        for &goal in &self.goals {
+            if grid.is_passable(goal.as_ivec2()) {
+                let index = grid.index(goal);
+                self.distances[index] = 0;
+                self.open.push(Reverse((0, index as u32)));
+            }
+        }
+    }
+
+    pub fn goals(&self) -> &[UVec2] {
+        &self.goals
+    }
+
+    /// Returns true if all distances have been computed.
+    pub fn is_complete(&self) -> bool {
+        self.open.is_empty()
+    }
+
+    /// Continues computing distances, processing at most `budget` cells. Returns true if
+    /// complete.
+    pub fn step(&mut self, grid: &PathGrid, budget: usize) -> bool {
+        debug_assert_eq!(grid.dims(), self.dims);
+        for _ in 0..budget {
+            let Some(Reverse((distance, index))) = self.open.pop() else {
+                break;
+            };
+            if self.distances[index as usize] != distance {
+                // Stale.
+                continue;
+            }
+            let pos = grid.pos(index as usize).as_ivec2();
+            for direction in DIRECTIONS {
+                if !grid.can_move(pos, direction) {
+                    continue;
+                }
+                let neighbor = grid.index((pos + direction).as_uvec2());
+                let neighbor_distance = distance + move_cost(direction);
+                if neighbor_distance < self.distances[neighbor] {
+                    self.distances[neighbor] = neighbor_distance;
+                    self.open
+                        .push(Reverse((neighbor_distance, neighbor as u32)));
+                }
+            }
+        }
+        self.is_complete()
+    }
+
+    /// Completes computing distances, regardless of how long it takes.
+    pub fn finish(&mut self, grid: &PathGrid) {
+        self.step(grid, usize::MAX);
+    }
+
+    /// Returns the cost of moving from `pos` to the nearest goal, or `None` if unreachable or
+    /// outside the grid. Only accurate once complete.
+    pub fn distance(&self, pos: UVec2) -> Option<u32> {
+        if pos.cmpge(self.dims).any() {
+            return None;
+        }
+        let distance = *self.distances.get((pos.y * self.dims.x + pos.x) as usize)?;
+        (distance != UNREACHABLE).then_some(distance)
+    }
+
+    /// Returns the direction of the next step from `pos` towards the nearest goal, or `None` if
+    /// `pos` is a goal, unreachable or outside the grid. Only accurate once complete.
+    pub fn direction(&self, grid: &PathGrid, pos: UVec2) -> Option<IVec2> {
+        let distance = self.distance(pos)?;
+        if distance == 0 {
+            return None;
+        }
+        let pos = pos.as_ivec2();
+        DIRECTIONS
+            .into_iter()
+            .filter(|&direction| grid.can_move(pos, direction))
+            .filter_map(|direction| {
+                let neighbor = self.distance((pos + direction).as_uvec2())?;
+                Some((neighbor + move_cost(direction), direction))
+            })
+            // Ties go to the first direction.
+            .min_by_key(|&(distance, _)| distance)
+            .map(|(_, direction)| direction)
+    }
+
+    /// Repairs distances after `pos` changed passability in `grid`. If not complete yet,
+    /// starts over instead.
+    pub fn update(&mut self, grid: &PathGrid, pos: UVec2) {
+        if !self.is_complete() || grid.dims() != self.dims {
+            self.restart(grid);
+            return;
+        }
+        let pos = pos.as_ivec2();
+        let in_bounds =
+            |pos: IVec2| pos.cmpge(IVec2::ZERO).all() && pos.as_uvec2().cmplt(self.dims).all();
+
+        // Cells whose distance may have increased because their shortest path used a move that
+        // is no longer allowed, i.e. into `pos` or diagonally around its corner.
+        let mut invalid = Vec::new();
+        if !grid.is_passable(pos) {
+            if self.distance(pos.as_uvec2()).is_some() {
+                invalid.push(grid.index(pos.as_uvec2()));
+            }
+            for i in 0..4 {
+                let a = pos + DIRECTIONS[i];
+                let b = pos + DIRECTIONS[(i + 1) % 4];
+                if !in_bounds(a) || !in_bounds(b) {
+                    continue;
+                }
+                let (a, b) = (grid.index(a.as_uvec2()), grid.index(b.as_uvec2()));
+                for (from, to) in [(a, b), (b, a)] {
+                    let from_distance = self.distances[from];
+                    if from_distance != UNREACHABLE
+                        && self.distances[to] == from_distance + DIAGONAL_COST
+                    {
+                        invalid.push(to);
+                    }
+                }
+            }
+        }
+
+        // Invalidate everything that depends on them.
+        let mut visited: FxHashSet<usize> = invalid.iter().copied().collect();
+        let mut stack = invalid.clone();
+        while let Some(index) = stack.pop() {
+            let distance = self.distances[index];
+            let cell = grid.pos(index).as_ivec2();
+            for direction in DIRECTIONS {
+                let neighbor = cell + direction;
+                if !in_bounds(neighbor) {
+                    continue;
+                }
+                let neighbor = grid.index(neighbor.as_uvec2());
+                if self.distances[neighbor] == distance + move_cost(direction)
+                    && visited.insert(neighbor)
+                {
+                    invalid.push(neighbor);
+                    stack.push(neighbor);
+                }
+            }
+        }
+        for &index in &invalid {
+            self.distances[index] = UNREACHABLE;
+        }
+
+        // Seed invalidated cells, and those around `pos` which may now be closer, from their
+        // valid neighbors.
+        let around = DIRECTIONS
+            .into_iter()
+            .map(|direction| pos + direction)
+            .chain([pos])
+            .filter(|&cell| in_bounds(cell))
+            .map(|cell| grid.index(cell.as_uvec2()));
+        for index in invalid.into_iter().chain(around) {
+            let cell = grid.pos(index);
+            if !grid.is_passable(cell.as_ivec2()) {
+                continue;
+            }
+            let best = if self.goals.contains(&cell) {
+                0
+            } else {
+                DIRECTIONS
+                    .into_iter()
+                    .filter(|&direction| grid.can_move(cell.as_ivec2(), direction))
+                    .filter_map(|direction| {
+                        let neighbor = (cell.as_ivec2() + direction).as_uvec2();
+                        Some(self.distance(neighbor)? + move_cost(direction))
+                    })
+                    .min()
+                    .unwrap_or(UNREACHABLE)
+            };
+            if best < self.distances[index] {
+                self.distances[index] = best;
+                self.open.push(Reverse((best, index as u32)));
+            }
+        }
+    }
+}
@@ -0,0 +1,13 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+mod flow_field;
+mod navmesh_2d;
+mod path_grid;
+mod path_search;
+mod tests;
+
+pub use self::flow_field::FlowField;
+pub use self::navmesh_2d::{NavPolygon2d, NavPortal2d, Navmesh2d};
+pub use self::path_grid::{octile_distance, PathGrid, DIAGONAL_COST, STRAIGHT_COST};
+pub use self::path_search::{PathAlgorithm, PathSearch, PathStatus};
@@ -0,0 +1,345 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use crate::{Aabb2d, Mask2d};
+use glam::{uvec2, vec2, UVec2, Vec2};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::ops::RangeInclusive;
+
+const NONE: u32 = u32::MAX;
+
+/// An edge shared by two [`NavPolygon2d`]s.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct NavPortal2d {
+    /// Index of the polygon on the other side.
+    pub neighbor: usize,
+    /// End on the left, facing the neighbor.
+    pub left: Vec2,
+    /// End on the right, facing the neighbor.
+    pub right: Vec2,
+}
+
+impl NavPortal2d {
+    pub fn center(&self) -> Vec2 {
+        (self.left + self.right) * 0.5
+    }
+}
+
+/// A convex walkable area of a [`Navmesh2d`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct NavPolygon2d {
+    pub aabb: Aabb2d,
+    pub portals: Vec<NavPortal2d>,
+}
+
+impl NavPolygon2d {
+    /// Returns the vertices in counter-clockwise order.
+    pub fn vertices(&self) -> [Vec2; 4] {
+        let Aabb2d { min, max } = self.aabb;
+        [min, vec2(max.x, min.y), max, vec2(min.x, max.y)]
+    }
+}
+
+/// A navigation mesh of convex walkable polygons, connected by portals, for finding smooth paths
+/// in continuous space. Cell `(x, y)` of the source [`Mask2d`] covers `[x, x + 1) * scale` by
+/// `[y, y + 1) * scale`.
+#[derive(Clone, Debug)]
+pub struct Navmesh2d {
+    scale: f32,
+    dims: UVec2,
+    polygons: Vec<NavPolygon2d>,
+    /// Index of the polygon containing each cell, or [`NONE`] if it isn't walkable.
+    cells: Box<[u32]>,
+}
+
+impl Navmesh2d {
+    /// Builds a navmesh for agents of `agent_radius` (in cells) avoiding the cells contained in
+    /// `obstacles` and the edges of the map.
+    ///
+    /// **Panics**
+    ///
+    /// If `scale` isn't positive.
+    pub fn new(obstacles: &Mask2d, agent_radius: u32, scale: f32) -> Self {
+        assert!(scale > 0.0);
+        let dims = obstacles.dims();
+        let cells = || (0..dims.y).flat_map(move |y| (0..dims.x).map(move |x| uvec2(x, y)));
+
+        // Erode the walkable area by expanding the obstacles.
+        let expanded = Mask2d::new_expanded(
+            cells().filter(|&cell| obstacles.get(cell)),
+            dims,
+            agent_radius * 2 + 1,
+        );
+        let margin = UVec2::splat(agent_radius);
+        let mut walkable = Mask2d::new(
+            cells().filter(|&cell| {
+                !expanded.get(cell) && cell.cmpge(margin).all() && (cell + margin).cmplt(dims).all()
+            }),
+            dims,
+        );
+
+        let mut me = Self {
+            scale,
+            dims,
+            polygons: Vec::new(),
+            cells: vec![NONE; (dims.x * dims.y) as usize].into(),
+        };
+        let rects = walkable.take_rects();
+        for (index, &(start, end)) in rects.iter().enumerate() {
+            for y in start.y..=end.y {
+                for x in start.x..=end.x {
+                    me.cells[(y * dims.x + x) as usize] = index as u32;
+                }
+            }
+        }
+        me.polygons = rects
+            .iter()
+            .map(|&(start, end)| NavPolygon2d {
+                aabb: Aabb2d::new(
+                    start.as_vec2() * scale,
+                    (end + UVec2::ONE).as_vec2() * scale,
+                ),
+                portals: me.portals(start, end),
+            })
+            .collect();
+        me
+    }
+
+    /// Finds the portals of the rect from `start` to `end` (inclusive cells) by scanning the
+    /// cells just outside each side.
+    fn portals(&self, start: UVec2, end: UVec2) -> Vec<NavPortal2d> {
+        let mut portals = Vec::new();
+        // `edge` is the coordinate of the side, and `positive` is whether it faces +x or +y.
+        let mut scan = |outside: Option<u32>,
+                        edge: u32,
+                        along: RangeInclusive<u32>,
+                        horizontal: bool,
+                        positive: bool| {
+            let Some(outside) = outside else {
+                return;
+            };
+            let mut run: Option<(u32, u32, u32)> = None;
+            for i in along.chain([NONE]) {
+                let polygon = if i == NONE {
+                    NONE
+                } else if horizontal {
+                    self.cell(uvec2(i, outside))
+                } else {
+                    self.cell(uvec2(outside, i))
+                };
+                if let Some((neighbor, run_start, run_end)) = run {
+                    if neighbor == polygon {
+                        run = Some((neighbor, run_start, i));
+                        continue;
+                    }
+                    let (a, b) = if horizontal {
+                        (uvec2(run_start, edge), uvec2(run_end + 1, edge))
+                    } else {
+                        (uvec2(edge, run_start), uvec2(edge, run_end + 1))
+                    };
+                    let (a, b) = (a.as_vec2() * self.scale, b.as_vec2() * self.scale);
+                    // Facing +x, left is +y. Facing +y, left is -x.
+                    let (left, right) = if horizontal == positive {
+                        (a, b)
+                    } else {
+                        (b, a)
+                    };
+                    portals.push(NavPortal2d {
+                        neighbor: neighbor as usize,
+                        left,
+                        right,
+                    });
+                    run = None;
+                }
+                if polygon != NONE {
+                    run = Some((polygon, i, i));
+                }
+            }
+        };
+        let right = Some(end.x + 1).filter(|&x| x < self.dims.x);
+        let top = Some(end.y + 1).filter(|&y| y < self.dims.y);
+        scan(
+            start.x.checked_sub(1),
+            start.x,
+            start.y..=end.y,
+            false,
+            false,
+        );
+        scan(right, end.x + 1, start.y..=end.y, false, true);
+        scan(
+            start.y.checked_sub(1),
+            start.y,
+            start.x..=end.x,
+            true,
+            false,
+        );
+        scan(top, end.y + 1, start.x..=end.x, true, true);
+        portals
+    }
+
+    fn cell(&self, cell: UVec2) -> u32 {
+        self.cells[(cell.y * self.dims.x + cell.x) as usize]
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn polygons(&self) -> &[NavPolygon2d] {
+        &self.polygons
+    }
+
+    /// Returns the index of the polygon containing `point`, or `None` if it isn't walkable.
+    pub fn locate(&self, point: Vec2) -> Option<usize> {
+        let cell = (point / self.scale).floor();
+        if cell.cmplt(Vec2::ZERO).any() || cell.cmpge(self.dims.as_vec2()).any() {
+            return None;
+        }
+        let polygon = self.cell(cell.as_uvec2());
+        (polygon != NONE).then_some(polygon as usize)
+    }
+
+    /// Returns the polygons to pass through from `start` to `goal`, inclusive, or `None` if
+    /// either isn't walkable or they aren't connected.
+    pub fn corridor(&self, start: Vec2, goal: Vec2) -> Option<Vec<usize>> {
+        let start_polygon = self.locate(start)?;
+        let goal_polygon = self.locate(goal)?;
+
+        // A* over portals, crossing each at the point nearest to where the previous one was
+        // crossed. Node 0 is the start, then the portals of each polygon, then the goal.
+        let mut offsets = Vec::with_capacity(self.polygons.len());
+        let mut goal_node = 1;
+        for polygon in &self.polygons {
+            offsets.push(goal_node);
+            goal_node += polygon.portals.len();
+        }
+        // (cost, parent node, polygon entered, crossing point) of each reached node.
+        let mut reached = vec![None::<(f32, usize, usize, Vec2)>; goal_node + 1];
+        let mut open = BinaryHeap::new();
+        // Non-negative floats are ordered like their bits.
+        let estimate = |cost: f32, point: Vec2| (cost + point.distance(goal)).to_bits();
+        reached[0] = Some((0.0, 0, start_polygon, start));
+        open.push(Reverse((estimate(0.0, start), 0)));
+        while let Some(Reverse((node_estimate, node))) = open.pop() {
+            let (cost, parent, polygon, point) = reached[node].unwrap();
+            if node_estimate != estimate(cost, point) {
+                // Stale.
+                continue;
+            }
+            if node == goal_node {
+                let mut corridor = Vec::new();
+                let mut node = parent;
+                while node != 0 {
+                    let (_, parent, polygon, _) = reached[node].unwrap();
+                    corridor.push(polygon);
+                    node = parent;
+                }
+                corridor.push(start_polygon);
+                corridor.reverse();
+                return Some(corridor);
+            }
+
+            let goal_portal = (polygon == goal_polygon).then_some((goal_node, goal, goal_polygon));
+            let portals = self.polygons[polygon]
+                .portals
+                .iter()
+                .enumerate()
+                .map(|(i, portal)| {
+                    let entry = closest_point_on_segment(portal.left, portal.right, point);
+                    (offsets[polygon] + i, entry, portal.neighbor)
+                });
+            for (next, entry, next_polygon) in goal_portal.into_iter().chain(portals) {
+                let next_cost = cost + point.distance(entry);
+                if matches!(reached[next], Some((old, ..)) if old <= next_cost) {
+                    continue;
+                }
+                reached[next] = Some((next_cost, node, next_polygon, entry));
+                open.push(Reverse((estimate(next_cost, entry), next)));
+            }
+        }
+        None
+    }
+
+    /// Returns a smooth path from `start` to `goal`, inclusive, that only turns at corners, or
+    /// `None` if either isn't walkable or they aren't connected.
+    pub fn find_path(&self, start: Vec2, goal: Vec2) -> Option<Vec<Vec2>> {
+        let corridor = self.corridor(start, goal)?;
+        let portals = corridor.array_windows().map(|&[from, to]| {
+            let portal = self.polygons[from]
+                .portals
+                .iter()
+                .find(|portal| portal.neighbor == to)
+                .unwrap();
+            (portal.left, portal.right)
+        });
+        Some(funnel(
+            [(start, start)]
+                .into_iter()
+                .chain(portals)
+                .chain([(goal, goal)])
+                .collect(),
+        ))
+    }
+}
+
+fn closest_point_on_segment(a: Vec2, b: Vec2, point: Vec2) -> Vec2 {
+    let ab = b - a;
+    a + ab * ((point - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)
+}
+
+/// Positive if `c` is to the left of the ray from `a` through `b`.
+fn cross(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+    (b - a).perp_dot(c - a)
+}
+
+/// Pulls a path taut through (left, right) `portals`, the first of which is the start and the
+/// last of which is the goal. See: http://digestingduck.blogspot.com/2010/03/simple-stupid-funnel-algorithm.html
+fn funnel(portals: Vec<(Vec2, Vec2)>) -> Vec<Vec2> {
+    let (mut apex, _) = portals[0];
+    let (mut left, mut right) = portals[0];
+    let (mut left_index, mut right_index) = (0, 0);
+    let mut path = vec![apex];
+    let mut i = 1;
+    while i < portals.len() {
+        let (portal_left, portal_right) = portals[i];
+        i += 1;
+
+        // Try to narrow the right side of the funnel.
+        if cross(apex, right, portal_right) >= 0.0 {
+            if apex == right || cross(apex, left, portal_right) < 0.0 {
+                right = portal_right;
+                right_index = i - 1;
+            } else {
+                // Crossed over the left side, so it's a corner.
+                apex = left;
+                path.push(apex);
+                right = apex;
+                right_index = left_index;
+                i = left_index + 1;
+                continue;
+            }
+        }
+
+        // Try to narrow the left side of the funnel.
+        if cross(apex, left, portal_left) <= 0.0 {
+            if apex == left || cross(apex, right, portal_left) > 0.0 {
+                left = portal_left;
+                left_index = i - 1;
+            } else {
+                // Crossed over the right side, so it's a corner.
+                apex = right;
+                path.push(apex);
+                left = apex;
+                left_index = right_index;
+                i = right_index + 1;
+                continue;
+            }
+        }
+    }
+    let (goal, _) = portals[portals.len() - 1];
+    if path.last() != Some(&goal) {
+        path.push(goal);
+    }
+    path
+}
@@ -0,0 +1,139 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use crate::{Mask2d, SectorArray2d};
+use glam::{ivec2, uvec2, IVec2, UVec2};
+
+/// Cost of moving to an orthogonal neighbor.
+pub const STRAIGHT_COST: u32 = 100;
+/// Cost of moving to a diagonal neighbor, approximately `STRAIGHT_COST * 2.sqrt()`.
+pub const DIAGONAL_COST: u32 = 141;
+
+/// The 8 directions of movement, in the order that ties are broken.
+pub(crate) const DIRECTIONS: [IVec2; 8] = [
+    ivec2(1, 0),
+    ivec2(0, 1),
+    ivec2(-1, 0),
+    ivec2(0, -1),
+    ivec2(1, 1),
+    ivec2(-1, 1),
+    ivec2(-1, -1),
+    ivec2(1, -1),
+];
+
+/// Returns the cost of moving one step in `direction`.
+pub(crate) fn move_cost(direction: IVec2) -> u32 {
+    if direction.x != 0 && direction.y != 0 {
+        DIAGONAL_COST
+    } else {
+        STRAIGHT_COST
+    }
+}
+
+/// Returns the cost of the shortest path from `a` to `b`, ignoring obstacles.
+pub fn octile_distance(a: UVec2, b: UVec2) -> u32 {
+    let delta = (a.as_ivec2() - b.as_ivec2()).abs().as_uvec2();
+    let (min, max) = (delta.min_element(), delta.max_element());
+    min * DIAGONAL_COST + (max - min) * STRAIGHT_COST
+}
+
+/// A grid of passable and impassable cells, for pathfinding. Movement is 8-directional, but
+/// diagonal moves can't cut the corners of impassable cells.
+#[derive(Clone, Debug)]
+pub struct PathGrid {
+    dims: UVec2,
+    /// Length must be `dims.x * dims.y`.
+    passable: Box<[bool]>,
+    /// Incremented whenever passability changes.
+    revision: u64,
+}
+
+impl PathGrid {
+    /// Creates a grid where every cell is `passable`.
+    pub fn new(dims: UVec2, passable: bool) -> Self {
+        Self::from_fn(dims, |_| passable)
+    }
+
+    /// Creates a grid where each cell is passable if `f` returns true.
+    pub fn from_fn(dims: UVec2, f: impl FnMut(UVec2) -> bool) -> Self {
+        Self {
+            dims,
+            passable: (0..dims.y)
+                .flat_map(|y| (0..dims.x).map(move |x| uvec2(x, y)))
+                .map(f)
+                .collect(),
+            revision: 0,
+        }
+    }
+
+    /// Creates a grid where cells contained in `mask` are impassable.
+    pub fn from_mask(mask: &Mask2d) -> Self {
+        Self::from_fn(mask.dims(), |pos| !mask.get(pos))
+    }
+
+    /// Creates a grid with a cell per sector, passable if `f` returns true.
+    pub fn from_sectors<T, const WIDTH: usize, const HEIGHT: usize, const SCALE: u16>(
+        sectors: &SectorArray2d<T, WIDTH, HEIGHT, SCALE>,
+        mut f: impl FnMut(&T) -> bool,
+    ) -> Self {
+        let mut me = Self::new(uvec2(WIDTH as u32, HEIGHT as u32), false);
+        for (sector_id, sector) in sectors.iter() {
+            let index = me.index(uvec2(sector_id.x as u32, sector_id.y as u32));
+            me.passable[index] = f(sector);
+        }
+        me
+    }
+
+    pub fn dims(&self) -> UVec2 {
+        self.dims
+    }
+
+    /// Incremented whenever passability changes, so stale searches can be detected.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Returns true if `pos` is in bounds and passable.
+    pub fn is_passable(&self, pos: IVec2) -> bool {
+        pos.cmpge(IVec2::ZERO).all()
+            && pos.as_uvec2().cmplt(self.dims).all()
+            && self.passable[self.index(pos.as_uvec2())]
+    }
+
+    /// Sets whether `pos` is passable, returning true if it changed. Any [`FlowField`]s over the
+    /// grid should be [`update`][`crate::FlowField::update`]d afterwards.
+    ///
+    /// [`FlowField`]: crate::FlowField
+    ///
+    /// **Panics**
+    ///
+    /// If `pos` is out of bounds.
+    pub fn set_passable(&mut self, pos: UVec2, passable: bool) -> bool {
+        assert!(pos.cmplt(self.dims).all(), "out of bounds");
+        let index = self.index(pos);
+        let changed = self.passable[index] != passable;
+        if changed {
+            self.passable[index] = passable;
+            self.revision += 1;
+        }
+        changed
+    }
+
+    /// Returns true if one step in `direction` from `pos` is allowed, i.e. the destination is
+    /// passable and, if diagonal, neither corner is impassable.
+    pub fn can_move(&self, pos: IVec2, direction: IVec2) -> bool {
+        self.is_passable(pos + direction)
+            && (direction.x == 0
+                || direction.y == 0
+                || (self.is_passable(pos + ivec2(direction.x, 0))
+                    && self.is_passable(pos + ivec2(0, direction.y))))
+    }
+
+    pub(crate) fn index(&self, pos: UVec2) -> usize {
+        (pos.y * self.dims.x + pos.x) as usize
+    }
+
+    pub(crate) fn pos(&self, index: usize) -> UVec2 {
+        uvec2(index as u32 % self.dims.x, index as u32 / self.dims.x)
+    }
+}
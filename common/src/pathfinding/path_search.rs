@@ -0,0 +1,236 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::path_grid::{move_cost, octile_distance, PathGrid, DIRECTIONS};
+use arrayvec::ArrayVec;
+use fxhash::FxHashMap;
+use glam::{ivec2, IVec2, UVec2};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum PathAlgorithm {
+    /// Expands every neighbor of every cell.
+    #[default]
+    AStar,
+    /// Jump point search, which skips over open areas, expanding far fewer cells than A*.
+    JumpPoint,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PathStatus {
+    /// The search isn't done, so [`PathSearch::step`] should be called again.
+    Pending,
+    /// The cells of a shortest path from start to goal, inclusive.
+    Found(Vec<UVec2>),
+    /// There is no path from start to goal.
+    Unreachable,
+}
+
+/// A shortest path search on a [`PathGrid`] that can be spread over multiple ticks.
+#[derive(Clone, Debug)]
+pub struct PathSearch {
+    algorithm: PathAlgorithm,
+    start: UVec2,
+    goal: UVec2,
+    /// [`PathGrid::revision`] that the search is for.
+    revision: u64,
+    /// (cost from start + heuristic, heuristic, index) of cells to expand.
+    open: BinaryHeap<Reverse<(u32, u32, u32)>>,
+    /// Cost from start and parent index of each reached cell.
+    reached: FxHashMap<u32, (u32, u32)>,
+    status: PathStatus,
+}
+
+impl PathSearch {
+    /// Starts a search from `start` to `goal`, without doing any work yet.
+    pub fn new(grid: &PathGrid, start: UVec2, goal: UVec2, algorithm: PathAlgorithm) -> Self {
+        let mut me = Self {
+            algorithm,
+            start,
+            goal,
+            revision: grid.revision(),
+            open: BinaryHeap::new(),
+            reached: FxHashMap::default(),
+            status: PathStatus::Pending,
+        };
+        me.restart(grid);
+        me
+    }
+
+    pub fn start(&self) -> UVec2 {
+        self.start
+    }
+
+    pub fn goal(&self) -> UVec2 {
+        self.goal
+    }
+
+    pub fn status(&self) -> &PathStatus {
+        &self.status
+    }
+
+    fn restart(&mut self, grid: &PathGrid) {
+        self.revision = grid.revision();
+        self.open.clear();
+        self.reached.clear();
+        if !grid.is_passable(self.start.as_ivec2()) || !grid.is_passable(self.goal.as_ivec2()) {
+            self.status = PathStatus::Unreachable;
+            return;
+        }
+        self.status = PathStatus::Pending;
+        let start = grid.index(self.start) as u32;
+        let h = octile_distance(self.start, self.goal);
+        self.reached.insert(start, (0, start));
+        self.open.push(Reverse((h, h, start)));
+    }
+
+    /// Continues the search, examining roughly `budget` cells before returning. If the grid
+    /// changed since the search started, it starts over.
+    pub fn step(&mut self, grid: &PathGrid, budget: usize) -> &PathStatus {
+        if grid.revision() != self.revision {
+            self.restart(grid);
+        }
+        if self.status != PathStatus::Pending {
+            return &self.status;
+        }
+
+        let goal = grid.index(self.goal) as u32;
+        let mut work = 0;
+        while work < budget {
+            let Some(Reverse((f, h, index))) = self.open.pop() else {
+                self.status = PathStatus::Unreachable;
+                break;
+            };
+            let g = f - h;
+            if self.reached[&index].0 != g {
+                // Stale.
+                continue;
+            }
+            if index == goal {
+                self.status = PathStatus::Found(self.path(grid));
+                break;
+            }
+            self.expand(grid, index, g, &mut work);
+        }
+        &self.status
+    }
+
+    /// Completes the search, regardless of how long it takes.
+    pub fn finish(&mut self, grid: &PathGrid) -> &PathStatus {
+        self.step(grid, usize::MAX)
+    }
+
+    fn expand(&mut self, grid: &PathGrid, index: u32, g: u32, work: &mut usize) {
+        let pos = grid.pos(index as usize).as_ivec2();
+        match self.algorithm {
+            PathAlgorithm::AStar => {
+                for direction in DIRECTIONS {
+                    *work += 1;
+                    if grid.can_move(pos, direction) {
+                        self.reach(grid, pos + direction, g + move_cost(direction), index);
+                    }
+                }
+            }
+            PathAlgorithm::JumpPoint => {
+                let parent = grid.pos(self.reached[&index].1 as usize).as_ivec2();
+                let goal = self.goal.as_ivec2();
+                for direction in jump_directions(grid, pos, (pos - parent).signum()) {
+                    if let Some(jump_point) = jump(grid, pos, direction, goal, work) {
+                        let cost = octile_distance(pos.as_uvec2(), jump_point.as_uvec2());
+                        self.reach(grid, jump_point, g + cost, index);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Records reaching `pos` with cost `g` from `parent`, if it's the cheapest way so far.
+    fn reach(&mut self, grid: &PathGrid, pos: IVec2, g: u32, parent: u32) {
+        let index = grid.index(pos.as_uvec2()) as u32;
+        if matches!(self.reached.get(&index), Some(&(old, _)) if old <= g) {
+            return;
+        }
+        self.reached.insert(index, (g, parent));
+        let h = octile_distance(pos.as_uvec2(), self.goal);
+        self.open.push(Reverse((g + h, h, index)));
+    }
+
+    /// Follows parents back from the goal, filling in cells between jump points.
+    fn path(&self, grid: &PathGrid) -> Vec<UVec2> {
+        let mut path = vec![self.goal];
+        let mut index = grid.index(self.goal) as u32;
+        while index != grid.index(self.start) as u32 {
+            let parent = self.reached[&index].1;
+            let parent_pos = grid.pos(parent as usize).as_ivec2();
+            let mut pos = grid.pos(index as usize).as_ivec2();
+            // Jump points are always in a straight or diagonal line.
+            let direction = (parent_pos - pos).signum();
+            while pos != parent_pos {
+                pos += direction;
+                path.push(pos.as_uvec2());
+            }
+            index = parent;
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Returns the directions worth searching from `pos`, having arrived moving in `direction` (zero
+/// at the start). Diagonal moves never cut corners, so only orthogonal moves have forced
+/// neighbors.
+fn jump_directions(
+    grid: &PathGrid,
+    pos: IVec2,
+    direction: IVec2,
+) -> impl Iterator<Item = IVec2> + '_ {
+    let mut directions = ArrayVec::<IVec2, 8>::new();
+    if direction == IVec2::ZERO {
+        directions.extend(DIRECTIONS);
+    } else if direction.x != 0 && direction.y != 0 {
+        directions.extend([ivec2(direction.x, 0), ivec2(0, direction.y), direction]);
+    } else {
+        let side = direction.perp();
+        directions.extend([direction, direction + side, direction - side, side, -side]);
+    }
+    directions
+        .into_iter()
+        .filter(move |&direction| grid.can_move(pos, direction))
+}
+
+/// Moves from `pos` in `direction` until reaching a jump point, which is returned, or an
+/// obstacle.
+fn jump(
+    grid: &PathGrid,
+    mut pos: IVec2,
+    direction: IVec2,
+    goal: IVec2,
+    work: &mut usize,
+) -> Option<IVec2> {
+    loop {
+        *work += 1;
+        if !grid.can_move(pos, direction) {
+            return None;
+        }
+        pos += direction;
+        if pos == goal {
+            return Some(pos);
+        }
+        if direction.x != 0 && direction.y != 0 {
+            if jump(grid, pos, ivec2(direction.x, 0), goal, work).is_some()
+                || jump(grid, pos, ivec2(0, direction.y), goal, work).is_some()
+            {
+                return Some(pos);
+            }
+        } else {
+            // A side opened up that couldn't be reached diagonally from the previous cell.
+            let side = direction.perp();
+            if (grid.is_passable(pos + side) && !grid.is_passable(pos - direction + side))
+                || (grid.is_passable(pos - side) && !grid.is_passable(pos - direction - side))
+            {
+                return Some(pos);
+            }
+        }
+    }
+}
@@ -0,0 +1,364 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+#[cfg(test)]
+mod pathfinding_tests {
+    use crate::{
+        octile_distance, FlowField, Mask2d, PathAlgorithm, PathGrid, PathSearch, PathStatus,
+        SectorArray2d,
+    };
+    use glam::{uvec2, UVec2};
+    use rand::prelude::*;
+    use rand_chacha::ChaCha20Rng;
+    use test::bench::{black_box, Bencher};
+
+    fn random_grid(rng: &mut impl Rng, dims: UVec2, blocked: f32) -> PathGrid {
+        PathGrid::from_fn(dims, |_| !rng.gen_bool(blocked as f64))
+    }
+
+    fn random_pos(rng: &mut impl Rng, dims: UVec2) -> UVec2 {
+        uvec2(rng.gen_range(0..dims.x), rng.gen_range(0..dims.y))
+    }
+
+    /// Asserts `path` is a valid path, and returns its cost.
+    fn path_cost(grid: &PathGrid, path: &[UVec2]) -> u32 {
+        path.windows(2)
+            .map(|pair| {
+                let direction = pair[1].as_ivec2() - pair[0].as_ivec2();
+                assert!(
+                    direction.abs().max_element() == 1,
+                    "{:?} {:?}",
+                    pair[0],
+                    pair[1]
+                );
+                assert!(grid.can_move(pair[0].as_ivec2(), direction));
+                octile_distance(pair[0], pair[1])
+            })
+            .sum()
+    }
+
+    #[test]
+    fn shortest_paths() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let mut found = 0;
+        for i in 0..300 {
+            let dims = uvec2(rng.gen_range(1..40), rng.gen_range(1..40));
+            let grid = random_grid(&mut rng, dims, [0.0, 0.2, 0.4][i % 3]);
+            let start = random_pos(&mut rng, dims);
+            let goal = random_pos(&mut rng, dims);
+
+            let mut field = FlowField::new(&grid, [goal]);
+            field.finish(&grid);
+            let expected = grid
+                .is_passable(start.as_ivec2())
+                .then(|| field.distance(start))
+                .flatten();
+
+            for algorithm in [PathAlgorithm::AStar, PathAlgorithm::JumpPoint] {
+                let mut search = PathSearch::new(&grid, start, goal, algorithm);
+                match search.finish(&grid).clone() {
+                    PathStatus::Found(path) => {
+                        assert_eq!(path.first(), Some(&start));
+                        assert_eq!(path.last(), Some(&goal));
+                        assert_eq!(Some(path_cost(&grid, &path)), expected, "{algorithm:?}");
+
+                        // Time slicing doesn't change the result.
+                        let mut sliced = PathSearch::new(&grid, start, goal, algorithm);
+                        while sliced.step(&grid, 10) == &PathStatus::Pending {}
+                        assert_eq!(sliced.status(), &PathStatus::Found(path));
+                        found += 1;
+                    }
+                    PathStatus::Unreachable => assert_eq!(expected, None, "{algorithm:?}"),
+                    PathStatus::Pending => unreachable!(),
+                }
+            }
+        }
+        assert!(found > 100, "{found}");
+    }
+
+    #[test]
+    fn restart_on_change() {
+        let mut grid = PathGrid::new(uvec2(10, 3), true);
+        let mut search = PathSearch::new(&grid, uvec2(0, 1), uvec2(9, 1), PathAlgorithm::AStar);
+        assert_eq!(search.step(&grid, 1), &PathStatus::Pending);
+        for y in 0..3 {
+            grid.set_passable(uvec2(5, y), false);
+        }
+        assert_eq!(search.finish(&grid), &PathStatus::Unreachable);
+        assert!(grid.set_passable(uvec2(5, 0), true));
+        assert!(!grid.set_passable(uvec2(5, 0), true));
+        let PathStatus::Found(path) = search.finish(&grid) else {
+            panic!();
+        };
+        assert!(path.contains(&uvec2(5, 0)));
+    }
+
+    #[test]
+    fn flow_field_update() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        for _ in 0..20 {
+            let dims = uvec2(rng.gen_range(1..30), rng.gen_range(1..30));
+            let mut grid = random_grid(&mut rng, dims, 0.25);
+            let goals: Vec<UVec2> = (0..rng.gen_range(1..4))
+                .map(|_| random_pos(&mut rng, dims))
+                .collect();
+            let mut field = FlowField::new(&grid, goals.iter().copied());
+            while !field.step(&grid, 50) {}
+
+            for _ in 0..50 {
+                let pos = random_pos(&mut rng, dims);
+                grid.set_passable(pos, !grid.is_passable(pos.as_ivec2()));
+                field.update(&grid, pos);
+                field.finish(&grid);
+
+                let mut expected = FlowField::new(&grid, goals.iter().copied());
+                expected.finish(&grid);
+                for y in 0..dims.y {
+                    for x in 0..dims.x {
+                        let pos = uvec2(x, y);
+                        assert_eq!(field.distance(pos), expected.distance(pos), "{pos}");
+                    }
+                }
+            }
+
+            // Following directions reaches a goal at the expected cost.
+            for _ in 0..10 {
+                let mut pos = random_pos(&mut rng, dims);
+                let Some(distance) = field.distance(pos) else {
+                    continue;
+                };
+                let mut path = vec![pos];
+                while let Some(direction) = field.direction(&grid, pos) {
+                    pos = (pos.as_ivec2() + direction).as_uvec2();
+                    path.push(pos);
+                }
+                assert!(goals.contains(&pos));
+                assert_eq!(path_cost(&grid, &path), distance);
+            }
+
+            for pos in [uvec2(dims.x, 0), uvec2(0, dims.y), UVec2::splat(u32::MAX)] {
+                assert_eq!(field.distance(pos), None);
+                assert_eq!(field.direction(&grid, pos), None);
+            }
+        }
+    }
+
+    #[test]
+    fn bridges() {
+        let mask = Mask2d::new([uvec2(1, 0), uvec2(1, 1)], uvec2(3, 3));
+        let grid = PathGrid::from_mask(&mask);
+        assert_eq!(grid.dims(), uvec2(3, 3));
+        assert!(!grid.is_passable(uvec2(1, 1).as_ivec2()));
+        assert!(grid.is_passable(uvec2(1, 2).as_ivec2()));
+        let mut search = PathSearch::new(&grid, uvec2(0, 0), uvec2(2, 0), PathAlgorithm::JumpPoint);
+        assert_eq!(
+            search.finish(&grid),
+            &PathStatus::Found(vec![
+                uvec2(0, 0),
+                uvec2(0, 1),
+                uvec2(0, 2),
+                uvec2(1, 2),
+                uvec2(2, 2),
+                uvec2(2, 1),
+                uvec2(2, 0)
+            ])
+        );
+
+        let sectors = SectorArray2d::<bool, 4, 2, 100>::from_fn(|id| id.x != 2 || id.y == 1);
+        let grid = PathGrid::from_sectors(&sectors, |&passable| passable);
+        assert_eq!(grid.dims(), uvec2(4, 2));
+        assert!(!grid.is_passable(uvec2(2, 0).as_ivec2()));
+        assert!(grid.is_passable(uvec2(2, 1).as_ivec2()));
+    }
+
+    fn bench_path_search(b: &mut Bencher, algorithm: PathAlgorithm) {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let dims = uvec2(256, 256);
+        let grid = random_grid(&mut rng, dims, 0.2);
+        let searches: Vec<_> = (0..20)
+            .map(|_| (random_pos(&mut rng, dims), random_pos(&mut rng, dims)))
+            .collect();
+        b.iter(|| {
+            for &(start, goal) in &searches {
+                let mut search = PathSearch::new(&grid, start, goal, algorithm);
+                black_box(search.finish(&grid));
+            }
+        });
+    }
+
+    #[bench]
+    fn bench_a_star(b: &mut Bencher) {
+        bench_path_search(b, PathAlgorithm::AStar);
+    }
+
+    #[bench]
+    fn bench_jump_point(b: &mut Bencher) {
+        bench_path_search(b, PathAlgorithm::JumpPoint);
+    }
+}
+
+#[cfg(test)]
+mod navmesh_2d_tests {
+    use crate::{Mask2d, Navmesh2d, PathAlgorithm, PathGrid, PathSearch, PathStatus};
+    use glam::{uvec2, vec2, UVec2, Vec2};
+    use rand::prelude::*;
+    use rand_chacha::ChaCha20Rng;
+
+    /// A wall at `x = 5` from `y = 0` up to, but not including, `height`.
+    fn wall(dims: UVec2, height: u32) -> Mask2d {
+        Mask2d::new((0..height).map(|y| uvec2(5, y)), dims)
+    }
+
+    #[test]
+    fn around_wall() {
+        let navmesh = Navmesh2d::new(&wall(uvec2(10, 10), 8), 0, 1.0);
+        assert_eq!(navmesh.locate(vec2(5.5, 2.0)), None);
+        assert_eq!(navmesh.locate(vec2(-0.5, 2.0)), None);
+        assert!(navmesh.locate(vec2(5.5, 8.5)).is_some());
+        assert_eq!(
+            navmesh.find_path(vec2(2.5, 2.5), vec2(7.5, 2.5)),
+            Some(vec![
+                vec2(2.5, 2.5),
+                vec2(5.0, 8.0),
+                vec2(6.0, 8.0),
+                vec2(7.5, 2.5)
+            ])
+        );
+        assert_eq!(
+            navmesh.find_path(vec2(2.5, 2.5), vec2(1.5, 7.5)),
+            Some(vec![vec2(2.5, 2.5), vec2(1.5, 7.5)])
+        );
+
+        // Scaled.
+        let navmesh = Navmesh2d::new(&wall(uvec2(10, 10), 8), 0, 2.0);
+        assert_eq!(
+            navmesh.find_path(vec2(5.0, 5.0), vec2(15.0, 5.0)),
+            Some(vec![
+                vec2(5.0, 5.0),
+                vec2(10.0, 16.0),
+                vec2(12.0, 16.0),
+                vec2(15.0, 5.0)
+            ])
+        );
+    }
+
+    #[test]
+    fn agent_radius() {
+        // The wall and edges are eroded by a cell, leaving a gap from y = 9 to 11.
+        let navmesh = Navmesh2d::new(&wall(uvec2(12, 12), 8), 1, 1.0);
+        assert_eq!(navmesh.locate(vec2(4.5, 2.5)), None);
+        assert_eq!(navmesh.locate(vec2(0.5, 2.5)), None);
+        assert_eq!(navmesh.locate(vec2(2.5, 11.5)), None);
+        assert_eq!(
+            navmesh.find_path(vec2(2.5, 2.5), vec2(9.5, 2.5)),
+            Some(vec![
+                vec2(2.5, 2.5),
+                vec2(4.0, 9.0),
+                vec2(7.0, 9.0),
+                vec2(9.5, 2.5)
+            ])
+        );
+
+        // No gap.
+        let navmesh = Navmesh2d::new(&wall(uvec2(12, 12), 10), 1, 1.0);
+        assert_eq!(navmesh.find_path(vec2(2.5, 2.5), vec2(9.5, 2.5)), None);
+    }
+
+    #[test]
+    fn random_paths() {
+        let mut rng = ChaCha20Rng::from_seed(Default::default());
+        let mut found = 0;
+        for _ in 0..200 {
+            let dims = uvec2(rng.gen_range(1..40), rng.gen_range(1..40));
+            let obstacles: Vec<UVec2> = (0..dims.x * dims.y / 8)
+                .map(|_| uvec2(rng.gen_range(0..dims.x), rng.gen_range(0..dims.y)))
+                .collect();
+            let mask = Mask2d::new(obstacles.iter().copied(), dims);
+            let navmesh = Navmesh2d::new(&mask, 0, 1.0);
+            let grid = PathGrid::from_mask(&mask);
+
+            // Portals are consistent.
+            for (i, polygon) in navmesh.polygons().iter().enumerate() {
+                for portal in &polygon.portals {
+                    let neighbor = &navmesh.polygons()[portal.neighbor];
+                    assert!(neighbor.portals.iter().any(|p| p.neighbor == i
+                        && p.left == portal.right
+                        && p.right == portal.left));
+                    assert!(polygon.aabb.contains_point(portal.left));
+                    assert!(neighbor.aabb.contains_point(portal.right));
+                    // Left is on the left, facing the neighbor.
+                    let facing = neighbor.aabb.center() - polygon.aabb.center();
+                    assert!(facing.perp_dot(portal.left - portal.right) > 0.0);
+                }
+            }
+
+            for _ in 0..10 {
+                let start = vec2(
+                    rng.gen_range(0.0..dims.x as f32),
+                    rng.gen_range(0.0..dims.y as f32),
+                );
+                let goal = vec2(
+                    rng.gen_range(0.0..dims.x as f32),
+                    rng.gen_range(0.0..dims.y as f32),
+                );
+                let path = navmesh.find_path(start, goal);
+
+                let mut search = PathSearch::new(
+                    &grid,
+                    start.floor().as_uvec2(),
+                    goal.floor().as_uvec2(),
+                    PathAlgorithm::AStar,
+                );
+                let reachable = matches!(search.finish(&grid), PathStatus::Found(_));
+                assert_eq!(path.is_some(), reachable, "{mask:?} {start} {goal}");
+
+                let Some(path) = path else {
+                    continue;
+                };
+                found += 1;
+                assert_eq!(path.first(), Some(&start));
+                assert_eq!(path.last(), Some(&goal));
+                // Every segment stays walkable, allowing for touching corners.
+                for &[a, b] in path.array_windows() {
+                    for i in 0..=100 {
+                        let point = a.lerp(b, i as f32 / 100.0);
+                        assert!(
+                            [
+                                vec2(1.0, 1.0),
+                                vec2(-1.0, 1.0),
+                                vec2(-1.0, -1.0),
+                                vec2(1.0, -1.0)
+                            ]
+                            .into_iter()
+                            .any(|offset| navmesh.locate(point + offset * 0.001).is_some()),
+                            "{mask:?} {path:?} {point}"
+                        );
+                    }
+                }
+                // The corridor search isn't exact, but it shouldn't be much longer than the path
+                // through grid cell centers.
+                if let PathStatus::Found(cells) = search.status() {
+                    let cell_path = [start]
+                        .into_iter()
+                        .chain(cells.iter().map(|cell| cell.as_vec2() + 0.5))
+                        .chain([goal]);
+                    let cell_length: f32 = cell_path
+                        .clone()
+                        .zip(cell_path.skip(1))
+                        .map(|(a, b)| a.distance(b))
+                        .sum();
+                    let length: f32 = path.array_windows().map(|&[a, b]| a.distance(b)).sum();
+                    assert!(length <= cell_length * 1.25, "{mask:?} {path:?}");
+                }
+            }
+        }
+        assert!(found > 200, "{found}");
+    }
+
+    #[test]
+    fn empty() {
+        let navmesh = Navmesh2d::new(&Mask2d::new([], uvec2(4, 4)), 2, 1.0);
+        assert!(navmesh.polygons().is_empty());
+        assert_eq!(navmesh.find_path(Vec2::ONE, Vec2::ONE), None);
+    }
+}
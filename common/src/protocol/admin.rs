@@ -6,9 +6,11 @@ use crate::{
     NonZeroUnixMillis, Owned, PlayerAlias, PlayerId, Referrer, RegionId, ServerId, ServerNumber,
     SessionToken, TeamId, UserAgentId,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
 use std::net::IpAddr;
+use std::str::FromStr;
 
 /// Admin requests are from the admin interface to the core service.
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -18,6 +20,10 @@ use std::net::IpAddr;
     rtype(result = "Result<AdminUpdate, &'static str>")
 )]
 pub enum AdminRequest {
+    /// Add a runtime IP access rule (in addition to those loaded from file).
+    AddIpAccessRule(IpAccessRule),
+    /// Stop accepting players, redirect existing ones, and shut down within n seconds.
+    Drain(u16),
     OverridePlayerAlias {
         player_id: PlayerId,
         alias: PlayerAlias,
@@ -26,10 +32,14 @@ pub enum AdminRequest {
         player_id: PlayerId,
         moderator: bool,
     },
+    /// Remove an IP access rule, whether it was added at runtime or loaded from file.
+    RemoveIpAccessRule(IpAccessRule),
     RequestDay {
         filter: Option<MetricFilter>,
     },
     RequestGames,
+    /// Request all IP access rules currently in effect.
+    RequestIpAccessRules,
     RequestPlayers,
     /// Request an n-second CPU profile.
     RequestCpuProfile(u16),
@@ -59,8 +69,13 @@ pub enum AdminRequest {
 pub enum AdminUpdate {
     ChatSent,
     DayRequested(Owned<[(NonZeroUnixMillis, EngineMetricsDataPointDto)]>),
+    /// False if already draining.
+    Draining(bool),
     GamesRequested(Box<[(GameId, f32)]>),
     HttpServerRestarting,
+    IpAccessRuleAdded(bool),
+    IpAccessRuleRemoved(bool),
+    IpAccessRulesRequested(Box<[IpAccessRule]>),
     PlayerAliasOverridden(PlayerAlias),
     PlayerModeratorOverridden(bool),
     PlayerMuted(usize),
@@ -91,6 +106,283 @@ pub struct AdminPlayerDto {
     pub rtt: Option<u16>,
 }
 
+/// A range of IP addresses in CIDR notation, e.g. `192.0.2.0/24` or `2001:db8::/32`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct IpNetwork {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    /// Returns [`None`] if `prefix_len` is too long for the address family. Host bits of `addr`
+    /// are cleared.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Option<Self> {
+        let max = match addr {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        (prefix_len <= max).then(|| Self {
+            addr: Self::mask(addr, prefix_len),
+            prefix_len,
+        })
+    }
+
+    /// The first address in the range.
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// The number of leading bits that are fixed.
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Returns true if `ip` is within the range. IPv4-mapped IPv6 addresses match IPv4 ranges.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(v6) if self.addr.is_ipv4() => {
+                v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip)
+            }
+            _ => ip,
+        };
+        ip.is_ipv4() == self.addr.is_ipv4() && Self::mask(ip, self.prefix_len) == self.addr
+    }
+
+    fn mask(addr: IpAddr, prefix_len: u8) -> IpAddr {
+        match addr {
+            IpAddr::V4(v4) => {
+                let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+                IpAddr::V4((u32::from(v4) & mask).into())
+            }
+            IpAddr::V6(v6) => {
+                let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+                IpAddr::V6((u128::from(v6) & mask).into())
+            }
+        }
+    }
+}
+
+impl From<IpAddr> for IpNetwork {
+    fn from(addr: IpAddr) -> Self {
+        Self {
+            addr,
+            prefix_len: if addr.is_ipv4() { 32 } else { 128 },
+        }
+    }
+}
+
+impl Display for IpNetwork {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = &'static str;
+
+    /// A bare address is treated as a single-address range.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((addr, prefix_len)) = s.split_once('/') {
+            let addr = IpAddr::from_str(addr).map_err(|_| "invalid address")?;
+            let prefix_len = u8::from_str(prefix_len).map_err(|_| "invalid prefix length")?;
+            Self::new(addr, prefix_len).ok_or("prefix length too long")
+        } else {
+            IpAddr::from_str(s)
+                .map(Self::from)
+                .map_err(|_| "invalid address")
+        }
+    }
+}
+
+impl Serialize for IpNetwork {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for IpNetwork {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// An ISO 3166-1 alpha-2 country code, e.g. `US`. Always uppercase.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct CountryCode([u8; 2]);
+
+impl CountryCode {
+    pub fn as_str(&self) -> &str {
+        // Always ASCII letters.
+        std::str::from_utf8(&self.0).unwrap()
+    }
+}
+
+impl Display for CountryCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for CountryCode {
+    type Err = &'static str;
+
+    /// Case insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match *s.as_bytes() {
+            [a, b] if a.is_ascii_alphabetic() && b.is_ascii_alphabetic() => {
+                Ok(Self([a.to_ascii_uppercase(), b.to_ascii_uppercase()]))
+            }
+            _ => Err("invalid country code"),
+        }
+    }
+}
+
+impl Serialize for CountryCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for CountryCode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+/// The (approximate) geolocation of an IP address, as far as [`IpAccessRule`]s are concerned.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct IpLocation {
+    pub region_id: Option<RegionId>,
+    pub country_code: Option<CountryCode>,
+}
+
+/// A single entry of an IP allow/deny list.
+///
+/// Textual form (one per line in the access list file) is `allow 10.0.0.0/8`,
+/// `deny 2001:db8::/32`, `allow region Europe`, `deny region Asia` or `deny country US`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
+pub enum IpAccessRule {
+    AllowNetwork(IpNetwork),
+    DenyNetwork(IpNetwork),
+    /// Uses the same (approximate) geolocation as region metrics.
+    AllowRegion(RegionId),
+    /// Uses the same (approximate) geolocation as region metrics.
+    DenyRegion(RegionId),
+    AllowCountry(CountryCode),
+    DenyCountry(CountryCode),
+}
+
+impl IpAccessRule {
+    pub fn is_allow(&self) -> bool {
+        matches!(
+            self,
+            Self::AllowNetwork(_) | Self::AllowRegion(_) | Self::AllowCountry(_)
+        )
+    }
+
+    pub fn region_id(&self) -> Option<RegionId> {
+        match self {
+            Self::AllowRegion(region_id) | Self::DenyRegion(region_id) => Some(*region_id),
+            _ => None,
+        }
+    }
+
+    pub fn country_code(&self) -> Option<CountryCode> {
+        match self {
+            Self::AllowCountry(country_code) | Self::DenyCountry(country_code) => {
+                Some(*country_code)
+            }
+            _ => None,
+        }
+    }
+
+    /// Higher is more specific, and takes precedence when multiple rules match. Network rules are
+    /// ranked by prefix length, and rank above country rules, which rank above region rules,
+    /// except for `/0`, which ranks below everything.
+    pub fn specificity(&self) -> u16 {
+        match self {
+            Self::AllowNetwork(network) | Self::DenyNetwork(network) => {
+                if network.prefix_len() == 0 {
+                    0
+                } else {
+                    network.prefix_len() as u16 + 2
+                }
+            }
+            Self::AllowCountry(_) | Self::DenyCountry(_) => 2,
+            Self::AllowRegion(_) | Self::DenyRegion(_) => 1,
+        }
+    }
+
+    /// `location` is only consulted for region and country rules, and may be expensive to
+    /// compute.
+    pub fn matches(&self, ip: IpAddr, location: impl FnOnce() -> IpLocation) -> bool {
+        match self {
+            Self::AllowNetwork(network) | Self::DenyNetwork(network) => network.contains(ip),
+            Self::AllowRegion(r) | Self::DenyRegion(r) => location().region_id == Some(*r),
+            Self::AllowCountry(c) | Self::DenyCountry(c) => location().country_code == Some(*c),
+        }
+    }
+}
+
+impl Display for IpAccessRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AllowNetwork(network) => write!(f, "allow {network}"),
+            Self::DenyNetwork(network) => write!(f, "deny {network}"),
+            Self::AllowRegion(region_id) => write!(f, "allow region {region_id}"),
+            Self::DenyRegion(region_id) => write!(f, "deny region {region_id}"),
+            Self::AllowCountry(country_code) => write!(f, "allow country {country_code}"),
+            Self::DenyCountry(country_code) => write!(f, "deny country {country_code}"),
+        }
+    }
+}
+
+impl FromStr for IpAccessRule {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let allow = match words.next() {
+            Some("allow") => true,
+            Some("deny") => false,
+            _ => return Err("expected allow or deny"),
+        };
+        let rule = match (words.next(), words.next()) {
+            (Some("region"), Some(region_id)) => {
+                let region_id = RegionId::from_str(region_id).map_err(|_| "invalid region")?;
+                if allow {
+                    Self::AllowRegion(region_id)
+                } else {
+                    Self::DenyRegion(region_id)
+                }
+            }
+            (Some("country"), Some(country_code)) => {
+                let country_code = CountryCode::from_str(country_code)?;
+                if allow {
+                    Self::AllowCountry(country_code)
+                } else {
+                    Self::DenyCountry(country_code)
+                }
+            }
+            (Some(network), None) => {
+                let network = IpNetwork::from_str(network)?;
+                if allow {
+                    Self::AllowNetwork(network)
+                } else {
+                    Self::DenyNetwork(network)
+                }
+            }
+            _ => return Err("expected network, region or country"),
+        };
+        if words.next().is_some() {
+            return Err("trailing input");
+        }
+        Ok(rule)
+    }
+}
+
 /// Deprecated. Like [`InstancePickerDto`] but more details.
 #[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 pub struct AdminServerDto {
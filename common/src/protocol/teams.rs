@@ -2,13 +2,13 @@
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
 use crate::bitcode::{self, *};
-use crate::{Manifestation, Member, PlayerId, TeamId, TeamName};
+use crate::{Manifestation, Member, PlayerId, TeamId, TeamName, TeamRole};
 
 /// For leaderboard fairness, surplus progress (e.g. points beyond current level)
 /// shouldn't transfer between teams (for now, team names count as separate teams).
 #[derive(Clone, Debug, PartialEq, Hash, Encode, Decode)]
 pub enum TeamRequest {
-    /// Names the current (anonymous) team.
+    /// Names the current (anonymous) team, or renames the current named team if permitted.
     Name(TeamName),
     /// Requests to join the designated team.
     Join(TeamId),
@@ -21,6 +21,12 @@ pub enum TeamRequest {
     /// Removes the requesting player from their current named
     /// team and places them in a new anonymous team.
     Leave,
+    /// Makes the designated member an officer (leader only).
+    Promote(PlayerId),
+    /// Makes the designated officer a regular member (leader only).
+    Demote(PlayerId),
+    /// Makes the designated member the leader, and the current leader an officer (leader only).
+    TransferLeadership(PlayerId),
 }
 
 #[derive(Clone, Debug, PartialEq, Hash, Encode, Decode)]
@@ -35,6 +41,11 @@ pub enum TeamUpdate<M: Manifestation> {
     /// the corresponding joiner.
     AddMember(Member<M>),
     ReplaceMember(Member<M>),
-    /// Removes a member from the designated team.
+    /// Removes a member from the designated team. If it's the leader, [`Self::SetLeader`]
+    /// must be sent first (unless they are the last member).
     RemoveMember(PlayerId),
+    /// Changes the role of a non-leader member to [`TeamRole::Officer`] or [`TeamRole::Member`].
+    SetRole(PlayerId, TeamRole),
+    /// Moves a member to the front, as the new leader. The old leader becomes an officer.
+    SetLeader(PlayerId),
 }
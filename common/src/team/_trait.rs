@@ -4,7 +4,7 @@
 use rand::seq::IteratorRandom;
 use rand::Rng;
 
-use super::{Manifestation, Member, MemberId, PlayerStatus, Team};
+use super::{Manifestation, Member, MemberId, PlayerStatus, Team, TeamRole};
 use crate::{
     JoinUpdate, JoinedStatus, PlayerAlias, PlayerId, TeamId, TeamName, TeamRequest, TeamUpdate,
 };
@@ -85,6 +85,18 @@ pub trait PlayerTeamModel {
             })
     }
 
+    /// Removes a member from a team that has other members, first passing on leadership if
+    /// necessary (don't need to override).
+    fn remove_team_member(&mut self, team_id: TeamId, player_id: PlayerId) {
+        let team = self.get_team(team_id);
+        if team.leader().map(|l| l.player_id) == Some(player_id)
+            && let Some(successor) = team.successor()
+        {
+            self.update_team(team_id, TeamUpdate::SetLeader(successor));
+        }
+        self.update_team(team_id, TeamUpdate::RemoveMember(player_id));
+    }
+
     fn player_quit_game(&mut self, player_id: PlayerId) {
         let PlayerStatus::Joined(JoinedStatus { team_id, joins }) =
             self.get_player_status(player_id)
@@ -114,7 +126,7 @@ pub trait PlayerTeamModel {
             }
             self.delete_team(team_id);
         } else {
-            self.update_team(team_id, TeamUpdate::RemoveMember(player_id));
+            self.remove_team_member(team_id, player_id);
         }
         self.update_player(player_id, JoinUpdate::Quit);
         for join in joins {
@@ -139,12 +151,15 @@ pub trait PlayerTeamModel {
             .chain(std::iter::once(TeamId(rng.gen())))
             .choose(rng)
             .unwrap();
-        match rng.gen_range(0..61) {
+        match rng.gen_range(0..76) {
             0..=19 => TeamRequest::Accept(random_player_id),
             20..=29 => TeamRequest::Join(random_team_id),
             30..=39 => TeamRequest::Kick(random_player_id),
             40..=49 => TeamRequest::Name(random_team_name()),
             50..=59 => TeamRequest::Reject(random_player_id),
+            60..=64 => TeamRequest::Promote(random_player_id),
+            65..=69 => TeamRequest::Demote(random_player_id),
+            70..=74 => TeamRequest::TransferLeadership(random_player_id),
             _ => TeamRequest::Leave,
         }
     }
@@ -181,12 +196,32 @@ pub trait PlayerTeamModel {
                 };
 
                 let req_team = self.get_team(req_team_id);
+                if let Some(old_name) = req_team.name {
+                    if !req_team.permissions(req_player_id).rename {
+                        return Err("unauthorized");
+                    }
+                    if old_name == new_name {
+                        return Err("team already has that name");
+                    }
+                    let new_team_data = req_team.data.clone();
+                    for mut member in req_team.iter().cloned().collect::<Vec<_>>() {
+                        self.swap_manifestation(
+                            MemberId {
+                                team_id: req_team_id,
+                                player_id: member.player_id,
+                            },
+                            &mut member.manifestation,
+                            req_team_id,
+                            &new_team_data,
+                        );
+                        self.update_team(req_team_id, TeamUpdate::ReplaceMember(member));
+                    }
+                    self.update_team(req_team_id, TeamUpdate::SetName(Some(new_name)));
+                    return Ok(());
+                }
                 if req_team.leader().map(|l| l.player_id) != Some(req_player_id) {
                     return Err("unauthorized");
                 }
-                if req_team.name.is_some() {
-                    return Err("team already named");
-                }
                 let new_team_data = req_team.data.clone();
                 assert_eq!(req_team.members.len(), 1);
                 assert!(req_team.joiners.is_empty());
@@ -254,7 +289,7 @@ pub trait PlayerTeamModel {
                 };
 
                 let req_team = self.get_team(req_team_id);
-                if req_team.leader().map(|l| l.player_id) != Some(req_player_id) {
+                if !req_team.permissions(req_player_id).accept {
                     return Err("unauthorized");
                 }
                 if !req_team.joiners.contains(&accept_player_id) {
@@ -301,6 +336,7 @@ pub trait PlayerTeamModel {
                     TeamUpdate::AddMember(Member {
                         player_id: accept_player_id,
                         manifestation,
+                        role: TeamRole::Member,
                     }),
                 );
                 // team had one player, but don't bother removing with
@@ -313,7 +349,7 @@ pub trait PlayerTeamModel {
                 };
 
                 let req_team = self.get_team(req_team_id);
-                if req_team.leader().map(|l| l.player_id) != Some(req_player_id) {
+                if !req_team.permissions(req_player_id).accept {
                     return Err("unauthorized");
                 }
                 if !req_team.joiners.contains(&reject_player_id) {
@@ -333,15 +369,16 @@ pub trait PlayerTeamModel {
                 };
 
                 let req_team = self.get_team(req_team_id);
-                if req_team.leader().map(|l| l.player_id) != Some(req_player_id) {
-                    return Err("unauthorized");
-                }
                 if kick_player_id == req_player_id {
                     return Err("cannot kick self");
                 }
                 if !req_team.members.contains(kick_player_id) {
                     return Err("cannot kick non-member");
                 }
+                // Also prevents kicking the leader.
+                if !req_team.can_kick(req_player_id, kick_player_id) {
+                    return Err("unauthorized");
+                }
                 // We know the team has a name since anonymous teams only have one member, so there
                 // is no one to kick.
                 // TODO: investigate whether the error ought to be handled.
@@ -408,17 +445,52 @@ pub trait PlayerTeamModel {
                         }
                         self.delete_team(old_team_id);
                     } else {
-                        self.update_team(old_team_id, TeamUpdate::RemoveMember(req_player_id));
+                        self.remove_team_member(old_team_id, req_player_id);
                     }
                     self.update_team(
                         new_team_id,
                         TeamUpdate::AddMember(Member {
                             player_id: req_player_id,
                             manifestation,
+                            role: TeamRole::Leader,
                         }),
                     );
                 }
             }
+            TeamRequest::Promote(target_player_id)
+            | TeamRequest::Demote(target_player_id)
+            | TeamRequest::TransferLeadership(target_player_id) => {
+                let Some(req_team_id) = self.get_player_status(req_player_id).team_id() else {
+                    return Err("not in team");
+                };
+
+                let req_team = self.get_team(req_team_id);
+                if req_team.leader().map(|l| l.player_id) != Some(req_player_id) {
+                    return Err("unauthorized");
+                }
+                if target_player_id == req_player_id {
+                    return Err("already leader");
+                }
+                let Some(role) = req_team.role(target_player_id) else {
+                    return Err("not a member");
+                };
+                let update = match request {
+                    TeamRequest::Promote(_) => {
+                        if role == TeamRole::Officer {
+                            return Err("already officer");
+                        }
+                        TeamUpdate::SetRole(target_player_id, TeamRole::Officer)
+                    }
+                    TeamRequest::Demote(_) => {
+                        if role == TeamRole::Member {
+                            return Err("not an officer");
+                        }
+                        TeamUpdate::SetRole(target_player_id, TeamRole::Member)
+                    }
+                    _ => TeamUpdate::SetLeader(target_player_id),
+                };
+                self.update_team(req_team_id, update);
+            }
         }
         Ok(())
     }
@@ -454,7 +526,8 @@ pub trait PlayerTeamModel {
         for team_id in team_ids {
             assert!(self.has_team(team_id));
             let team = self.get_team(team_id);
-            for member in team.iter() {
+            for (i, member) in team.iter().enumerate() {
+                assert_eq!(member.role == TeamRole::Leader, i == 0);
                 let member_player_status = self.get_player_status(member.player_id);
                 let PlayerStatus::Joined(JoinedStatus {
                     team_id: joined_team_id,
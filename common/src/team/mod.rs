@@ -9,7 +9,9 @@ mod color;
 mod id_or_alias;
 mod joined_status;
 mod members;
+mod role;
 mod team;
+mod tests;
 
 #[cfg(feature = "server")]
 pub use self::_trait::PlayerTeamModel;
@@ -17,6 +19,7 @@ pub use self::color::{InvalidTeamColor, TeamColor};
 pub use self::id_or_alias::PlayerIdOrAlias;
 pub use self::joined_status::{JoinUpdate, JoinedStatus, PlayerStatus};
 pub use self::members::Members;
+pub use self::role::{TeamPermissions, TeamRole, TeamSuccession};
 #[cfg(feature = "server")]
 pub use self::team::random_bot_team_name;
 pub use self::team::{allocate_team_id, Manifestation, Member, MemberId, Team};
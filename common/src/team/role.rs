@@ -0,0 +1,50 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use crate::bitcode::{self, *};
+
+/// A [`Member`][`super::Member`]'s rank within a [`Team`][`super::Team`], ordered from least to
+/// most authority.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Encode, Decode)]
+pub enum TeamRole {
+    #[default]
+    Member,
+    /// Has [`Manifestation::OFFICER_PERMISSIONS`][`super::Manifestation::OFFICER_PERMISSIONS`].
+    Officer,
+    /// Has all permissions, and is always the first member. There is exactly one per team.
+    Leader,
+}
+
+/// What a [`TeamRole`] may do, see [`Manifestation::permissions`][`super::Manifestation::permissions`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TeamPermissions {
+    /// Accept or reject joiners.
+    pub accept: bool,
+    /// Kick members with a lower [`TeamRole`].
+    pub kick: bool,
+    /// Rename the team.
+    pub rename: bool,
+}
+
+impl TeamPermissions {
+    pub const ALL: Self = Self {
+        accept: true,
+        kick: true,
+        rename: true,
+    };
+    pub const NONE: Self = Self {
+        accept: false,
+        kick: false,
+        rename: false,
+    };
+}
+
+/// Who becomes leader when the leader leaves or quits the game.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TeamSuccession {
+    /// The longest-serving officer, or if there are none, the longest-serving member.
+    #[default]
+    Officer,
+    /// The longest-serving member, regardless of role.
+    Oldest,
+}
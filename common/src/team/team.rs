@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::Members;
+use super::{Members, TeamPermissions, TeamRole, TeamSuccession};
 use crate::bitcode::{self, *};
 use crate::{PlayerAlias, PlayerId, TeamId, TeamName, TeamUpdate};
 use std::fmt::Debug;
@@ -13,7 +13,8 @@ use std::ops::{Deref, DerefMut};
 pub struct Team<D, M> {
     pub name: Option<TeamName>,
     pub data: D,
-    /// Common invariant: first is always `Some`, the leader.
+    /// Common invariant: first is always `Some`, the leader, and has [`TeamRole::Leader`].
+    /// Otherwise, ordered by when they joined.
     pub members: Members<M>,
     pub joiners: Vec<PlayerId>,
 }
@@ -60,6 +61,38 @@ impl<D, M: Manifestation> Team<D, M> {
         self.members.0.get_mut(0)
     }
 
+    /// Returns the [`TeamRole`] of a member, or `None` if they aren't a member.
+    pub fn role(&self, player_id: PlayerId) -> Option<TeamRole> {
+        self.get(player_id).map(|m| m.role)
+    }
+
+    /// Returns what a member may do, or [`TeamPermissions::NONE`] if they aren't a member.
+    pub fn permissions(&self, player_id: PlayerId) -> TeamPermissions {
+        self.role(player_id)
+            .map(M::permissions)
+            .unwrap_or(TeamPermissions::NONE)
+    }
+
+    /// Returns true iff `player_id` may kick `kick_player_id`.
+    pub fn can_kick(&self, player_id: PlayerId, kick_player_id: PlayerId) -> bool {
+        self.permissions(player_id).kick
+            && self
+                .role(kick_player_id)
+                .zip(self.role(player_id))
+                .is_some_and(|(kick_role, role)| kick_role < role)
+    }
+
+    /// Returns who would become leader if the leader left, according to
+    /// [`Manifestation::SUCCESSION`], or `None` if nobody else is on the team.
+    pub fn successor(&self) -> Option<PlayerId> {
+        let oldest = self.iter().nth(1);
+        match M::SUCCESSION {
+            TeamSuccession::Officer => self.iter().find(|m| m.role == TeamRole::Officer).or(oldest),
+            TeamSuccession::Oldest => oldest,
+        }
+        .map(|m| m.player_id)
+    }
+
     pub fn get(&self, player_id: PlayerId) -> Option<&Member<M>> {
         self.members
             .iter()
@@ -106,16 +139,50 @@ impl<D, M: Manifestation> Team<D, M> {
             TeamUpdate::RemoveJoiner(joiner) => {
                 self.joiners.retain(|id| *id != joiner);
             }
-            TeamUpdate::AddMember(member) => {
+            TeamUpdate::AddMember(mut member) => {
                 self.update(TeamUpdate::<M>::RemoveJoiner(member.player_id));
                 assert!(!self.members.contains(member.player_id), "duplicate member");
+                // The first member is the leader, and nobody else can be.
+                member.role = if self.members.is_empty() {
+                    TeamRole::Leader
+                } else {
+                    member.role.min(TeamRole::Officer)
+                };
                 self.members.push(member);
             }
             TeamUpdate::ReplaceMember(member) => {
                 self.get_mut(member.player_id).unwrap().manifestation = member.manifestation;
             }
             TeamUpdate::RemoveMember(player_id) => {
+                debug_assert!(
+                    self.members.len() == 1
+                        || self.leader().map(|l| l.player_id) != Some(player_id),
+                    "removed leader without successor"
+                );
                 self.members.remove(player_id);
+                if let Some(leader) = self.leader_mut() {
+                    // Maintain invariant, even if `SetLeader` wasn't sent.
+                    leader.role = TeamRole::Leader;
+                }
+            }
+            TeamUpdate::SetRole(player_id, role) => {
+                assert_ne!(role, TeamRole::Leader, "use SetLeader");
+                let member = self.get_mut(player_id).unwrap();
+                assert_ne!(member.role, TeamRole::Leader, "use SetLeader");
+                member.role = role;
+            }
+            TeamUpdate::SetLeader(player_id) => {
+                let index = self
+                    .members
+                    .iter()
+                    .position(|m| m.player_id == player_id)
+                    .unwrap();
+                if let Some(leader) = self.leader_mut() {
+                    leader.role = TeamRole::Officer;
+                }
+                let mut leader = self.members.0.remove(index);
+                leader.role = TeamRole::Leader;
+                self.members.0.insert(0, leader);
             }
         }
     }
@@ -139,6 +206,7 @@ pub fn allocate_team_id<V>(map: &impl crate::actor_model::Map<TeamId, V>) -> Tea
 pub struct Member<M> {
     pub player_id: PlayerId,
     pub manifestation: M,
+    pub role: TeamRole,
 }
 
 pub trait Manifestation: Clone {
@@ -149,6 +217,16 @@ pub trait Manifestation: Clone {
     const MEMBERS_CAN_LEAVE: bool = true;
     const CAN_LEAVE_SOLO_TEAM: bool = true;
     const CAN_REUSE_SOLO_TEAM: bool = true;
+    /// What [`TeamRole::Officer`]s may do (leaders may do everything).
+    const OFFICER_PERMISSIONS: TeamPermissions = TeamPermissions {
+        accept: true,
+        kick: true,
+        rename: false,
+    };
+    /// What [`TeamRole::Member`]s may do.
+    const MEMBER_PERMISSIONS: TeamPermissions = TeamPermissions::NONE;
+    /// Who becomes leader when the leader leaves.
+    const SUCCESSION: TeamSuccession = TeamSuccession::Officer;
 
     fn new(alias: PlayerAlias) -> Self;
 
@@ -159,6 +237,15 @@ pub trait Manifestation: Clone {
     fn is_dead(&self) -> bool {
         !self.is_alive()
     }
+
+    /// What a [`TeamRole`] may do (don't need to override).
+    fn permissions(role: TeamRole) -> TeamPermissions {
+        match role {
+            TeamRole::Member => Self::MEMBER_PERMISSIONS,
+            TeamRole::Officer => Self::OFFICER_PERMISSIONS,
+            TeamRole::Leader => TeamPermissions::ALL,
+        }
+    }
 }
 
 impl Manifestation for () {
@@ -178,6 +265,7 @@ impl<M: Manifestation> Member<M> {
         Self {
             player_id,
             manifestation: M::new(alias),
+            role: TeamRole::Member,
         }
     }
 }
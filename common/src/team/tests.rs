@@ -0,0 +1,78 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+#[cfg(test)]
+mod team_tests {
+    use crate::{
+        Manifestation, Member, PlayerAlias, PlayerId, Team, TeamPermissions, TeamRole, TeamUpdate,
+    };
+
+    fn player(n: usize) -> PlayerId {
+        PlayerId::nth_client(n).unwrap()
+    }
+
+    fn roles(team: &Team<(), ()>) -> Vec<(PlayerId, TeamRole)> {
+        team.iter().map(|m| (m.player_id, m.role)).collect()
+    }
+
+    #[test]
+    fn roles_and_succession() {
+        let mut team = Team::<(), ()>::new(());
+        assert_eq!(team.successor(), None);
+        for n in 0..4 {
+            let member = Member::new(player(n), PlayerAlias::new_unsanitized("a"));
+            team.update(TeamUpdate::AddMember(member));
+        }
+        assert_eq!(team.role(player(0)), Some(TeamRole::Leader));
+        assert_eq!(team.role(player(1)), Some(TeamRole::Member));
+        assert_eq!(team.role(player(5)), None);
+        // Oldest member.
+        assert_eq!(team.successor(), Some(player(1)));
+
+        team.update(TeamUpdate::SetRole(player(2), TeamRole::Officer));
+        assert_eq!(team.permissions(player(0)), TeamPermissions::ALL);
+        assert_eq!(
+            team.permissions(player(2)),
+            <() as Manifestation>::OFFICER_PERMISSIONS
+        );
+        assert_eq!(team.permissions(player(1)), TeamPermissions::NONE);
+        assert_eq!(team.permissions(player(5)), TeamPermissions::NONE);
+        assert!(team.can_kick(player(0), player(2)));
+        assert!(team.can_kick(player(2), player(1)));
+        assert!(!team.can_kick(player(2), player(0)));
+        assert!(!team.can_kick(player(1), player(3)));
+        assert!(!team.can_kick(player(2), player(5)));
+        // Officers take precedence.
+        assert_eq!(team.successor(), Some(player(2)));
+
+        team.update(TeamUpdate::SetLeader(player(2)));
+        assert_eq!(
+            roles(&team),
+            [
+                (player(2), TeamRole::Leader),
+                (player(0), TeamRole::Officer),
+                (player(1), TeamRole::Member),
+                (player(3), TeamRole::Member),
+            ]
+        );
+
+        team.update(TeamUpdate::SetRole(player(0), TeamRole::Member));
+        team.update(TeamUpdate::SetLeader(player(3)));
+        team.update(TeamUpdate::RemoveMember(player(2)));
+        assert_eq!(
+            roles(&team),
+            [
+                (player(3), TeamRole::Leader),
+                (player(0), TeamRole::Member),
+                (player(1), TeamRole::Member),
+            ]
+        );
+        assert_eq!(team.leader().unwrap().player_id, player(3));
+
+        // Can't add a second leader.
+        let mut member = Member::new(player(4), PlayerAlias::new_unsanitized("b"));
+        member.role = TeamRole::Leader;
+        team.update(TeamUpdate::AddMember(member));
+        assert_eq!(team.role(player(4)), Some(TeamRole::Officer));
+    }
+}
@@ -1,6 +1,9 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
+mod scheduler;
+mod tests;
 mod ticks;
 
+pub use self::scheduler::{TickScheduler, TimerId};
 pub use self::ticks::{GenTicks, TicksRepr, TicksTrait};
@@ -0,0 +1,198 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::{GenTicks, TicksRepr};
+use crate::bitcode::{self, *};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Debug, Formatter};
+
+/// Identifies a timer in a [`TickScheduler`]. Never reused by the same scheduler, so a stale id
+/// won't cancel an unrelated timer.
+#[derive(
+    Copy, Clone, Debug, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Encode, Decode,
+)]
+pub struct TimerId(u64);
+
+#[derive(Clone, Debug, Hash, Serialize, Deserialize, Encode, Decode)]
+struct Timer<E> {
+    /// Absolute tick of the next firing.
+    due: u64,
+    /// Zero if the timer only fires once.
+    period: TicksRepr,
+    event: E,
+}
+
+/// Schedules events of type `E` at future ticks, for cooldowns and delayed or periodic game
+/// logic. Call [`Self::tick`] (or [`Self::tick_into`]) exactly once per game tick.
+///
+/// Deterministic: timers that are due on the same tick fire in the order they were created, and
+/// counting elapsed ticks in a `u64` means there is no wrapping, unlike [`GenTicks`]. Can be kept
+/// in lockstep state and snapshots if `E` is serializable. Events can be [`Message`]s, which
+/// [`Self::tick_into`] delivers directly into a `define_events!` struct, or `fn` pointers to use
+/// as callbacks.
+///
+/// [`Message`]: crate::actor_model::Message
+#[derive(Clone, Hash, Serialize, Deserialize, Encode, Decode)]
+pub struct TickScheduler<E, const FREQUENCY_HZ: TicksRepr> {
+    /// Ticks elapsed since creation.
+    now: u64,
+    next_id: u64,
+    timers: BTreeMap<TimerId, Timer<E>>,
+    /// Invariant: Contains exactly the `(due, id)` of each of `timers`. If decoded from untrusted
+    /// data, entries without a matching timer are ignored, and timers without an entry never fire.
+    queue: BTreeSet<(u64, TimerId)>,
+}
+
+// Can't derive since it would bound E: Default.
+impl<E, const FREQUENCY_HZ: TicksRepr> Default for TickScheduler<E, FREQUENCY_HZ> {
+    fn default() -> Self {
+        Self {
+            now: 0,
+            next_id: 0,
+            timers: BTreeMap::new(),
+            queue: BTreeSet::new(),
+        }
+    }
+}
+
+impl<E: Debug, const FREQUENCY_HZ: TicksRepr> Debug for TickScheduler<E, FREQUENCY_HZ> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TickScheduler")
+            .field("now", &self.now)
+            .field("timers", &self.queued().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<E, const FREQUENCY_HZ: TicksRepr> TickScheduler<E, FREQUENCY_HZ> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of ticks elapsed since creation.
+    pub fn now(&self) -> u64 {
+        self.now
+    }
+
+    /// Schedules `event` to fire once, `delay` ticks from now. A `delay` of zero fires on the
+    /// next tick, like a `delay` of one.
+    pub fn schedule(&mut self, delay: GenTicks<FREQUENCY_HZ>, event: E) -> TimerId {
+        self.insert(delay, 0, event)
+    }
+
+    /// Schedules `event` to fire `delay` ticks from now, and then every `period` ticks until
+    /// cancelled.
+    ///
+    /// **Panics**
+    ///
+    /// If `period` is zero.
+    pub fn schedule_repeating(
+        &mut self,
+        delay: GenTicks<FREQUENCY_HZ>,
+        period: GenTicks<FREQUENCY_HZ>,
+        event: E,
+    ) -> TimerId {
+        assert_ne!(period.0, 0, "period must be positive");
+        self.insert(delay, period.0, event)
+    }
+
+    fn insert(&mut self, delay: GenTicks<FREQUENCY_HZ>, period: TicksRepr, event: E) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+        let due = self.now + delay.0.max(1) as u64;
+        self.timers.insert(id, Timer { due, period, event });
+        self.queue.insert((due, id));
+        id
+    }
+
+    /// Cancels a timer, returning its event, or `None` if it already fired (and isn't
+    /// repeating) or was cancelled.
+    pub fn cancel(&mut self, id: TimerId) -> Option<E> {
+        let timer = self.timers.remove(&id)?;
+        self.queue.remove(&(timer.due, id));
+        Some(timer.event)
+    }
+
+    /// Returns true if the timer will fire in the future.
+    pub fn is_scheduled(&self, id: TimerId) -> bool {
+        self.timers.contains_key(&id)
+    }
+
+    /// Returns the number of ticks until the timer next fires (at least one), saturating at
+    /// [`GenTicks::MAX`][`super::TicksTrait::MAX`], or `None` if it isn't scheduled.
+    pub fn remaining(&self, id: TimerId) -> Option<GenTicks<FREQUENCY_HZ>> {
+        let timer = self.timers.get(&id)?;
+        let remaining = timer.due.saturating_sub(self.now).max(1);
+        Some(GenTicks(remaining.min(TicksRepr::MAX as u64) as TicksRepr))
+    }
+
+    /// Returns the event of a scheduled timer.
+    pub fn get(&self, id: TimerId) -> Option<&E> {
+        self.timers.get(&id).map(|timer| &timer.event)
+    }
+
+    /// Returns the event of a scheduled timer, which can be modified before it fires.
+    pub fn get_mut(&mut self, id: TimerId) -> Option<&mut E> {
+        self.timers.get_mut(&id).map(|timer| &mut timer.event)
+    }
+
+    /// Returns the number of scheduled timers.
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    /// Cancels all timers, without resetting [`Self::now`].
+    pub fn clear(&mut self) {
+        self.timers.clear();
+        self.queue.clear();
+    }
+
+    /// Iterates scheduled timers in the order they will fire.
+    pub fn iter(&self) -> impl Iterator<Item = (TimerId, &E)> + '_ {
+        self.queued().map(|(id, timer)| (id, &timer.event))
+    }
+
+    /// Iterates timers in the order they will fire, skipping queue entries without a matching
+    /// timer.
+    fn queued(&self) -> impl Iterator<Item = (TimerId, &Timer<E>)> + '_ {
+        self.queue.iter().filter_map(|&(due, id)| {
+            let timer = self.timers.get(&id)?;
+            (timer.due == due).then_some((id, timer))
+        })
+    }
+}
+
+impl<E: Clone, const FREQUENCY_HZ: TicksRepr> TickScheduler<E, FREQUENCY_HZ> {
+    /// Advances one tick and calls `fire` with each timer that became due, in order.
+    pub fn tick(&mut self, mut fire: impl FnMut(TimerId, E)) {
+        self.now += 1;
+        while let Some(&(due, id)) = self.queue.first()
+            && due <= self.now
+        {
+            self.queue.pop_first();
+            let Some(timer) = self.timers.get_mut(&id).filter(|timer| timer.due == due) else {
+                continue;
+            };
+            if timer.period == 0 {
+                let timer = self.timers.remove(&id).unwrap();
+                fire(id, timer.event);
+            } else {
+                timer.due = due + timer.period as u64;
+                self.queue.insert((timer.due, id));
+                fire(id, timer.event.clone());
+            }
+        }
+    }
+
+    /// Advances one tick and extends `events` with the events of timers that became due, in
+    /// order. For example, `events` can be a struct generated by `define_events!`, which is
+    /// [`Extend`] for each of its event types.
+    pub fn tick_into(&mut self, events: &mut impl Extend<E>) {
+        self.tick(|_, event| events.extend_one(event));
+    }
+}
@@ -0,0 +1,145 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+#[cfg(test)]
+mod scheduler_tests {
+    use crate::actor_model::*;
+    use crate::{bitcode, define_apply, define_events, GenTicks, TickScheduler, TimerId};
+
+    type Ticks = GenTicks<10>;
+
+    fn tick(scheduler: &mut TickScheduler<&'static str, 10>) -> Vec<(TimerId, &'static str)> {
+        let mut fired = Vec::new();
+        scheduler.tick(|id, event| fired.push((id, event)));
+        fired
+    }
+
+    #[test]
+    fn schedule_and_cancel() {
+        let mut scheduler = TickScheduler::<&'static str, 10>::new();
+        let b = scheduler.schedule(Ticks::from_repr(2), "b");
+        let a = scheduler.schedule(Ticks::ZERO, "a");
+        let c = scheduler.schedule(Ticks::from_repr(2), "c");
+        let d = scheduler.schedule(Ticks::from_repr(3), "d");
+        assert_eq!(scheduler.len(), 4);
+        assert_eq!(scheduler.remaining(b), Some(Ticks::from_repr(2)));
+        assert_eq!(
+            scheduler.iter().map(|(_, e)| *e).collect::<Vec<_>>(),
+            ["a", "b", "c", "d"]
+        );
+
+        assert_eq!(scheduler.cancel(d), Some("d"));
+        assert_eq!(scheduler.cancel(d), None);
+        assert!(!scheduler.is_scheduled(d));
+
+        assert_eq!(tick(&mut scheduler), [(a, "a")]);
+        assert_eq!(scheduler.remaining(b), Some(Ticks::ONE));
+        // Same tick fires in order of creation.
+        assert_eq!(tick(&mut scheduler), [(b, "b"), (c, "c")]);
+        assert_eq!(tick(&mut scheduler), []);
+        assert!(scheduler.is_empty());
+        assert_eq!(scheduler.now(), 3);
+        assert_eq!(scheduler.remaining(a), None);
+    }
+
+    #[test]
+    fn repeating() {
+        let mut scheduler = TickScheduler::<&'static str, 10>::new();
+        let repeating =
+            scheduler.schedule_repeating(Ticks::from_repr(1), Ticks::from_repr(3), "repeat");
+        let once = scheduler.schedule(Ticks::from_repr(4), "once");
+
+        let mut fired = Vec::new();
+        for _ in 0..10 {
+            let now = scheduler.now() + 1;
+            fired.extend(tick(&mut scheduler).into_iter().map(|(_, e)| (now, e)));
+        }
+        assert_eq!(
+            fired,
+            [
+                (1, "repeat"),
+                (4, "repeat"),
+                (4, "once"),
+                (7, "repeat"),
+                (10, "repeat")
+            ]
+        );
+        assert!(!scheduler.is_scheduled(once));
+        assert_eq!(scheduler.remaining(repeating), Some(Ticks::from_repr(3)));
+
+        *scheduler.get_mut(repeating).unwrap() = "changed";
+        assert_eq!(scheduler.get(repeating), Some(&"changed"));
+        scheduler.clear();
+        assert!(scheduler.is_empty());
+        assert_eq!(tick(&mut scheduler), []);
+    }
+
+    #[test]
+    fn serialize() {
+        let mut scheduler = TickScheduler::<u32, 10>::new();
+        scheduler.schedule(Ticks::from_repr(5), 1);
+        scheduler.schedule_repeating(Ticks::from_repr(2), Ticks::from_repr(2), 2);
+        scheduler.tick(|_, _| {});
+
+        let mut decoded: TickScheduler<u32, 10> =
+            bitcode::decode(&bitcode::encode(&scheduler)).unwrap();
+        let mut expected = Vec::new();
+        let mut actual = Vec::new();
+        for _ in 0..10 {
+            scheduler.tick(|id, e| expected.push((id, e)));
+            decoded.tick(|id, e| actual.push((id, e)));
+        }
+        assert_eq!(actual, expected);
+        // Continues allocating ids where it left off.
+        assert_eq!(
+            decoded.schedule(Ticks::ONE, 3),
+            scheduler.schedule(Ticks::ONE, 3)
+        );
+    }
+
+    #[test]
+    fn inconsistent() {
+        let mut scheduler = TickScheduler::<u32, 10>::new();
+        let a = scheduler.schedule(Ticks::ONE, 1);
+        let b = scheduler.schedule(Ticks::from_repr(2), 2);
+
+        // E.g. decoded from untrusted data. Only `a` has a matching queue entry.
+        let mut json = serde_json::to_value(&scheduler).unwrap();
+        json["queue"] = serde_json::json!([[1, 0], [1, 1], [1, 5]]);
+        let mut decoded: TickScheduler<u32, 10> = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.iter().collect::<Vec<_>>(), [(a, &1)]);
+        assert!(!format!("{decoded:?}").is_empty());
+
+        let mut fired = Vec::new();
+        for _ in 0..3 {
+            decoded.tick(|id, e| fired.push((id, e)));
+        }
+        assert_eq!(fired, [(a, 1)]);
+        assert_eq!(decoded.remaining(b), Some(Ticks::ONE));
+        assert_eq!(decoded.cancel(b), Some(2));
+        assert!(decoded.is_empty());
+    }
+
+    define_apply!();
+
+    #[derive(Clone, Debug, PartialEq)]
+    pub struct Explode(u8);
+
+    impl Message for Explode {}
+
+    // Generates `BombEventsFromServer`.
+    define_events!(Bomb, Server, Explode);
+
+    #[test]
+    fn define_events() {
+        let mut scheduler = TickScheduler::<Explode, 10>::new();
+        scheduler.schedule(Ticks::from_repr(2), Explode(1));
+        scheduler.schedule(Ticks::from_repr(2), Explode(2));
+
+        let mut events = BombEventsFromServer::default();
+        scheduler.tick_into(&mut events);
+        assert!(events.explode.is_empty());
+        scheduler.tick_into(&mut events);
+        assert_eq!(events.explode, [Explode(1), Explode(2)]);
+    }
+}
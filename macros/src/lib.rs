@@ -11,6 +11,7 @@
 mod audio;
 mod hb_hash;
 mod layer;
+mod lockstep_diff;
 #[cfg(feature = "ply")]
 mod ply;
 mod settings;
@@ -80,6 +81,11 @@ pub fn derive_hb_hash(input: TokenStream) -> TokenStream {
     hb_hash::derive_hb_hash(input)
 }
 
+#[proc_macro_derive(LockstepDiff, attributes(lockstep_diff))]
+pub fn derive_lockstep_diff(input: TokenStream) -> TokenStream {
+    lockstep_diff::derive_lockstep_diff(input)
+}
+
 #[proc_macro_derive(SmolRoutable, attributes(at, not_found))]
 pub fn smol_routable_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as smol_routable::SmolRoutable);
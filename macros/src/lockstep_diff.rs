@@ -0,0 +1,143 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DataEnum, DataStruct, DeriveInput, Fields,
+    GenericParam, Generics, Meta, NestedMeta,
+};
+
+/// Compares each field with `LockstepDiffer::field`, or `LockstepDiffer::field_whole` if marked
+/// `#[lockstep_diff(whole)]`. Enums with different variants are compared as a whole.
+pub(crate) fn derive_lockstep_diff(input: TokenStream) -> TokenStream {
+    let DeriveInput {
+        ident,
+        data,
+        generics,
+        ..
+    } = parse_macro_input!(input);
+
+    /// Destructures `fields` into variables starting with `prefix`.
+    fn destructure_fields(fields: &Fields, prefix: &str) -> TokenStream2 {
+        let names = (0..fields.len()).map(|i| binding(prefix, i));
+        match fields {
+            Fields::Named(named) => {
+                let idents = named.named.iter().map(|field| &field.ident);
+                quote! {
+                    {#(#idents: #names),*}
+                }
+            }
+            Fields::Unnamed(_) => {
+                quote! {
+                    (#(#names),*)
+                }
+            }
+            Fields::Unit => {
+                quote! {}
+            }
+        }
+    }
+
+    fn binding(prefix: &str, i: usize) -> Ident {
+        Ident::new(&format!("{prefix}{i}"), Span::mixed_site())
+    }
+
+    fn diff_fields(fields: &Fields) -> impl Iterator<Item = TokenStream2> + '_ {
+        fields.iter().enumerate().map(|(i, field)| {
+            let name = field
+                .ident
+                .as_ref()
+                .map(|ident| ident.to_string())
+                .unwrap_or_else(|| i.to_string());
+            let client = binding("client_", i);
+            let server = binding("server_", i);
+            let method = if is_whole(&field.attrs) {
+                quote!(field_whole)
+            } else {
+                quote!(field)
+            };
+            quote! {
+                differ.#method(#name, #client, #server);
+            }
+        })
+    }
+
+    let output = match data {
+        Data::Struct(DataStruct { fields, .. }) if fields.is_empty() => {
+            quote! {
+                differ.whole(self, server);
+            }
+        }
+        Data::Struct(DataStruct { fields, .. }) => {
+            let destructure_client = destructure_fields(&fields, "client_");
+            let destructure_server = destructure_fields(&fields, "server_");
+            let diff_fields = diff_fields(&fields);
+
+            quote! {
+                let Self #destructure_client = self;
+                let Self #destructure_server = server;
+                #(#diff_fields)*
+            }
+        }
+        Data::Enum(DataEnum { variants, .. }) => {
+            let diff_variants = variants.iter().map(|variant| {
+                let ident = &variant.ident;
+                let destructure_client = destructure_fields(&variant.fields, "client_");
+                let destructure_server = destructure_fields(&variant.fields, "server_");
+                let diff_fields = diff_fields(&variant.fields);
+                quote! {
+                    (Self::#ident #destructure_client, Self::#ident #destructure_server) => {
+                        #(#diff_fields)*
+                    }
+                }
+            });
+
+            quote! {
+                #[allow(unreachable_patterns)]
+                match (self, server) {
+                    #(#diff_variants)*
+                    _ => differ.whole(self, server),
+                }
+            }
+        }
+        Data::Union(_) => panic!("unions not supported"),
+    };
+
+    let generics = add_trait_bounds(generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics kodiak_common::LockstepDiff for #ident #ty_generics #where_clause {
+            fn diff(&self, server: &Self, differ: &mut kodiak_common::LockstepDiffer) {
+                #output
+            }
+        }
+    }
+    .into()
+}
+
+fn is_whole(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        let Ok(Meta::List(list)) = attr.parse_meta() else {
+            return false;
+        };
+        list.path.is_ident("lockstep_diff")
+            && list.nested.iter().any(
+                |nested| matches!(nested, NestedMeta::Meta(Meta::Path(path)) if path.is_ident("whole")),
+            )
+    })
+}
+
+// Add a bound `T: LockstepDiff` to every type parameter T.
+fn add_trait_bounds(mut generics: Generics) -> Generics {
+    for param in &mut generics.params {
+        if let GenericParam::Type(ref mut type_param) = *param {
+            type_param
+                .bounds
+                .push(parse_quote!(kodiak_common::LockstepDiff));
+        }
+    }
+    generics
+}
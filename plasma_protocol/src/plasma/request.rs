@@ -80,6 +80,10 @@ pub enum PlasmaRequestV1 {
         /// if the certificate is missing.
         #[serde(default, skip_serializing_if = "Option::is_none")]
         date_certificate_expires: Option<NonZeroUnixMillis>,
+        /// The server is draining, e.g. for a rolling deploy, so shouldn't be sent new players.
+        /// Sent immediately when it changes.
+        #[serde(default, skip_serializing_if = "is_default")]
+        draining: bool,
         /// Heartbeats for each arena on the server.
         #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
         realms: BTreeMap<RealmId, RealmHeartbeat>,
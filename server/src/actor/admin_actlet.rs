@@ -2,10 +2,11 @@
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
 use crate::actor::ServerActor;
+use crate::entry_point::IP_ACCESS_LIST;
 use crate::service::{ArenaService, Bundle, MetricBundle, MetricRepo, PlayerRepo, Score};
 use crate::{
-    AdminPlayerDto, AdminRequest, AdminUpdate, ClientHash, EngineMetrics, MetricFilter,
-    PlayerAlias, PlayerId, RealmId, RegionId, SceneId, UserAgentId,
+    AdminPlayerDto, AdminRequest, AdminUpdate, ClientHash, EngineMetrics, IpAccessRule,
+    MetricFilter, PlayerAlias, PlayerId, RealmId, RegionId, SceneId, UserAgentId,
 };
 use actix::{fut, ActorFutureExt, Handler, ResponseActFuture, WrapFuture};
 use std::collections::HashMap;
@@ -118,6 +119,27 @@ impl<G: ArenaService> AdminActlet<G> {
         ))
     }
 
+    /// Adds a runtime IP access rule. Existing connections are unaffected.
+    fn add_ip_access_rule(rule: IpAccessRule) -> Result<AdminUpdate, &'static str> {
+        Ok(AdminUpdate::IpAccessRuleAdded(
+            IP_ACCESS_LIST.lock().unwrap().add(rule),
+        ))
+    }
+
+    /// Removes an IP access rule, whether added at runtime or loaded from file.
+    fn remove_ip_access_rule(rule: IpAccessRule) -> Result<AdminUpdate, &'static str> {
+        Ok(AdminUpdate::IpAccessRuleRemoved(
+            IP_ACCESS_LIST.lock().unwrap().remove(rule),
+        ))
+    }
+
+    /// Get all IP access rules in effect.
+    fn request_ip_access_rules() -> Result<AdminUpdate, &'static str> {
+        Ok(AdminUpdate::IpAccessRulesRequested(
+            IP_ACCESS_LIST.lock().unwrap().rules().copied().collect(),
+        ))
+    }
+
     /// Get admin view of real players in the game.
     fn request_players(&self, players: &PlayerRepo<G>) -> Result<AdminUpdate, &'static str> {
         Ok(AdminUpdate::PlayersRequested(
@@ -272,6 +294,12 @@ impl<G: ArenaService> Handler<AdminRequest> for ServerActor<G> {
 
     fn handle(&mut self, request: AdminRequest, _ctx: &mut Self::Context) -> Self::Result {
         match request {
+            AdminRequest::AddIpAccessRule(rule) => {
+                Box::pin(fut::ready(AdminActlet::<G>::add_ip_access_rule(rule)))
+            }
+            AdminRequest::Drain(seconds) => Box::pin(fut::ready(Ok(AdminUpdate::Draining(
+                self.start_drain(Duration::from_secs(seconds as u64)),
+            )))),
             AdminRequest::OverridePlayerAlias { player_id, alias } => {
                 Box::pin(fut::ready(if let Some(tier) = self.realms.main_mut() {
                     self.admin.override_player_alias(
@@ -296,10 +324,16 @@ impl<G: ArenaService> Handler<AdminRequest> for ServerActor<G> {
             } else {
                 Err("no main arena")
             })),
+            AdminRequest::RemoveIpAccessRule(rule) => {
+                Box::pin(fut::ready(AdminActlet::<G>::remove_ip_access_rule(rule)))
+            }
             AdminRequest::RequestDay { filter } => {
                 Box::pin(fut::ready(AdminActlet::request_day(&self.metrics, filter)))
             }
             AdminRequest::RequestGames => Box::pin(fut::ready(self.admin.request_games())),
+            AdminRequest::RequestIpAccessRules => {
+                Box::pin(fut::ready(AdminActlet::<G>::request_ip_access_rules()))
+            }
             AdminRequest::RequestPlayers => Box::pin(fut::ready(
                 if let Some(realm) = self.realms.realm(RealmId::PublicDefault) {
                     if let Some(scene) = realm.scene_repo.get(&SceneId::default()) {
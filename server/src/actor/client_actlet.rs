@@ -938,12 +938,27 @@ impl<G: ArenaService> ClientActlet<G> {
         if !player.regulator.active() {
             return Err("inactive");
         }
+        Self::redirect(player_id, server_id, arena_id, service, context, metrics)
+    }
+
+    /// Sends a connected player to another server, whether or not they are in game.
+    pub(crate) fn redirect(
+        player_id: PlayerId,
+        server_id: ServerId,
+        arena_id: ArenaQuery,
+        service: &mut G,
+        context: &mut ArenaContext<G>,
+        metrics: &mut MetricRepo<G>,
+    ) -> Result<Option<ClientUpdate>, &'static str> {
+        let player = context.players.get_mut(player_id).ok_or("missing player")?;
         let client = player.client_mut().ok_or("not a client")?;
         if !client.status.is_connected() {
             return Err("not connected");
         }
-        player.regulator.leave();
-        service.player_quit(player_id, player);
+        if player.regulator.active() {
+            player.regulator.leave();
+            service.player_quit(player_id, player);
+        }
         if player.was_alive {
             player.was_alive = false;
             metrics.stop_play(player);
@@ -2058,6 +2073,9 @@ impl<G: ArenaService> Handler<ClientAuthRequest> for ServerActor<G> {
         let player_id = if let Some(existing) = player_id {
             existing
         } else {
+            if self.drain.is_some() {
+                return Err(ClientAuthErr::UnsanctionedServer);
+            }
             // Deliberately allow new players when closing without redirecting, because that
             // seems safer.
             if self.plasma.role.is_redirected()
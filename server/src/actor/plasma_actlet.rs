@@ -35,6 +35,8 @@ pub struct PlasmaActlet {
     /// Last outbound heartbeat time.
     last_heartbeat: Option<Instant>,
     last_acknowledged_heartbeat: Option<Instant>,
+    /// Whether the last outbound heartbeat reported draining.
+    reported_draining: bool,
     /// Last server log update.
     last_server_log: Option<Instant>,
     last_quest_samples: Option<Instant>,
@@ -42,7 +44,8 @@ pub struct PlasmaActlet {
     pub role: ServerRole,
     pub redirecting_since: Option<Instant>,
     pub server_token: &'static AtomicU64,
-    pub(crate) rustls_config: RustlsConfig,
+    /// [`None`] if certificates are loaded from files instead.
+    pub(crate) rustls_config: Option<RustlsConfig>,
     pub(crate) cors_alternative_domains: &'static Mutex<Arc<[DomainName]>>,
     pub(crate) date_certificate_expires: Option<NonZeroUnixMillis>,
     pub servers: HashMap<ServerId, ServerUseTopology>,
@@ -58,12 +61,14 @@ impl PlasmaActlet {
     pub(crate) fn new<G: ArenaService>(
         redirect_server_number: &'static AtomicU8,
         server_token: &'static AtomicU64,
-        rustls_config: RustlsConfig,
+        rustls_config: Option<RustlsConfig>,
         cors_alternative_domains: &'static Mutex<Arc<[DomainName]>>,
         domain_backup: Option<Arc<str>>,
     ) -> Self {
         let mut date_certificate_expires = None;
-        if let Some(domain_backup) = &domain_backup {
+        if let Some(domain_backup) = &domain_backup
+            && let Some(rustls_config) = &rustls_config
+        {
             if let Ok(contents) = std::fs::read_to_string(&**domain_backup) {
                 if let Ok(domains) = serde_json::from_str::<Box<[DomainDto]>>(&contents) {
                     if let Some((config, date)) = load_domains::<G>(&domains) {
@@ -87,6 +92,7 @@ impl PlasmaActlet {
             infrastructure: None,
             last_heartbeat: None,
             last_acknowledged_heartbeat: None,
+            reported_draining: false,
             last_server_log: None,
             last_quest_samples: None,
             quest_fraction: if cfg!(debug_assertions) { 1.0 } else { 0.2 },
@@ -167,6 +173,7 @@ impl PlasmaActlet {
         metrics: &mut MetricRepo<G>,
         region_id: RegionId,
         client_hash: ClientHash,
+        draining: bool,
         ctx: &mut <ServerActor<G> as Actor>::Context,
     ) {
         let cpu = self.health.cpu() + self.health.cpu_steal();
@@ -198,21 +205,23 @@ impl PlasmaActlet {
             self.last_hiccup = None;
         }
 
-        if since_heartbeat
-            < Duration::from_secs(if hiccup {
-                15
-            } else if recent_hiccup {
-                30
-            } else if matches!(region_id, RegionId::Asia | RegionId::SouthAmerica) {
-                // Mitigate frequent disconnects.
-                45
-            } else {
-                60
-            })
+        if draining == self.reported_draining
+            && since_heartbeat
+                < Duration::from_secs(if hiccup {
+                    15
+                } else if recent_hiccup {
+                    30
+                } else if matches!(region_id, RegionId::Asia | RegionId::SouthAmerica) {
+                    // Mitigate frequent disconnects.
+                    45
+                } else {
+                    60
+                })
         {
             return;
         }
         self.last_heartbeat = Some(now);
+        self.reported_draining = draining;
         let mut requests = Vec::new();
         if since_message > Duration::from_secs(130) {
             // Fail-open to avoid locking players out.
@@ -257,6 +266,7 @@ impl PlasmaActlet {
             missed_ticks,
             client_hash,
             date_certificate_expires: self.date_certificate_expires,
+            draining,
             realms: realms
                 .realms()
                 .map(|(realm_id, r)| {
@@ -692,8 +702,11 @@ impl<G: ArenaService> Handler<PlasmaUpdate> for ServerActor<G> {
                         .collect();
                     *self.plasma.cors_alternative_domains.lock().unwrap() =
                         self.system.alternative_domains.clone();
-                    if let Some((config, date_certificate_expires)) = load_domains::<G>(&*domains) {
-                        self.plasma.rustls_config.reload_from_config(config);
+                    if let Some(rustls_config) = &self.plasma.rustls_config
+                        && let Some((config, date_certificate_expires)) =
+                            load_domains::<G>(&*domains)
+                    {
+                        rustls_config.reload_from_config(config);
                         self.plasma.date_certificate_expires = Some(date_certificate_expires);
                     }
                 }
@@ -6,6 +6,7 @@ use crate::rate_limiter::RateLimiterProps;
 use crate::service::{
     ArenaService, InvitationRepo, LeaderboardRepo, MetricRepo, RealmRepo, ShardContextProvider,
 };
+use crate::shutdown::Drain;
 use crate::{ArenaId, ClientHash, PlasmaRequestV1, Referrer, RegionId, ServerId};
 use actix::{Actor, AsyncContext, Context as ActorContext};
 use axum_server::tls_rustls::RustlsConfig;
@@ -45,6 +46,7 @@ pub struct ServerActor<G: ArenaService> {
     last_tick_end: Instant,
 
     /// Misc.
+    pub(crate) drain: Option<Drain>,
     stop_tx: Option<oneshot::Sender<()>>,
 }
 
@@ -104,7 +106,7 @@ impl<G: ArenaService> ServerActor<G> {
         bots: Option<u16>,
         ads_txt: Arc<RwLock<HashMap<Option<Referrer>, Bytes>>>,
         server_token: &'static AtomicU64,
-        rustls_config: RustlsConfig,
+        rustls_config: Option<RustlsConfig>,
         cors_alternative_domains: &'static Mutex<Arc<[DomainName]>>,
         domain_backup: Option<Arc<str>>,
         client_authenticate: RateLimiterProps,
@@ -130,6 +132,7 @@ impl<G: ArenaService> ServerActor<G> {
             metrics: MetricRepo::new(),
             last_update: now,
             last_tick_end: now,
+            drain: None,
             stop_tx: Some(stop_tx),
         }
     }
@@ -248,9 +251,11 @@ impl<G: ArenaService> ServerActor<G> {
             &mut self.metrics,
             self.region_id,
             self.admin.client_hash,
+            self.drain.is_some(),
             ctx,
         );
         self.realms
             .collect_arenas(self.server_id, &mut self.invitations, &self.plasma);
+        self.update_drain(ctx);
     }
 }
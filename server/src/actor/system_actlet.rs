@@ -182,6 +182,8 @@ impl<G: ArenaService> Handler<SystemRequest> for ServerActor<G> {
                 self.plasma.role.is_unlisted().then_some(self.server_id),
                 self.server_id,
             )
+            // A draining server is unhealthy.
+            .filter(|&(server_id, _)| self.drain.is_none() || server_id != self.server_id)
             .min_by_key(|&(_, priority)| priority)
             .map(|(s, _)| s)
             .unwrap_or(self.server_id)
@@ -1,10 +1,11 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use crate::{RegionId, ServerId, ServerKind, ServerToken};
+use crate::{IpNetwork, RegionId, ServerId, ServerKind, ServerToken};
 use clap::Parser;
 use log::LevelFilter;
 use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
 
 /// Server options, to be specified as arguments.
 #[derive(Debug, Parser)]
@@ -58,14 +59,16 @@ pub struct Options {
     #[deprecated = "now from game id"]
     #[clap(long)]
     pub domain: Option<String>,
-    /// Certificate chain path.
+    /// Certificate chain path, reloaded when modified. Overrides certificates from plasma.
     #[clap(long)]
-    #[deprecated]
-    pub certificate_path: Option<String>,
-    /// Private key path.
+    pub certificate_path: Option<PathBuf>,
+    /// Private key path, reloaded when modified.
     #[clap(long)]
-    #[deprecated]
-    pub private_key_path: Option<String>,
+    pub private_key_path: Option<PathBuf>,
+    /// Directory in which a local ACME client (e.g. `certbot --webroot`) writes HTTP-01
+    /// challenges, under `.well-known/acme-challenge`.
+    #[clap(long)]
+    pub acme_webroot: Option<PathBuf>,
     /// HTTP request bandwidth limiting (in bytes per second).
     #[clap(long, default_value = "500000")]
     pub http_bandwidth_limit: u32,
@@ -74,12 +77,25 @@ pub struct Options {
     /// Implicit minimum is double the total size of the client static files.
     #[clap(long)]
     pub http_bandwidth_burst: Option<u32>,
+    /// Path to an IP access list (one `allow`/`deny` rule per line), reloaded when modified.
+    #[clap(long)]
+    pub ip_access_list_path: Option<PathBuf>,
+    /// Expect a PROXY protocol (v1 or v2) header on every TCP connection, e.g. from a load balancer.
+    #[clap(long)]
+    pub proxy_protocol: bool,
+    /// Comma-separated networks of reverse proxies whose `Forwarded`/`X-Forwarded-For` headers are
+    /// believed. If specified, PROXY protocol headers are only accepted from these networks.
+    #[clap(long, value_delimiter = ',')]
+    pub trusted_proxies: Vec<IpNetwork>,
     /// Client authenticate rate limiting period (in seconds).
     #[clap(long, default_value = "10")]
     pub client_authenticate_rate_limit: u64,
     /// Client authenticate rate limiting burst.
     #[clap(long, default_value = "16")]
     pub client_authenticate_burst: u32,
+    /// How long to wait for matches to end when draining (in seconds), upon SIGTERM or Ctrl+C.
+    #[clap(long, default_value = "600")]
+    pub drain_timeout: u64,
     #[clap(long)]
     pub cpu_profile: bool,
     #[clap(long)]
@@ -90,9 +106,7 @@ impl Options {
     pub(crate) const STANDARD_HTTPS_PORT: u16 = 443;
     pub(crate) const STANDARD_HTTP_PORT: u16 = 80;
 
-    #[deprecated]
-    pub(crate) fn certificate_private_key_paths(&self) -> Option<(&str, &str)> {
-        #[allow(deprecated)]
+    pub(crate) fn certificate_private_key_paths(&self) -> Option<(&Path, &Path)> {
         self.certificate_path
             .as_deref()
             .zip(self.private_key_path.as_deref())
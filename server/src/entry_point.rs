@@ -7,7 +7,10 @@
 use crate::actor::ServerActor;
 use crate::cli::Options;
 use crate::files::{set_open_file_limit, static_size_and_hash};
-use crate::net::{get_own_public_ip, ip_to_region_id, load_domains, CustomAcceptor, IpRateLimiter};
+use crate::net::{
+    get_own_public_ip, ip_to_region_id, load_domains, CertificateFiles, CustomAcceptor,
+    IpAccessList, IpRateLimiter, TrustedProxies,
+};
 use crate::rate_limiter::RateLimiterProps;
 use crate::router::new_router;
 use crate::service::ArenaService;
@@ -28,12 +31,11 @@ use kodiak_common::rand::{thread_rng, Rng};
 use kodiak_common::DomainName;
 use log::{error, info, warn};
 use minicdn::MiniCdn;
-use std::borrow::Cow;
 use std::fs::File;
 use std::io::{Read, Write};
 use std::net::{IpAddr, SocketAddr};
 use std::process::ExitCode;
-use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
 use std::sync::{Arc, LazyLock, Mutex, RwLock};
 use std::time::Duration;
 use tokio::net::TcpStream;
@@ -42,6 +44,9 @@ use tokio::runtime::Builder;
 /// 0 is no redirect.
 pub static REDIRECT_TO_SERVER_ID: AtomicU8 = AtomicU8::new(0);
 
+/// The server started draining, see [`crate::shutdown::Drain`].
+pub static DRAINING: AtomicBool = AtomicBool::new(false);
+
 /// Admin password.
 pub static SERVER_TOKEN: AtomicU64 = AtomicU64::new(0);
 
@@ -49,9 +54,29 @@ pub static SERVER_TOKEN: AtomicU64 = AtomicU64::new(0);
 pub static HTTP_RATE_LIMITER: LazyLock<Mutex<IpRateLimiter>> =
     LazyLock::new(|| Mutex::new(IpRateLimiter::new_bandwidth_limiter(1, 0)));
 
+// Will be overwritten first thing.
+pub static IP_ACCESS_LIST: LazyLock<Mutex<IpAccessList>> =
+    LazyLock::new(|| Mutex::new(IpAccessList::new(None)));
+
 pub static CORS_ALTERNATIVE_DOMAINS: LazyLock<Mutex<Arc<[DomainName]>>> =
     LazyLock::new(|| Mutex::new(Vec::new().into()));
 
+/// Resolves on SIGTERM, which asks for a graceful drain (e.g. during a rolling deploy).
+async fn terminate_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+                return;
+            }
+            Err(e) => error!("could not listen for SIGTERM: {e}"),
+        }
+    }
+    std::future::pending().await
+}
+
 pub struct Authenticated;
 
 impl Authenticated {
@@ -233,6 +258,16 @@ where
 
         *HTTP_RATE_LIMITER.lock().unwrap() =
             IpRateLimiter::new_bandwidth_limiter(options.http_bandwidth_limit, bandwidth_burst);
+        *IP_ACCESS_LIST.lock().unwrap() = IpAccessList::new(options.ip_access_list_path.clone());
+        if options.ip_access_list_path.is_some() {
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(5));
+                loop {
+                    interval.tick().await;
+                    IP_ACCESS_LIST.lock().unwrap().reload_if_modified();
+                }
+            });
+        }
         SERVER_TOKEN.store(
             if let Some(token) = options.server_token {
                 token.0.get()
@@ -276,28 +311,39 @@ where
 
         let game_client = Arc::new(RwLock::new(game_client));
         let ads_txt = Arc::default();
-        #[allow(deprecated)]
-        let (certificate, private_key) = options
+        let mut certificate_files = options
             .certificate_private_key_paths()
-            .map(|(c, p)| {
-                (
-                    Cow::Owned(std::fs::read_to_string(c).unwrap()),
-                    Cow::Owned(std::fs::read_to_string(p).unwrap()),
-                )
-            })
-            .unwrap_or((
-                Cow::Borrowed(include_str!("./net/certificate.pem")),
-                Cow::Borrowed(include_str!("./net/private_key.pem")),
-            ));
-        let rustls_config = RustlsConfig::from_config(
-            load_domains::<G>(&[DomainDto {
-                domain: G::GAME_CONSTANTS.domain_name(),
-                certificate: certificate.into(),
-                private_key: private_key.into(),
-            }])
-            .unwrap()
-            .0,
-        );
+            .map(|(c, p)| CertificateFiles::new(c.to_owned(), p.to_owned()));
+        let rustls_config =
+            RustlsConfig::from_config(if let Some(files) = &mut certificate_files {
+                files
+                    .load_if_modified::<G>()
+                    .expect("could not load certificate files")
+            } else {
+                load_domains::<G>(&[DomainDto {
+                    domain: G::GAME_CONSTANTS.domain_name(),
+                    certificate: include_str!("./net/certificate.pem").into(),
+                    private_key: include_str!("./net/private_key.pem").into(),
+                }])
+                .unwrap()
+                .0
+            });
+        // Certificates from files take precedence over those from plasma.
+        let plasma_rustls_config = certificate_files.is_none().then(|| rustls_config.clone());
+        if let Some(mut files) = certificate_files {
+            let rustls_config = rustls_config.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(5));
+                loop {
+                    interval.tick().await;
+                    // Existing connections keep their certificate. WebTransport picks up the new
+                    // one when it next reloads its config.
+                    if let Some(config) = files.load_if_modified::<G>() {
+                        rustls_config.reload_from_config(config);
+                    }
+                }
+            });
+        }
         // Awaiting https://github.com/actix/actix-net/issues/588
         let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
 
@@ -324,7 +370,7 @@ where
                 options.bots,
                 Arc::clone(&ads_txt),
                 &SERVER_TOKEN,
-                rustls_config.clone(),
+                plasma_rustls_config,
                 &*CORS_ALTERNATIVE_DOMAINS,
                 Some(options.domain_backup.into()),
                 RateLimiterProps::new(
@@ -371,7 +417,17 @@ where
             });
         }
 
-        let app = new_router(server_id, srv.clone(), game_client, ads_txt);
+        let acme_webroot = options.acme_webroot.as_deref().map(Arc::from);
+        let trusted_proxies = TrustedProxies::new(options.trusted_proxies.clone());
+        let proxy_protocol = options.proxy_protocol;
+        let app = new_router(
+            server_id,
+            srv.clone(),
+            game_client,
+            ads_txt,
+            trusted_proxies.clone(),
+            acme_webroot.clone(),
+        );
 
         #[cfg(not(debug_assertions))]
         let http_app = axum::Router::new().fallback_service(axum::routing::get(
@@ -408,15 +464,28 @@ where
                     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())
             },
         ));
+        #[cfg(not(debug_assertions))]
+        let http_app = http_app.route(
+            crate::router::ACME_CHALLENGE_ROUTE,
+            crate::router::acme_challenge_route(acme_webroot),
+        );
 
         #[cfg(debug_assertions)]
         let http_app = app.clone();
 
         trait ConfigureExt<S, A> {
-            fn configure(self) -> axum_server::Server<CustomAcceptor<S, A>>;
+            fn configure(
+                self,
+                proxy_protocol: bool,
+                trusted_proxies: &TrustedProxies,
+            ) -> axum_server::Server<CustomAcceptor<S, A>>;
         }
         impl<S, A: Accept<TcpStream, S>> ConfigureExt<S, A> for axum_server::Server<A> {
-            fn configure(mut self) -> axum_server::Server<CustomAcceptor<S, A>> {
+            fn configure(
+                mut self,
+                proxy_protocol: bool,
+                trusted_proxies: &TrustedProxies,
+            ) -> axum_server::Server<CustomAcceptor<S, A>> {
                 let http = self.http_builder();
                 // `header_read_timeout` applies to all requests.
                 http.http1()
@@ -433,19 +502,21 @@ where
                     .max_header_list_size(512 * 1024)
                     .max_send_buf_size(64 * 1024)
                     .max_concurrent_streams(16);
-                self.map(CustomAcceptor::new)
+                self.map(|inner| {
+                    CustomAcceptor::new(inner, proxy_protocol, trusted_proxies.clone())
+                })
             }
         }
 
         let http_server = axum_server::bind(SocketAddr::from(([0, 0, 0, 0], http_port)))
-            .configure()
+            .configure(proxy_protocol, &trusted_proxies)
             .serve(http_app.into_make_service_with_connect_info::<SocketAddr>());
 
         let https_server = axum_server::bind_rustls(
             SocketAddr::from(([0, 0, 0, 0], https_port)),
             rustls_config.clone(),
         )
-        .configure()
+        .configure(proxy_protocol, &trusted_proxies)
         .serve(app.into_make_service_with_connect_info::<SocketAddr>());
 
         let wt_server: OptionFuture<_> = if G::GAME_CONSTANTS.udp_enabled {
@@ -455,28 +526,65 @@ where
         }
         .into();
 
+        let terminate = terminate_signal();
+        tokio::pin!(http_server, https_server, wt_server, terminate);
+        let mut stop_rx = stop_rx;
         let mut exit_code = ExitCode::FAILURE;
-        tokio::select! {
-            result = http_server => {
-                error!("http server stopped: {result:?}");
-            }
-            result = https_server => {
-                error!("https server stopped: {result:?}");
-            }
-            result = wt_server, if G::GAME_CONSTANTS.udp_enabled => {
-                error!("wt server stopped: {result:?}");
-            }
-            result = stop_rx => {
-                if result.is_ok () {
-                    error!("server actor stopped");
-                } else {
-                    error!("server actor dropped");
+        // `terminate` must not be polled again after it completes. Can't use `DRAINING` for this,
+        // since the server actor sets it later.
+        let mut sigterm_received = false;
+        let mut ctrl_c_received = false;
+        let mut ctrl_c_failed = false;
+        let start_drain = || {
+            srv.do_send(crate::shutdown::StartDrain {
+                timeout: Duration::from_secs(options.drain_timeout),
+            })
+        };
+        loop {
+            tokio::select! {
+                result = &mut http_server => {
+                    error!("http server stopped: {result:?}");
+                }
+                result = &mut https_server => {
+                    error!("https server stopped: {result:?}");
+                }
+                result = &mut wt_server, if G::GAME_CONSTANTS.udp_enabled => {
+                    error!("wt server stopped: {result:?}");
+                }
+                result = &mut stop_rx => {
+                    if result.is_err() {
+                        error!("server actor dropped");
+                    } else if DRAINING.load(Ordering::Relaxed) {
+                        warn!("server actor stopped after draining");
+                        exit_code = ExitCode::SUCCESS;
+                    } else {
+                        error!("server actor stopped");
+                    }
+                }
+                _ = &mut terminate, if !sigterm_received => {
+                    warn!("received SIGTERM");
+                    sigterm_received = true;
+                    start_drain();
+                    continue;
+                }
+                result = tokio::signal::ctrl_c(), if !ctrl_c_failed => {
+                    if let Err(e) = result {
+                        error!("could not listen for Ctrl+C: {e}");
+                        ctrl_c_failed = true;
+                        continue;
+                    } else if ctrl_c_received {
+                        // Don't make an impatient operator wait for the drain.
+                        error!("received second Ctrl+C / SIGINT");
+                        exit_code = ExitCode::SUCCESS;
+                    } else {
+                        warn!("received Ctrl+C / SIGINT, press again to stop immediately");
+                        ctrl_c_received = true;
+                        start_drain();
+                        continue;
+                    }
                 }
             }
-            _ = tokio::signal::ctrl_c() => {
-                error!("received Ctrl+C / SIGINT");
-                exit_code = ExitCode::SUCCESS;
-            }
+            break;
         }
 
         srv.do_send(crate::shutdown::Shutdown);
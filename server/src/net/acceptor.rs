@@ -1,15 +1,22 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
+use super::ip_access_list::IpAccessList;
 use super::ip_rate_limiter::ConnectionPermit;
+use super::proxy_protocol::read_proxy_header;
+use super::trusted_proxies::TrustedProxies;
 use axum::extract::Request;
+use axum::http::StatusCode;
 use axum_server::accept::Accept;
+use futures::future::{BoxFuture, Either};
 use log::error;
 use socket2::{SockRef, TcpKeepalive};
 use std::future::Future;
 use std::io::{self, ErrorKind};
 use std::marker::PhantomData;
+use std::net::{IpAddr, SocketAddr};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
 use tokio::io::{AsyncRead, AsyncWrite};
@@ -17,15 +24,80 @@ use tokio::net::TcpStream;
 use tokio::sync::mpsc;
 use tower::Service;
 
-#[derive(Clone, Copy, Debug, Default)]
-pub struct CustomAcceptor<S, I>(I, PhantomData<S>);
+/// Accepts TCP connections, applying access control and connection limits to the original client
+/// (as reported by a PROXY protocol header, if enabled). Connections from trusted proxies are
+/// exempt, and their clients are checked per request instead (see [`ForwardedPermit`]).
+#[derive(Clone, Debug)]
+pub struct CustomAcceptor<S, I> {
+    inner: I,
+    /// Expect a PROXY protocol header at the start of every connection.
+    proxy_protocol: bool,
+    /// If non-empty, only these peers may send PROXY protocol headers. Exempt from access control
+    /// and connection limits.
+    trusted_proxies: TrustedProxies,
+    _spooky: PhantomData<S>,
+}
 
 impl<S, I> CustomAcceptor<S, I> {
-    pub(crate) fn new(inner: I) -> Self {
-        Self(inner, PhantomData)
+    pub(crate) fn new(inner: I, proxy_protocol: bool, trusted_proxies: TrustedProxies) -> Self {
+        Self {
+            inner,
+            proxy_protocol,
+            trusted_proxies,
+            _spooky: PhantomData,
+        }
+    }
+}
+
+/// Address of the original client of a TCP connection, which differs from the peer address if
+/// the connection came through a PROXY protocol load balancer.
+///
+/// Unlike [`axum::extract::ConnectInfo`], this survives being inserted before the router.
+#[derive(Clone, Copy, Debug)]
+pub struct PeerAddr(pub SocketAddr);
+
+/// Counts a client whose address was forwarded by a trusted proxy towards its connection limit,
+/// for as long as its request (or the WebSocket it upgrades to) lasts.
+#[derive(Clone, Debug)]
+pub struct ForwardedPermit(#[allow(unused)] Arc<ConnectionPermit>);
+
+impl ForwardedPermit {
+    /// Applies access control and connection limits to `client`, whose address was forwarded by
+    /// the trusted proxy `peer`. Returns [`None`] if `client` is `peer`, which was already checked
+    /// (or exempted) when it connected.
+    pub(crate) fn new(client: IpAddr, peer: IpAddr) -> Result<Option<Self>, StatusCode> {
+        if client == peer {
+            return Ok(None);
+        }
+        if IpAccessList::should_deny_outer(client, "forwarded request") {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        ConnectionPermit::new(client, "forwarded request")
+            .map(|permit| Some(Self(Arc::new(permit))))
+            .ok_or(StatusCode::TOO_MANY_REQUESTS)
+    }
+}
+
+/// Applies access control and connection limits to a new connection from `ip`, returning the
+/// permit to hold for its lifetime. Trusted proxies relay many clients, so they are exempt.
+fn admit(ip: IpAddr, trusted_proxies: &TrustedProxies) -> io::Result<Option<ConnectionPermit>> {
+    if trusted_proxies.contains(ip) {
+        return Ok(None);
     }
+    if IpAccessList::should_deny_outer(ip, "TCP connection") {
+        return Err(io::Error::new(
+            ErrorKind::PermissionDenied,
+            "denied by IP access list",
+        ));
+    }
+    ConnectionPermit::new(ip, "TCP connection")
+        .map(Some)
+        .ok_or_else(|| io::Error::new(ErrorKind::PermissionDenied, "too many connections"))
 }
 
+/// How long a load balancer has to send the PROXY protocol header.
+const PROXY_HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[pin_project::pin_project(project = FutureOrImmediateProj)]
 pub enum FutureOrImmediate<F: Future> {
     Future(#[pin] F),
@@ -48,39 +120,88 @@ impl<F: Future> Future for FutureOrImmediate<F> {
     }
 }
 
-impl<S, I: Accept<KillSwitchStream<TcpStream>, AddExtension<S, KillSwitch>>>
-    axum_server::accept::Accept<TcpStream, S> for CustomAcceptor<S, I>
+type ConnectionService<S> = AddExtension<AddExtension<S, KillSwitch>, PeerAddr>;
+
+impl<S, I> axum_server::accept::Accept<TcpStream, S> for CustomAcceptor<S, I>
+where
+    S: Send + 'static,
+    I: Accept<KillSwitchStream<TcpStream>, ConnectionService<S>> + Clone + Send + Sync + 'static,
+    I::Future: Send + 'static,
 {
-    type Future = FutureOrImmediate<I::Future>;
-    type Service = <I as Accept<KillSwitchStream<TcpStream>, AddExtension<S, KillSwitch>>>::Service;
-    type Stream = <I as Accept<KillSwitchStream<TcpStream>, AddExtension<S, KillSwitch>>>::Stream;
-
-    fn accept(&self, stream: TcpStream, service: S) -> FutureOrImmediate<I::Future> {
-        let Some(_permit) = stream
-            .peer_addr()
-            .ok()
-            .map(|s| s.ip())
-            .and_then(|ip| ConnectionPermit::new(ip, "TCP connection"))
-        else {
-            return FutureOrImmediate::Immediate(Some(Err(io::Error::new(
-                ErrorKind::PermissionDenied,
-                "too many connections",
-            ))));
+    type Future = Either<
+        FutureOrImmediate<I::Future>,
+        BoxFuture<'static, io::Result<(Self::Stream, Self::Service)>>,
+    >;
+    type Service = <I as Accept<KillSwitchStream<TcpStream>, ConnectionService<S>>>::Service;
+    type Stream = <I as Accept<KillSwitchStream<TcpStream>, ConnectionService<S>>>::Stream;
+
+    fn accept(&self, stream: TcpStream, service: S) -> Self::Future {
+        if !self.proxy_protocol {
+            return Either::Left(match stream.peer_addr() {
+                Ok(addr) => {
+                    Self::accept_from(&self.inner, &self.trusted_proxies, stream, addr, service)
+                }
+                Err(e) => FutureOrImmediate::Immediate(Some(Err(e))),
+            });
+        }
+
+        let inner = self.inner.clone();
+        let trusted_proxies = self.trusted_proxies.clone();
+        Either::Right(Box::pin(async move {
+            let mut stream = stream;
+            let peer = stream.peer_addr()?;
+            if !trusted_proxies.is_empty() && !trusted_proxies.contains(peer.ip()) {
+                return Err(io::Error::new(
+                    ErrorKind::PermissionDenied,
+                    "untrusted PROXY protocol peer",
+                ));
+            }
+            let source = tokio::time::timeout(PROXY_HEADER_TIMEOUT, read_proxy_header(&mut stream))
+                .await
+                .map_err(|_| io::Error::new(ErrorKind::TimedOut, "PROXY header timeout"))??;
+            // Health checks from the load balancer itself have no source.
+            Self::accept_from(
+                &inner,
+                &trusted_proxies,
+                stream,
+                source.unwrap_or(peer),
+                service,
+            )
+            .await
+        }))
+    }
+}
+
+impl<S, I: Accept<KillSwitchStream<TcpStream>, ConnectionService<S>>> CustomAcceptor<S, I> {
+    /// Accepts a connection on behalf of the client at `addr`.
+    fn accept_from(
+        inner: &I,
+        trusted_proxies: &TrustedProxies,
+        stream: TcpStream,
+        addr: SocketAddr,
+        service: S,
+    ) -> FutureOrImmediate<I::Future> {
+        let _permit = match admit(addr.ip(), trusted_proxies) {
+            Ok(permit) => permit,
+            Err(e) => return FutureOrImmediate::Immediate(Some(Err(e))),
         };
 
         nodelay_keepalive(&stream, 10, 2);
 
         let (kill, killed) = mpsc::channel::<()>(1);
 
-        FutureOrImmediate::Future(self.0.accept(
+        FutureOrImmediate::Future(inner.accept(
             KillSwitchStream {
                 stream,
                 killed,
                 _permit,
             },
             AddExtension {
-                service,
-                value: KillSwitch { kill },
+                service: AddExtension {
+                    service,
+                    value: KillSwitch { kill },
+                },
+                value: PeerAddr(addr),
             },
         ))
     }
@@ -155,7 +276,8 @@ pub struct KillSwitchStream<S> {
     stream: S,
     #[pin]
     killed: mpsc::Receiver<()>,
-    _permit: ConnectionPermit,
+    /// [`None`] for trusted proxies.
+    _permit: Option<ConnectionPermit>,
 }
 
 #[inline(always)]
@@ -218,3 +340,43 @@ impl<S: AsyncWrite> AsyncWrite for KillSwitchStream<S> {
         self.project().stream.poll_shutdown(cx)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{admit, ForwardedPermit};
+    use crate::entry_point::HTTP_RATE_LIMITER;
+    use crate::net::{IpRateLimiter, TrustedProxies};
+    use crate::rate_limiter::RateLimiterProps;
+    use crate::IpNetwork;
+    use std::net::IpAddr;
+    use std::str::FromStr;
+
+    #[test]
+    fn trusted_proxy() {
+        *HTTP_RATE_LIMITER.lock().unwrap() = IpRateLimiter::from(RateLimiterProps::no_limit());
+        let trusted = TrustedProxies::new(vec![IpNetwork::from_str("10.0.0.0/8").unwrap()]);
+        let proxy = IpAddr::from([10, 0, 0, 1]);
+
+        // The proxy isn't limited, since it relays many clients.
+        let connections = (0..50)
+            .map(|_| admit(proxy, &trusted).unwrap())
+            .collect::<Vec<_>>();
+        assert!(connections.iter().all(Option::is_none));
+        let clients = (0..50)
+            .map(|i| ForwardedPermit::new(IpAddr::from([198, 51, 100, i]), proxy))
+            .collect::<Vec<_>>();
+        assert!(clients.iter().all(|permit| matches!(permit, Ok(Some(_)))));
+        assert!(matches!(ForwardedPermit::new(proxy, proxy), Ok(None)));
+
+        // But each forwarded client is, like a client that connects directly.
+        let client = IpAddr::from([198, 51, 100, 200]);
+        let forwarded = (0..50)
+            .map_while(|_| ForwardedPermit::new(client, proxy).ok().flatten())
+            .collect::<Vec<_>>();
+        assert!((12..=15).contains(&forwarded.len()), "{}", forwarded.len());
+        let direct = (0..50)
+            .map_while(|_| admit(IpAddr::from([192, 0, 2, 1]), &trusted).ok())
+            .collect::<Vec<_>>();
+        assert!((12..=15).contains(&direct.len()), "{}", direct.len());
+    }
+}
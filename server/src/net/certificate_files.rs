@@ -0,0 +1,166 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::tls::load_domains_with_default;
+use crate::{ArenaService, DomainDto, DomainName};
+use log::{error, info};
+use rustls::server::ServerConfig;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Certificate chain and private key PEM files, e.g. maintained by a local ACME client, which are
+/// reloaded when they change.
+#[derive(Debug)]
+pub struct CertificateFiles {
+    certificate_path: PathBuf,
+    private_key_path: PathBuf,
+    /// Modification times of the last successfully loaded files.
+    modified: Option<(SystemTime, SystemTime)>,
+    /// Only log once per failure to load.
+    load_failed: bool,
+}
+
+impl CertificateFiles {
+    pub fn new(certificate_path: PathBuf, private_key_path: PathBuf) -> Self {
+        Self {
+            certificate_path,
+            private_key_path,
+            modified: None,
+            load_failed: false,
+        }
+    }
+
+    /// Returns a new config if either file changed since the last successful load, and both are
+    /// valid. ACME clients may replace the files one at a time, so a mismatched pair is retried
+    /// next time instead of being remembered.
+    pub(crate) fn load_if_modified<G: ArenaService>(&mut self) -> Option<Arc<ServerConfig>> {
+        self.load_if_modified_for(G::GAME_CONSTANTS.domain_name())
+    }
+
+    fn load_if_modified_for(&mut self, domain: DomainName) -> Option<Arc<ServerConfig>> {
+        let result = self.modified().and_then(|modified| {
+            if self.modified == Some(modified) {
+                return Ok(None);
+            }
+            let dto = DomainDto {
+                domain,
+                certificate: std::fs::read_to_string(&self.certificate_path)?.into(),
+                private_key: std::fs::read_to_string(&self.private_key_path)?.into(),
+            };
+            load_domains_with_default(&[dto], domain)
+                .map(|(config, _)| Some((modified, config)))
+                .ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "invalid certificate or key")
+                })
+        });
+        match result {
+            Ok(Some((modified, config))) => {
+                self.modified = Some(modified);
+                self.load_failed = false;
+                info!("loaded certificate from {:?}", self.certificate_path);
+                Some(config)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                // Keep serving the previous certificate.
+                if !std::mem::replace(&mut self.load_failed, true) {
+                    error!(
+                        "could not load certificate {:?} with key {:?}: {e}",
+                        self.certificate_path, self.private_key_path
+                    );
+                }
+                None
+            }
+        }
+    }
+
+    fn modified(&self) -> io::Result<(SystemTime, SystemTime)> {
+        Ok((
+            std::fs::metadata(&self.certificate_path)?.modified()?,
+            std::fs::metadata(&self.private_key_path)?.modified()?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CertificateFiles;
+    use crate::DomainName;
+    use std::fs::File;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    const CERTIFICATE: &str = include_str!("./certificate.pem");
+    const PRIVATE_KEY: &str = include_str!("./private_key.pem");
+
+    fn domain() -> DomainName {
+        DomainName::new("localhost.test").unwrap()
+    }
+
+    /// Writes `contents` to `path`, with an explicit modification time so tests don't depend on
+    /// the file system's timestamp granularity.
+    fn write(path: &Path, contents: &str, modified: SystemTime) {
+        std::fs::write(path, contents).unwrap();
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    fn files(name: &str) -> (PathBuf, PathBuf, CertificateFiles) {
+        let dir = std::env::temp_dir().join(format!(
+            "kodiak_certificate_files_{name}_{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let certificate_path = dir.join("certificate.pem");
+        let private_key_path = dir.join("private_key.pem");
+        let files = CertificateFiles::new(certificate_path.clone(), private_key_path.clone());
+        (certificate_path, private_key_path, files)
+    }
+
+    #[test]
+    fn reload_when_modified() {
+        let (certificate_path, private_key_path, mut files) = files("reload");
+        let t = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        write(&certificate_path, CERTIFICATE, t);
+        write(&private_key_path, PRIVATE_KEY, t);
+
+        assert!(files.load_if_modified_for(domain()).is_some());
+        assert!(files.load_if_modified_for(domain()).is_none());
+
+        write(&certificate_path, CERTIFICATE, t + Duration::from_secs(60));
+        assert!(files.load_if_modified_for(domain()).is_some());
+        assert!(files.load_if_modified_for(domain()).is_none());
+
+        let _ = std::fs::remove_dir_all(certificate_path.parent().unwrap());
+    }
+
+    #[test]
+    fn keep_old_when_invalid() {
+        let (certificate_path, private_key_path, mut files) = files("invalid");
+        let t = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        write(&certificate_path, CERTIFICATE, t);
+        write(&private_key_path, PRIVATE_KEY, t);
+        assert!(files.load_if_modified_for(domain()).is_some());
+        let loaded = files.modified;
+
+        // The caller keeps serving the old config, and the bad pair is retried until replaced.
+        let t = t + Duration::from_secs(60);
+        write(&private_key_path, "not a private key", t);
+        assert!(files.load_if_modified_for(domain()).is_none());
+        assert!(files.load_if_modified_for(domain()).is_none());
+        assert_eq!(files.modified, loaded);
+        assert!(files.load_failed);
+
+        write(&private_key_path, PRIVATE_KEY, t + Duration::from_secs(60));
+        assert!(files.load_if_modified_for(domain()).is_some());
+        assert!(!files.load_failed);
+
+        let _ = std::fs::remove_dir_all(certificate_path.parent().unwrap());
+    }
+}
@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use crate::RegionId;
+use crate::{CountryCode, RegionId};
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
 use hyper::http::HeaderValue;
@@ -37,6 +37,17 @@ pub fn ip_to_region_id(ip: IpAddr) -> Option<RegionId> {
     DB_IP.get(&ip).map(region_to_region_id)
 }
 
+pub fn ip_to_country_code(ip: IpAddr) -> Option<CountryCode> {
+    use db_ip::{include_country_code_database, DbIpDatabase};
+
+    static DB_IP: LazyLock<DbIpDatabase<db_ip::CountryCode>> =
+        LazyLock::new(|| include_country_code_database!());
+
+    DB_IP
+        .get(&ip)
+        .and_then(|country_code| CountryCode::from_str(&country_code.to_string()).ok())
+}
+
 pub trait IpAddrType: Hash + Eq + Copy + Display + FromStr<Err = AddrParseError> {
     /// URLs that return this type of address.
     const CHECKERS: &'static [&'static str];
@@ -0,0 +1,315 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use super::ip::{ip_to_country_code, ip_to_region_id};
+use crate::entry_point::IP_ACCESS_LIST;
+use crate::rate_limiter::{RateLimiterProps, RateLimiterState};
+use crate::{IpAccessRule, IpLocation};
+use log::{error, info, warn};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Decides which IP addresses may connect at all, based on CIDR, region and country rules.
+///
+/// Rules come from an optional file (reloaded when it changes) and from admin requests at runtime.
+/// The most specific matching rule (see [`IpAccessRule::specificity`]) decides, so an allow rule
+/// can punch a hole in a denied range or region, and a deny rule can in turn exclude a subnet of
+/// an allowed range. If equally specific rules disagree, deny wins. Unmatched addresses are
+/// allowed.
+#[derive(Debug)]
+pub struct IpAccessList {
+    /// Loaded from [`Self::path`].
+    file_rules: Vec<IpAccessRule>,
+    /// Added by admin requests.
+    admin_rules: Vec<IpAccessRule>,
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    /// Only log once per failure to read [`Self::path`].
+    read_failed: bool,
+    warning_limiter: RateLimiterState,
+}
+
+const WARNING_LIMIT: RateLimiterProps = RateLimiterProps::const_new(Duration::from_millis(100), 3);
+
+impl IpAccessList {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        let mut this = Self {
+            file_rules: Vec::new(),
+            admin_rules: Vec::new(),
+            path,
+            modified: None,
+            read_failed: false,
+            warning_limiter: Default::default(),
+        };
+        this.reload_if_modified();
+        this
+    }
+
+    /// Returns true if the connection should be refused, logging (rate limited) if so.
+    pub(crate) fn should_deny_outer(ip: IpAddr, label: &str) -> bool {
+        // Look up before locking, so other connections don't wait on it, and only if needed.
+        let (region, country) = IP_ACCESS_LIST.lock().unwrap().needs_location();
+        let location = IpLocation {
+            region_id: region.then(|| ip_to_region_id(ip)).flatten(),
+            country_code: country.then(|| ip_to_country_code(ip)).flatten(),
+        };
+        let mut this = IP_ACCESS_LIST.lock().unwrap();
+        let should_deny = this.should_deny_with_location(ip, || location);
+        if should_deny
+            && !this
+                .warning_limiter
+                .should_limit_rate_with_now(&WARNING_LIMIT, Instant::now())
+        {
+            warn!("Denying {label} for {ip}");
+        }
+        should_deny
+    }
+
+    /// Returns true if `ip` is denied by the rules.
+    pub fn should_deny(&self, ip: IpAddr) -> bool {
+        self.should_deny_with_location(ip, || IpLocation {
+            region_id: ip_to_region_id(ip),
+            country_code: ip_to_country_code(ip),
+        })
+    }
+
+    /// Like [`Self::should_deny`] but with a custom location lookup, which is called at most once.
+    pub fn should_deny_with_location(
+        &self,
+        ip: IpAddr,
+        location: impl FnOnce() -> IpLocation,
+    ) -> bool {
+        let mut location = Some(location);
+        let mut cached = IpLocation::default();
+        let mut lookup = || {
+            if let Some(location) = location.take() {
+                cached = location();
+            }
+            cached
+        };
+        // (specificity, deny) of the decisive rule so far.
+        let mut decisive = None;
+        for rule in self.rules() {
+            let key = Some((rule.specificity(), !rule.is_allow()));
+            if key > decisive && rule.matches(ip, &mut lookup) {
+                decisive = key;
+            }
+        }
+        decisive.is_some_and(|(_, deny)| deny)
+    }
+
+    /// Returns whether any rule needs the (region, country) of an address.
+    fn needs_location(&self) -> (bool, bool) {
+        (
+            self.rules().any(|rule| rule.region_id().is_some()),
+            self.rules().any(|rule| rule.country_code().is_some()),
+        )
+    }
+
+    /// All rules currently in effect (file rules first).
+    pub fn rules(&self) -> impl Iterator<Item = &IpAccessRule> + '_ {
+        self.file_rules.iter().chain(self.admin_rules.iter())
+    }
+
+    /// Returns false if the rule was already present.
+    pub fn add(&mut self, rule: IpAccessRule) -> bool {
+        if self.rules().any(|r| *r == rule) {
+            return false;
+        }
+        info!("adding IP access rule: {rule}");
+        self.admin_rules.push(rule);
+        true
+    }
+
+    /// Returns false if the rule wasn't present. File rules that are removed come back if the file
+    /// changes.
+    pub fn remove(&mut self, rule: IpAccessRule) -> bool {
+        let before = self.file_rules.len() + self.admin_rules.len();
+        self.file_rules.retain(|r| *r != rule);
+        self.admin_rules.retain(|r| *r != rule);
+        let removed = self.file_rules.len() + self.admin_rules.len() < before;
+        if removed {
+            info!("removed IP access rule: {rule}");
+        }
+        removed
+    }
+
+    /// Reloads file rules if the file's modification time changed. Called periodically.
+    pub(crate) fn reload_if_modified(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .and_then(|modified| {
+                if self.modified == Some(modified) {
+                    Ok(None)
+                } else {
+                    std::fs::read_to_string(path).map(|contents| Some((modified, contents)))
+                }
+            });
+        match result {
+            Ok(Some((modified, contents))) => {
+                self.file_rules = Self::parse(&contents);
+                self.modified = Some(modified);
+                self.read_failed = false;
+                info!(
+                    "loaded {} IP access rule(s) from {path:?}",
+                    self.file_rules.len()
+                );
+            }
+            Ok(None) => {}
+            Err(e) => {
+                // Keep the previous rules.
+                if !std::mem::replace(&mut self.read_failed, true) {
+                    error!("could not read IP access list {path:?}: {e}");
+                }
+            }
+        }
+    }
+
+    /// One rule per line. Blank lines and `#` comments are ignored. Invalid lines are logged and
+    /// skipped, so one typo doesn't take down the whole list.
+    fn parse(contents: &str) -> Vec<IpAccessRule> {
+        contents
+            .lines()
+            .enumerate()
+            .filter_map(|(i, line)| {
+                let line = line.split('#').next().unwrap_or("").trim();
+                if line.is_empty() {
+                    return None;
+                }
+                IpAccessRule::from_str(line)
+                    .map_err(|e| warn!("IP access list line {}: {e}: {line:?}", i + 1))
+                    .ok()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::IpAccessList;
+    use crate::{CountryCode, IpAccessRule, IpLocation, IpNetwork, RegionId};
+    use std::net::IpAddr;
+    use std::str::FromStr;
+
+    fn ip(s: &str) -> IpAddr {
+        IpAddr::from_str(s).unwrap()
+    }
+
+    fn located(region_id: RegionId, country_code: &str) -> impl Fn() -> IpLocation + Copy {
+        let country_code = CountryCode::from_str(country_code).unwrap();
+        move || IpLocation {
+            region_id: Some(region_id),
+            country_code: Some(country_code),
+        }
+    }
+
+    #[test]
+    fn network() {
+        let network = IpNetwork::from_str("192.0.2.77/24").unwrap();
+        assert_eq!(network.to_string(), "192.0.2.0/24");
+        assert!(network.contains(ip("192.0.2.1")));
+        assert!(network.contains(ip("::ffff:192.0.2.1")));
+        assert!(!network.contains(ip("192.0.3.1")));
+        assert!(!network.contains(ip("2001:db8::1")));
+
+        let network = IpNetwork::from_str("2001:db8::/32").unwrap();
+        assert!(network.contains(ip("2001:db8:ffff::1")));
+        assert!(!network.contains(ip("2001:db9::1")));
+
+        assert!(IpNetwork::from_str("0.0.0.0/0")
+            .unwrap()
+            .contains(ip("255.255.255.255")));
+        assert!(IpNetwork::from_str("10.0.0.0/33").is_err());
+        assert_eq!(
+            IpNetwork::from_str("10.1.2.3").unwrap(),
+            IpNetwork::from_str("10.1.2.3/32").unwrap()
+        );
+    }
+
+    #[test]
+    fn rules() {
+        let rules = IpAccessList::parse(
+            "
+            # Comment.
+            deny 10.0.0.0/8
+            allow 10.1.0.0/16 # Punch a hole.
+            deny region Asia
+            bogus line
+            ",
+        );
+        assert_eq!(rules.len(), 3);
+        for rule in &rules {
+            assert_eq!(IpAccessRule::from_str(&rule.to_string()).unwrap(), *rule);
+        }
+
+        let mut list = IpAccessList::new(None);
+        list.file_rules = rules;
+        let europe = located(RegionId::Europe, "FR");
+        let asia = located(RegionId::Asia, "JP");
+        assert!(list.should_deny_with_location(ip("10.2.3.4"), europe));
+        assert!(!list.should_deny_with_location(ip("10.1.3.4"), europe));
+        assert!(!list.should_deny_with_location(ip("11.2.3.4"), europe));
+        assert!(list.should_deny_with_location(ip("11.2.3.4"), asia));
+        assert!(!list.should_deny_with_location(ip("10.1.3.4"), asia));
+
+        // A more specific deny rule beats a less specific allow rule.
+        let rule = IpAccessRule::DenyNetwork(IpNetwork::from_str("10.1.2.0/24").unwrap());
+        assert!(list.add(rule));
+        assert!(list.should_deny_with_location(ip("10.1.2.3"), europe));
+        assert!(!list.should_deny_with_location(ip("10.1.3.4"), europe));
+        assert!(list.remove(rule));
+
+        // Equally specific rules that disagree deny.
+        let rule = IpAccessRule::AllowNetwork(IpNetwork::from_str("10.0.0.0/8").unwrap());
+        assert!(list.add(rule));
+        assert!(list.should_deny_with_location(ip("10.2.3.4"), europe));
+        assert!(list.remove(rule));
+
+        let rule = IpAccessRule::AllowNetwork(IpNetwork::from(ip("11.2.3.4")));
+        assert!(list.add(rule));
+        assert!(!list.add(rule));
+        assert!(!list.should_deny_with_location(ip("11.2.3.4"), asia));
+        assert!(list.remove(rule));
+        assert!(!list.remove(rule));
+        assert!(list.should_deny_with_location(ip("11.2.3.4"), asia));
+        assert_eq!(list.needs_location(), (true, false));
+    }
+
+    #[test]
+    fn countries() {
+        let rules = IpAccessList::parse(
+            "
+            deny region Europe
+            allow country fr
+            deny country US
+            deny country USA
+            ",
+        );
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[1].to_string(), "allow country FR");
+        for rule in &rules {
+            assert_eq!(IpAccessRule::from_str(&rule.to_string()).unwrap(), *rule);
+        }
+
+        let mut list = IpAccessList::new(None);
+        assert_eq!(list.needs_location(), (false, false));
+        list.file_rules = rules;
+        assert_eq!(list.needs_location(), (true, true));
+        // A country rule beats a region rule, but not a network rule.
+        assert!(!list.should_deny_with_location(ip("10.2.3.4"), located(RegionId::Europe, "FR")));
+        assert!(list.should_deny_with_location(ip("10.2.3.4"), located(RegionId::Europe, "DE")));
+        assert!(
+            list.should_deny_with_location(ip("10.2.3.4"), located(RegionId::NorthAmerica, "US"))
+        );
+        let rule = IpAccessRule::AllowNetwork(IpNetwork::from_str("10.2.0.0/16").unwrap());
+        assert!(list.add(rule));
+        assert!(
+            !list.should_deny_with_location(ip("10.2.3.4"), located(RegionId::NorthAmerica, "US"))
+        );
+    }
+}
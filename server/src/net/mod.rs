@@ -2,19 +2,26 @@
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
 mod acceptor;
+mod certificate_files;
 mod http;
 mod ip;
+mod ip_access_list;
 mod ip_rate_limiter;
+mod proxy_protocol;
 mod referrer;
 mod tls;
+mod trusted_proxies;
 mod user_agent;
 mod web_socket;
 
-pub use self::acceptor::{CustomAcceptor, KillSwitch};
+pub use self::acceptor::{CustomAcceptor, ForwardedPermit, KillSwitch, PeerAddr};
+pub use self::certificate_files::CertificateFiles;
 pub use self::http::limit_content_length;
 pub use self::ip::{get_own_public_ip, ip_to_region_id};
+pub use self::ip_access_list::IpAccessList;
 pub use self::ip_rate_limiter::{ActivePermit, ConnectionPermit, IpRateLimiter};
 pub use self::referrer::ExtractReferrer;
 pub use self::tls::load_domains;
+pub use self::trusted_proxies::TrustedProxies;
 pub use self::user_agent::user_agent_into_id;
 pub use self::web_socket::WebSocket;
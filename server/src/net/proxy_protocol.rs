@@ -0,0 +1,237 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use std::io::{self, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Shortest possible v1 header, `PROXY UNKNOWN\r\n`. Reading this much never over-reads.
+const V1_MIN_LEN: usize = 15;
+/// Longest possible v1 header, including CRLF.
+const V1_MAX_LEN: usize = 107;
+const V1_PREFIX: &[u8] = b"PROXY ";
+const V2_SIGNATURE: [u8; 12] = *b"\r\n\r\n\0\r\nQUIT\n";
+/// Signature, version/command, family/protocol, and length.
+const V2_HEADER_LEN: usize = 16;
+/// Generous, since TLVs may carry TLS details we ignore.
+const V2_MAX_BODY_LEN: usize = 4096;
+
+/// Reads a HAProxy PROXY protocol (v1 or v2) header from the start of `stream`, without consuming
+/// anything after it.
+///
+/// Returns the original source address, or [`None`] if the proxy connected on its own behalf (e.g.
+/// a health check) or didn't know the source.
+pub async fn read_proxy_header<S: AsyncRead + Unpin>(
+    stream: &mut S,
+) -> io::Result<Option<SocketAddr>> {
+    let mut buf = [0u8; V1_MAX_LEN];
+    stream.read_exact(&mut buf[..V1_MIN_LEN]).await?;
+    if buf.starts_with(&V2_SIGNATURE) {
+        stream
+            .read_exact(&mut buf[V1_MIN_LEN..V2_HEADER_LEN])
+            .await?;
+        let header: &[u8; V2_HEADER_LEN] = buf[..V2_HEADER_LEN].try_into().unwrap();
+        let len = u16::from_be_bytes([header[14], header[15]]) as usize;
+        if len > V2_MAX_BODY_LEN {
+            return Err(invalid("PROXY v2 header too long"));
+        }
+        let mut body = vec![0u8; len];
+        stream.read_exact(&mut body).await?;
+        parse_v2(header, &body)
+    } else if buf.starts_with(V1_PREFIX) {
+        let mut len = V1_MIN_LEN;
+        while !buf[..len].ends_with(b"\r\n") {
+            if len == V1_MAX_LEN {
+                return Err(invalid("PROXY v1 header too long"));
+            }
+            stream.read_exact(&mut buf[len..len + 1]).await?;
+            len += 1;
+        }
+        parse_v1(&buf[..len])
+    } else {
+        Err(invalid("missing PROXY header"))
+    }
+}
+
+/// Parses a complete v1 header, e.g. `PROXY TCP4 192.0.2.1 198.51.100.1 56324 443\r\n`.
+fn parse_v1(line: &[u8]) -> io::Result<Option<SocketAddr>> {
+    let line = line
+        .strip_suffix(b"\r\n")
+        .and_then(|line| std::str::from_utf8(line).ok())
+        .ok_or_else(|| invalid("malformed PROXY v1 header"))?;
+    let mut words = line.split(' ');
+    if words.next() != Some("PROXY") {
+        return Err(invalid("malformed PROXY v1 header"));
+    }
+    let v6 = match words.next() {
+        Some("TCP4") => false,
+        Some("TCP6") => true,
+        // Remainder of line must be ignored.
+        Some("UNKNOWN") => return Ok(None),
+        _ => return Err(invalid("unsupported PROXY v1 protocol")),
+    };
+    let (Some(src), Some(_dst), Some(src_port), Some(_dst_port), None) = (
+        words.next(),
+        words.next(),
+        words.next(),
+        words.next(),
+        words.next(),
+    ) else {
+        return Err(invalid("malformed PROXY v1 header"));
+    };
+    let ip = if v6 {
+        Ipv6Addr::from_str(src).map(IpAddr::V6)
+    } else {
+        Ipv4Addr::from_str(src).map(IpAddr::V4)
+    }
+    .map_err(|_| invalid("invalid PROXY v1 source address"))?;
+    let port = u16::from_str(src_port).map_err(|_| invalid("invalid PROXY v1 source port"))?;
+    Ok(Some(SocketAddr::new(ip, port)))
+}
+
+/// Parses a v2 header, given its fixed-size part and the variable-size body.
+fn parse_v2(header: &[u8; V2_HEADER_LEN], body: &[u8]) -> io::Result<Option<SocketAddr>> {
+    if !header.starts_with(&V2_SIGNATURE) {
+        return Err(invalid("malformed PROXY v2 header"));
+    }
+    let version_command = header[12];
+    if version_command >> 4 != 2 {
+        return Err(invalid("unsupported PROXY version"));
+    }
+    match version_command & 0xF {
+        // LOCAL: the proxy's own connection, addresses must be ignored.
+        0 => return Ok(None),
+        // PROXY.
+        1 => {}
+        _ => return Err(invalid("unsupported PROXY v2 command")),
+    }
+    let family = header[13] >> 4;
+    match family {
+        // AF_INET.
+        1 if body.len() >= 12 => {
+            let ip = Ipv4Addr::from(<[u8; 4]>::try_from(&body[0..4]).unwrap());
+            let port = u16::from_be_bytes([body[8], body[9]]);
+            Ok(Some(SocketAddr::new(IpAddr::V4(ip), port)))
+        }
+        // AF_INET6.
+        2 if body.len() >= 36 => {
+            let ip = Ipv6Addr::from(<[u8; 16]>::try_from(&body[0..16]).unwrap());
+            let port = u16::from_be_bytes([body[32], body[33]]);
+            Ok(Some(SocketAddr::new(IpAddr::V6(ip), port)))
+        }
+        1 | 2 => Err(invalid("truncated PROXY v2 addresses")),
+        // AF_UNSPEC or AF_UNIX.
+        _ => Ok(None),
+    }
+}
+
+fn invalid(message: &'static str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read_proxy_header, V2_SIGNATURE};
+    use std::net::SocketAddr;
+    use std::str::FromStr;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Connects to a local listener as if it were a load balancer, sending `header` and then
+    /// `payload`, and returns what the listener parsed and what remained in the stream.
+    fn through_fake_proxy(
+        header: Vec<u8>,
+        payload: &'static [u8],
+    ) -> (Option<SocketAddr>, Vec<u8>) {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()
+            .unwrap()
+            .block_on(async move {
+                let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+                let addr = listener.local_addr().unwrap();
+                let proxy = tokio::spawn(async move {
+                    let mut stream = TcpStream::connect(addr).await.unwrap();
+                    // Split writes to exercise partial reads.
+                    let (a, b) = header.split_at(header.len() / 2);
+                    stream.write_all(a).await.unwrap();
+                    stream.flush().await.unwrap();
+                    stream.write_all(b).await.unwrap();
+                    stream.write_all(payload).await.unwrap();
+                    stream.shutdown().await.unwrap();
+                });
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let source = read_proxy_header(&mut stream).await.unwrap();
+                let mut rest = Vec::new();
+                stream.read_to_end(&mut rest).await.unwrap();
+                proxy.await.unwrap();
+                (source, rest)
+            })
+    }
+
+    #[test]
+    fn v1() {
+        let (source, rest) = through_fake_proxy(
+            b"PROXY TCP4 192.0.2.1 198.51.100.1 56324 443\r\n".to_vec(),
+            b"GET / HTTP/1.1\r\n",
+        );
+        assert_eq!(
+            source,
+            Some(SocketAddr::from_str("192.0.2.1:56324").unwrap())
+        );
+        assert_eq!(rest, b"GET / HTTP/1.1\r\n");
+
+        let (source, rest) = through_fake_proxy(
+            b"PROXY TCP6 2001:db8::1 2001:db8::2 4711 443\r\n".to_vec(),
+            b"x",
+        );
+        assert_eq!(
+            source,
+            Some(SocketAddr::from_str("[2001:db8::1]:4711").unwrap())
+        );
+        assert_eq!(rest, b"x");
+
+        let (source, rest) = through_fake_proxy(b"PROXY UNKNOWN\r\n".to_vec(), b"y");
+        assert_eq!(source, None);
+        assert_eq!(rest, b"y");
+    }
+
+    #[test]
+    fn v2() {
+        let mut header = V2_SIGNATURE.to_vec();
+        // Version 2, PROXY command, AF_INET, STREAM.
+        header.extend_from_slice(&[0x21, 0x11, 0, 12 + 5]);
+        header.extend_from_slice(&[203, 0, 113, 7, 10, 0, 0, 1]);
+        header.extend_from_slice(&1234u16.to_be_bytes());
+        header.extend_from_slice(&443u16.to_be_bytes());
+        // A TLV to be skipped.
+        header.extend_from_slice(&[0x04, 0, 2, 0xAB, 0xCD]);
+        let (source, rest) = through_fake_proxy(header, b"\x16\x03\x01");
+        assert_eq!(
+            source,
+            Some(SocketAddr::from_str("203.0.113.7:1234").unwrap())
+        );
+        assert_eq!(rest, b"\x16\x03\x01");
+
+        let mut header = V2_SIGNATURE.to_vec();
+        // Version 2, LOCAL command, AF_UNSPEC.
+        header.extend_from_slice(&[0x20, 0x00, 0, 0]);
+        let (source, rest) = through_fake_proxy(header, b"z");
+        assert_eq!(source, None);
+        assert_eq!(rest, b"z");
+    }
+
+    #[test]
+    fn missing() {
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(async {
+                let mut stream: &[u8] = b"GET / HTTP/1.1\r\nHost: x\r\n\r\n";
+                assert!(read_proxy_header(&mut stream).await.is_err());
+                let mut stream: &[u8] = b"PROXY TCP4 1.2.3.4 5.6.7.8 1 2 3\r\n";
+                assert!(read_proxy_header(&mut stream).await.is_err());
+            });
+    }
+}
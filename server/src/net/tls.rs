@@ -1,7 +1,7 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use crate::{ArenaService, DomainDto, NonZeroUnixMillis, UnixTime};
+use crate::{ArenaService, DomainDto, DomainName, NonZeroUnixMillis, UnixTime};
 use log::warn;
 use rustls::server::{ClientHello, ServerConfig};
 use rustls::sign::CertifiedKey;
@@ -14,6 +14,14 @@ use x509_parser::prelude::X509Certificate;
 
 pub fn load_domains<G: ArenaService>(
     domains: &[DomainDto],
+) -> Option<(Arc<ServerConfig>, NonZeroUnixMillis)> {
+    load_domains_with_default(domains, G::GAME_CONSTANTS.domain_name())
+}
+
+/// Like [`load_domains`], but `default` is served to clients that don't match any other domain.
+pub(crate) fn load_domains_with_default(
+    domains: &[DomainDto],
+    default: DomainName,
 ) -> Option<(Arc<ServerConfig>, NonZeroUnixMillis)> {
     let mut certificates = domains
         .iter()
@@ -60,7 +68,7 @@ pub fn load_domains<G: ArenaService>(
 
     if let Some(default) = certificates
         .iter()
-        .position(|c| &*c.0 == G::GAME_CONSTANTS.domain.as_bytes())
+        .position(|c| &*c.0 == default.as_bytes())
     {
         let (_, default, date_certificate_expiers) = certificates.swap_remove(default);
         let resolver = ResolvesServerCertUsingSniOrDefault {
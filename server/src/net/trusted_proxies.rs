@@ -0,0 +1,162 @@
+// SPDX-FileCopyrightText: 2024 Softbear, Inc.
+// SPDX-License-Identifier: LGPL-3.0-or-later
+
+use crate::IpNetwork;
+use axum::http::HeaderMap;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Reverse proxies (e.g. load balancers) whose `Forwarded` and `X-Forwarded-For` headers, and
+/// PROXY protocol headers, are believed.
+#[derive(Clone, Debug, Default)]
+pub struct TrustedProxies(Arc<[IpNetwork]>);
+
+impl TrustedProxies {
+    pub fn new(networks: Vec<IpNetwork>) -> Self {
+        Self(networks.into())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|network| network.contains(ip))
+    }
+
+    /// Returns the address of the original client, given the immediate `peer`.
+    ///
+    /// Forwarding headers are only believed if `peer` is trusted. They are walked from the most
+    /// recent hop backwards, stopping at the first hop that isn't a trusted proxy, since anything
+    /// before that could have been forged by the client.
+    pub fn client_addr(&self, headers: &HeaderMap, peer: SocketAddr) -> SocketAddr {
+        if !self.contains(peer.ip()) {
+            return peer;
+        }
+        let mut client = peer;
+        for hop in forwarded_hops(headers).into_iter().rev() {
+            // Unknown or obfuscated hop, can't see past it.
+            let Some(hop) = hop else {
+                break;
+            };
+            client = hop;
+            if !self.contains(hop.ip()) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// Hops in order of increasing recency, preferring the standard `Forwarded` header.
+fn forwarded_hops(headers: &HeaderMap) -> Vec<Option<SocketAddr>> {
+    let values = |name: &str| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .collect::<Vec<_>>()
+    };
+    let forwarded = values("forwarded");
+    if !forwarded.is_empty() {
+        forwarded
+            .into_iter()
+            .map(|element| {
+                element.split(';').find_map(|pair| {
+                    let (key, value) = pair.split_once('=')?;
+                    key.trim()
+                        .eq_ignore_ascii_case("for")
+                        .then(|| parse_node(value))
+                        .flatten()
+                })
+            })
+            .collect()
+    } else {
+        values("x-forwarded-for")
+            .into_iter()
+            .map(parse_node)
+            .collect()
+    }
+}
+
+/// Parses `192.0.2.1`, `192.0.2.1:1234`, `2001:db8::1`, `"[2001:db8::1]:1234"`, etc. A missing
+/// port is reported as zero.
+fn parse_node(node: &str) -> Option<SocketAddr> {
+    let node = node.trim().trim_matches('"');
+    SocketAddr::from_str(node).ok().or_else(|| {
+        let ip = node.strip_prefix('[').and_then(|n| n.strip_suffix(']'));
+        IpAddr::from_str(ip.unwrap_or(node))
+            .ok()
+            .map(|ip| SocketAddr::new(ip, 0))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::TrustedProxies;
+    use crate::IpNetwork;
+    use axum::http::{HeaderMap, HeaderValue};
+    use std::net::SocketAddr;
+    use std::str::FromStr;
+
+    fn addr(s: &str) -> SocketAddr {
+        SocketAddr::from_str(s).unwrap()
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for &(name, value) in pairs {
+            headers.append(name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn client_addr() {
+        let trusted = TrustedProxies::new(vec![
+            IpNetwork::from_str("10.0.0.0/8").unwrap(),
+            IpNetwork::from_str("2001:db8:ffff::/48").unwrap(),
+        ]);
+        let proxy = addr("10.0.0.1:5000");
+
+        // Untrusted peers can't spoof.
+        let spoofed = headers(&[("x-forwarded-for", "1.2.3.4")]);
+        assert_eq!(
+            trusted.client_addr(&spoofed, addr("192.0.2.1:80")),
+            addr("192.0.2.1:80")
+        );
+        assert_eq!(
+            TrustedProxies::default().client_addr(&spoofed, proxy),
+            proxy
+        );
+
+        // No headers.
+        assert_eq!(trusted.client_addr(&HeaderMap::new(), proxy), proxy);
+
+        // Client-supplied prefix is ignored.
+        let chain = headers(&[
+            ("x-forwarded-for", "1.2.3.4, 198.51.100.7"),
+            ("x-forwarded-for", "10.1.2.3"),
+        ]);
+        assert_eq!(trusted.client_addr(&chain, proxy), addr("198.51.100.7:0"));
+
+        // Standard header takes precedence.
+        let forwarded = headers(&[
+            ("x-forwarded-for", "1.2.3.4"),
+            (
+                "forwarded",
+                r#"for=192.0.2.60;proto=http, For="[2001:db8:cafe::17]:4711";by=10.0.0.1"#,
+            ),
+        ]);
+        assert_eq!(
+            trusted.client_addr(&forwarded, proxy),
+            addr("[2001:db8:cafe::17]:4711")
+        );
+
+        // Obfuscated hop.
+        let obfuscated = headers(&[("forwarded", "for=192.0.2.60, for=_hidden, for=10.0.0.2")]);
+        assert_eq!(trusted.client_addr(&obfuscated, proxy), addr("10.0.0.2:0"));
+    }
+}
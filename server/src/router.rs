@@ -3,7 +3,9 @@
 
 use super::actor::ServerActor;
 use super::entry_point::{Authenticated, CORS_ALTERNATIVE_DOMAINS, REDIRECT_TO_SERVER_ID};
-use super::net::{limit_content_length, IpRateLimiter, KillSwitch};
+use super::net::{
+    limit_content_length, ForwardedPermit, IpRateLimiter, KillSwitch, PeerAddr, TrustedProxies,
+};
 use super::rate_limiter::{RateLimiterProps, RateLimiterState};
 use super::service::ArenaService;
 use super::socket::ws_request;
@@ -19,14 +21,15 @@ use axum::extract::{ConnectInfo, State};
 use axum::http::uri::{Authority, Scheme};
 use axum::http::{HeaderName, HeaderValue, Method, StatusCode, Uri};
 use axum::response::{IntoResponse, Redirect, Response};
-use axum::routing::{any, get, post};
+use axum::routing::{any, get, post, MethodRouter};
 use axum::{Json, Router};
 use bytes::Bytes;
-use hyper::header::{CACHE_CONTROL, CONNECTION, CONTENT_LENGTH};
+use hyper::header::{CACHE_CONTROL, CONNECTION, CONTENT_LENGTH, CONTENT_TYPE};
 use kodiak_common::DomainName;
 use minicdn::MiniCdn;
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::path::Path;
 use std::str::FromStr;
 use std::sync::atomic::Ordering;
 use std::sync::{Arc, LazyLock, Mutex, RwLock};
@@ -48,11 +51,48 @@ pub async fn admin_request<G: ArenaService>(
     }
 }
 
+/// Where a local ACME client writes HTTP-01 challenge responses, relative to its webroot.
+const ACME_CHALLENGE_DIR: &str = ".well-known/acme-challenge";
+pub const ACME_CHALLENGE_ROUTE: &str = "/.well-known/acme-challenge/{token}";
+
+/// Serves HTTP-01 challenge responses written by a local ACME client to `acme_webroot`.
+pub fn acme_challenge_route<S: Clone + Send + Sync + 'static>(
+    acme_webroot: Option<Arc<Path>>,
+) -> MethodRouter<S> {
+    get(
+        move |axum::extract::Path(token): axum::extract::Path<String>| async move {
+            let Some(acme_webroot) = acme_webroot else {
+                return Err(StatusCode::NOT_FOUND);
+            };
+            // Tokens are base64url, which also rules out path traversal.
+            if token.is_empty()
+                || !token
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+            {
+                return Err(StatusCode::NOT_FOUND);
+            }
+            let path = acme_webroot.join(ACME_CHALLENGE_DIR).join(token);
+            tokio::fs::read(path)
+                .await
+                .map(|key_authorization| {
+                    (
+                        [(CONTENT_TYPE, "application/octet-stream")],
+                        key_authorization,
+                    )
+                })
+                .map_err(|_| StatusCode::NOT_FOUND)
+        },
+    )
+}
+
 pub fn new_router<G: ArenaService>(
     server_id: ServerId,
     infrastructure: Addr<ServerActor<G>>,
     game_client: Arc<RwLock<MiniCdn>>,
     ads_txt: Arc<RwLock<HashMap<Option<Referrer>, Bytes>>>,
+    trusted_proxies: TrustedProxies,
+    acme_webroot: Option<Arc<Path>>,
 ) -> Router {
     let cors_layer = CorsLayer::new()
         .allow_origin(tower_http::cors::AllowOrigin::predicate(
@@ -88,6 +128,7 @@ pub fn new_router<G: ArenaService>(
         // Need both, see https://github.com/tokio-rs/axum/issues/1607#issuecomment-1335025399
         .route("/admin/", post(admin_request))
         .route("/admin/{*path}", post(admin_request))
+        .route(ACME_CHALLENGE_ROUTE, acme_challenge_route(acme_webroot))
         .route("/ads.txt", get(ads_txt_file))
         .route("/robots.txt", get(robots_txt_file::<G>))
         .route("/sitemap.txt", get(sitemap_txt_file::<G>))
@@ -104,6 +145,11 @@ pub fn new_router<G: ArenaService>(
         // We limit even further later on.
         .layer(axum::extract::DefaultBodyLimit::max(64 * 1024 * 1024))
         .layer(axum_server_timing::ServerTimingLayer::new("Router"))
+        // Outermost, so everything else sees the original client.
+        .layer(axum::middleware::from_fn_with_state(
+            trusted_proxies,
+            client_addr_middleware,
+        ))
 }
 
 #[derive(Clone, Debug)]
@@ -192,6 +238,39 @@ fn check_origin_quiet<G: ArenaService>(origin: &str) -> Option<AllowedOrigin> {
     }
 }
 
+/// Replaces [`ConnectInfo`] with the address of the original client, which may have been relayed
+/// by a PROXY protocol header and/or trusted reverse proxies' forwarding headers. Forwarded clients
+/// are subject to access control and connection limits here, since their proxy's connection was
+/// exempt.
+async fn client_addr_middleware(
+    State(trusted_proxies): State<TrustedProxies>,
+    mut request: axum::http::Request<Body>,
+    next: axum::middleware::Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<PeerAddr>()
+        .map(|a| a.0)
+        .or_else(|| {
+            request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ci| ci.0)
+        });
+    if let Some(peer) = peer {
+        let addr = trusted_proxies.client_addr(request.headers(), peer);
+        match ForwardedPermit::new(addr.ip(), peer.ip()) {
+            Ok(Some(permit)) => {
+                request.extensions_mut().insert(permit);
+            }
+            Ok(None) => {}
+            Err(status) => return status.into_response(),
+        }
+        request.extensions_mut().insert(ConnectInfo(addr));
+    }
+    next.run(request).await
+}
+
 async fn security_middleware(
     request: axum::http::Request<Body>,
     next: axum::middleware::Next,
@@ -325,3 +404,58 @@ async fn redirect_middleware<G: ArenaService>(
 
     Ok(next.run(request).await)
 }
+
+#[cfg(test)]
+mod test {
+    use super::{acme_challenge_route, ACME_CHALLENGE_DIR, ACME_CHALLENGE_ROUTE};
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::Router;
+    use std::path::Path;
+    use std::sync::Arc;
+    use tower::Service;
+
+    #[test]
+    fn acme_challenge() {
+        let webroot = std::env::temp_dir().join(format!("kodiak_acme_{}", std::process::id()));
+        let challenges = webroot.join(ACME_CHALLENGE_DIR);
+        std::fs::create_dir_all(&challenges).unwrap();
+        std::fs::write(challenges.join("abc-DEF_123"), "abc-DEF_123.thumbprint").unwrap();
+        // Reachable from the challenge directory with "../secret".
+        std::fs::write(challenges.parent().unwrap().join("secret"), "secret").unwrap();
+
+        let router = Router::new().route(
+            ACME_CHALLENGE_ROUTE,
+            acme_challenge_route(Some(Arc::<Path>::from(webroot.as_path()))),
+        );
+        let get = |token: &str| {
+            let request = Request::builder()
+                .uri(format!("/.well-known/acme-challenge/{token}"))
+                .body(Body::empty())
+                .unwrap();
+            let mut router = router.clone();
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(async move {
+                    let response = router.call(request).await.unwrap();
+                    let status = response.status();
+                    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                        .await
+                        .unwrap();
+                    (status, body)
+                })
+        };
+
+        let (status, body) = get("abc-DEF_123");
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(&*body, b"abc-DEF_123.thumbprint");
+
+        assert_eq!(get("missing").0, StatusCode::NOT_FOUND);
+        assert_eq!(get("..%2Fsecret").0, StatusCode::NOT_FOUND);
+        assert_eq!(get("%2E%2E%2Fsecret").0, StatusCode::NOT_FOUND);
+
+        let _ = std::fs::remove_dir_all(&webroot);
+    }
+}
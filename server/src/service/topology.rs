@@ -17,6 +17,8 @@ pub struct Topology {
     /// For lag calculations.
     pub regions: HashMap<ServerId, RegionId>,
     pub tiers: BTreeMap<Option<TierNumber>, Tier>,
+    /// The local server is draining, so it is unhealthy and omitted from [`Self::tiers`].
+    pub draining: bool,
 }
 
 #[derive(Default, Debug)]
@@ -37,6 +39,7 @@ impl Topology {
             local_arena_id,
             regions: Default::default(),
             tiers: Default::default(),
+            draining: false,
         }
     }
 
@@ -49,6 +52,9 @@ impl Topology {
         self.tiers.clear();
         for (&server_id, server) in servers {
             self.regions.insert(server_id, server.region_id);
+            if self.draining && server_id == self.local_server_id {
+                continue;
+            }
             let Some(realm) = server.realm(self.local_arena_id.realm_id) else {
                 continue;
             };
@@ -61,6 +67,44 @@ impl Topology {
         }
     }
 
+    /// Another server to move players to, preferring the local tier, then the local region, then
+    /// fewer players.
+    pub fn drain_target(&self) -> Option<(ServerId, ArenaId)> {
+        let local_tier_number = self.local_arena_id.scene_id.tier_number;
+        let local_region_id = self.region_id(self.local_server_id);
+        self.tiers
+            .iter()
+            .flat_map(|(&tier_number, tier)| {
+                tier.arenas
+                    .iter()
+                    .map(move |(&server_id, arena)| (tier_number, server_id, arena))
+            })
+            .filter(|&(_, server_id, _)| server_id != self.local_server_id)
+            .min_by_key(|&(tier_number, server_id, arena)| {
+                (
+                    tier_number != local_tier_number,
+                    self.region_id(server_id) != local_region_id,
+                    arena.player_count,
+                )
+            })
+            .map(|(tier_number, server_id, arena)| {
+                (
+                    server_id,
+                    ArenaId::new(
+                        self.local_arena_id.realm_id,
+                        SceneId::new(
+                            tier_number,
+                            arena
+                                .instance_numbers
+                                .choose(&mut thread_rng())
+                                .cloned()
+                                .unwrap_or_default(),
+                        ),
+                    ),
+                )
+            })
+    }
+
     pub fn max_tier_number(&self) -> Option<TierNumber> {
         self.max_sanctioned_tier_number()
             .max(self.local_arena_id.scene_id.tier_number)
@@ -237,3 +281,76 @@ impl Topology {
         ))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Arena, Topology};
+    use crate::{
+        ArenaId, InstanceNumber, RealmId, RegionId, SceneId, ServerId, ServerKind, ServerNumber,
+        TierNumber,
+    };
+
+    fn server(n: u8) -> ServerId {
+        ServerId {
+            kind: ServerKind::Cloud,
+            number: ServerNumber::new(n).unwrap(),
+        }
+    }
+
+    fn add(
+        topology: &mut Topology,
+        server_id: ServerId,
+        tier_number: Option<TierNumber>,
+        region_id: RegionId,
+        player_count: u16,
+    ) {
+        topology.regions.insert(server_id, region_id);
+        topology
+            .tiers
+            .entry(tier_number)
+            .or_default()
+            .arenas
+            .insert(
+                server_id,
+                Arena {
+                    player_count,
+                    instance_numbers: vec![InstanceNumber(server_id.number.0.get())],
+                },
+            );
+    }
+
+    #[test]
+    fn drain_target() {
+        let tier = TierNumber::new(1);
+        let local_arena_id = ArenaId::new(
+            RealmId::PublicDefault,
+            SceneId::new(tier, Default::default()),
+        );
+        let mut topology = Topology::new(server(1), local_arena_id);
+        assert_eq!(topology.drain_target(), None);
+
+        // Never the local server itself.
+        add(&mut topology, server(1), tier, RegionId::Europe, 0);
+        assert_eq!(topology.drain_target(), None);
+
+        let target = |server_id: ServerId, tier_number| {
+            Some((
+                server_id,
+                ArenaId::new(
+                    RealmId::PublicDefault,
+                    SceneId::new(tier_number, InstanceNumber(server_id.number.0.get())),
+                ),
+            ))
+        };
+
+        // Prefer the local tier, then the local region, then fewer players.
+        add(&mut topology, server(2), None, RegionId::Europe, 0);
+        assert_eq!(topology.drain_target(), target(server(2), None));
+        add(&mut topology, server(3), tier, RegionId::Asia, 5);
+        assert_eq!(topology.drain_target(), target(server(3), tier));
+        add(&mut topology, server(4), tier, RegionId::Europe, 10);
+        assert_eq!(topology.drain_target(), target(server(4), tier));
+        add(&mut topology, server(5), tier, RegionId::Europe, 9);
+        assert_eq!(topology.drain_target(), target(server(5), tier));
+    }
+}
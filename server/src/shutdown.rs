@@ -1,9 +1,14 @@
 // SPDX-FileCopyrightText: 2024 Softbear, Inc.
 // SPDX-License-Identifier: LGPL-3.0-or-later
 
-use super::actor::ServerActor;
+use super::actor::{ClientActlet, ClientStatus, ServerActor};
+use super::entry_point::DRAINING;
 use super::service::ArenaService;
+use crate::{ArenaQuery, PlayerId};
 use actix::{ActorContext, Handler, Message};
+use log::{info, warn};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
 
 /// Asks the server to stop itself.
 #[derive(Message)]
@@ -17,3 +22,195 @@ impl<G: ArenaService> Handler<Shutdown> for ServerActor<G> {
         ctx.stop();
     }
 }
+
+/// Asks the server to drain, e.g. for a rolling deploy, then stop itself.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct StartDrain {
+    /// Stop even if players are still in matches after this long.
+    pub timeout: Duration,
+}
+
+impl<G: ArenaService> Handler<StartDrain> for ServerActor<G> {
+    type Result = ();
+
+    fn handle(&mut self, request: StartDrain, _ctx: &mut Self::Context) -> Self::Result {
+        self.start_drain(request.timeout);
+    }
+}
+
+/// Progress of draining the server.
+///
+/// While draining, new players are refused, the server reports itself as draining to plasma and is
+/// absent from its own [`Topology`](crate::service::Topology), and players are redirected to other
+/// servers as soon as they aren't in the middle of a match. Connections stay open, so existing
+/// players can still reconnect and admin requests and health checks are still served. Once no
+/// players remain connected, or the deadline passes, the server stops.
+#[derive(Debug)]
+pub(crate) struct Drain {
+    deadline: Instant,
+}
+
+/// How a client holds up a [`Drain`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum DrainClient {
+    /// Connected and in the middle of a match, so wait for it to end.
+    Playing,
+    /// Connected but not in a match, so redirect them.
+    Idle,
+    /// Wait for the other server to accept them (or the redirect to expire).
+    Redirecting,
+    /// Not holding up the drain.
+    Gone,
+}
+
+impl DrainClient {
+    fn new<G: ArenaService>(status: &ClientStatus<G>, alive: bool) -> Self {
+        match status {
+            ClientStatus::Connected { .. } if alive => Self::Playing,
+            ClientStatus::Connected { .. } => Self::Idle,
+            ClientStatus::Redirected {
+                id_token: None,
+                send_close: true,
+                ..
+            } => Self::Redirecting,
+            _ => Self::Gone,
+        }
+    }
+}
+
+impl Drain {
+    /// Returns the players to redirect now, and how many players are still holding up the drain.
+    fn plan(clients: impl IntoIterator<Item = (PlayerId, DrainClient)>) -> (Vec<PlayerId>, usize) {
+        let mut to_redirect = Vec::new();
+        let mut remaining = 0;
+        for (player_id, client) in clients {
+            match client {
+                DrainClient::Playing | DrainClient::Redirecting => {}
+                DrainClient::Idle => to_redirect.push(player_id),
+                DrainClient::Gone => continue,
+            }
+            remaining += 1;
+        }
+        (to_redirect, remaining)
+    }
+
+    /// Returns true if the server should stop, given how many players are still holding up the
+    /// drain.
+    fn is_over(&self, remaining: usize, now: Instant) -> bool {
+        if remaining == 0 {
+            info!("drained");
+            true
+        } else if now >= self.deadline {
+            warn!("drain deadline passed with {remaining} player(s) connected");
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<G: ArenaService> ServerActor<G> {
+    /// Returns false if already draining, in which case the deadline is unchanged.
+    pub(crate) fn start_drain(&mut self, timeout: Duration) -> bool {
+        if self.drain.is_some() {
+            return false;
+        }
+        warn!("draining for up to {}s", timeout.as_secs());
+        DRAINING.store(true, Ordering::Relaxed);
+        self.drain = Some(Drain {
+            deadline: Instant::now() + timeout,
+        });
+        true
+    }
+
+    /// Call once every tick.
+    pub(crate) fn update_drain(&mut self, ctx: &mut <Self as actix::Actor>::Context) {
+        if self.drain.is_none() {
+            return;
+        }
+        let mut remaining = 0;
+        for (_, scene) in self.realms.iter_mut() {
+            let arena = &mut scene.arena;
+            arena.arena_context.topology.draining = true;
+            let (to_redirect, arena_remaining) =
+                Drain::plan(arena.arena_context.players.iter().filter_map(
+                    |(player_id, player)| {
+                        let client = player.client()?;
+                        Some((
+                            player_id,
+                            DrainClient::new(&client.status, player.is_alive()),
+                        ))
+                    },
+                ));
+            remaining += arena_remaining;
+            if to_redirect.is_empty() {
+                continue;
+            }
+            let Some((server_id, arena_id)) = arena.arena_context.topology.drain_target() else {
+                continue;
+            };
+            for player_id in to_redirect {
+                if let Err(e) = ClientActlet::redirect(
+                    player_id,
+                    server_id,
+                    ArenaQuery::Specific(arena_id, None),
+                    &mut arena.arena_service,
+                    &mut arena.arena_context,
+                    &mut self.metrics,
+                ) {
+                    warn!("could not redirect {player_id:?} while draining: {e}");
+                }
+            }
+        }
+
+        if let Some(drain) = &self.drain
+            && drain.is_over(remaining, Instant::now())
+        {
+            ctx.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Drain, DrainClient};
+    use crate::PlayerId;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn plan() {
+        let player = |n| PlayerId::nth_client(n).unwrap();
+        assert_eq!(Drain::plan([]), (vec![], 0));
+        assert_eq!(
+            Drain::plan([
+                (player(0), DrainClient::Playing),
+                (player(1), DrainClient::Idle),
+                (player(2), DrainClient::Redirecting),
+                (player(3), DrainClient::Gone),
+                (player(4), DrainClient::Idle),
+            ]),
+            (vec![player(1), player(4)], 4)
+        );
+        // Once everyone has been redirected and accepted elsewhere, the drain is done.
+        assert_eq!(
+            Drain::plan([
+                (player(0), DrainClient::Gone),
+                (player(1), DrainClient::Gone)
+            ]),
+            (vec![], 0)
+        );
+    }
+
+    #[test]
+    fn is_over() {
+        let now = Instant::now();
+        let drain = Drain {
+            deadline: now + Duration::from_secs(60),
+        };
+        assert!(drain.is_over(0, now));
+        assert!(!drain.is_over(1, now));
+        assert!(!drain.is_over(1, now + Duration::from_secs(59)));
+        assert!(drain.is_over(1, now + Duration::from_secs(60)));
+    }
+}
@@ -3,13 +3,14 @@
 
 use super::{INBOUND_HARD_LIMIT, KEEPALIVE_INTERVAL};
 use crate::actor::{ClientAuthErr, ClientAuthRequest};
+use crate::net::ForwardedPermit;
 use crate::router::check_origin;
 use crate::service::ArenaService;
 use crate::socket::{Socket, SocketMessage, KEEPALIVE_HARD_TIMEOUT};
 use crate::state::AppState;
 use crate::{Compression, CompressionImpl, Compressor, NonZeroUnixMillis, SocketQuery, UnixTime};
 use axum::body::Body;
-use axum::extract::{ConnectInfo, Query, State};
+use axum::extract::{ConnectInfo, Extension, Query, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum_extra::TypedHeader;
@@ -32,6 +33,7 @@ pub async fn ws_request<G: ArenaService>(
     State(state): State<AppState<G>>,
     upgrade: WebSocketUpgrade,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    permit: Option<Extension<ForwardedPermit>>,
     user_agent: Option<TypedHeader<axum_extra::headers::UserAgent>>,
     Query(query): Query<SocketQuery>,
     headers: HeaderMap,
@@ -92,6 +94,8 @@ pub async fn ws_request<G: ArenaService>(
                 compressor: Default::default(),
             };
             async move {
+                // Keep counting a forwarded client's connection until the WebSocket closes.
+                let _permit = permit;
                 std::pin::pin!(web_socket)
                     .as_mut()
                     .serve(origin, user_agent_id, arena_id, player_id, state.server)
@@ -3,7 +3,7 @@
 
 use super::{KEEPALIVE_HARD_TIMEOUT, KEEPALIVE_INTERVAL};
 use crate::actor::{ClientAuthErr, ClientAuthRequest, ServerActor};
-use crate::net::ConnectionPermit;
+use crate::net::{ConnectionPermit, IpAccessList};
 use crate::rate_limiter::RateLimiter;
 use crate::router::check_origin;
 use crate::socket::{Socket, SocketMessage, INBOUND_HARD_LIMIT};
@@ -211,6 +211,10 @@ pub async fn web_transport<G: ArenaService>(
         }
 
         let ip = canonize(incoming_session.remote_address()).ip();
+        if IpAccessList::should_deny_outer(ip, "QUIC connection") {
+            incoming_session.refuse();
+            continue;
+        }
         let Some(permit) = ConnectionPermit::new(ip, "QUIC connection") else {
             incoming_session.refuse();
             continue;